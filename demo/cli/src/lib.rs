@@ -121,6 +121,13 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 					intentions: vec![],
 					transaction_base_fee: 100,
 					transaction_byte_fee: 1,
+					existential_deposit: 0,
+					creation_fee: 0,
+					misbehavior_slash: 0,
+					reporter_reward: 0,
+					session_reward: 0,
+					offline_slash: 0,
+					offline_slash_grace: 0,
 					balances: vec![(god_key.clone(), 1u64 << 63)].into_iter().collect(),
 					validator_count: 12,
 					sessions_per_era: 24,	// 24 hours per era.
@@ -129,6 +136,7 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 				democracy: Some(DemocracyConfig {
 					launch_period: 120 * 24 * 14,	// 2 weeks per public referendum
 					voting_period: 120 * 24 * 28,	// 4 weeks to discuss & vote on an active referendum
+					enactment_period: 120 * 24 * 14,	// 2 weeks between a referendum passing and its enactment
 					minimum_deposit: 1000,	// 1000 as the minimum deposit for a referendum
 				}),
 				council: Some(CouncilConfig {
@@ -48,8 +48,9 @@ mod tests {
 	use primitives::twox_128;
 	use demo_primitives::{Hash, BlockNumber};
 	use runtime_primitives::traits::Header as HeaderT;
+	use runtime_primitives::{ApplyResult, ApplyError};
 	use {staking, system};
-	use demo_runtime::{Header, Block, UncheckedExtrinsic, Extrinsic, Call, Concrete, Staking,
+	use demo_runtime::{Header, Block, UncheckedExtrinsic, Extrinsic, Era, Call, Concrete, Staking,
 		BuildExternalities, GenesisConfig, SessionConfig, StakingConfig};
 	use ed25519::{Public, Pair};
 
@@ -67,12 +68,14 @@ mod tests {
 		let extrinsic = Extrinsic {
 			signed: Alice.into(),
 			index: 0,
-			function: Call::Staking(staking::Call::transfer::<Concrete>(Bob.into(), 69)),
+			function: Call::Staking(staking::Call::transfer::<Concrete>(Bob.into(), 69, 0, 0, vec![])),
 		};
+		let era = Era::Immortal;
+		let genesis_hash: Hash = [0u8; 32].into();
 		let signature = Keyring::from_raw_public(extrinsic.signed).unwrap()
-			.sign(&extrinsic.encode()).into();
+			.sign(&extrinsic.signing_payload(&era, &genesis_hash, &genesis_hash)).into();
 
-		UncheckedExtrinsic { extrinsic, signature }
+		UncheckedExtrinsic { extrinsic, era, signature }
 	}
 
 	fn from_block_number(n: u64) -> Header {
@@ -80,7 +83,7 @@ mod tests {
 	}
 
 	#[test]
-	fn panic_execution_with_foreign_code_gives_error() {
+	fn unpayable_extrinsic_with_foreign_code_gives_error() {
 		let mut t: TestExternalities = map![
 			twox_128(&<staking::FreeBalance<Concrete>>::key_for(*Alice)).to_vec() => vec![69u8, 0, 0, 0, 0, 0, 0, 0],
 			twox_128(<staking::TransactionBaseFee<Concrete>>::key()).to_vec() => vec![70u8; 8],
@@ -91,11 +94,11 @@ mod tests {
 		let r = Executor::new().call(&mut t, BLOATY_CODE, "initialise_block", &vec![].and(&from_block_number(1u64)));
 		assert!(r.is_ok());
 		let r = Executor::new().call(&mut t, BLOATY_CODE, "apply_extrinsic", &vec![].and(&xt()));
-		assert!(r.is_err());
+		assert_eq!(ApplyResult::decode(&mut &r.unwrap()[..]), Some(Err(ApplyError::CantPay)));
 	}
 
 	#[test]
-	fn panic_execution_with_native_equivalent_code_gives_error() {
+	fn unpayable_extrinsic_with_native_equivalent_code_gives_error() {
 		let mut t: TestExternalities = map![
 			twox_128(&<staking::FreeBalance<Concrete>>::key_for(*Alice)).to_vec() => vec![69u8, 0, 0, 0, 0, 0, 0, 0],
 			twox_128(<staking::TransactionBaseFee<Concrete>>::key()).to_vec() => vec![70u8; 8],
@@ -106,7 +109,7 @@ mod tests {
 		let r = Executor::new().call(&mut t, COMPACT_CODE, "initialise_block", &vec![].and(&from_block_number(1u64)));
 		assert!(r.is_ok());
 		let r = Executor::new().call(&mut t, COMPACT_CODE, "apply_extrinsic", &vec![].and(&xt()));
-		assert!(r.is_err());
+		assert_eq!(ApplyResult::decode(&mut &r.unwrap()[..]), Some(Err(ApplyError::CantPay)));
 	}
 
 	#[test]
@@ -168,6 +171,13 @@ mod tests {
 				bonding_duration: 0,
 				transaction_base_fee: 1,
 				transaction_byte_fee: 0,
+				existential_deposit: 0,
+				creation_fee: 0,
+				misbehavior_slash: 0,
+				reporter_reward: 0,
+				session_reward: 0,
+				offline_slash: 0,
+				offline_slash_grace: 0,
 			}),
 			democracy: Some(Default::default()),
 			council: Some(Default::default()),
@@ -177,11 +187,13 @@ mod tests {
 	fn construct_block(number: BlockNumber, parent_hash: Hash, state_root: Hash, extrinsics: Vec<Extrinsic>) -> (Vec<u8>, Hash) {
 		use triehash::ordered_trie_root;
 
+		let genesis_hash: Hash = [69u8; 32].into();
 		let extrinsics = extrinsics.into_iter().map(|extrinsic| {
+			let era = Era::Immortal;
 			let signature = Pair::from(Keyring::from_public(Public::from_raw(extrinsic.signed)).unwrap())
-				.sign(&extrinsic.encode()).into();
+				.sign(&extrinsic.signing_payload(&era, &genesis_hash, &genesis_hash)).into();
 
-			UncheckedExtrinsic { extrinsic, signature }
+			UncheckedExtrinsic { extrinsic, era, signature }
 		}).collect::<Vec<_>>();
 
 		let extrinsics_root = ordered_trie_root(extrinsics.iter().map(Slicable::encode)).0.into();
@@ -206,7 +218,7 @@ mod tests {
 			vec![Extrinsic {
 				signed: Alice.into(),
 				index: 0,
-				function: Call::Staking(staking::Call::transfer(Bob.into(), 69)),
+				function: Call::Staking(staking::Call::transfer(Bob.into(), 69, 0, 0, vec![])),
 			}]
 		)
 	}
@@ -220,12 +232,12 @@ mod tests {
 				Extrinsic {
 					signed: Bob.into(),
 					index: 0,
-					function: Call::Staking(staking::Call::transfer(Alice.into(), 5)),
+					function: Call::Staking(staking::Call::transfer(Alice.into(), 5, 0, 0, vec![])),
 				},
 				Extrinsic {
 					signed: Alice.into(),
 					index: 1,
-					function: Call::Staking(staking::Call::transfer(Bob.into(), 15)),
+					function: Call::Staking(staking::Call::transfer(Bob.into(), 15, 0, 0, vec![])),
 				}
 			]
 		)
@@ -270,7 +282,7 @@ mod tests {
 	}
 
 	#[test]
-	fn panic_execution_gives_error() {
+	fn unpayable_extrinsic_gives_error() {
 		let mut t: TestExternalities = map![
 			twox_128(&<staking::FreeBalance<Concrete>>::key_for(*Alice)).to_vec() => vec![69u8, 0, 0, 0, 0, 0, 0, 0],
 			twox_128(<staking::TransactionBaseFee<Concrete>>::key()).to_vec() => vec![70u8; 8],
@@ -282,7 +294,7 @@ mod tests {
 		let r = WasmExecutor.call(&mut t, &foreign_code[..], "initialise_block", &vec![].and(&from_block_number(1u64)));
 		assert!(r.is_ok());
 		let r = WasmExecutor.call(&mut t, &foreign_code[..], "apply_extrinsic", &vec![].and(&xt()));
-		assert!(r.is_err());
+		assert_eq!(ApplyResult::decode(&mut &r.unwrap()[..]), Some(Err(ApplyError::CantPay)));
 	}
 
 	#[test]
@@ -47,6 +47,8 @@ use runtime_primitives::traits::{Identity, HasPublicAux};
 #[cfg(any(feature = "std", test))]
 pub use runtime_primitives::BuildExternalities;
 
+pub use runtime_primitives::generic::Era;
+
 /// Concrete runtime type used to parameterize the various modules.
 pub struct Concrete;
 
@@ -62,14 +64,18 @@ impl system::Trait for Concrete {
 	type Digest = generic::Digest<Vec<u8>>;
 	type AccountId = AccountId;
 	type Header = generic::Header<BlockNumber, Hash, Vec<u8>>;
+	type Event = Event;
 }
 
 /// System module for this concrete runtime.
 pub type System = system::Module<Concrete>;
 
 impl consensus::Trait for Concrete {
+	const NOTE_OFFLINE_POSITION: u32 = 1;
 	type PublicAux = <Self as HasPublicAux>::PublicAux;
 	type SessionKey = SessionKey;
+	type OnMisbehavior = Staking;
+	type OnOfflineValidator = Staking;
 }
 
 /// Consensus module for this concrete runtime.
@@ -94,6 +100,8 @@ pub type Session = session::Module<Concrete>;
 impl staking::Trait for Concrete {
 	type Balance = Balance;
 	type DetermineContractAddress = BlakeTwo256;
+	type OnAccountKill = ();
+	type Event = Event;
 }
 
 /// Staking module for this concrete runtime.
@@ -101,18 +109,29 @@ pub type Staking = staking::Module<Concrete>;
 
 impl democracy::Trait for Concrete {
 	type Proposal = PrivCall;
+	type Event = Event;
 }
 
 /// Democracy module for this concrete runtime.
 pub type Democracy = democracy::Module<Concrete>;
 
-impl council::Trait for Concrete {}
+impl council::Trait for Concrete {
+	type Event = Event;
+}
 
 /// Council module for this concrete runtime.
 pub type Council = council::Module<Concrete>;
 /// Council voting module for this concrete runtime.
 pub type CouncilVoting = council::voting::Module<Concrete>;
 
+impl_outer_event! {
+	pub enum Event for Concrete {
+		staking<T> = 0,
+		democracy<T> = 1,
+		council<T> = 2,
+	}
+}
+
 impl_outer_dispatch! {
 	pub enum Call where aux: <Concrete as HasPublicAux>::PublicAux {
 		Consensus = 0,
@@ -142,6 +161,8 @@ pub type Block = generic::Block<BlockNumber, Hash, Vec<u8>, AccountId, Index, Ca
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<AccountId, Index, Call, Signature>;
 /// Extrinsic type as expected by this runtime.
 pub type Extrinsic = generic::Extrinsic<AccountId, Index, Call>;
+/// Checked extrinsic type as expected by this runtime.
+pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Index, Call, Signature>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Concrete, Block, Staking,
 	(((((), Council), Democracy), Staking), Session)>;
@@ -165,6 +186,10 @@ pub mod api {
 		execute_block => |block| super::Executive::execute_block(block),
 		finalise_block => |()| super::Executive::finalise_block(),
 		validator_count => |()| super::Session::validator_count(),
-		validators => |()| super::Session::validators()
+		validators => |()| super::Session::validators(),
+		call_contract => |(origin, dest, value, gas_limit, data): (super::AccountId, super::AccountId, super::Balance, u64, Vec<u8>)|
+			super::Staking::call_contract(&origin, &dest, value, gas_limit, &data).map_err(|e| e.as_bytes().to_vec()),
+		logs => |(addresses, topics): (Vec<super::AccountId>, Vec<super::Hash>)|
+			super::System::log_records_matching(&addresses, &topics)
 	);
 }
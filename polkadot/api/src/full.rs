@@ -22,9 +22,9 @@ use polkadot_executor::Executor as LocalDispatch;
 use substrate_executor::{NativeExecutionDispatch, NativeExecutor};
 use state_machine::{self, OverlayedChanges};
 
-use primitives::{AccountId, BlockId, Hash, Index, SessionKey, Timestamp};
-use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId};
-use runtime::{self, Block, Header, UncheckedExtrinsic, Extrinsic, Call, TimestampCall, ParachainsCall};
+use primitives::{AccountId, Balance, BlockId, BlockNumber, Hash, Index, SessionKey, Timestamp};
+use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId, Message};
+use runtime::{self, Block, Header, UncheckedExtrinsic, Extrinsic, Era, Call, TimestampCall, ParachainsCall, ConsensusCall};
 
 use {CheckedBlockId, BlockBuilder, PolkadotApi, LocalPolkadotApi, ErrorKind, Error, Result};
 
@@ -102,21 +102,27 @@ impl<S: state_machine::Backend> ClientBlockBuilder<S>
 	}
 
 	// executes a extrinsic, inherent or otherwise, without appending to the list.
+	// extrinsics which cannot be applied leave the pending changes untouched.
 	fn apply_extrinsic(&mut self, extrinsic: UncheckedExtrinsic) -> Result<()> {
 		let result = {
 			let mut ext = state_machine::Ext::new(&mut self.changes, &self.state);
+			let xt = extrinsic.clone();
 
 			::substrate_executor::with_native_environment(
 				&mut ext,
-				move || runtime::Executive::apply_extrinsic(extrinsic),
-			).map_err(Into::into)
+				move || runtime::Executive::apply_extrinsic(xt),
+			).map_err(Error::from)
 		};
 
 		match result {
-			Ok(_) => {
+			Ok(Ok(_)) => {
 				self.changes.commit_prospective();
 				Ok(())
 			}
+			Ok(Err(e)) => {
+				self.changes.discard_prospective();
+				Err(ErrorKind::ApplyFailed(extrinsic, e).into())
+			}
 			Err(e) => {
 				self.changes.discard_prospective();
 				Err(e)
@@ -217,7 +223,19 @@ impl<B: LocalBackend> PolkadotApi for Client<B, LocalCallExecutor<B, NativeExecu
 		with_runtime!(self, at, || ::runtime::Parachains::parachain_head(parachain))
 	}
 
-	fn build_block(&self, parent: &CheckedId, timestamp: Timestamp, parachains: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder> {
+	fn ingress_roots(&self, at: &CheckedId, parachain: ParaId) -> Result<Vec<(ParaId, Vec<Hash>)>> {
+		with_runtime!(self, at, || ::runtime::Parachains::ingress_roots(parachain))
+	}
+
+	fn unrouted_egress(&self, at: &CheckedId, from: ParaId, to: ParaId) -> Result<Vec<Vec<Message>>> {
+		with_runtime!(self, at, || ::runtime::Parachains::unrouted_egress((from, to)))
+	}
+
+	fn reward_history(&self, at: &CheckedId, era: BlockNumber) -> Result<Vec<(AccountId, Balance)>> {
+		with_runtime!(self, at, || ::runtime::Staking::reward_history(era))
+	}
+
+	fn build_block(&self, parent: &CheckedId, timestamp: Timestamp, parachains: Vec<CandidateReceipt>, offline_indices: Vec<u32>) -> Result<Self::BlockBuilder> {
 		let parent = parent.block_id();
 		let header = Header {
 			parent_hash: self.block_hash_from_id(parent)?.ok_or(ErrorKind::UnknownBlock(*parent))?,
@@ -227,13 +245,14 @@ impl<B: LocalBackend> PolkadotApi for Client<B, LocalCallExecutor<B, NativeExecu
 			digest: Default::default(),
 		};
 
-		let extrinsics = vec![
+		let mut extrinsics = vec![
 			UncheckedExtrinsic {
 				extrinsic: Extrinsic {
 					signed: Default::default(),
 					index: Default::default(),
 					function: Call::Timestamp(TimestampCall::set(timestamp)),
 				},
+				era: Era::Immortal,
 				signature: Default::default(),
 			},
 			UncheckedExtrinsic {
@@ -242,10 +261,23 @@ impl<B: LocalBackend> PolkadotApi for Client<B, LocalCallExecutor<B, NativeExecu
 					index: Default::default(),
 					function: Call::Parachains(ParachainsCall::set_heads(parachains)),
 				},
+				era: Era::Immortal,
 				signature: Default::default(),
 			}
 		];
 
+		if !offline_indices.is_empty() {
+			extrinsics.push(UncheckedExtrinsic {
+				extrinsic: Extrinsic {
+					signed: Default::default(),
+					index: Default::default(),
+					function: Call::Consensus(ConsensusCall::note_offline(offline_indices)),
+				},
+				era: Era::Immortal,
+				signature: Default::default(),
+			});
+		}
+
 		let mut builder = ClientBlockBuilder {
 			parent: *parent,
 			changes: OverlayedChanges::default(),
@@ -330,18 +362,41 @@ mod tests {
 		let client = client();
 
 		let id = client.check_id(BlockId::Number(0)).unwrap();
-		let block_builder = client.build_block(&id, 1_000_000, Vec::new()).unwrap();
+		let block_builder = client.build_block(&id, 1_000_000, Vec::new(), Vec::new()).unwrap();
 		let block = block_builder.bake();
 
 		assert_eq!(block.header.number, 1);
 		assert!(block.header.extrinsics_root != Default::default());
 	}
 
+	#[test]
+	fn build_block_with_offline_validators() {
+		let client = client();
+
+		let id = client.check_id(BlockId::Number(0)).unwrap();
+		let block_builder = client.build_block(&id, 1_000_000, Vec::new(), vec![1]).unwrap();
+		let block = block_builder.bake();
+
+		assert_eq!(block.extrinsics.len(), 3);
+		assert_eq!(
+			block.extrinsics[2].extrinsic.function,
+			Call::Consensus(ConsensusCall::note_offline(vec![1]))
+		);
+	}
+
 	#[test]
 	fn fails_to_check_id_for_unknown_block() {
 		assert!(client().check_id(BlockId::Number(100)).is_err());
 	}
 
+	#[test]
+	fn no_rewards_paid_out_at_genesis() {
+		let client = client();
+
+		let id = client.check_id(BlockId::Number(0)).unwrap();
+		assert_eq!(client.reward_history(&id, 0).unwrap(), Vec::new());
+	}
+
 	#[test]
 	fn gets_random_seed_with_genesis() {
 		let client = client();
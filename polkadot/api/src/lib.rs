@@ -25,6 +25,7 @@ extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_client as client;
 extern crate substrate_executor as substrate_executor;
 extern crate substrate_runtime_executive;
+extern crate substrate_runtime_primitives as runtime_primitives;
 extern crate substrate_primitives;
 extern crate substrate_state_machine as state_machine;
 
@@ -37,9 +38,10 @@ extern crate substrate_keyring as keyring;
 pub mod full;
 pub mod light;
 
-use primitives::{AccountId, BlockId, Hash, Index, SessionKey, Timestamp};
-use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId};
+use primitives::{AccountId, Balance, BlockId, BlockNumber, Hash, Index, SessionKey, Timestamp};
+use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId, Message};
 use runtime::{Block, UncheckedExtrinsic};
+use runtime_primitives::ApplyError;
 
 error_chain! {
 	errors {
@@ -63,6 +65,11 @@ error_chain! {
 			description("Attempted to push a badly-formed extrinsic to a block."),
 			display("Pushed badly-formed extrinsic to a block: {:?}", xt),
 		}
+		/// Extrinsic could not be applied to the block.
+		ApplyFailed(xt: UncheckedExtrinsic, e: ApplyError) {
+			description("Attempted to push an extrinsic which could not be applied to the block."),
+			display("Extrinsic {:?} could not be applied: {:?}", xt, e),
+		}
 		/// Some other error.
 		// TODO: allow to be specified as associated type of PolkadotApi
 		Other(e: Box<::std::error::Error + Send>) {
@@ -142,12 +149,24 @@ pub trait PolkadotApi {
 	/// Get the chain head of a parachain. If the parachain is active, this will always return `Some`.
 	fn parachain_head(&self, at: &Self::CheckedBlockId, parachain: ParaId) -> Result<Option<Vec<u8>>>;
 
+	/// Get the roots of the egress queues routed to a parachain and not yet processed by it,
+	/// grouped by source parachain in ascending order.
+	fn ingress_roots(&self, at: &Self::CheckedBlockId, parachain: ParaId) -> Result<Vec<(ParaId, Vec<Hash>)>>;
+
+	/// Get the egress queues posted from one parachain to another and not yet processed by
+	/// the destination, oldest first.
+	fn unrouted_egress(&self, at: &Self::CheckedBlockId, from: ParaId, to: ParaId) -> Result<Vec<Vec<Message>>>;
+
+	/// Get the rewards paid out to each validator at the end of an era, as recorded at a block.
+	fn reward_history(&self, at: &Self::CheckedBlockId, era: BlockNumber) -> Result<Vec<(AccountId, Balance)>>;
+
 	/// Evaluate a block. Returns true if the block is good, false if it is known to be bad,
 	/// and an error if we can't evaluate for some reason.
 	fn evaluate_block(&self, at: &Self::CheckedBlockId, block: Block) -> Result<bool>;
 
-	/// Create a block builder on top of the parent block.
-	fn build_block(&self, parent: &Self::CheckedBlockId, timestamp: Timestamp, parachains: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder>;
+	/// Create a block builder on top of the parent block, noting the validators at the given
+	/// indices as offline.
+	fn build_block(&self, parent: &Self::CheckedBlockId, timestamp: Timestamp, parachains: Vec<CandidateReceipt>, offline_indices: Vec<u32>) -> Result<Self::BlockBuilder>;
 }
 
 /// Mark for all Polkadot API implementations, that are making use of state data, stored locally.
@@ -21,8 +21,8 @@ use client::backend::{Backend, RemoteBackend};
 use client::{Client, CallExecutor};
 use codec::Slicable;
 use state_machine;
-use primitives::{AccountId, BlockId, Hash, Index, SessionKey, Timestamp};
-use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId};
+use primitives::{AccountId, Balance, BlockId, BlockNumber, Hash, Index, SessionKey, Timestamp};
+use primitives::parachain::{DutyRoster, CandidateReceipt, Id as ParaId, Message};
 use runtime::{Block, UncheckedExtrinsic};
 use full::CheckedId;
 use {PolkadotApi, RemotePolkadotApi, BlockBuilder, CheckedBlockId, Result, ErrorKind};
@@ -33,6 +33,17 @@ pub struct RemotePolkadotApiWrapper<B: Backend, E: CallExecutor>(pub Arc<Client<
 /// Block builder for light client.
 pub struct LightBlockBuilder;
 
+impl<B: Backend, E: CallExecutor> RemotePolkadotApiWrapper<B, E>
+	where ::client::error::Error: From<<<B as Backend>::State as state_machine::backend::Backend>::Error>
+{
+	fn call<I: Slicable, R: Slicable>(&self, at: &CheckedId, method: &'static str, input: &I) -> Result<R> {
+		self.0.executor().call(at.block_id(), method, &input.encode())
+			.and_then(|r| R::decode(&mut &r.return_data[..])
+				.ok_or_else(|| format!("error decoding {} result", method).into()))
+			.map_err(Into::into)
+	}
+}
+
 impl<B: Backend, E: CallExecutor> PolkadotApi for RemotePolkadotApiWrapper<B, E>
 	where ::client::error::Error: From<<<B as Backend>::State as state_machine::backend::Backend>::Error>
 {
@@ -44,38 +55,35 @@ impl<B: Backend, E: CallExecutor> PolkadotApi for RemotePolkadotApiWrapper<B, E>
 	}
 
 	fn session_keys(&self, at: &CheckedId) -> Result<Vec<SessionKey>> {
-		self.0.executor().call(at.block_id(), "authorities", &[])
-			.and_then(|r| Vec::<SessionKey>::decode(&mut &r.return_data[..])
-				.ok_or("error decoding session keys".into()))
-			.map_err(Into::into)
+		self.call(at, "authorities", &())
 	}
 
-	fn validators(&self, _at: &CheckedId) -> Result<Vec<AccountId>> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn validators(&self, at: &CheckedId) -> Result<Vec<AccountId>> {
+		self.call(at, "validators", &())
 	}
 
-	fn random_seed(&self, _at: &Self::CheckedBlockId) -> Result<Hash> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn random_seed(&self, at: &Self::CheckedBlockId) -> Result<Hash> {
+		self.call(at, "random_seed", &())
 	}
 
-	fn duty_roster(&self, _at: &CheckedId) -> Result<DutyRoster> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn duty_roster(&self, at: &CheckedId) -> Result<DutyRoster> {
+		self.call(at, "duty_roster", &())
 	}
 
-	fn timestamp(&self, _at: &CheckedId) -> Result<Timestamp> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn timestamp(&self, at: &CheckedId) -> Result<Timestamp> {
+		self.call(at, "timestamp", &())
 	}
 
 	fn evaluate_block(&self, _at: &CheckedId, _block: Block) -> Result<bool> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
-	fn index(&self, _at: &CheckedId, _account: AccountId) -> Result<Index> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn index(&self, at: &CheckedId, account: AccountId) -> Result<Index> {
+		self.call(at, "account_index", &account)
 	}
 
-	fn active_parachains(&self, _at: &Self::CheckedBlockId) -> Result<Vec<ParaId>> {
-		Err(ErrorKind::UnknownRuntime.into())
+	fn active_parachains(&self, at: &Self::CheckedBlockId) -> Result<Vec<ParaId>> {
+		self.call(at, "active_parachains", &())
 	}
 
 	fn parachain_code(&self, _at: &Self::CheckedBlockId, _parachain: ParaId) -> Result<Option<Vec<u8>>> {
@@ -86,7 +94,19 @@ impl<B: Backend, E: CallExecutor> PolkadotApi for RemotePolkadotApiWrapper<B, E>
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 
-	fn build_block(&self, _parent: &CheckedId, _timestamp: Timestamp, _parachains: Vec<CandidateReceipt>) -> Result<Self::BlockBuilder> {
+	fn ingress_roots(&self, at: &Self::CheckedBlockId, parachain: ParaId) -> Result<Vec<(ParaId, Vec<Hash>)>> {
+		self.call(at, "ingress_roots", &parachain)
+	}
+
+	fn unrouted_egress(&self, at: &Self::CheckedBlockId, from: ParaId, to: ParaId) -> Result<Vec<Vec<Message>>> {
+		self.call(at, "unrouted_egress", &(from, to))
+	}
+
+	fn reward_history(&self, at: &CheckedId, era: BlockNumber) -> Result<Vec<(AccountId, Balance)>> {
+		self.call(at, "reward_history", &era)
+	}
+
+	fn build_block(&self, _parent: &CheckedId, _timestamp: Timestamp, _parachains: Vec<CandidateReceipt>, _offline_indices: Vec<u32>) -> Result<Self::BlockBuilder> {
 		Err(ErrorKind::UnknownRuntime.into())
 	}
 }
@@ -39,7 +39,9 @@ extern crate substrate_primitives as primitives;
 extern crate substrate_network as network;
 extern crate substrate_rpc;
 extern crate substrate_rpc_servers as rpc;
+extern crate jsonrpc_core;
 extern crate substrate_runtime_support as runtime_support;
+extern crate polkadot_api;
 extern crate polkadot_primitives;
 extern crate polkadot_executor;
 extern crate polkadot_runtime;
@@ -54,9 +56,12 @@ extern crate clap;
 extern crate error_chain;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate jsonrpc_macros;
 
 pub mod error;
 mod informant;
+mod staking;
 
 use std::io;
 use std::net::SocketAddr;
@@ -131,6 +136,15 @@ pub fn run<I, T>(args: I) -> error::Result<()> where
 
 	config.database_path = db_path(&base_path).to_string_lossy().into();
 
+	config.pruning = match matches.value_of("pruning") {
+		Some("archive") => service::PruningMode::ArchiveAll,
+		None => service::PruningMode::default(),
+		Some(s) => match s.parse() {
+			Ok(blocks) => service::PruningMode::Constrained(blocks),
+			Err(_) => return Err(format!("Invalid pruning mode specified: {}", s).into()),
+		},
+	};
+
 	let mut role = service::Role::FULL;
 	if matches.is_present("collator") {
 		info!("Starting collator.");
@@ -210,12 +224,14 @@ fn run_until_exit<B, E>(mut core: reactor::Core, service: service::Service<B, E>
 
 		let handler = || {
 			let chain = rpc::apis::chain::Chain::new(service.client(), core.remote());
-			rpc::rpc_handler(
+			let mut io = rpc::rpc_handler(
 				service.client(),
 				chain,
 				service.transaction_pool(),
 				Configuration(config.clone()),
-			)
+			);
+			io.extend_with(staking::StakingApi::to_delegate(staking::Staking::new(service.client())));
+			io
 		};
 		(
 			start_server(http_address, |address| rpc::start_http(address, handler())),
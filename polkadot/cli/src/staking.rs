@@ -0,0 +1,66 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Polkadot staking RPC API.
+
+use std::sync::Arc;
+use client::{self, Client};
+use codec::Slicable;
+use jsonrpc_core::Error;
+use polkadot_api::PolkadotApi;
+use polkadot_api::light::RemotePolkadotApiWrapper;
+use polkadot_primitives::{BlockId, BlockNumber};
+use primitives::{H256, U256};
+use state_machine;
+
+build_rpc_trait! {
+	/// Polkadot staking API
+	pub trait StakingApi {
+		/// Returns the reward paid out to each validator at the end of the given era, as of the
+		/// best block.
+		#[rpc(name = "staking_rewardHistory")]
+		fn reward_history(&self, BlockNumber) -> Result<Vec<(H256, U256)>, Error>;
+	}
+}
+
+/// Staking RPC API backed by a client.
+pub struct Staking<B: client::backend::Backend, E: client::CallExecutor> {
+	api: RemotePolkadotApiWrapper<B, E>,
+}
+
+impl<B: client::backend::Backend, E: client::CallExecutor> Staking<B, E> {
+	/// Create a new staking API for the given client.
+	pub fn new(client: Arc<Client<B, E>>) -> Self {
+		Staking { api: RemotePolkadotApiWrapper(client) }
+	}
+}
+
+impl<B, E> StakingApi for Staking<B, E> where
+	B: client::backend::Backend + Send + Sync + 'static,
+	E: client::CallExecutor + Send + Sync + 'static,
+	client::error::Error: From<<<B as client::backend::Backend>::State as state_machine::backend::Backend>::Error>,
+{
+	fn reward_history(&self, era: BlockNumber) -> Result<Vec<(H256, U256)>, Error> {
+		let best = self.api.0.info().map_err(|_| Error::internal_error())?.chain.best_hash;
+		let rewards = self.api.check_id(BlockId::Hash(best))
+			.and_then(|at| self.api.reward_history(&at, era))
+			.map_err(|_| Error::internal_error())?;
+
+		Ok(rewards.into_iter()
+			.map(|(validator, reward)| (validator.into(), U256::from_little_endian(&reward.encode())))
+			.collect())
+	}
+}
@@ -42,27 +42,50 @@
 //! chain history.
 //!
 //! This crate defines traits which provide context necessary for collation logic
-//! to be performed, as the collation logic itself.
+//! to be performed, as the collation logic itself, and a collator node which runs
+//! it on top of a relay chain client with `run_collator`.
 
+extern crate ed25519;
 extern crate futures;
+extern crate tokio_core;
+extern crate substrate_client as client;
 extern crate substrate_codec as codec;
+extern crate substrate_network;
 extern crate substrate_primitives as primitives;
+extern crate substrate_state_machine as state_machine;
+extern crate polkadot_api;
+extern crate polkadot_consensus;
+extern crate polkadot_parachain;
 extern crate polkadot_primitives;
+extern crate polkadot_service as service;
+
+#[macro_use]
+extern crate log;
 
 use std::collections::{BTreeSet, BTreeMap};
+use std::sync::Arc;
 
+use client::BlockchainEvents;
+use codec::Slicable;
 use futures::{stream, Stream, Future, IntoFuture};
-use polkadot_primitives::parachain::{self, ConsolidatedIngress, Message, Id as ParaId};
+use polkadot_api::PolkadotApi;
+use polkadot_parachain::OutgoingMessage;
+use polkadot_primitives::parachain::{self, ConsolidatedIngress, Message, Id as ParaId, CandidateReceipt};
+use primitives::block::Id as BlockId;
+use substrate_network::ConsensusService;
+use tokio_core::reactor;
 
 /// Parachain context needed for collation.
 ///
 /// This can be implemented through an externally attached service or a stub.
 pub trait ParachainContext {
 	/// Produce a candidate, given the latest ingress queue information.
+	/// Returns the block data and the head data of the produced parachain block,
+	/// along with the messages it posts to other parachains.
 	fn produce_candidate<I: IntoIterator<Item=(ParaId, Message)>>(
 		&self,
 		ingress: I,
-	) -> (parachain::BlockData, polkadot_primitives::AccountId, polkadot_primitives::Signature);
+	) -> (parachain::BlockData, parachain::HeadData, Vec<OutgoingMessage>);
 }
 
 /// Relay chain context needed to collate.
@@ -119,9 +142,17 @@ pub fn collate_ingress<'a, R>(relay_context: R)
 		.map(ConsolidatedIngress))
 }
 
-/// Produce a candidate for the parachain.
-pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
-	-> Box<Future<Item=parachain::Candidate, Error=R::Error> + 'a>
+/// A candidate signed by the collator, along with its receipt.
+pub struct Collation {
+	/// The candidate.
+	pub candidate: parachain::Candidate,
+	/// The candidate receipt.
+	pub receipt: CandidateReceipt,
+}
+
+/// Produce a candidate for the parachain, signed with the collator key.
+pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P, key: Arc<ed25519::Pair>)
+	-> Box<Future<Item=Collation, Error=R::Error> + 'a>
 	where
 		R: RelayChainContext,
 	    R::Error: 'a,
@@ -129,19 +160,190 @@ pub fn collate<'a, R, P>(local_id: ParaId, relay_context: R, para_context: P)
 		P: ParachainContext + 'a,
 {
 	Box::new(collate_ingress(relay_context).map(move |ingress| {
-		let (block_data, _, signature) = para_context.produce_candidate(
+		let (block_data, head_data, egress) = para_context.produce_candidate(
 			ingress.0.iter().flat_map(|&(id, ref msgs)| msgs.iter().cloned().map(move |msg| (id, msg)))
 		);
 
-		parachain::Candidate {
+		let signature = key.sign(&block_data.0);
+		let receipt = CandidateReceipt {
 			parachain_index: local_id,
-			collator_signature: signature,
-			block: block_data,
-			unprocessed_ingress: ingress,
+			collator: key.public().0,
+			head_data,
+			balance_uploads: Vec::new(),
+			egress_queue_roots: polkadot_consensus::egress_roots(&egress),
+			egress_queues: polkadot_consensus::egress_queues(&egress),
+			processed_ingress: polkadot_consensus::processed_ingress(&ingress),
+			fees: 0,
+		};
+
+		Collation {
+			candidate: parachain::Candidate {
+				parachain_index: local_id,
+				collator_signature: signature.into(),
+				block: block_data,
+				unprocessed_ingress: ingress,
+			},
+			receipt,
 		}
 	}))
 }
 
+/// Relay chain context backed by the relay chain state at a given block.
+struct ApiContext<A: PolkadotApi> {
+	api: Arc<A>,
+	at: A::CheckedBlockId,
+	local_id: ParaId,
+}
+
+impl<A: PolkadotApi> RelayChainContext for ApiContext<A> {
+	type Error = polkadot_api::Error;
+	type FutureEgress = Result<Vec<Vec<Message>>, polkadot_api::Error>;
+
+	fn routing_parachains(&self) -> BTreeSet<ParaId> {
+		match self.api.active_parachains(&self.at) {
+			Ok(active) => active.into_iter().filter(|id| *id != self.local_id).collect(),
+			Err(e) => {
+				warn!("Error reading active parachains: {:?}", e);
+				BTreeSet::new()
+			}
+		}
+	}
+
+	fn unrouted_egress(&self, id: ParaId) -> Self::FutureEgress {
+		self.api.unrouted_egress(&self.at, id, self.local_id)
+	}
+}
+
+// The session keys of the validators assigned to the parachain at the given block.
+fn assigned_validators<A: PolkadotApi>(api: &A, at: &A::CheckedBlockId, para_id: ParaId)
+	-> Result<Vec<primitives::AuthorityId>, polkadot_api::Error>
+{
+	let roster = api.duty_roster(at)?;
+	let session_keys = api.session_keys(at)?;
+	Ok(session_keys.into_iter()
+		.zip(roster.validator_duty)
+		.filter(|&(_, ref duty)| *duty == parachain::Chain::Parachain(para_id))
+		.map(|(key, _)| key)
+		.collect())
+}
+
+/// Run a collator node for the given parachain until `exit` resolves.
+///
+/// This starts a relay chain client (a light one if `config.roles` includes the light role)
+/// and on every new best relay chain block produces a candidate with `parachain_context`,
+/// signs it with `key` and sends it to the connected validators assigned to the parachain.
+pub fn run_collator<P, E>(
+	parachain_context: P,
+	para_id: ParaId,
+	exit: E,
+	key: Arc<ed25519::Pair>,
+	mut config: service::Configuration,
+) -> Result<(), service::Error>
+	where
+		P: ParachainContext + Clone + 'static,
+		E: IntoFuture<Item=(), Error=()>,
+{
+	config.roles = config.roles | service::Role::COLLATOR;
+	config.parachain_id = Some(para_id.into_inner() as u64);
+
+	let core = reactor::Core::new().expect("tokio::Core could not be created");
+	if config.roles.contains(service::Role::LIGHT) {
+		let service = service::new_light(config)?;
+		let api = Arc::new(polkadot_api::light::RemotePolkadotApiWrapper(service.client()));
+		run_collator_node(core, &service, api, parachain_context, para_id, exit, key)
+	} else {
+		let service = service::new_full(config)?;
+		let api = service.client();
+		run_collator_node(core, &service, api, parachain_context, para_id, exit, key)
+	}
+}
+
+fn run_collator_node<B, E, A, P, X>(
+	mut core: reactor::Core,
+	service: &service::Service<B, E>,
+	api: Arc<A>,
+	parachain_context: P,
+	para_id: ParaId,
+	exit: X,
+	key: Arc<ed25519::Pair>,
+) -> Result<(), service::Error>
+	where
+		B: client::backend::Backend + Send + Sync + 'static,
+		E: client::CallExecutor + Send + Sync + 'static,
+		client::error::Error: From<<<B as client::backend::Backend>::State as state_machine::backend::Backend>::Error>,
+		A: PolkadotApi + 'static,
+		P: ParachainContext + Clone + 'static,
+		X: IntoFuture<Item=(), Error=()>,
+{
+	let network = service.network();
+	let handle = core.handle();
+
+	let work = service.client().import_notification_stream().for_each(move |notification| {
+		if !notification.is_new_best {
+			return Ok(());
+		}
+
+		let relay_parent = notification.hash;
+		let at = match api.check_id(BlockId::Hash(relay_parent)) {
+			Ok(at) => at,
+			Err(e) => {
+				warn!("Error checking relay chain block {}: {:?}", relay_parent, e);
+				return Ok(());
+			}
+		};
+
+		match api.active_parachains(&at) {
+			Ok(ref active) if active.contains(&para_id) => {},
+			Ok(_) => {
+				debug!("Parachain {:?} is not active at relay chain block {}", para_id, relay_parent);
+				return Ok(());
+			}
+			Err(e) => {
+				warn!("Error reading active parachains at {}: {:?}", relay_parent, e);
+				return Ok(());
+			}
+		}
+
+		let validators = match assigned_validators(&*api, &at, para_id) {
+			Ok(validators) => validators,
+			Err(e) => {
+				warn!("Error reading duty roster at {}: {:?}", relay_parent, e);
+				return Ok(());
+			}
+		};
+
+		let context = ApiContext {
+			api: api.clone(),
+			at,
+			local_id: para_id,
+		};
+
+		let network = network.clone();
+		let collation_work = collate(para_id, context, parachain_context.clone(), key.clone())
+			.map(move |collation| {
+				info!("Produced candidate for parachain {:?} on relay chain block {}", para_id, relay_parent);
+				network.send_collation(substrate_network::Collation {
+					relay_parent,
+					parachain_id: para_id.into_inner() as u64,
+					receipt: collation.receipt.encode(),
+					signature: collation.candidate.collator_signature.into(),
+					block_data: collation.candidate.block.0,
+					ingress: collation.candidate.unprocessed_ingress.encode(),
+				}, validators);
+			})
+			.map_err(|e| warn!("Error collating: {:?}", e));
+
+		handle.spawn(collation_work);
+		Ok(())
+	});
+
+	let exit = exit.into_future();
+	match core.run(work.map_err(|_| ()).select(exit)) {
+		Ok(_) => Ok(()),
+		Err(_) => Err("Collator stopped unexpectedly".into()),
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -149,7 +351,7 @@ mod tests {
 	use std::collections::{HashMap, BTreeSet};
 
 	use futures::Future;
-	use polkadot_primitives::parachain::{Message, Id as ParaId};
+	use polkadot_primitives::parachain::{Message, Id as ParaId, BlockData, HeadData};
 
 	pub struct DummyRelayChainCtx {
 		egresses: HashMap<ParaId, Vec<Vec<Message>>>,
@@ -169,6 +371,20 @@ mod tests {
 		}
 	}
 
+	#[derive(Clone)]
+	struct DummyParachainCtx;
+
+	impl ParachainContext for DummyParachainCtx {
+		fn produce_candidate<I: IntoIterator<Item=(ParaId, Message)>>(
+			&self,
+			ingress: I,
+		) -> (BlockData, HeadData, Vec<OutgoingMessage>) {
+			let data = ingress.into_iter().flat_map(|(_, msg)| msg.0).collect::<Vec<_>>();
+			let egress = vec![OutgoingMessage { target: 2, data: data.clone() }];
+			(BlockData(data), HeadData(vec![42]), egress)
+		}
+	}
+
     #[test]
 	fn collates_ingress() {
 		let route_from = |x: &[ParaId]| {
@@ -214,4 +430,29 @@ mod tests {
 			]
 		))
 	}
+
+	#[test]
+	fn collation_is_signed_by_collator() {
+		let key = Arc::new(ed25519::Pair::from_seed(b"12345678901234567890123456789012"));
+		let dummy_ctx = DummyRelayChainCtx {
+			currently_routing: vec![2.into()].into_iter().collect(),
+			egresses: vec![
+				(2.into(), vec![vec![Message(vec![1, 2]), Message(vec![3])]]),
+			].into_iter().collect(),
+		};
+
+		let collation = collate(5.into(), dummy_ctx, DummyParachainCtx, key.clone()).wait().unwrap();
+
+		assert_eq!(collation.candidate.block, BlockData(vec![1, 2, 3]));
+		assert_eq!(collation.receipt.parachain_index, 5.into());
+		assert_eq!(collation.receipt.collator, key.public().0);
+		assert_eq!(collation.receipt.head_data, HeadData(vec![42]));
+		assert_eq!(
+			collation.receipt.egress_queue_roots,
+			polkadot_consensus::egress_roots(&[OutgoingMessage { target: 2, data: vec![1, 2, 3] }]),
+		);
+
+		let signature: ed25519::Signature = collation.candidate.collator_signature.into();
+		assert!(ed25519::verify_strong(&signature, &[1, 2, 3], key.public()));
+	}
 }
@@ -19,11 +19,13 @@
 //! This module contains type definitions, a trait for a batch of collators, and a trait for
 //! attempting to fetch a collation repeatedly until a valid one is obtained.
 
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use polkadot_api::PolkadotApi;
 use polkadot_primitives::{Hash, AccountId};
-use polkadot_primitives::parachain::{Id as ParaId, Chain, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{Id as ParaId, Chain, BlockData, Extrinsic, CandidateReceipt, ConsolidatedIngress, Message};
+use parachain::OutgoingMessage;
 
 use futures::prelude::*;
 
@@ -33,6 +35,8 @@ pub struct Collation {
 	pub block_data: BlockData,
 	/// The candidate receipt itself.
 	pub receipt: CandidateReceipt,
+	/// The ingress queue the candidate was built upon.
+	pub ingress: ConsolidatedIngress,
 }
 
 /// Encapsulates connections to collators and allows collation on any parachain.
@@ -64,18 +68,21 @@ pub struct CollationFetch<C: Collators, P: PolkadotApi> {
 }
 
 impl<C: Collators, P: PolkadotApi> CollationFetch<C, P> {
-	/// Create a new collation fetcher for the given chain.
+	/// Create a new collation fetcher for the given chain. Collation starts immediately.
 	pub fn new(parachain: Chain, relay_parent: P::CheckedBlockId, relay_parent_hash: Hash, collators: C, client: Arc<P>) -> Self {
+		let parachain = match parachain {
+			Chain::Parachain(id) => Some(id),
+			Chain::Relay => None,
+		};
+		let live_fetch = parachain.map(|id| collators.collate(id, relay_parent_hash).into_future());
+
 		CollationFetch {
 			relay_parent_hash,
 			relay_parent,
 			collators,
 			client,
-			parachain: match parachain {
-				Chain::Parachain(id) => Some(id),
-				Chain::Relay => None,
-			},
-			live_fetch: None,
+			parachain,
+			live_fetch,
 		}
 	}
 }
@@ -137,6 +144,22 @@ error_chain! {
 			description("Parachain validation produced wrong head data."),
 			display("Parachain validation produced wrong head data (expected: {:?}, got {:?}", expected, got),
 		}
+		EgressRootMismatch(expected: Vec<(ParaId, Hash)>, got: Vec<(ParaId, Hash)>) {
+			description("Parachain validation produced egress which does not match the receipt."),
+			display("Parachain validation produced egress which does not match the receipt (expected: {:?}, got {:?}", expected, got),
+		}
+		EgressMismatch {
+			description("Parachain validation produced egress messages which do not match the receipt."),
+			display("Parachain validation produced egress messages which do not match the receipt."),
+		}
+		IngressRootMismatch(expected: Vec<(ParaId, Vec<Hash>)>, got: Vec<(ParaId, Vec<Hash>)>) {
+			description("Collated ingress does not match the egress routed on the relay chain."),
+			display("Collated ingress does not match the egress routed on the relay chain (expected: {:?}, got {:?}", expected, got),
+		}
+		ProcessedIngressMismatch(expected: Vec<(ParaId, Hash)>, got: Vec<(ParaId, Hash)>) {
+			description("Receipt does not commit to the collated ingress."),
+			display("Receipt does not commit to the collated ingress (expected: {:?}, got {:?}", expected, got),
+		}
 	}
 
 	links {
@@ -144,9 +167,59 @@ error_chain! {
 	}
 }
 
+/// Compute the egress queues for the given outgoing messages.
+///
+/// Messages are grouped by destination, preserving the order in which they were sent.
+/// The result is sorted by destination, matching the order required of
+/// `CandidateReceipt::egress_queues`.
+pub fn egress_queues(egress: &[OutgoingMessage]) -> Vec<(ParaId, Vec<Message>)> {
+	let mut queues: BTreeMap<ParaId, Vec<Message>> = BTreeMap::new();
+	for message in egress {
+		queues.entry(message.target.into()).or_insert_with(Vec::new).push(Message(message.data.clone()));
+	}
+
+	queues.into_iter().collect()
+}
+
+/// Compute the egress queue roots for the given outgoing messages.
+///
+/// Each queue built by `egress_queues` is committed to with an ordered trie root. The result
+/// is sorted by destination, matching the order required of `CandidateReceipt::egress_queue_roots`.
+pub fn egress_roots(egress: &[OutgoingMessage]) -> Vec<(ParaId, Hash)> {
+	egress_queues(egress).into_iter()
+		.map(|(target, messages)| (target, queue_root(&messages)))
+		.collect()
+}
+
+fn queue_root(messages: &[Message]) -> Hash {
+	let messages: Vec<&[u8]> = messages.iter().map(|message| &message.0[..]).collect();
+	runtime_io::enumerated_trie_root(&messages).into()
+}
+
+/// Compute the roots of the egress queues making up the given ingress, grouped by source
+/// parachain in ascending order and preserving the order of the queues of each source.
+///
+/// This matches the layout of the ingress roots kept by the relay chain.
+pub fn ingress_roots(ingress: &ConsolidatedIngress) -> Vec<(ParaId, Vec<Hash>)> {
+	let mut roots: BTreeMap<ParaId, Vec<Hash>> = BTreeMap::new();
+	for &(source, ref messages) in &ingress.0 {
+		roots.entry(source).or_insert_with(Vec::new).push(queue_root(messages));
+	}
+
+	roots.into_iter().collect()
+}
+
+/// Compute the roots of the newest egress queue of each source parachain in the given
+/// ingress, as committed to by `CandidateReceipt::processed_ingress`.
+pub fn processed_ingress(ingress: &ConsolidatedIngress) -> Vec<(ParaId, Hash)> {
+	ingress_roots(ingress).into_iter()
+		.filter_map(|(source, roots)| roots.last().map(|root| (source, *root)))
+		.collect()
+}
+
 /// Check whether a given collation is valid. Returns `Ok`  on success, error otherwise.
 pub fn validate_collation<P: PolkadotApi>(client: &P, relay_parent: &P::CheckedBlockId, collation: &Collation) -> Result<(), Error> {
-	use parachain::{self, ValidationParams};
+	use parachain::{self, ValidationParams, IncomingMessage};
 
 	let para_id = collation.receipt.parachain_index;
 	let validation_code = client.parachain_code(relay_parent, para_id)?
@@ -155,22 +228,107 @@ pub fn validate_collation<P: PolkadotApi>(client: &P, relay_parent: &P::CheckedB
 	let chain_head = client.parachain_head(relay_parent, para_id)?
 		.ok_or_else(|| ErrorKind::InactiveParachain(para_id))?;
 
+	let routed = client.ingress_roots(relay_parent, para_id)?;
+	let collated = ingress_roots(&collation.ingress);
+	if routed != collated {
+		return Err(ErrorKind::IngressRootMismatch(routed, collated).into());
+	}
+
+	let processed = processed_ingress(&collation.ingress);
+	if processed != collation.receipt.processed_ingress {
+		return Err(ErrorKind::ProcessedIngressMismatch(processed, collation.receipt.processed_ingress.clone()).into());
+	}
+
+	let ingress = collation.ingress.0.iter()
+		.flat_map(|&(source, ref messages)| messages.iter().map(move |message| IncomingMessage {
+			source: source.into_inner(),
+			data: message.0.clone(),
+		}))
+		.collect();
+
 	let params = ValidationParams {
 		parent_head: chain_head,
 		block_data: collation.block_data.0.clone(),
+		ingress,
 	};
 
 	match parachain::wasm::validate_candidate(&validation_code, params) {
 		Ok(result) => {
-			if result.head_data == collation.receipt.head_data.0 {
-				Ok(())
-			} else {
-				Err(ErrorKind::WrongHeadData(
+			if result.head_data != collation.receipt.head_data.0 {
+				return Err(ErrorKind::WrongHeadData(
 					collation.receipt.head_data.0.clone(),
 					result.head_data
-				).into())
+				).into());
+			}
+
+			let roots = egress_roots(&result.egress);
+			if roots != collation.receipt.egress_queue_roots {
+				return Err(ErrorKind::EgressRootMismatch(
+					collation.receipt.egress_queue_roots.clone(),
+					roots,
+				).into());
 			}
+
+			if egress_queues(&result.egress) != collation.receipt.egress_queues {
+				return Err(ErrorKind::EgressMismatch.into());
+			}
+
+			Ok(())
 		}
 		Err(_) => Err(ErrorKind::ValidationFailure.into())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn message(target: u32, data: &[u8]) -> OutgoingMessage {
+		OutgoingMessage { target, data: data.to_vec() }
+	}
+
+	#[test]
+	fn egress_roots_are_sorted_by_destination() {
+		let roots = egress_roots(&[
+			message(5, b"hello"),
+			message(2, b"world"),
+			message(5, b"again"),
+		]);
+
+		assert_eq!(roots.len(), 2);
+		assert_eq!(roots[0].0, 2.into());
+		assert_eq!(roots[1].0, 5.into());
+		assert_eq!(roots[1].1, runtime_io::enumerated_trie_root(&[&b"hello"[..], &b"again"[..]]).into());
+	}
+
+	#[test]
+	fn egress_roots_commit_to_message_order() {
+		let a = egress_roots(&[message(1, b"a"), message(1, b"b")]);
+		let b = egress_roots(&[message(1, b"b"), message(1, b"a")]);
+
+		assert!(a != b);
+		assert!(egress_roots(&[]).is_empty());
+	}
+
+	#[test]
+	fn ingress_roots_match_routed_egress_roots() {
+		use polkadot_primitives::parachain::Message;
+
+		// queues from parachain 2 to 7, posted in two blocks, and one from 1 to 7.
+		let first = egress_roots(&[message(7, b"a"), message(7, b"b")]);
+		let second = egress_roots(&[message(7, b"c")]);
+		let other = egress_roots(&[message(7, b"d")]);
+
+		let ingress = ConsolidatedIngress(vec![
+			(2.into(), vec![Message(b"a".to_vec()), Message(b"b".to_vec())]),
+			(1.into(), vec![Message(b"d".to_vec())]),
+			(2.into(), vec![Message(b"c".to_vec())]),
+		]);
+
+		assert_eq!(ingress_roots(&ingress), vec![
+			(1.into(), vec![other[0].1]),
+			(2.into(), vec![first[0].1, second[0].1]),
+		]);
+		assert_eq!(processed_ingress(&ingress), vec![(1.into(), other[0].1), (2.into(), second[0].1)]);
+	}
+}
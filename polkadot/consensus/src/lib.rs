@@ -32,7 +32,6 @@
 extern crate ed25519;
 extern crate parking_lot;
 extern crate polkadot_api;
-extern crate polkadot_collator as collator;
 extern crate polkadot_statement_table as table;
 extern crate polkadot_parachain as parachain;
 extern crate polkadot_primitives;
@@ -42,6 +41,7 @@ extern crate polkadot_runtime;
 extern crate substrate_bft as bft;
 extern crate substrate_codec as codec;
 extern crate substrate_primitives as primitives;
+extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_runtime_support as runtime_support;
 extern crate substrate_network;
 
@@ -66,11 +66,12 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use codec::Slicable;
+use parking_lot::{Mutex, RwLock};
 use table::generic::Statement as GenericStatement;
 use runtime_support::Hashable;
 use polkadot_api::{PolkadotApi, BlockBuilder};
 use polkadot_primitives::{Hash, Timestamp};
-use polkadot_primitives::parachain::{Id as ParaId, Chain, DutyRoster, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{Id as ParaId, Chain, DutyRoster, BlockData, Extrinsic, CandidateReceipt, ConsolidatedIngress};
 use primitives::block::{Block as SubstrateBlock, Header as SubstrateHeader, HeaderHash, Id as BlockId, Number as BlockNumber};
 use primitives::AuthorityId;
 use transaction_pool::{Ready, TransactionPool};
@@ -81,8 +82,9 @@ use futures::future::{self, Shared};
 use collation::CollationFetch;
 use dynamic_inclusion::DynamicInclusion;
 
-pub use self::collation::{Collators, Collation};
+pub use self::collation::{Collators, Collation, egress_queues, egress_roots, ingress_roots, processed_ingress};
 pub use self::error::{ErrorKind, Error};
+pub use self::offline_tracker::OfflineTracker;
 pub use self::shared_table::{SharedTable, StatementSource, StatementProducer, ProducedStatements};
 pub use service::Service;
 
@@ -90,27 +92,32 @@ mod collation;
 mod dynamic_inclusion;
 mod evaluation;
 mod error;
+mod offline_tracker;
 mod service;
 mod shared_table;
 
 // block size limit.
 const MAX_TRANSACTIONS_SIZE: usize = 4 * 1024 * 1024;
 
+// number of blocks after their parent for which misbehavior reports remain valid.
+const MISBEHAVIOR_REPORT_PERIOD: u64 = 64;
+
 /// A handle to a statement table router.
 ///
 /// This is expected to be a lightweight, shared type like an `Arc`.
 pub trait TableRouter: Clone {
 	/// Errors when fetching data from the network.
 	type Error;
-	/// Future that resolves when candidate data is fetched.
-	type FetchCandidate: IntoFuture<Item=BlockData,Error=Self::Error>;
+	/// Future that resolves when candidate data and the ingress it was built upon are fetched.
+	type FetchCandidate: IntoFuture<Item=(BlockData, ConsolidatedIngress),Error=Self::Error>;
 	/// Future that resolves when extrinsic candidate data is fetched.
 	type FetchExtrinsic: IntoFuture<Item=Extrinsic,Error=Self::Error>;
 
-	/// Note local candidate data, making it available on the network to other validators.
-	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, extrinsic: Extrinsic);
+	/// Note local candidate data and the ingress it was built upon, making them available on
+	/// the network to other validators.
+	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, ingress: ConsolidatedIngress, extrinsic: Extrinsic);
 
-	/// Fetch block data for a specific candidate.
+	/// Fetch block data and ingress for a specific candidate.
 	fn fetch_block_data(&self, candidate: &CandidateReceipt) -> Self::FetchCandidate;
 
 	/// Fetch extrinsic data for a specific candidate.
@@ -220,6 +227,9 @@ fn timer_error(e: &::std::io::Error) -> Error {
 	ErrorKind::Timer(format!("{}", e)).into()
 }
 
+/// An offline validator tracker shared between proposers.
+pub type SharedOfflineTracker = Arc<RwLock<OfflineTracker>>;
+
 /// Polkadot proposer factory.
 pub struct ProposerFactory<C, N, P> {
 	/// The client instance.
@@ -234,6 +244,10 @@ pub struct ProposerFactory<C, N, P> {
 	pub handle: Handle,
 	/// The duration after which parachain-empty blocks will be allowed.
 	pub parachain_empty_duration: Duration,
+	/// Tracker of validators which are offline, persisting across blocks.
+	pub offline: SharedOfflineTracker,
+	/// Hash of the genesis block, mixed into the signature of locally issued extrinsics.
+	pub genesis_hash: HeaderHash,
 }
 
 impl<C, N, P> bft::ProposerFactory for ProposerFactory<C, N, P>
@@ -264,6 +278,8 @@ impl<C, N, P> bft::ProposerFactory for ProposerFactory<C, N, P>
 
 		let active_parachains = self.client.active_parachains(&checked_id)?;
 
+		self.offline.write().note_new_block(authorities);
+
 		let n_parachains = active_parachains.len();
 		let table = Arc::new(SharedTable::new(group_info, sign_with.clone(), parent_hash));
 		let router = self.network.table_router(table.clone());
@@ -279,15 +295,26 @@ impl<C, N, P> bft::ProposerFactory for ProposerFactory<C, N, P>
 		debug!(target: "bft", "Initialising consensus proposer. Refusing to evaluate for {:?} from now.",
 			DELAY_UNTIL);
 
-		// TODO [PoC-2]: kick off collation process.
+		// kick off the collation process, so collations may arrive while the delay elapses.
+		let collation = CollationFetch::new(
+			local_duty.validation,
+			checked_id.clone(),
+			parent_hash,
+			self.collators.clone(),
+			self.client.clone(),
+		);
+
 		Ok(Proposer {
 			client: self.client.clone(),
+			collation: Mutex::new(Some(collation)),
 			collators: self.collators.clone(),
 			delay: timeout.shared(),
 			handle: self.handle.clone(),
 			dynamic_inclusion,
+			genesis_hash: self.genesis_hash,
 			local_duty,
 			local_key: sign_with,
+			offline: self.offline.clone(),
 			parent_hash,
 			parent_id: checked_id,
 			parent_number: parent_header.number,
@@ -295,6 +322,7 @@ impl<C, N, P> bft::ProposerFactory for ProposerFactory<C, N, P>
 			router,
 			table,
 			transaction_pool: self.transaction_pool.clone(),
+			validators: authorities.to_vec(),
 		})
 	}
 }
@@ -304,14 +332,17 @@ struct LocalDuty {
 }
 
 /// The Polkadot proposer logic.
-pub struct Proposer<C: PolkadotApi, R, P> {
+pub struct Proposer<C: PolkadotApi, R, P: Collators> {
 	client: Arc<C>,
+	collation: Mutex<Option<CollationFetch<P, C>>>,
 	collators: P,
 	delay: Shared<Timeout>,
 	dynamic_inclusion: DynamicInclusion,
+	genesis_hash: HeaderHash,
 	handle: Handle,
 	local_duty: LocalDuty,
 	local_key: Arc<ed25519::Pair>,
+	offline: SharedOfflineTracker,
 	parent_hash: HeaderHash,
 	parent_id: C::CheckedBlockId,
 	parent_number: BlockNumber,
@@ -319,6 +350,18 @@ pub struct Proposer<C: PolkadotApi, R, P> {
 	router: R,
 	table: Arc<SharedTable>,
 	transaction_pool: Arc<TransactionPool>,
+	validators: Vec<AuthorityId>,
+}
+
+impl<C: PolkadotApi, R, P: Collators> Proposer<C, R, P> {
+	fn primary_index(&self, round_number: usize, len: usize) -> usize {
+		use primitives::uint::U256;
+
+		let big_len = U256::from(len);
+		let offset = U256::from_big_endian(&self.random_seed.0) % big_len;
+		let offset = offset.low_u64() as usize + round_number;
+		offset % len
+	}
 }
 
 impl<C, R, P> bft::Proposer for Proposer<C, R, P>
@@ -372,16 +415,18 @@ impl<C, R, P> bft::Proposer for Proposer<C, R, P>
 			parent_id: self.parent_id.clone(),
 			client: self.client.clone(),
 			transaction_pool: self.transaction_pool.clone(),
-			collation: CollationFetch::new(
+			collation: self.collation.lock().take().unwrap_or_else(|| CollationFetch::new(
 				self.local_duty.validation,
 				self.parent_id.clone(),
 				self.parent_hash.clone(),
 				self.collators.clone(),
 				self.client.clone()
-			),
+			)),
 			table: self.table.clone(),
 			router: self.router.clone(),
 			timing,
+			validators: self.validators.clone(),
+			offline: self.offline.clone(),
 		})
 	}
 
@@ -413,6 +458,12 @@ impl<C, R, P> bft::Proposer for Proposer<C, R, P>
 			}
 		};
 
+		// check that the offline reports are consistent with our view of the validators.
+		if !self.offline.read().check_consistency(&self.validators, proposal.noted_offline()) {
+			debug!(target: "bft", "Rejecting proposal with inconsistent offline reports: {:?}", proposal.noted_offline());
+			return Box::new(future::ok(false));
+		}
+
 		let vote_delays = {
 			// delay casting vote until able (according to minimum block time)
 			let minimum_delay = self.delay.clone()
@@ -477,22 +528,25 @@ impl<C, R, P> bft::Proposer for Proposer<C, R, P>
 	}
 
 	fn round_proposer(&self, round_number: usize, authorities: &[AuthorityId]) -> AuthorityId {
-		use primitives::uint::U256;
-
-		let len: U256 = authorities.len().into();
-		let offset = U256::from_big_endian(&self.random_seed.0) % len;
-		let offset = offset.low_u64() as usize + round_number;
-
-		let proposer = authorities[offset % authorities.len()].clone();
+		let offset = self.primary_index(round_number, authorities.len());
+		let proposer = authorities[offset].clone();
 		trace!(target: "bft", "proposer for round {} is {}", round_number, Hash::from(proposer));
 
 		proposer
 	}
 
+	fn on_round_end(&self, round_number: usize, was_proposed: bool) {
+		let primary_validator = self.validators[
+			self.primary_index(round_number, self.validators.len())
+		];
+
+		self.offline.write().note_round_end(primary_validator, was_proposed, Instant::now());
+	}
+
 	fn import_misbehavior(&self, misbehavior: Vec<(AuthorityId, bft::Misbehavior)>) {
 		use bft::generic::Misbehavior as GenericMisbehavior;
 		use primitives::bft::{MisbehaviorKind, MisbehaviorReport};
-		use polkadot_runtime::{Call, Extrinsic, UncheckedExtrinsic, ConsensusCall};
+		use polkadot_runtime::{Call, Extrinsic, UncheckedExtrinsic, ConsensusCall, Era};
 
 
 		let local_id = self.local_key.public().0;
@@ -536,8 +590,10 @@ impl<C, R, P> bft::Proposer for Proposer<C, R, P>
 
 			next_index += 1;
 
-			let signature = self.local_key.sign(&extrinsic.encode()).into();
-			let uxt = UncheckedExtrinsic { extrinsic, signature };
+			let era = Era::Mortal(self.parent_number, MISBEHAVIOR_REPORT_PERIOD);
+			let payload = extrinsic.signing_payload(&era, &self.parent_hash, &self.genesis_hash);
+			let signature = self.local_key.sign(&payload).into();
+			let uxt = UncheckedExtrinsic { extrinsic, era, signature };
 
 			self.transaction_pool.import_unchecked_extrinsic(uxt).expect("locally signed extrinsic is valid; qed");
 		}
@@ -612,6 +668,8 @@ pub struct CreateProposal<C: PolkadotApi, R, P: Collators>  {
 	router: R,
 	table: Arc<SharedTable>,
 	timing: ProposalTiming,
+	validators: Vec<AuthorityId>,
+	offline: SharedOfflineTracker,
 }
 
 impl<C, R, P> CreateProposal<C, R, P>
@@ -623,10 +681,12 @@ impl<C, R, P> CreateProposal<C, R, P>
 	fn propose_with(&self, candidates: Vec<CandidateReceipt>) -> Result<SubstrateBlock, Error> {
 		// TODO: handle case when current timestamp behind that in state.
 		let timestamp = current_timestamp();
+		let offline_indices = self.offline.read().reports(&self.validators);
 		let mut block_builder = self.client.build_block(
 			&self.parent_id,
 			timestamp,
 			candidates,
+			offline_indices,
 		)?;
 
 		{
@@ -701,7 +761,7 @@ impl<C, R, P> Future for CreateProposal<C, R, P>
 		match self.collation.poll() {
 			Ok(Async::Ready((collation, extrinsic))) => {
 				let hash = collation.receipt.hash();
-				self.router.local_candidate_data(hash, collation.block_data, extrinsic);
+				self.router.local_candidate_data(hash, collation.block_data, collation.ingress, extrinsic);
 
 				// TODO: if we are an availability guarantor also, we should produce an availability statement.
 				self.table.sign_and_import(&self.router, GenericStatement::Candidate(collation.receipt));
@@ -0,0 +1,137 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracks offline validators.
+
+use primitives::AuthorityId;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// time a validator must be missing its proposal rounds for before we report it.
+const REPORT_TIME: Duration = Duration::from_secs(60 * 5);
+
+struct Observed {
+	first_missed: Instant,
+	last_missed: Instant,
+}
+
+impl Observed {
+	fn is_active(&self) -> bool {
+		self.last_missed.duration_since(self.first_missed) < REPORT_TIME
+	}
+}
+
+/// Tracks offline validators and can issue a report for those offline.
+///
+/// A validator is considered offline once it has failed to propose in all of its
+/// rounds over a period of time, without being seen to propose in between.
+pub struct OfflineTracker {
+	observed: HashMap<AuthorityId, Observed>,
+}
+
+impl OfflineTracker {
+	/// Create a new tracker.
+	pub fn new() -> Self {
+		OfflineTracker { observed: HashMap::new() }
+	}
+
+	/// Note a new block. Supply a list of all validators.
+	pub fn note_new_block(&mut self, validators: &[AuthorityId]) {
+		self.observed.retain(|v, _| validators.contains(v));
+	}
+
+	/// Note that a round has ended for the given proposer, and whether it proposed.
+	pub fn note_round_end(&mut self, proposer: AuthorityId, was_proposed: bool, now: Instant) {
+		if was_proposed {
+			self.observed.remove(&proposer);
+		} else {
+			let observed = self.observed.entry(proposer).or_insert_with(|| Observed {
+				first_missed: now,
+				last_missed: now,
+			});
+
+			observed.last_missed = now;
+		}
+	}
+
+	/// Generate a vector of indices for offline validators.
+	pub fn reports(&self, validators: &[AuthorityId]) -> Vec<u32> {
+		validators.iter()
+			.enumerate()
+			.filter_map(|(i, v)| if self.is_online(v) {
+				None
+			} else {
+				Some(i as u32)
+			})
+			.collect()
+	}
+
+	/// Whether reports on a validator set are consistent with our view of things.
+	pub fn check_consistency(&self, validators: &[AuthorityId], reports: &[u32]) -> bool {
+		reports.iter().cloned().all(|r| {
+			let v = match validators.get(r as usize) {
+				Some(v) => v,
+				None => return false,
+			};
+
+			// we must think all validators reported externally are offline.
+			!self.is_online(v)
+		})
+	}
+
+	fn is_online(&self, v: &AuthorityId) -> bool {
+		self.observed.get(v).map(Observed::is_active).unwrap_or(true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn validator_offline() {
+		let mut tracker = OfflineTracker::new();
+		let v = [0; 32];
+		let v2 = [1; 32];
+		let v3 = [2; 32];
+		let now = Instant::now();
+
+		tracker.note_round_end(v, true, now);
+		tracker.note_round_end(v2, true, now);
+		tracker.note_round_end(v3, true, now);
+
+		let slash_time = REPORT_TIME + Duration::from_secs(5);
+		tracker.note_round_end(v, false, now);
+		tracker.note_round_end(v, false, now + slash_time);
+		tracker.note_round_end(v2, false, now + slash_time);
+
+		assert_eq!(tracker.reports(&[v, v2, v3]), vec![0]);
+		assert!(tracker.check_consistency(&[v, v2, v3], &[0]));
+		assert!(!tracker.check_consistency(&[v, v2, v3], &[0, 1]));
+		assert!(!tracker.check_consistency(&[v, v2, v3], &[3]));
+
+		// a proposal clears the record.
+		tracker.note_round_end(v, true, now + slash_time);
+		assert!(tracker.reports(&[v, v2, v3]).is_empty());
+
+		// validators which leave the set are forgotten.
+		tracker.note_round_end(v3, false, now);
+		tracker.note_round_end(v3, false, now + slash_time);
+		tracker.note_new_block(&[v, v2]);
+		assert!(tracker.reports(&[v, v2, v3]).is_empty());
+	}
+}
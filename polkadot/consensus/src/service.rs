@@ -19,18 +19,21 @@
 /// Consensus service. A long runnung service that manages BFT agreement and parachain
 /// candidate agreement over the network.
 
+use std::collections::HashSet;
 use std::thread;
 use std::time::{Duration, Instant};
 use std::sync::Arc;
 
 use bft::{self, BftService};
 use client::{BlockchainEvents, ChainHead};
+use codec::Slicable;
 use ed25519;
 use futures::prelude::*;
 use futures::{future, Canceled};
-use polkadot_api::LocalPolkadotApi;
+use parking_lot::{Mutex, RwLock};
+use polkadot_api::{PolkadotApi, LocalPolkadotApi};
 use polkadot_primitives::AccountId;
-use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt, ConsolidatedIngress};
 use primitives::{Hash, AuthorityId};
 use primitives::block::{Id as BlockId, HeaderHash, Header};
 use runtime_support::Hashable;
@@ -38,11 +41,12 @@ use substrate_network as net;
 use tokio_core::reactor;
 use transaction_pool::TransactionPool;
 
-use super::{TableRouter, SharedTable, ProposerFactory};
+use super::{TableRouter, SharedTable, ProposerFactory, OfflineTracker};
 use error;
 
 const TIMER_DELAY_MS: u64 = 5000;
 const TIMER_INTERVAL_MS: u64 = 500;
+const COLLATION_TIMEOUT_MS: u64 = 10000;
 
 struct BftSink<E> {
 	network: Arc<net::ConsensusService>,
@@ -239,6 +243,7 @@ impl Service {
 		transaction_pool: Arc<TransactionPool>,
 		parachain_empty_duration: Duration,
 		key: ed25519::Pair,
+		genesis_hash: HeaderHash,
 	) -> Service
 		where
 			A: LocalPolkadotApi + Send + Sync + 'static,
@@ -253,9 +258,15 @@ impl Service {
 				client: api.clone(),
 				transaction_pool: transaction_pool.clone(),
 				network: Network(network.clone()),
-				collators: NoCollators,
+				collators: NetworkCollators {
+					network: network.clone(),
+					bad_collators: Default::default(),
+					handle: core.handle(),
+				},
 				parachain_empty_duration,
 				handle: core.handle(),
+				offline: Arc::new(RwLock::new(OfflineTracker::new())),
+				genesis_hash,
 			};
 			let bft_service = Arc::new(BftService::new(client.clone(), key, factory));
 
@@ -263,10 +274,19 @@ impl Service {
 				let handle = core.handle();
 				let network = network.clone();
 				let client = client.clone();
+				let api = api.clone();
 				let bft_service = bft_service.clone();
 
 				client.import_notification_stream().for_each(move |notification| {
 					if notification.is_new_best {
+						let parachains = api.check_id(BlockId::Hash(notification.hash))
+							.and_then(|id| api.active_parachains(&id));
+						match parachains {
+							Ok(parachains) => network.set_parachains(
+								parachains.into_iter().map(|id| u32::from(id) as u64).collect()
+							),
+							Err(e) => debug!("Error reading active parachains: {:?}", e),
+						}
 						start_bft(&notification.header, handle.clone(), &*client, network.clone(), &*bft_service);
 					}
 					Ok(())
@@ -330,23 +350,74 @@ impl Drop for Service {
 	}
 }
 
-// Collators implementation which never collates anything.
-// TODO: do a real implementation.
-#[derive(Clone, Copy)]
-struct NoCollators;
+// Collators implementation which waits for collations pushed by the collators over the network.
+// The first collation from a collator not noted as bad is selected.
+#[derive(Clone)]
+struct NetworkCollators {
+	network: Arc<net::ConsensusService>,
+	bad_collators: Arc<Mutex<HashSet<AccountId>>>,
+	handle: reactor::Handle,
+}
 
-impl ::collation::Collators for NoCollators {
+impl ::collation::Collators for NetworkCollators {
 	type Error = ();
-	type Collation = future::Empty<::collation::Collation, ()>;
+	type Collation = Box<Future<Item=::collation::Collation, Error=()>>;
+
+	fn collate(&self, parachain: ParaId, relay_parent: Hash) -> Self::Collation {
+		let timeout = match reactor::Timeout::new(Duration::from_millis(COLLATION_TIMEOUT_MS), &self.handle) {
+			Ok(timeout) => timeout,
+			Err(e) => {
+				debug!("Error creating collation timeout: {:?}", e);
+				return Box::new(future::err(()));
+			}
+		};
 
-	fn collate(&self, _parachain: ParaId, _relay_parent: Hash) -> Self::Collation {
-		future::empty()
+		let bad_collators = self.bad_collators.clone();
+		let collation = self.network.collations(u32::from(parachain) as u64, relay_parent)
+			.filter_map(move |collation| {
+				let receipt = CandidateReceipt::decode(&mut &collation.receipt[..])?;
+				let ingress = ConsolidatedIngress::decode(&mut &collation.ingress[..])?;
+				if receipt.parachain_index != parachain || bad_collators.lock().contains(&receipt.collator) {
+					return None;
+				}
+
+				if !ed25519::verify_strong(&collation.signature, &collation.block_data, ed25519::Public(receipt.collator)) {
+					debug!("Ignoring collation with bad signature from {}", Hash::from(receipt.collator));
+					return None;
+				}
+
+				Some(::collation::Collation {
+					block_data: BlockData(collation.block_data),
+					receipt,
+					ingress,
+				})
+			})
+			.into_future()
+			.map_err(|_| ())
+			.and_then(|(collation, _)| collation.ok_or(()));
+
+		let timeout = timeout
+			.map_err(|e| debug!("Collation timer error: {:?}", e))
+			.and_then(move |_| {
+				debug!("Timed out waiting for a collation on parachain {:?}", parachain);
+				Err::<::collation::Collation, ()>(())
+			});
+
+		Box::new(collation.select(timeout)
+			.map(|(collation, _)| collation)
+			.map_err(|(e, _)| e))
 	}
 
-	fn note_bad_collator(&self, _collator: AccountId) { }
+	fn note_bad_collator(&self, collator: AccountId) {
+		self.bad_collators.lock().insert(collator);
+	}
 }
 
-type FetchCandidateAdapter = future::Map<net::FetchFuture, fn(Vec<u8>) -> BlockData>;
+type FetchCandidateAdapter = future::AndThen<
+	net::FetchFuture,
+	Result<(BlockData, ConsolidatedIngress), Canceled>,
+	fn(Vec<u8>) -> Result<(BlockData, ConsolidatedIngress), Canceled>,
+>;
 
 #[derive(Clone)]
 struct Router {
@@ -354,8 +425,9 @@ struct Router {
 }
 
 impl Router {
-	fn fetch_candidate_adapter(data: Vec<u8>) -> BlockData {
-		BlockData(data)
+	fn fetch_candidate_adapter(data: Vec<u8>) -> Result<(BlockData, ConsolidatedIngress), Canceled> {
+		let (block_data, ingress) = <(Vec<u8>, ConsolidatedIngress)>::decode(&mut &data[..]).ok_or(Canceled)?;
+		Ok((BlockData(block_data), ingress))
 	}
 }
 
@@ -364,14 +436,14 @@ impl TableRouter for Router {
 	type FetchCandidate =  FetchCandidateAdapter;
 	type FetchExtrinsic = future::FutureResult<Extrinsic, Self::Error>;
 
-	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, _extrinsic: Extrinsic) {
-		let data = block_data.0;
+	fn local_candidate_data(&self, hash: Hash, block_data: BlockData, ingress: ConsolidatedIngress, _extrinsic: Extrinsic) {
+		let data = (block_data.0, ingress).encode();
 		self.network.set_local_candidate(Some((hash, data)))
 	}
 
 	fn fetch_block_data(&self, candidate: &CandidateReceipt) -> Self::FetchCandidate {
 		let hash = candidate.hash();
-		self.network.fetch_candidate(&hash).map(Self::fetch_candidate_adapter)
+		self.network.fetch_candidate(&hash).and_then(Self::fetch_candidate_adapter)
 	}
 
 	fn fetch_extrinsic_data(&self, _candidate: &CandidateReceipt) -> Self::FetchExtrinsic {
@@ -24,7 +24,7 @@ use table::{self, Table, Context as TableContextTrait};
 use table::generic::Statement as GenericStatement;
 use collation::Collation;
 use polkadot_primitives::Hash;
-use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt};
+use polkadot_primitives::parachain::{Id as ParaId, BlockData, Extrinsic, CandidateReceipt, ConsolidatedIngress};
 use primitives::AuthorityId;
 
 use parking_lot::Mutex;
@@ -224,7 +224,7 @@ impl<D: Future, E: Future, C> Default for StatementProducer<D, E, C> {
 
 impl<D, E, C, Err> Future for StatementProducer<D, E, C>
 	where
-		D: Future<Item=BlockData,Error=Err>,
+		D: Future<Item=(BlockData, ConsolidatedIngress),Error=Err>,
 		E: Future<Item=Extrinsic,Error=Err>,
 		C: FnMut(Collation) -> bool,
 {
@@ -237,12 +237,13 @@ impl<D, E, C, Err> Future for StatementProducer<D, E, C>
 			None => return Ok(Async::Ready(::std::mem::replace(&mut self.produced_statements, Default::default()))),
 		};
 
-		if let Async::Ready(block_data) = work.fetch_block_data.poll()? {
+		if let Async::Ready((block_data, ingress)) = work.fetch_block_data.poll()? {
 			self.produced_statements.block_data = Some(block_data.clone());
 			if work.evaluate {
 				let is_good = (work.check_candidate)(Collation {
 					block_data,
 					receipt: work.candidate_receipt.clone(),
+					ingress,
 				});
 
 				let hash = work.candidate_receipt.hash();
@@ -444,11 +445,11 @@ mod tests {
 	struct DummyRouter;
 	impl TableRouter for DummyRouter {
 		type Error = ();
-		type FetchCandidate = ::futures::future::Empty<BlockData,()>;
+		type FetchCandidate = ::futures::future::Empty<(BlockData, ConsolidatedIngress),()>;
 		type FetchExtrinsic = ::futures::future::Empty<Extrinsic,()>;
 
 		/// Note local candidate data, making it available on the network to other validators.
-		fn local_candidate_data(&self, _hash: Hash, _block_data: BlockData, _extrinsic: Extrinsic) {
+		fn local_candidate_data(&self, _hash: Hash, _block_data: BlockData, _ingress: ConsolidatedIngress, _extrinsic: Extrinsic) {
 
 		}
 
@@ -490,6 +491,8 @@ mod tests {
 			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
 			balance_uploads: Vec::new(),
 			egress_queue_roots: Vec::new(),
+			egress_queues: Vec::new(),
+			processed_ingress: Vec::new(),
 			fees: 1_000_000,
 		};
 
@@ -540,6 +543,8 @@ mod tests {
 			head_data: ::polkadot_primitives::parachain::HeadData(vec![1, 2, 3, 4]),
 			balance_uploads: Vec::new(),
 			egress_queue_roots: Vec::new(),
+			egress_queues: Vec::new(),
+			processed_ingress: Vec::new(),
 			fees: 1_000_000,
 		};
 
@@ -66,8 +66,64 @@ use codec::Slicable;
 #[cfg(feature = "std")]
 pub mod wasm;
 
+/// A message routed to the parachain from another parachain.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct IncomingMessage {
+	/// The ID of the parachain which sent the message.
+	pub source: u32,
+	/// The message data.
+	pub data: Vec<u8>,
+}
+
+impl Slicable for IncomingMessage {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.source.using_encoded(|s| v.extend(s));
+		self.data.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		Some(IncomingMessage {
+			source: Slicable::decode(input)?,
+			data: Slicable::decode(input)?,
+		})
+	}
+}
+
+/// A message posted by the parachain to another parachain.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OutgoingMessage {
+	/// The ID of the parachain the message is destined for.
+	pub target: u32,
+	/// The message data.
+	pub data: Vec<u8>,
+}
+
+impl Slicable for OutgoingMessage {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.target.using_encoded(|s| v.extend(s));
+		self.data.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		Some(OutgoingMessage {
+			target: Slicable::decode(input)?,
+			data: Slicable::decode(input)?,
+		})
+	}
+}
+
 /// Validation parameters for evaluating the parachain validity function.
-// TODO: consolidated ingress and balance downloads
+// TODO: balance downloads
 #[derive(PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct ValidationParams {
@@ -75,6 +131,8 @@ pub struct ValidationParams {
 	pub block_data: Vec<u8>,
 	/// Previous head-data.
 	pub parent_head: Vec<u8>,
+	/// The consolidated ingress queue, in the order it should be processed.
+	pub ingress: Vec<IncomingMessage>,
 }
 
 impl Slicable for ValidationParams {
@@ -83,6 +141,7 @@ impl Slicable for ValidationParams {
 
 		self.block_data.using_encoded(|s| v.extend(s));
 		self.parent_head.using_encoded(|s| v.extend(s));
+		self.ingress.using_encoded(|s| v.extend(s));
 
 		v
 	}
@@ -91,27 +150,36 @@ impl Slicable for ValidationParams {
 		Some(ValidationParams {
 			block_data: Slicable::decode(input)?,
 			parent_head: Slicable::decode(input)?,
+			ingress: Slicable::decode(input)?,
 		})
 	}
 }
 
 /// The result of parachain validation.
-// TODO: egress and balance uploads
+// TODO: balance uploads
 #[derive(PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct ValidationResult {
 	/// New head data that should be included in the relay chain state.
-	pub head_data: Vec<u8>
+	pub head_data: Vec<u8>,
+	/// Messages posted to other parachains, in the order they were sent.
+	pub egress: Vec<OutgoingMessage>,
 }
 
 impl Slicable for ValidationResult {
 	fn encode(&self) -> Vec<u8> {
-		self.head_data.encode()
+		let mut v = Vec::new();
+
+		self.head_data.using_encoded(|s| v.extend(s));
+		self.egress.using_encoded(|s| v.extend(s));
+
+		v
 	}
 
 	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
 		Some(ValidationResult {
 			head_data: Slicable::decode(input)?,
+			egress: Slicable::decode(input)?,
 		})
 	}
 }
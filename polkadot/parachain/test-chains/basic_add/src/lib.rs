@@ -132,6 +132,7 @@ mod tests {
 		let ret = parachain::wasm::validate_candidate(TEST_CODE, ValidationParams {
 			parent_head: parent_head.encode(),
 			block_data: block_data.encode(),
+			ingress: Vec::new(),
 		}).unwrap();
 
 		let new_head = HeadData::decode(&mut &ret.head_data[..]).unwrap();
@@ -19,6 +19,7 @@
 use parachain::{self, ValidationResult};
 use parachain::codec::Slicable;
 use super::{HeadData, BlockData};
+use alloc::vec::Vec;
 
 #[lang = "panic_fmt"]
 #[no_mangle]
@@ -54,5 +55,5 @@ pub extern fn validate(offset: usize, len: usize) -> usize {
 		post_state: hash_state(new_state),
 	};
 
-	parachain::write_result(ValidationResult { head_data: new_head.encode() })
+	parachain::write_result(ValidationResult { head_data: new_head.encode(), egress: Vec::new() })
 }
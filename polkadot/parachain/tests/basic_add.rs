@@ -106,6 +106,7 @@ fn execute_good_on_parent() {
 	let ret = parachain::wasm::validate_candidate(TEST_CODE, ValidationParams {
 		parent_head: parent_head.encode(),
 		block_data: block_data.encode(),
+		ingress: Vec::new(),
 	}).unwrap();
 
 	let new_head = HeadData::decode(&mut &ret.head_data[..]).unwrap();
@@ -136,6 +137,7 @@ fn execute_good_chain_on_parent() {
 		let ret = parachain::wasm::validate_candidate(TEST_CODE, ValidationParams {
 			parent_head: parent_head.encode(),
 			block_data: block_data.encode(),
+			ingress: Vec::new(),
 		}).unwrap();
 
 		let new_head = HeadData::decode(&mut &ret.head_data[..]).unwrap();
@@ -166,5 +168,6 @@ fn execute_bad_on_parent() {
 	let _ret = parachain::wasm::validate_candidate(TEST_CODE, ValidationParams {
 		parent_head: parent_head.encode(),
 		block_data: block_data.encode(),
+		ingress: Vec::new(),
 	}).unwrap_err();
 }
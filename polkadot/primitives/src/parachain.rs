@@ -169,6 +169,12 @@ pub struct CandidateReceipt {
 	pub balance_uploads: Vec<(::AccountId, u64)>,
 	/// Egress queue roots.
 	pub egress_queue_roots: Vec<(Id, primitives::H256)>,
+	/// Egress queues, holding the messages posted to each destination in the order they
+	/// were sent. Sorted by destination, matching `egress_queue_roots`.
+	pub egress_queues: Vec<(Id, Vec<Message>)>,
+	/// Roots of the newest egress queues processed by the candidate, one per source
+	/// parachain, sorted by source.
+	pub processed_ingress: Vec<(Id, primitives::H256)>,
 	/// Fees paid from the chain to the relay chain validators
 	pub fees: u64,
 }
@@ -182,6 +188,8 @@ impl Slicable for CandidateReceipt {
 		self.head_data.0.using_encoded(|s| v.extend(s));
 		self.balance_uploads.using_encoded(|s| v.extend(s));
 		self.egress_queue_roots.using_encoded(|s| v.extend(s));
+		self.egress_queues.using_encoded(|s| v.extend(s));
+		self.processed_ingress.using_encoded(|s| v.extend(s));
 		self.fees.using_encoded(|s| v.extend(s));
 
 		v
@@ -194,6 +202,8 @@ impl Slicable for CandidateReceipt {
 			head_data: Slicable::decode(input).map(HeadData)?,
 			balance_uploads: Slicable::decode(input)?,
 			egress_queue_roots: Slicable::decode(input)?,
+			egress_queues: Slicable::decode(input)?,
+			processed_ingress: Slicable::decode(input)?,
 			fees: Slicable::decode(input)?,
 		})
 	}
@@ -235,6 +245,26 @@ pub struct Message(#[cfg_attr(feature = "std", serde(with="bytes"))] pub Vec<u8>
 #[cfg_attr(feature = "std", derive(Serialize, Debug))]
 pub struct ConsolidatedIngress(pub Vec<(Id, Vec<Message>)>);
 
+impl Slicable for Message {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Vec::<u8>::decode(input).map(Message)
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		self.0.using_encoded(f)
+	}
+}
+
+impl Slicable for ConsolidatedIngress {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Vec::<(Id, Vec<Message>)>::decode(input).map(ConsolidatedIngress)
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		self.0.using_encoded(f)
+	}
+}
+
 /// Parachain block data.
 ///
 /// contains everything required to validate para-block, may contain block and witness data
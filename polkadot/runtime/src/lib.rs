@@ -45,6 +45,7 @@ extern crate substrate_runtime_democracy as democracy;
 extern crate substrate_runtime_executive as executive;
 extern crate substrate_runtime_session as session;
 extern crate substrate_runtime_staking as staking;
+#[macro_use]
 extern crate substrate_runtime_system as system;
 extern crate substrate_runtime_timestamp as timestamp;
 extern crate polkadot_primitives;
@@ -59,6 +60,7 @@ use runtime_primitives::traits::{Identity, HasPublicAux};
 #[cfg(feature = "std")]
 pub use runtime_primitives::BuildExternalities;
 
+pub use runtime_primitives::generic::Era;
 pub use consensus::Call as ConsensusCall;
 pub use timestamp::Call as TimestampCall;
 pub use parachains::Call as ParachainsCall;
@@ -68,6 +70,8 @@ pub use parachains::Call as ParachainsCall;
 pub const TIMESTAMP_SET_POSITION: u32 = 0;
 /// The position of the parachains set extrinsic.
 pub const PARACHAINS_SET_POSITION: u32 = 1;
+/// The position of the offline nodes noting extrinsic.
+pub const NOTE_OFFLINE_POSITION: u32 = 2;
 
 /// Concrete runtime type used to parameterize the various modules.
 pub struct Concrete;
@@ -84,13 +88,17 @@ impl system::Trait for Concrete {
 	type Digest = generic::Digest<Log>;
 	type AccountId = AccountId;
 	type Header = generic::Header<BlockNumber, Hash, Log>;
+	type Event = Event;
 }
 /// System module for this concrete runtime.
 pub type System = system::Module<Concrete>;
 
 impl consensus::Trait for Concrete {
+	const NOTE_OFFLINE_POSITION: u32 = NOTE_OFFLINE_POSITION;
 	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
 	type SessionKey = SessionKey;
+	type OnMisbehavior = Staking;
+	type OnOfflineValidator = Staking;
 }
 /// Consensus module for this concrete runtime.
 pub type Consensus = consensus::Module<Concrete>;
@@ -111,17 +119,22 @@ pub type Session = session::Module<Concrete>;
 impl staking::Trait for Concrete {
 	type Balance = Balance;
 	type DetermineContractAddress = BlakeTwo256;
+	type OnAccountKill = ();
+	type Event = Event;
 }
 /// Staking module for this concrete runtime.
 pub type Staking = staking::Module<Concrete>;
 
 impl democracy::Trait for Concrete {
 	type Proposal = PrivCall;
+	type Event = Event;
 }
 /// Democracy module for this concrete runtime.
 pub type Democracy = democracy::Module<Concrete>;
 
-impl council::Trait for Concrete {}
+impl council::Trait for Concrete {
+	type Event = Event;
+}
 /// Council module for this concrete runtime.
 pub type Council = council::Module<Concrete>;
 /// Council voting module for this concrete runtime.
@@ -131,9 +144,19 @@ impl parachains::Trait for Concrete {
 	const SET_POSITION: u32 = PARACHAINS_SET_POSITION;
 
 	type PublicAux = <Concrete as HasPublicAux>::PublicAux;
+	type Event = Event;
 }
 pub type Parachains = parachains::Module<Concrete>;
 
+impl_outer_event! {
+	pub enum Event for Concrete {
+		staking<T> = 0,
+		democracy<T> = 1,
+		council<T> = 2,
+		parachains = 3,
+	}
+}
+
 impl_outer_dispatch! {
 	pub enum Call where aux: <Concrete as HasPublicAux>::PublicAux {
 		Consensus = 0,
@@ -164,6 +187,8 @@ pub type Block = generic::Block<BlockNumber, Hash, Log, AccountId, Index, Call,
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<AccountId, Index, Call, Signature>;
 /// Extrinsic type as expected by this runtime.
 pub type Extrinsic = generic::Extrinsic<AccountId, Index, Call>;
+/// Checked extrinsic type as expected by this runtime.
+pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Index, Call, Signature>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Concrete, Block, Staking,
 	(((((((), Parachains), Council), Democracy), Staking), Session), Timestamp)>;
@@ -188,7 +213,17 @@ pub mod api {
 		execute_block => |block| super::Executive::execute_block(block),
 		finalise_block => |()| super::Executive::finalise_block(),
 		validator_count => |()| super::Session::validator_count(),
-		validators => |()| super::Session::validators()
+		validators => |()| super::Session::validators(),
+		duty_roster => |()| super::Parachains::calculate_duty_roster(),
+		active_parachains => |()| super::Parachains::active_parachains(),
+		ingress_roots => |id: ::polkadot_primitives::parachain::Id| super::Parachains::ingress_roots(id),
+		unrouted_egress => |route: (::polkadot_primitives::parachain::Id, ::polkadot_primitives::parachain::Id)| super::Parachains::unrouted_egress(route),
+		timestamp => |()| super::Timestamp::now(),
+		random_seed => |()| super::System::random_seed(),
+		account_index => |account: super::AccountId| super::System::account_index(&account),
+		reward_history => |era: super::BlockNumber| super::Staking::reward_history(era),
+		logs => |(addresses, topics): (Vec<super::AccountId>, Vec<super::Hash>)|
+			super::System::log_records_matching(&addresses, &topics)
 	);
 }
 
@@ -239,6 +274,7 @@ mod tests {
 						signed: Default::default(),
 						index: Default::default(),
 					},
+					era: Era::Immortal,
 					signature: Default::default(),
 				}
 			],
@@ -255,6 +291,7 @@ mod tests {
 				signed: Default::default(),
 				index: 10101,
 			},
+			era: Era::Immortal,
 			signature: Default::default(),
 		});
 
@@ -275,6 +312,7 @@ mod tests {
 						signed: Default::default(),
 						index: Default::default(),
 					},
+					era: Era::Immortal,
 					signature: Default::default(),
 				}
 			],
@@ -286,6 +324,7 @@ mod tests {
 				signed: Default::default(),
 				index: 10101,
 			},
+			era: Era::Immortal,
 			signature: Default::default(),
 		});
 
@@ -305,13 +344,15 @@ mod tests {
 				index: 999,
 				function: Call::Timestamp(TimestampCall::set(135135)),
 			},
+			era: Era::Mortal(7, 64),
 			signature: primitives::hash::H512([0; 64]).into(),
 		};
-		// 71000000
+		// 82000000
 		// 0101010101010101010101010101010101010101010101010101010101010101
 		// e703000000000000
 		// 00
 		// df0f0200
+		// 0107000000000000004000000000000000
 		// 0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000
 
 		let v = Slicable::encode(&tx);
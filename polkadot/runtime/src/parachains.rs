@@ -16,13 +16,13 @@
 
 //! Main parachains logic. For now this is just the determination of which validators do what.
 
-use polkadot_primitives;
+use polkadot_primitives::{self, Hash};
 use rstd::prelude::*;
 use codec::{Slicable, Joiner};
 use runtime_support::Hashable;
 
 use runtime_primitives::traits::{Executable, RefInto, MaybeEmpty};
-use polkadot_primitives::parachain::{Id, Chain, DutyRoster, CandidateReceipt};
+use polkadot_primitives::parachain::{Id, Chain, DutyRoster, CandidateReceipt, Message};
 use {system, session};
 
 use runtime_support::{StorageValue, StorageMap};
@@ -31,14 +31,27 @@ use runtime_support::dispatch::Result;
 #[cfg(any(feature = "std", test))]
 use rstd::marker::PhantomData;
 
+use runtime_io;
+
 #[cfg(any(feature = "std", test))]
-use {runtime_io, runtime_primitives};
+use runtime_primitives;
 
 pub trait Trait: system::Trait<Hash = polkadot_primitives::Hash> + session::Trait {
 	/// The position of the set_heads call in the block.
 	const SET_POSITION: u32;
 
 	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;
+
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_event! {
+	/// An event in this module.
+	pub enum Event {
+		/// Candidates for the given parachains have been included in the block.
+		CandidatesIncluded { parachains: Vec<Id> } = 0,
+	}
 }
 
 decl_module! {
@@ -57,6 +70,11 @@ decl_storage! {
 	pub Code get(parachain_code): b"para:code" => map [ Id => Vec<u8> ];
 	// The heads of the parachains registered at present. these are kept sorted.
 	pub Heads get(parachain_head): b"para:head" => map [ Id => Vec<u8> ];
+	// Roots of egress queues posted from one parachain to another which have not yet been
+	// routed, keyed by (from, to). The oldest root comes first.
+	pub Routing get(unrouted_egress_roots): b"para:routing" => default map [ (Id, Id) => Vec<Hash> ];
+	// The egress queues whose roots are kept in `Routing`, in the same order.
+	pub Egress get(unrouted_egress): b"para:egress" => default map [ (Id, Id) => Vec<Vec<Message>> ];
 
 	// Did the parachain heads get updated in this block?
 	DidUpdate: b"para:did" => default bool;
@@ -133,11 +151,28 @@ impl<T: Trait> Module<T> {
 			Err(_) => {}
 		}
 
+		for other in &parachains {
+			<Routing<T>>::remove((id, *other));
+			<Routing<T>>::remove((*other, id));
+			<Egress<T>>::remove((id, *other));
+			<Egress<T>>::remove((*other, id));
+		}
+
 		<Code<T>>::remove(id);
 		<Heads<T>>::remove(id);
 		<Parachains<T>>::put(parachains);
 	}
 
+	/// Get the roots of all egress queues routed to the given parachain and not yet
+	/// processed by it, grouped by source parachain in ascending order.
+	pub fn ingress_roots(id: Id) -> Vec<(Id, Vec<Hash>)> {
+		Self::active_parachains().into_iter()
+			.filter(|source| *source != id)
+			.map(|source| (source, Self::unrouted_egress_roots((source, id))))
+			.filter(|&(_, ref roots)| !roots.is_empty())
+			.collect()
+	}
+
 	fn set_heads(aux: &<T as Trait>::PublicAux, heads: Vec<CandidateReceipt>) -> Result {
 		ensure!(aux.is_empty(), "set_heads must not be signed");
 		ensure!(!<DidUpdate<T>>::exists(), "Parachain heads must be updated only once in the block");
@@ -150,26 +185,119 @@ impl<T: Trait> Module<T> {
 		let active_parachains = Self::active_parachains();
 		let mut iter = active_parachains.iter();
 
-		// perform this check before writing to storage.
+		// perform these checks before writing to storage.
 		for head in &heads {
 			ensure!(
 				iter.find(|&p| p == &head.parachain_index).is_some(),
 				"Submitted candidate for unregistered or out-of-order parachain {}"
 //				, head.parachain_index.into_inner()
 			);
+
+			Self::check_egress_queue_roots(head, &active_parachains)?;
+			Self::check_processed_ingress(head)?;
 		}
 
+		// included candidates have processed the egress routed to them up to the
+		// committed roots.
+		for head in &heads {
+			let to = head.parachain_index;
+			for &(from, root) in &head.processed_ingress {
+				let mut roots = Self::unrouted_egress_roots((from, to));
+				let mut queues = Self::unrouted_egress((from, to));
+				let processed = roots.iter().position(|r| *r == root)
+					.expect("processed ingress roots checked to be routed; qed");
+				roots.drain(..processed + 1);
+				queues.drain(..processed + 1);
+
+				if roots.is_empty() {
+					<Routing<T>>::remove((from, to));
+					<Egress<T>>::remove((from, to));
+				} else {
+					<Routing<T>>::insert((from, to), roots);
+					<Egress<T>>::insert((from, to), queues);
+				}
+			}
+		}
+
+		let included: Vec<Id> = heads.iter().map(|head| head.parachain_index).collect();
+
 		for head in heads {
 			let id = head.parachain_index.clone();
+			for (&(to, root), (_, queue)) in head.egress_queue_roots.iter().zip(head.egress_queues) {
+				let mut roots = Self::unrouted_egress_roots((id, to));
+				roots.push(root);
+				<Routing<T>>::insert((id, to), roots);
+
+				let mut queues = Self::unrouted_egress((id, to));
+				queues.push(queue);
+				<Egress<T>>::insert((id, to), queues);
+			}
+
 			<Heads<T>>::insert(id, head.head_data.0);
 		}
 
 		<DidUpdate<T>>::put(true);
+		Self::deposit_event(Event::CandidatesIncluded { parachains: included });
+
+		Ok(())
+	}
+
+	// egress queue roots must be sorted by destination, with each destination
+	// being an active parachain other than the sender, and commit to the egress queues.
+	fn check_egress_queue_roots(head: &CandidateReceipt, active_parachains: &[Id]) -> Result {
+		ensure!(
+			head.egress_queues.len() == head.egress_queue_roots.len(),
+			"Egress queues do not match their roots"
+		);
+		for (&(to, root), &(ref queue_to, ref queue)) in head.egress_queue_roots.iter().zip(&head.egress_queues) {
+			let messages: Vec<&[u8]> = queue.iter().map(|message| &message.0[..]).collect();
+			ensure!(
+				to == *queue_to && root == runtime_io::enumerated_trie_root(&messages).into(),
+				"Egress queues do not match their roots"
+			);
+		}
+
+		let mut last_to = None;
+		for &(to, _) in &head.egress_queue_roots {
+			ensure!(to != head.parachain_index, "Parachain sent egress to itself");
+			ensure!(
+				last_to.map_or(true, |last| last < to),
+				"Egress queue roots must be sorted by destination and unique"
+			);
+			ensure!(
+				active_parachains.binary_search(&to).is_ok(),
+				"Egress queue root submitted for unregistered parachain"
+			);
+
+			last_to = Some(to);
+		}
+
+		Ok(())
+	}
+
+	// processed ingress roots must be sorted by source, with each root being routed
+	// from the source to the candidate's parachain.
+	fn check_processed_ingress(head: &CandidateReceipt) -> Result {
+		let mut last_from = None;
+		for &(from, root) in &head.processed_ingress {
+			ensure!(
+				last_from.map_or(true, |last| last < from),
+				"Processed ingress roots must be sorted by source and unique"
+			);
+			ensure!(
+				Self::unrouted_egress_roots((from, head.parachain_index)).contains(&root),
+				"Processed ingress root was not routed to the parachain"
+			);
+
+			last_from = Some(from);
+		}
 
 		Ok(())
 	}
 }
 
+impl_deposit_event!(Module<T>, Event);
+
 impl<T: Trait> Executable for Module<T> {
 	fn execute() {
 		assert!(<Self as Store>::DidUpdate::take(), "Parachain heads must be updated once in the block");
@@ -236,8 +364,11 @@ mod tests {
 		type PublicAux = u64;
 	}
 	impl consensus::Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehavior = ();
+		type OnOfflineValidator = ();
 	}
 	impl system::Trait for Test {
 		type Index = u64;
@@ -247,6 +378,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = Event;
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -255,6 +387,7 @@ mod tests {
 		const SET_POSITION: u32 = 0;
 
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
+		type Event = Event;
 	}
 
 	type Parachains = Module<Test>;
@@ -315,6 +448,159 @@ mod tests {
 		});
 	}
 
+	// the root of an egress queue holding a single message `[tag]`.
+	fn root(tag: u8) -> Hash {
+		runtime_io::enumerated_trie_root(&[&[tag][..]]).into()
+	}
+
+	// a candidate posting single-message queues `(to, tag)` and having processed the
+	// queues `(from, tag)`.
+	fn candidate(id: u32, egress: Vec<(u32, u8)>) -> CandidateReceipt {
+		processing_candidate(id, egress, vec![])
+	}
+
+	fn processing_candidate(id: u32, egress: Vec<(u32, u8)>, processed: Vec<(u32, u8)>) -> CandidateReceipt {
+		use polkadot_primitives::parachain::HeadData;
+
+		CandidateReceipt {
+			parachain_index: id.into(),
+			collator: Default::default(),
+			head_data: HeadData(vec![id as u8]),
+			balance_uploads: vec![],
+			egress_queue_roots: egress.iter().map(|&(to, tag)| (to.into(), root(tag))).collect(),
+			egress_queues: egress.iter().map(|&(to, tag)| (to.into(), vec![Message(vec![tag])])).collect(),
+			processed_ingress: processed.into_iter().map(|(from, tag)| (from.into(), root(tag))).collect(),
+			fees: 0,
+		}
+	}
+
+	#[test]
+	fn egress_roots_are_routed() {
+		let parachains = vec![
+			(0u32.into(), vec![]),
+			(1u32.into(), vec![]),
+			(2u32.into(), vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_ok!(Parachains::set_heads(&0, vec![
+				candidate(0, vec![(1, 1), (2, 2)]),
+				candidate(1, vec![(2, 3)]),
+			]));
+
+			assert_eq!(Parachains::parachain_head(&0u32.into()), Some(vec![0]));
+			assert_eq!(Parachains::unrouted_egress_roots((Id::from(0), Id::from(1))), vec![root(1)]);
+			assert_eq!(Parachains::unrouted_egress((Id::from(0), Id::from(1))), vec![vec![Message(vec![1])]]);
+			assert_eq!(Parachains::ingress_roots(2u32.into()), vec![
+				(Id::from(0), vec![root(2)]),
+				(Id::from(1), vec![root(3)]),
+			]);
+			<Parachains as Store>::DidUpdate::kill();
+
+			// parachain 2 is included and routes its ingress, while 0 posts again.
+			assert_ok!(Parachains::set_heads(&0, vec![
+				candidate(0, vec![(1, 4)]),
+				processing_candidate(2, vec![], vec![(0, 2), (1, 3)]),
+			]));
+
+			assert!(Parachains::ingress_roots(2u32.into()).is_empty());
+			assert_eq!(Parachains::ingress_roots(1u32.into()), vec![
+				(Id::from(0), vec![root(1), root(4)]),
+			]);
+			<Parachains as Store>::DidUpdate::kill();
+
+			// queues posted after the processed root stay routed.
+			assert_ok!(Parachains::set_heads(&0, vec![
+				processing_candidate(1, vec![], vec![(0, 1)]),
+			]));
+			assert_eq!(Parachains::ingress_roots(1u32.into()), vec![
+				(Id::from(0), vec![root(4)]),
+			]);
+			assert_eq!(Parachains::unrouted_egress((Id::from(0), Id::from(1))), vec![vec![Message(vec![4])]]);
+			<Parachains as Store>::DidUpdate::kill();
+
+			// included candidates which processed nothing keep their ingress.
+			assert_ok!(Parachains::set_heads(&0, vec![candidate(1, vec![])]));
+			assert_eq!(Parachains::ingress_roots(1u32.into()), vec![
+				(Id::from(0), vec![root(4)]),
+			]);
+		});
+	}
+
+	#[test]
+	fn included_candidates_are_deposited_as_event() {
+		let parachains = vec![
+			(0u32.into(), vec![]),
+			(1u32.into(), vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_ok!(Parachains::set_heads(&0, vec![candidate(0, vec![]), candidate(1, vec![])]));
+			assert_eq!(system::Module::<Test>::events(), vec![system::EventRecord {
+				extrinsic_index: 0,
+				event: Event::CandidatesIncluded { parachains: vec![0u32.into(), 1u32.into()] },
+			}]);
+		});
+	}
+
+	#[test]
+	fn bad_processed_ingress_is_rejected() {
+		let parachains = vec![
+			(0u32.into(), vec![]),
+			(1u32.into(), vec![]),
+			(2u32.into(), vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_ok!(Parachains::set_heads(&0, vec![
+				candidate(0, vec![(2, 1)]),
+				candidate(1, vec![(2, 2)]),
+			]));
+			<Parachains as Store>::DidUpdate::kill();
+
+			assert_noop!(
+				Parachains::set_heads(&0, vec![processing_candidate(2, vec![], vec![(0, 2)])]),
+				"Processed ingress root was not routed to the parachain"
+			);
+			assert_noop!(
+				Parachains::set_heads(&0, vec![
+					processing_candidate(2, vec![], vec![(1, 2), (0, 1)]),
+				]),
+				"Processed ingress roots must be sorted by source and unique"
+			);
+		});
+	}
+
+	#[test]
+	fn bad_egress_roots_are_rejected() {
+		let parachains = vec![
+			(0u32.into(), vec![]),
+			(1u32.into(), vec![]),
+		];
+
+		with_externalities(&mut new_test_ext(parachains), || {
+			assert_noop!(
+				Parachains::set_heads(&0, vec![candidate(0, vec![(0, 1)])]),
+				"Parachain sent egress to itself"
+			);
+			assert_noop!(
+				Parachains::set_heads(&0, vec![candidate(0, vec![(5, 1)])]),
+				"Egress queue root submitted for unregistered parachain"
+			);
+			assert_noop!(
+				Parachains::set_heads(&0, vec![candidate(0, vec![(1, 1), (1, 2)])]),
+				"Egress queue roots must be sorted by destination and unique"
+			);
+
+			let mut wrong_queue = candidate(0, vec![(1, 1)]);
+			wrong_queue.egress_queues[0].1.push(Message(vec![2]));
+			assert_noop!(
+				Parachains::set_heads(&0, vec![wrong_queue]),
+				"Egress queues do not match their roots"
+			);
+		});
+	}
+
 	#[test]
 	fn duty_roster_works() {
 		let parachains = vec![
@@ -17,6 +17,7 @@
 //! Service configuration.
 
 use transaction_pool;
+pub use client_db::PruningMode;
 pub use network::Role;
 pub use network::NetworkConfiguration;
 
@@ -37,6 +38,8 @@ pub enum ChainSpec {
 pub struct Configuration {
 	/// Node roles.
 	pub roles: Role,
+	/// Parachain id to collate on. Required for the collator role.
+	pub parachain_id: Option<u64>,
 	/// Transaction pool configuration.
 	pub transaction_pool: transaction_pool::Options,
 	/// Network configuration.
@@ -45,6 +48,8 @@ pub struct Configuration {
 	pub keystore_path: String,
 	/// Path to the database.
 	pub database_path: String,
+	/// State pruning mode.
+	pub pruning: PruningMode,
 	/// Additional key seeds.
 	pub keys: Vec<String>,
 	/// Chain specification.
@@ -55,10 +60,12 @@ impl Default for Configuration {
 	fn default() -> Configuration {
 		Configuration {
 			roles: Role::FULL,
+			parachain_id: None,
 			transaction_pool: Default::default(),
 			network: Default::default(),
 			keystore_path: Default::default(),
 			database_path: Default::default(),
+			pruning: Default::default(),
 			keys: Default::default(),
 			chain_spec: ChainSpec::Development,
 		}
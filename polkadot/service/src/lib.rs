@@ -58,7 +58,7 @@ use tokio_core::reactor::Core;
 use codec::Slicable;
 use primitives::block::{Id as BlockId, ExtrinsicHash, HeaderHash, Header};
 use primitives::{AuthorityId};
-use transaction_pool::TransactionPool;
+use transaction_pool::{TransactionPool, ChainContext};
 use substrate_executor::NativeExecutor;
 use polkadot_executor::Executor as LocalDispatch;
 use keystore::Store as Keystore;
@@ -71,7 +71,7 @@ use network::ManageNetwork;
 use exit_future::Signal;
 
 pub use self::error::{ErrorKind, Error};
-pub use config::{Configuration, Role, ChainSpec};
+pub use config::{Configuration, Role, ChainSpec, PruningMode};
 
 type CodeExecutor = NativeExecutor<LocalDispatch>;
 
@@ -85,6 +85,34 @@ pub struct Service<B, E> {
 	_consensus: Option<consensus::Service>,
 }
 
+/// The chain context of the best block, against which transactions entering the pool are checked.
+struct BestChainContext<B, E> {
+	client: Arc<Client<B, E>>,
+}
+
+impl<B, E> ChainContext for BestChainContext<B, E>
+	where
+		B: Backend + Send + Sync,
+		E: CallExecutor + Send + Sync,
+		client::error::Error: From<<<B as Backend>::State as state_machine::backend::Backend>::Error>,
+{
+	type Hash = HeaderHash;
+
+	fn current_height(&self) -> u64 {
+		match self.client.info() {
+			Ok(info) => info.chain.best_number + 1,
+			Err(e) => {
+				debug!("Error getting best block: {:?}", e);
+				0
+			}
+		}
+	}
+
+	fn block_hash(&self, number: u64) -> Option<Self::Hash> {
+		self.client.block_hash(number).ok().and_then(|hash| hash)
+	}
+}
+
 struct TransactionPoolAdapter<B, E, A> where A: Send + Sync, E: Send + Sync {
 	pool: Arc<TransactionPool>,
 	client: Arc<Client<B, E>>,
@@ -173,6 +201,13 @@ fn poc_2_testnet_config() -> ChainConfig {
 			intentions: initial_authorities.clone(),
 			transaction_base_fee: 100,
 			transaction_byte_fee: 1,
+			existential_deposit: 500,
+			creation_fee: 1000,
+			misbehavior_slash: 1 << 50,
+			reporter_reward: 1 << 40,
+			session_reward: 1 << 40,
+			offline_slash: 1 << 40,
+			offline_slash_grace: 3,
 			balances: endowed_accounts.iter().map(|&k|(k, 1u128 << 60)).collect(),
 			validator_count: 12,
 			sessions_per_era: 24,	// 24 hours per era.
@@ -181,6 +216,7 @@ fn poc_2_testnet_config() -> ChainConfig {
 		democracy: Some(DemocracyConfig {
 			launch_period: 120 * 24 * 14,	// 2 weeks per public referendum
 			voting_period: 120 * 24 * 28,	// 4 weeks to discuss & vote on an active referendum
+			enactment_period: 120 * 24 * 14,	// 2 weeks between a referendum passing and its enactment
 			minimum_deposit: 1000,	// 1000 as the minimum deposit for a referendum
 		}),
 		council: Some(CouncilConfig {
@@ -232,6 +268,13 @@ fn testnet_config(initial_authorities: Vec<AuthorityId>) -> ChainConfig {
 			intentions: initial_authorities.clone(),
 			transaction_base_fee: 1,
 			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			creation_fee: 0,
+			misbehavior_slash: 1 << 50,
+			reporter_reward: 1 << 40,
+			session_reward: 1 << 40,
+			offline_slash: 1 << 40,
+			offline_slash_grace: 3,
 			balances: endowed_accounts.iter().map(|&k|(k, (1u128 << 60))).collect(),
 			validator_count: 2,
 			sessions_per_era: 5,
@@ -240,6 +283,7 @@ fn testnet_config(initial_authorities: Vec<AuthorityId>) -> ChainConfig {
 		democracy: Some(DemocracyConfig {
 			launch_period: 9,
 			voting_period: 18,
+			enactment_period: 9,
 			minimum_deposit: 10,
 		}),
 		council: Some(CouncilConfig {
@@ -289,7 +333,15 @@ impl client::GenesisBuilder for GenesisBuilder {
 }
 
 /// Creates light client and register protocol with the network service
-pub fn new_light(config: Configuration) -> Result<Service<client::light::Backend, client::RemoteCallExecutor<client::light::Backend, network::OnDemand<network::Service>>>, error::Error> {
+pub fn new_light(config: Configuration)
+	-> Result<
+		Service<
+			client::light::Backend<network::OnDemand<network::Service>>,
+			client::RemoteCallExecutor<client::light::Backend<network::OnDemand<network::Service>>, network::OnDemand<network::Service>>
+		>,
+		error::Error,
+	>
+{
 	Service::new(move |_, executor, genesis_builder: GenesisBuilder| {
 			let client_backend = client::light::new_light_backend();
 			let fetch_checker = Arc::new(client::light::new_fetch_checker(client_backend.clone(), executor));
@@ -316,6 +368,7 @@ pub fn new_full(config: Configuration) -> Result<Service<client_db::Backend, cli
 			// Load the first available key. Code above makes sure it exisis.
 			let key = keystore.load(&keystore.contents()?[0], "")?;
 			info!("Using authority key {:?}", key.public());
+			let genesis_hash = client.block_hash(0)?.expect("genesis block is always stored; qed");
 			Ok(Some(consensus::Service::new(
 				client.clone(),
 				client.clone(),
@@ -323,6 +376,7 @@ pub fn new_full(config: Configuration) -> Result<Service<client_db::Backend, cli
 				tx_pool.clone(),
 				::std::time::Duration::from_millis(4000), // TODO: dynamic
 				key,
+				genesis_hash,
 			)))
 		},
 		config)
@@ -384,21 +438,33 @@ impl<B, E> Service<B, E>
 		let db_settings = client_db::DatabaseSettings {
 			cache_size: None,
 			path: config.database_path.into(),
+			pruning: config.pruning,
 		};
 
 		let (client, on_demand) = client_creator(db_settings, executor, genesis_builder)?;
+		// light clients don't keep block bodies locally
+		let reimport_retracted = on_demand.is_none();
 		let api = api_creator(client.clone());
 		let best_header = client.best_block_header()?;
 		info!("Starting Polkadot. Best block is #{}", best_header.number);
-		let transaction_pool = Arc::new(TransactionPool::new(config.transaction_pool));
+		let chain_context = Arc::new(BestChainContext { client: client.clone() });
+		let transaction_pool = Arc::new(TransactionPool::new(config.transaction_pool, chain_context));
 		let transaction_pool_adapter = Arc::new(TransactionPoolAdapter {
 			pool: transaction_pool.clone(),
 			client: client.clone(),
 			api: api.clone(),
 		});
+		// validators announce the key they sign with, which is the first one in the keystore.
+		let validator_key = if (config.roles & Role::VALIDATOR) == Role::VALIDATOR {
+			Some(Arc::new(keystore.load(&keystore.contents()?[0], "")?))
+		} else {
+			None
+		};
 		let network_params = network::Params {
 			config: network::ProtocolConfig {
 				roles: config.roles,
+				parachain_id: config.parachain_id,
+				validator_key,
 			},
 			network_config: config.network,
 			chain: client.clone(),
@@ -425,10 +491,18 @@ impl<B, E> Service<B, E>
 				// block notifications
 				let network1 = network.clone();
 				let txpool1 = txpool.clone();
+				let client1 = client.clone();
 				let events = client.import_notification_stream()
 					.for_each(move |notification| {
 						network1.on_block_imported(notification.hash, &notification.header);
-						prune_imported(&*api, &*txpool1, notification.hash);
+						if reimport_retracted {
+							reimport_extrinsics(&*client1, &*txpool1, &notification.retracted);
+						}
+						if notification.enacted.is_empty() {
+							prune_imported(&*api, &*txpool1, &[notification.hash]);
+						} else {
+							prune_imported(&*api, &*txpool1, &notification.enacted);
+						}
 
 						Ok(())
 					});
@@ -483,17 +557,47 @@ impl<B, E> Service<B, E>
 	}
 }
 
-/// Produce a task which prunes any finalized transactions from the pool.
-pub fn prune_imported<A>(api: &A, pool: &TransactionPool, hash: HeaderHash)
+/// Prune any finalized transactions from the pool, as of each of the given blocks in turn.
+pub fn prune_imported<A>(api: &A, pool: &TransactionPool, hashes: &[HeaderHash])
 	where
 		A: PolkadotApi,
 {
-	match api.check_id(BlockId::Hash(hash)) {
-		Ok(id) => {
-			let ready = transaction_pool::Ready::create(id, api);
-			pool.cull(None, ready);
-		},
-		Err(e) => warn!("Failed to check block id: {:?}", e),
+	for hash in hashes {
+		match api.check_id(BlockId::Hash(*hash)) {
+			Ok(id) => {
+				let ready = transaction_pool::Ready::create(id, api);
+				pool.cull(None, ready);
+			},
+			Err(e) => warn!("Failed to check block id: {:?}", e),
+		}
+	}
+}
+
+/// Return extrinsics of the blocks, retracted from the best chain, back to the pool.
+pub fn reimport_extrinsics<B, E>(client: &Client<B, E>, pool: &TransactionPool, retracted: &[HeaderHash])
+	where
+		B: Backend,
+		E: CallExecutor,
+		client::error::Error: From<<<B as Backend>::State as state_machine::backend::Backend>::Error>,
+{
+	for hash in retracted {
+		let body = match client.body(&BlockId::Hash(*hash)) {
+			Ok(Some(body)) => body,
+			Ok(None) => continue,
+			Err(e) => {
+				warn!("Failed to read body of retracted block {}: {:?}", hash, e);
+				continue;
+			},
+		};
+
+		for xt in body {
+			match Slicable::decode(&mut &xt.0[..]) {
+				Some(uxt) => if let Err(e) = pool.import_unchecked_extrinsic(uxt) {
+					debug!("Extrinsic of retracted block {} has not been reimported: {:?}", hash, e);
+				},
+				None => debug!("Error decoding extrinsic of retracted block {}", hash),
+			}
+		}
 	}
 }
 
@@ -38,6 +38,11 @@ error_chain! {
 			description("Transaction had bad signature."),
 			display("Transaction had bad signature."),
 		}
+		/// Attempted to queue a transaction outside of its era.
+		Expired(xt: UncheckedExtrinsic) {
+			description("Transaction is outside of its era."),
+			display("Transaction is outside of its era."),
+		}
 		/// Attempted to queue a transaction that is already in the pool.
 		AlreadyImported(hash: Hash) {
 			description("Transaction is already in the pool."),
@@ -43,14 +43,19 @@ use extrinsic_pool::{Pool, txpool::{self, Readiness, scoring::{Change, Choice}}}
 use polkadot_api::PolkadotApi;
 use primitives::parachain::CandidateReceipt;
 use primitives::{AccountId, Timestamp, Hash};
-use runtime::{Block, UncheckedExtrinsic, TimestampCall, ParachainsCall, Call};
+use runtime::{Block, UncheckedExtrinsic, CheckedExtrinsic, TimestampCall, ParachainsCall, ConsensusCall, Call};
 use substrate_primitives::block::{Extrinsic, ExtrinsicHash};
 use substrate_primitives::hexdisplay::HexDisplay;
 use substrate_runtime_primitives::traits::{Bounded, Checkable};
 
 pub use extrinsic_pool::txpool::{Options, Status, LightStatus, VerifiedTransaction as VerifiedTransactionOps};
+pub use substrate_runtime_primitives::traits::ChainContext;
 pub use error::{Error, ErrorKind, Result};
 
+/// The chain against which the era and signature of incoming transactions are checked;
+/// usually that of the best block.
+pub type SharedChainContext = Arc<ChainContext<Hash = Hash> + Send + Sync>;
+
 /// Useful functions for working with Polkadot blocks.
 pub struct PolkadotBlock {
 	block: Block,
@@ -80,6 +85,14 @@ impl PolkadotBlock {
 			_ => return Err(unchecked),
 		}
 
+		// the offline validators may optionally be noted in an unsigned third extrinsic.
+		if unchecked.extrinsics.len() > 2 && !unchecked.extrinsics[2].is_signed() {
+			match unchecked.extrinsics[2].extrinsic.function {
+				Call::Consensus(ConsensusCall::note_offline(_)) => {},
+				_ => return Err(unchecked),
+			}
+		}
+
 		// any further checks...
 		Ok(PolkadotBlock { block: unchecked, location: None })
 	}
@@ -115,6 +128,17 @@ impl PolkadotBlock {
 			}
 		}
 	}
+
+	/// Retrieve the indices of the validators noted as offline in this block.
+	pub fn noted_offline(&self) -> &[u32] {
+		match self.block.extrinsics.get(2) {
+			Some(xt) if !xt.is_signed() => match xt.extrinsic.function {
+				Call::Consensus(ConsensusCall::note_offline(ref indices)) => &indices[..],
+				_ => &[],
+			},
+			_ => &[],
+		}
+	}
 }
 
 #[macro_export]
@@ -138,20 +162,20 @@ impl From<PolkadotBlock> for Block {
 /// A verified transaction which should be includable and non-inherent.
 #[derive(Debug, Clone)]
 pub struct VerifiedTransaction {
-	inner: <UncheckedExtrinsic as Checkable>::Checked,
+	inner: CheckedExtrinsic,
 	hash: ExtrinsicHash,
 	encoded_size: usize,
 }
 
 impl VerifiedTransaction {
-	/// Attempt to verify a transaction.
-	fn create(xt: UncheckedExtrinsic) -> Result<Self> {
+	/// Attempt to verify a transaction against the given chain context.
+	fn create(xt: UncheckedExtrinsic, context: &ChainContext<Hash = Hash>) -> Result<Self> {
 		if !xt.is_signed() {
 			bail!(ErrorKind::IsInherent(xt))
 		}
 
 		let message = codec::Slicable::encode(&xt);
-		match xt.check() {
+		match xt.check(context) {
 			Ok(xt) => {
 				let hash = substrate_primitives::hashing::blake2_256(&message);
 				Ok(VerifiedTransaction {
@@ -160,7 +184,11 @@ impl VerifiedTransaction {
 					encoded_size: message.len(),
 				})
 			}
-			Err(xt) => Err(ErrorKind::BadSignature(xt).into()),
+			Err(xt) => if xt.is_expired(context) {
+				Err(ErrorKind::Expired(xt).into())
+			} else {
+				Err(ErrorKind::BadSignature(xt).into())
+			},
 		}
 	}
 
@@ -170,7 +198,7 @@ impl VerifiedTransaction {
 	}
 
 	/// Consume the verified transaciton, yielding the unchecked counterpart.
-	pub fn into_inner(self) -> <UncheckedExtrinsic as Checkable>::Checked {
+	pub fn into_inner(self) -> CheckedExtrinsic {
 		self.inner
 	}
 
@@ -190,8 +218,8 @@ impl VerifiedTransaction {
 	}
 }
 
-impl AsRef< <UncheckedExtrinsic as Checkable>::Checked > for VerifiedTransaction {
-	fn as_ref(&self) -> &<UncheckedExtrinsic as Checkable>::Checked {
+impl AsRef<CheckedExtrinsic> for VerifiedTransaction {
+	fn as_ref(&self) -> &CheckedExtrinsic {
 		&self.inner
 	}
 }
@@ -301,7 +329,9 @@ impl<'a, T: 'a + PolkadotApi> txpool::Ready<VerifiedTransaction> for Ready<'a, T
 	}
 }
 
-pub struct Verifier;
+pub struct Verifier {
+	context: SharedChainContext,
+}
 
 impl txpool::Verifier<Extrinsic> for Verifier {
 	type VerifiedTransaction = VerifiedTransaction;
@@ -312,7 +342,7 @@ impl txpool::Verifier<Extrinsic> for Verifier {
 		let uxt = xt.using_encoded(|ref mut s| UncheckedExtrinsic::decode(s))
 			.ok_or_else(|| ErrorKind::InvalidExtrinsicFormat)?;
 		info!("Correctly formatted: {:?}", uxt);
-		VerifiedTransaction::create(uxt)
+		VerifiedTransaction::create(uxt, &*self.context)
 	}
 }
 
@@ -321,18 +351,20 @@ impl txpool::Verifier<Extrinsic> for Verifier {
 /// Wraps a `extrinsic_pool::Pool`.
 pub struct TransactionPool {
 	inner: Pool<Verifier, Scoring, Error>,
+	context: SharedChainContext,
 }
 
 impl TransactionPool {
-	/// Create a new transaction pool.
-	pub fn new(options: Options) -> Self {
+	/// Create a new transaction pool, checking transactions against the given chain context.
+	pub fn new(options: Options, context: SharedChainContext) -> Self {
 		TransactionPool {
-			inner: Pool::new(options, Verifier, Scoring),
+			inner: Pool::new(options, Verifier { context: context.clone() }, Scoring),
+			context,
 		}
 	}
 
 	pub fn import_unchecked_extrinsic(&self, uxt: UncheckedExtrinsic) -> Result<Arc<VerifiedTransaction>> {
-		Ok(self.inner.import(VerifiedTransaction::create(uxt)?)?)
+		Ok(self.inner.import(VerifiedTransaction::create(uxt, &*self.context)?)?)
 	}
 }
 
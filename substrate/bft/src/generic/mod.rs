@@ -179,6 +179,10 @@ pub trait Context {
 	/// length, and create a future that will resolve when the timeout is
 	/// concluded.
 	fn begin_round_timeout(&self, round: usize) -> Self::RoundTimeout;
+
+	/// Called when a round ends, either by advancing or by committing, indicating
+	/// whether the round's proposer was observed to make a proposal.
+	fn on_round_end(&self, _round: usize, _was_proposed: bool) {}
 }
 
 /// Communication that can occur between participants in consensus.
@@ -481,6 +485,9 @@ impl<C: Context> Strategy<C> {
 					justification: just.clone()
 				};
 
+				// a committed round necessarily had a proposal.
+				context.on_round_end(self.current_accumulator.round_number(), true);
+
 				return Ok(Async::Ready(committed))
 			}
 			_ => None,
@@ -693,6 +700,11 @@ impl<C: Context> Strategy<C> {
 		self.round_timeout = context.begin_round_timeout(round).fuse();
 		self.local_state = LocalState::Start;
 
+		context.on_round_end(
+			self.current_accumulator.round_number(),
+			self.current_accumulator.proposal().is_some(),
+		);
+
 		let new_future = Accumulator::new(
 			round + 1,
 			threshold,
@@ -143,6 +143,9 @@ pub trait Proposer {
 	/// Determine the proposer for a given round. This should be a deterministic function
 	/// with consistent results across all authorities.
 	fn round_proposer(&self, round_number: usize, authorities: &[AuthorityId]) -> AuthorityId;
+
+	/// Note that a round has ended, and whether its proposer was observed to make a proposal.
+	fn on_round_end(&self, _round_number: usize, _was_proposed: bool) {}
 }
 
 /// Block import trait.
@@ -213,6 +216,10 @@ impl<P: Proposer> generic::Context for BftInstance<P> {
 			.map_err(|_| Error::from(ErrorKind::FaultyTimer))
 			.map_err(Into::into))
 	}
+
+	fn on_round_end(&self, round_number: usize, was_proposed: bool) {
+		self.proposer.on_round_end(round_number, was_proposed);
+	}
 }
 
 /// A future that resolves either when canceled (witnessing a block from the network at same height)
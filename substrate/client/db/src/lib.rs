@@ -23,7 +23,6 @@ extern crate kvdb;
 extern crate hashdb;
 extern crate memorydb;
 extern crate parking_lot;
-extern crate patricia_trie;
 extern crate substrate_state_machine as state_machine;
 extern crate substrate_primitives as primitives;
 extern crate substrate_runtime_support as runtime_support;
@@ -35,23 +34,25 @@ extern crate log;
 #[cfg(test)]
 extern crate kvdb_memorydb;
 
+mod state_db;
+
 use std::sync::Arc;
 use std::path::PathBuf;
-use std::collections::HashMap;
 
 use codec::Slicable;
 use ethereum_types::H256 as TrieH256;
-use hashdb::{DBValue, HashDB};
+use hashdb::DBValue;
 use kvdb_rocksdb::{Database, DatabaseConfig};
 use kvdb::{KeyValueDB, DBTransaction};
 use memorydb::MemoryDB;
 use parking_lot::RwLock;
-use patricia_trie::{TrieDB, TrieDBMut, TrieError, Trie, TrieMut};
-use primitives::blake2_256;
 use primitives::block::{self, Id as BlockId, HeaderHash};
 use runtime_support::Hashable;
 use state_machine::backend::Backend as StateBackend;
 use state_machine::CodeExecutor;
+use state_db::StateDb;
+
+pub use state_db::PruningMode;
 
 /// Database settings.
 pub struct DatabaseSettings {
@@ -59,6 +60,8 @@ pub struct DatabaseSettings {
 	pub cache_size: Option<usize>,
 	/// Path to the database.
 	pub path: PathBuf,
+	/// State pruning mode.
+	pub pruning: PruningMode,
 }
 
 /// Create an instance of db-backed client.
@@ -79,15 +82,63 @@ pub fn new_client<E, F>(
 mod columns {
 	pub const META: Option<u32> = Some(0);
 	pub const STATE: Option<u32> = Some(1);
+	/// Canonical chain index: block number to block hash.
 	pub const BLOCK_INDEX: Option<u32> = Some(2);
 	pub const HEADER: Option<u32> = Some(3);
 	pub const BODY: Option<u32> = Some(4);
 	pub const JUSTIFICATION: Option<u32> = Some(5);
-	pub const NUM_COLUMNS: u32 = 6;
+	pub const STATE_REFS: Option<u32> = Some(6);
+	pub const JOURNAL: Option<u32> = Some(7);
+	pub const NUM_COLUMNS: u32 = 8;
 }
 
 mod meta {
 	pub const BEST_BLOCK: &[u8; 4] = b"best";
+	pub const LEAVES: &[u8; 6] = b"leaves";
+	pub const VERSION: &[u8; 7] = b"version";
+	pub const PRUNING_MODE: &[u8; 7] = b"pruning";
+}
+
+/// Version of the database layout. Bump when the meaning of existing columns changes; databases
+/// of another version are refused, as they would be read inconsistently.
+const DB_VERSION: u32 = 1;
+
+/// Check the layout version and the pruning mode of `db`, stamping both if the database is new.
+/// The reference counts of trie nodes are only maintained in the mode the database was created
+/// with, so opening it in another mode would prune nodes still in use.
+fn check_meta(db: &KeyValueDB, pruning: PruningMode) -> Result<(), client::error::Error> {
+	match db.get(columns::META, meta::VERSION).map_err(db_err)? {
+		Some(version) => match u32::decode(&mut &version[..]) {
+			Some(DB_VERSION) => check_pruning_mode(db, pruning),
+			Some(version) => Err(client::error::ErrorKind::Backend(
+				format!("Database version {} is not supported, expected {}; please resync", version, DB_VERSION)
+			).into()),
+			None => Err(client::error::ErrorKind::Backend("Error decoding database version".into()).into()),
+		},
+		None if db.get(columns::META, meta::BEST_BLOCK).map_err(db_err)?.is_some() =>
+			Err(client::error::ErrorKind::Backend(
+				format!("Database predates state pruning, expected version {}; please resync", DB_VERSION)
+			).into()),
+		None => {
+			let mut transaction = DBTransaction::new();
+			transaction.put(columns::META, meta::VERSION, &DB_VERSION.encode());
+			transaction.put(columns::META, meta::PRUNING_MODE, &pruning.encode());
+			db.write(transaction).map_err(db_err)
+		}
+	}
+}
+
+fn check_pruning_mode(db: &KeyValueDB, pruning: PruningMode) -> Result<(), client::error::Error> {
+	match db.get(columns::META, meta::PRUNING_MODE).map_err(db_err)? {
+		Some(mode) => match PruningMode::decode(&mut &mode[..]) {
+			Some(mode) if mode == pruning => Ok(()),
+			Some(mode) => Err(client::error::ErrorKind::Backend(
+				format!("Database was created with pruning mode {:?}, but {:?} was requested", mode, pruning)
+			).into()),
+			None => Err(client::error::ErrorKind::Backend("Error decoding database pruning mode".into()).into()),
+		},
+		None => Err(client::error::ErrorKind::Backend("Database pruning mode is unknown; please resync".into()).into()),
+	}
 }
 
 struct PendingBlock {
@@ -126,29 +177,18 @@ fn db_err(err: kvdb::Error) -> client::error::Error {
 	}
 }
 
-/// Block database
+/// Block database. Block data is keyed by block hash, so blocks from all forks are kept.
 pub struct BlockchainDb {
 	db: Arc<KeyValueDB>,
 	meta: RwLock<Meta>,
+	leaves: RwLock<Vec<HeaderHash>>,
 }
 
 impl BlockchainDb {
-	fn id(&self, id: BlockId) -> Result<Option<BlockKey>, client::error::Error> {
+	fn id(&self, id: BlockId) -> Result<Option<HeaderHash>, client::error::Error> {
 		match id {
-			BlockId::Hash(h) => {
-				{
-					let meta = self.meta.read();
-					if meta.best_hash == h {
-						return Ok(Some(number_to_db_key(meta.best_number)));
-					}
-				}
-				self.db.get(columns::BLOCK_INDEX, &h).map(|v| v.map(|v| {
-					let mut key: [u8; 4] = [0; 4];
-					key.copy_from_slice(&v);
-					key
-				})).map_err(db_err)
-			},
-			BlockId::Number(n) => Ok(Some(number_to_db_key(n))),
+			BlockId::Hash(h) => Ok(Some(h)),
+			BlockId::Number(n) => canonical_hash(&*self.db, n),
 		}
 	}
 
@@ -165,8 +205,12 @@ impl BlockchainDb {
 		} else {
 			(Default::default(), Default::default())
 		};
-		let genesis_hash = db.get(columns::HEADER, &number_to_db_key(0)).map_err(db_err)?
-			.map(|b| blake2_256(&b)).unwrap_or_default().into();
+		let genesis_hash = canonical_hash(&*db, 0)?.unwrap_or_default();
+		let leaves = match db.get(columns::META, meta::LEAVES).map_err(db_err)? {
+			Some(leaves) => Vec::<HeaderHash>::decode(&mut &leaves[..])
+				.ok_or_else(|| client::error::ErrorKind::Backend("Error decoding leaves".into()))?,
+			None => Vec::new(),
+		};
 
 		Ok(BlockchainDb {
 			db,
@@ -174,14 +218,15 @@ impl BlockchainDb {
 				best_hash,
 				best_number,
 				genesis_hash,
-			})
+			}),
+			leaves: RwLock::new(leaves),
 		})
 	}
 
 	fn read_db(&self, id: BlockId, column: Option<u32>) -> Result<Option<DBValue>, client::error::Error> {
-		self.id(id).and_then(|key|
-		 match key {
-			 Some(key) => self.db.get(column, &key).map_err(db_err),
+		self.id(id).and_then(|hash|
+		 match hash {
+			 Some(hash) => self.db.get(column, &hash).map_err(db_err),
 			 None => Ok(None),
 		 })
 	}
@@ -198,6 +243,12 @@ impl BlockchainDb {
 	}
 }
 
+// Read hash of the canonical block with given number.
+fn canonical_hash(db: &KeyValueDB, number: block::Number) -> Result<Option<HeaderHash>, client::error::Error> {
+	Ok(db.get(columns::BLOCK_INDEX, &number_to_db_key(number)).map_err(db_err)?
+		.map(|hash| HeaderHash::from_slice(&hash)))
+}
+
 impl client::blockchain::Backend for BlockchainDb {
 	fn header(&self, id: BlockId) -> Result<Option<block::Header>, client::error::Error> {
 		match self.read_db(id, columns::HEADER)? {
@@ -240,7 +291,7 @@ impl client::blockchain::Backend for BlockchainDb {
 
 	fn status(&self, id: BlockId) -> Result<client::blockchain::BlockStatus, client::error::Error> {
 		let exists = match id {
-			BlockId::Hash(_) => self.id(id)?.is_some(),
+			BlockId::Hash(h) => self.db.get(columns::HEADER, &h).map_err(db_err)?.is_some(),
 			BlockId::Number(n) => n <= self.meta.read().best_number,
 		};
 		match exists {
@@ -250,15 +301,18 @@ impl client::blockchain::Backend for BlockchainDb {
 	}
 
 	fn hash(&self, number: block::Number) -> Result<Option<block::HeaderHash>, client::error::Error> {
-		self.read_db(BlockId::Number(number), columns::HEADER).map(|x|
-			x.map(|raw| blake2_256(&raw[..])).map(Into::into)
-		)
+		self.id(BlockId::Number(number))
+	}
+
+	fn leaves(&self) -> Result<Vec<block::HeaderHash>, client::error::Error> {
+		Ok(self.leaves.read().clone())
 	}
 }
 
 /// Database transaction
 pub struct BlockImportOperation {
 	old_state: DbState,
+	storage: Arc<StorageDb>,
 	updates: MemoryDB,
 	pending_block: Option<PendingBlock>,
 }
@@ -287,146 +341,35 @@ impl client::backend::BlockImportOperation for BlockImportOperation {
 	}
 
 	fn reset_storage<I: Iterator<Item=(Vec<u8>, Vec<u8>)>>(&mut self, iter: I) -> Result<(), client::error::Error> {
-		// TODO: wipe out existing trie.
-		let (_, update) = self.old_state.storage_root(iter.into_iter().map(|(k, v)| (k, Some(v))));
+		// new state is built from scratch, ignoring the existing trie
+		let empty_state = DbState::with_storage_for_genesis(self.storage.clone());
+		let (_, update) = empty_state.storage_root(iter.into_iter().map(|(k, v)| (k, Some(v))));
 		self.updates = update;
 		Ok(())
 	}
 }
 
-struct Ephemeral<'a> {
-	backing: &'a KeyValueDB,
-	overlay: &'a mut MemoryDB,
-}
-
-impl<'a> HashDB for Ephemeral<'a> {
-	fn keys(&self) -> HashMap<TrieH256, i32> {
-		self.overlay.keys() // TODO: iterate backing
-	}
-
-	fn get(&self, key: &TrieH256) -> Option<DBValue> {
-		match self.overlay.raw(key) {
-			Some((val, i)) => {
-				if i <= 0 {
-					None
-				} else {
-					Some(val)
-				}
-			}
-			None => {
-				match self.backing.get(::columns::STATE, &key.0[..]) {
-					Ok(x) => x,
-					Err(e) => {
-						warn!("Failed to read from DB: {}", e);
-						None
-					}
-				}
-			}
-		}
-	}
-
-	fn contains(&self, key: &TrieH256) -> bool {
-		self.get(key).is_some()
-	}
-
-	fn insert(&mut self, value: &[u8]) -> TrieH256 {
-		self.overlay.insert(value)
-	}
-
-	fn emplace(&mut self, key: TrieH256, value: DBValue) {
-		self.overlay.emplace(key, value)
-	}
-
-	fn remove(&mut self, key: &TrieH256) {
-		self.overlay.remove(key)
-	}
-}
-
 /// DB-backed patricia trie state, transaction type is an overlay of changes to commit.
-#[derive(Clone)]
-pub struct DbState {
+pub type DbState = state_machine::TrieBackend;
+
+/// Trie nodes storage, backed by the `STATE` column of the database.
+struct StorageDb {
 	db: Arc<KeyValueDB>,
-	root: TrieH256,
 }
 
-impl state_machine::Backend for DbState {
-	type Error = client::error::Error;
-	type Transaction = MemoryDB;
-
-	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		let mut read_overlay = MemoryDB::default();
-		let eph = Ephemeral {
-			backing: &*self.db,
-			overlay: &mut read_overlay,
-		};
-
-		let map_e = |e: Box<TrieError>| ::client::error::Error::from(format!("Trie lookup error: {}", e));
-
-		TrieDB::new(&eph, &self.root).map_err(map_e)?
-			.get(key).map(|x| x.map(|val| val.to_vec())).map_err(map_e)
-	}
-
-	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
-		let mut read_overlay = MemoryDB::default();
-		let eph = Ephemeral {
-			backing: &*self.db,
-			overlay: &mut read_overlay,
-		};
-
-		let collect_all = || -> Result<_, Box<TrieError>> {
-			let trie = TrieDB::new(&eph, &self.root)?;
-			let mut v = Vec::new();
-			for x in trie.iter()? {
-				let (key, value) = x?;
-				v.push((key.to_vec(), value.to_vec()));
-			}
-
-			Ok(v)
-		};
-
-		match collect_all() {
-			Ok(v) => v,
-			Err(e) => {
-				debug!("Error extracting trie values: {}", e);
-				Vec::new()
-			}
-		}
-	}
-
-	fn storage_root<I>(&self, delta: I) -> ([u8; 32], MemoryDB)
-		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
-	{
-		let mut write_overlay = MemoryDB::default();
-		let mut root = self.root;
-		{
-			let mut eph = Ephemeral {
-				backing: &*self.db,
-				overlay: &mut write_overlay,
-			};
-
-			let mut trie = TrieDBMut::from_existing(&mut eph, &mut root).expect("prior state root to exist"); // TODO: handle gracefully
-			for (key, change) in delta {
-				let result = match change {
-					Some(val) => trie.insert(&key, &val),
-					None => trie.remove(&key), // TODO: archive mode
-				};
-
-				if let Err(e) = result {
-					warn!("Failed to write to trie: {}", e);
-				}
-			}
-		}
-
-		(root.0.into(), write_overlay)
+impl state_machine::Storage for StorageDb {
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String> {
+		self.db.get(columns::STATE, &key.0[..]).map_err(|e| format!("Database backend error: {:?}", e))
 	}
 }
 
 /// Disk backend. Keeps data in a key-value store. In archive mode, trie nodes are kept from all blocks.
-/// Otherwise, trie nodes are kept only from the most recent block.
+/// Otherwise, trie nodes are kept only for the most recent blocks within the pruning window.
 pub struct Backend {
 	db: Arc<KeyValueDB>,
+	storage: Arc<StorageDb>,
 	blockchain: BlockchainDb,
-	archive: bool,
+	state_db: StateDb,
 }
 
 impl Backend {
@@ -438,25 +381,80 @@ impl Backend {
 		let path = config.path.to_str().ok_or_else(|| client::error::ErrorKind::Backend("Invalid database path".into()))?;
 		let db = Arc::new(Database::open(&db_config, &path).map_err(db_err)?);
 
-		Backend::from_kvdb(db as Arc<_>, true)
+		Backend::from_kvdb(db as Arc<_>, config.pruning)
 	}
 
 	#[cfg(test)]
-	fn new_test() -> Backend {
+	fn new_test(pruning: PruningMode) -> Backend {
 		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
 
-		Backend::from_kvdb(db as Arc<_>, false).expect("failed to create test-db")
+		Backend::from_kvdb(db as Arc<_>, pruning).expect("failed to create test-db")
 	}
 
-	fn from_kvdb(db: Arc<KeyValueDB>, archive: bool) -> Result<Backend, client::error::Error> {
+	fn from_kvdb(db: Arc<KeyValueDB>, pruning: PruningMode) -> Result<Backend, client::error::Error> {
+		check_meta(&*db, pruning)?;
 		let blockchain = BlockchainDb::new(db.clone())?;
 
 		Ok(Backend {
+			storage: Arc::new(StorageDb { db: db.clone() }),
 			db,
 			blockchain,
-			archive
+			state_db: StateDb::new(pruning),
 		})
 	}
+
+	/// Revert up to `n` most recent blocks of the best chain, along with their state. Blocks whose
+	/// state has already been pruned can not be reverted. Returns the number of blocks reverted.
+	pub fn revert(&self, n: block::Number) -> Result<block::Number, client::error::Error> {
+		use client::blockchain::Backend as BcBackend;
+
+		let mut reverted = 0;
+		while reverted < n {
+			let best = self.blockchain.meta.read().clone();
+			if best.best_number == 0 {
+				break;
+			}
+
+			let mut transaction = DBTransaction::new();
+			if !self.state_db.revert_block(&*self.db, &mut transaction, best.best_number, &best.best_hash)? {
+				break;
+			}
+
+			let hash = best.best_hash;
+			let parent_hash = self.blockchain.header(BlockId::Hash(hash))?
+				.ok_or_else(|| client::error::ErrorKind::UnknownBlock(BlockId::Hash(hash)))?
+				.parent_hash;
+			transaction.delete(columns::HEADER, &hash);
+			transaction.delete(columns::BODY, &hash);
+			transaction.delete(columns::JUSTIFICATION, &hash);
+			transaction.delete(columns::BLOCK_INDEX, &number_to_db_key(best.best_number));
+			transaction.put(columns::META, meta::BEST_BLOCK, &parent_hash);
+
+			// parent becomes a leaf, unless there are other forks built on top of it
+			let mut leaves = self.blockchain.leaves.read().clone();
+			leaves.retain(|leaf| *leaf != hash);
+			let mut has_children = false;
+			for leaf in &leaves {
+				let route = client::blockchain::tree_route(&self.blockchain, BlockId::Hash(parent_hash), BlockId::Hash(*leaf))?;
+				if route.common_block().hash == parent_hash && !route.enacted().is_empty() {
+					has_children = true;
+					break;
+				}
+			}
+			if !has_children {
+				leaves.push(parent_hash);
+			}
+			transaction.put(columns::META, meta::LEAVES, &leaves.encode());
+
+			self.db.write(transaction).map_err(db_err)?;
+			*self.blockchain.leaves.write() = leaves;
+			self.blockchain.update_meta(parent_hash, best.best_number - 1, true);
+			debug!("DB Reverted {:?} ({})", hash, best.best_number);
+			reverted += 1;
+		}
+
+		Ok(reverted)
+	}
 }
 
 impl client::backend::Backend for Backend {
@@ -469,36 +467,77 @@ impl client::backend::Backend for Backend {
 		Ok(BlockImportOperation {
 			pending_block: None,
 			old_state: state,
+			storage: self.storage.clone(),
 			updates: MemoryDB::default(),
 		})
 	}
 
-	fn commit_operation(&self, mut operation: Self::BlockImportOperation) -> Result<(), client::error::Error> {
+	fn commit_operation(&self, operation: Self::BlockImportOperation) -> Result<(), client::error::Error> {
 		let mut transaction = DBTransaction::new();
 		if let Some(pending_block) = operation.pending_block {
 			let hash: block::HeaderHash = pending_block.header.blake2_256().into();
-			let number = pending_block.header.number;;
-			let key = number_to_db_key(pending_block.header.number);
-			transaction.put(columns::HEADER, &key, &pending_block.header.encode());
+			let number = pending_block.header.number;
+			let parent_hash = pending_block.header.parent_hash;
+			transaction.put(columns::HEADER, &hash, &pending_block.header.encode());
 			if let Some(body) = pending_block.body {
-				transaction.put(columns::BODY, &key, &body.encode());
+				transaction.put(columns::BODY, &hash, &body.encode());
 			}
 			if let Some(justification) = pending_block.justification {
-				transaction.put(columns::JUSTIFICATION, &key, &justification.encode());
+				transaction.put(columns::JUSTIFICATION, &hash, &justification.encode());
 			}
-			transaction.put(columns::BLOCK_INDEX, &hash, &key);
-			if pending_block.is_best {
-				transaction.put(columns::META, meta::BEST_BLOCK, &key);
+
+			let mut leaves = self.blockchain.leaves.read().clone();
+			leaves.retain(|leaf| *leaf != parent_hash);
+			if !leaves.contains(&hash) {
+				leaves.push(hash);
 			}
-			for (key, (val, rc)) in operation.updates.drain() {
-				if rc > 0 {
-					transaction.put(columns::STATE, &key.0[..], &val);
-				} else if rc < 0 && !self.archive {
-					transaction.delete(columns::STATE, &key.0[..]);
+			transaction.put(columns::META, meta::LEAVES, &leaves.encode());
+
+			// the journal of numbers which have left the pruning window is never visited again.
+			let best = self.blockchain.meta.read().clone();
+			if self.state_db.pruned_number(best.best_number).map_or(false, |pruned| number <= pruned) {
+				return Err(client::error::ErrorKind::Backend(
+					format!("Block {} is below the pruning window", number)
+				).into());
+			}
+
+			let mut pruned = Vec::new();
+			if pending_block.is_best {
+				let mut enacted = Vec::new();
+				if number > 0 && parent_hash != best.best_hash {
+					// reorganisation: rewrite the canonical index down to the common ancestor
+					let route = client::blockchain::tree_route(&self.blockchain, BlockId::Hash(best.best_hash), BlockId::Hash(parent_hash))?;
+					for retracted in route.retracted() {
+						transaction.delete(columns::BLOCK_INDEX, &number_to_db_key(retracted.number));
+					}
+					for entry in route.enacted() {
+						transaction.put(columns::BLOCK_INDEX, &number_to_db_key(entry.number), &entry.hash);
+					}
+					enacted.extend(route.enacted().iter().map(|entry| (entry.number, entry.hash)));
+				}
+				transaction.put(columns::BLOCK_INDEX, &number_to_db_key(number), &hash);
+				transaction.put(columns::META, meta::BEST_BLOCK, &hash);
+				enacted.push((number, hash));
+
+				// numbers which have left the pruning window since the previous best block
+				if let Some(last) = self.state_db.pruned_number(number) {
+					let first = self.state_db.pruned_number(best.best_number + 1).unwrap_or(0);
+					for pruned_number in first..last + 1 {
+						let canonical = match enacted.iter().find(|entry| entry.0 == pruned_number) {
+							Some(entry) => Some(entry.1),
+							None => canonical_hash(&*self.db, pruned_number)?,
+						};
+						if let Some(canonical) = canonical {
+							pruned.push((pruned_number, canonical));
+						}
+					}
 				}
 			}
+
+			self.state_db.insert_block(&*self.db, &mut transaction, number, &hash, &parent_hash, operation.updates, &pruned)?;
 			debug!("DB Commit {:?} ({})", hash, number);
 			self.db.write(transaction).map_err(db_err)?;
+			*self.blockchain.leaves.write() = leaves;
 			self.blockchain.update_meta(hash, number, pending_block.is_best);
 		}
 		Ok(())
@@ -513,25 +552,20 @@ impl client::backend::Backend for Backend {
 
 		// special case for genesis initialization
 		match block {
-			BlockId::Hash(h) if h == Default::default() => {
-				let mut root = TrieH256::default();
-				let mut db = MemoryDB::default();
-				TrieDBMut::new(&mut db, &mut root);
-
-				return Ok(DbState {
-					db: self.db.clone(),
-					root,
-				})
-			}
+			BlockId::Hash(h) if h == Default::default() =>
+				return Ok(DbState::with_storage_for_genesis(self.storage.clone())),
 			_ => {}
 		}
 
-		self.blockchain.header(block).and_then(|maybe_hdr| maybe_hdr.map(|hdr| {
-			DbState {
-				db: self.db.clone(),
-				root: hdr.state_root.0.into(),
-			}
-		}).ok_or_else(|| client::error::ErrorKind::UnknownBlock(block).into()))
+		let header = self.blockchain.header(block)?
+			.ok_or_else(|| client::error::ErrorKind::UnknownBlock(block))?;
+		let hash: HeaderHash = header.blake2_256().into();
+		let is_canonical = canonical_hash(&*self.db, header.number)? == Some(hash);
+		if !self.state_db.is_available(header.number, self.blockchain.meta.read().best_number, is_canonical) {
+			return Err(client::error::ErrorKind::Backend(format!("State of block {} has been pruned", header.number)).into());
+		}
+
+		Ok(DbState::with_storage(self.storage.clone(), header.state_root.0.into()))
 	}
 }
 
@@ -543,10 +577,11 @@ mod tests {
 	use client::backend::Backend as BTrait;
 	use client::backend::BlockImportOperation as Op;
 	use client::blockchain::Backend as BCTrait;
+	use hashdb::HashDB;
 
 	#[test]
 	fn block_hash_inserted_correctly() {
-		let db = Backend::new_test();
+		let db = Backend::new_test(PruningMode::Constrained(0));
 		for i in 0..10 {
 			assert!(db.blockchain().hash(i).unwrap().is_none());
 
@@ -560,7 +595,7 @@ mod tests {
 				let mut op = db.begin_operation(id).unwrap();
 				let header = block::Header {
 					number: i,
-					parent_hash: Default::default(),
+					parent_hash: if i == 0 { Default::default() } else { db.blockchain().hash(i - 1).unwrap().unwrap() },
 					state_root: Default::default(),
 					digest: Default::default(),
 					extrinsics_root: Default::default(),
@@ -581,7 +616,7 @@ mod tests {
 
 	#[test]
 	fn set_state_data() {
-		let db = Backend::new_test();
+		let db = Backend::new_test(PruningMode::Constrained(0));
 		{
 			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
 			let mut header = block::Header {
@@ -625,7 +660,7 @@ mod tests {
 			let mut op = db.begin_operation(BlockId::Number(0)).unwrap();
 			let mut header = block::Header {
 				number: 1,
-				parent_hash: Default::default(),
+				parent_hash: db.blockchain().hash(0).unwrap().unwrap(),
 				state_root: Default::default(),
 				digest: Default::default(),
 				extrinsics_root: Default::default(),
@@ -660,7 +695,7 @@ mod tests {
 	#[test]
 	fn delete_only_when_negative_rc() {
 		let key;
-		let db = Backend::new_test();
+		let db = Backend::new_test(PruningMode::Constrained(0));
 
 		{
 			let mut op = db.begin_operation(BlockId::Hash(Default::default())).unwrap();
@@ -699,7 +734,7 @@ mod tests {
 			let mut op = db.begin_operation(BlockId::Number(0)).unwrap();
 			let mut header = block::Header {
 				number: 1,
-				parent_hash: Default::default(),
+				parent_hash: db.blockchain().hash(0).unwrap().unwrap(),
 				state_root: Default::default(),
 				digest: Default::default(),
 				extrinsics_root: Default::default(),
@@ -730,8 +765,8 @@ mod tests {
 		{
 			let mut op = db.begin_operation(BlockId::Number(1)).unwrap();
 			let mut header = block::Header {
-				number: 1,
-				parent_hash: Default::default(),
+				number: 2,
+				parent_hash: db.blockchain().hash(1).unwrap().unwrap(),
 				state_root: Default::default(),
 				digest: Default::default(),
 				extrinsics_root: Default::default(),
@@ -758,4 +793,165 @@ mod tests {
 			assert!(db.db.get(::columns::STATE, &key.0[..]).unwrap().is_none());
 		}
 	}
+
+	fn insert_block(db: &Backend, parent: Option<HeaderHash>, number: block::Number, value: u8, is_best: bool) -> HeaderHash {
+		let id = BlockId::Hash(parent.unwrap_or_default());
+		let mut op = db.begin_operation(id).unwrap();
+		let storage = vec![(vec![1, 2, 3], Some(vec![value]))];
+		let (root, overlay) = op.old_state.storage_root(storage.into_iter());
+		op.update_storage(overlay).unwrap();
+		let header = block::Header {
+			number,
+			parent_hash: parent.unwrap_or_default(),
+			state_root: root.into(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.blake2_256().into();
+
+		op.set_block_data(header, Some(vec![]), None, is_best).unwrap();
+		db.commit_operation(op).unwrap();
+		hash
+	}
+
+	fn insert_blocks(db: &Backend, count: block::Number) {
+		let mut parent = None;
+		for i in 0..count {
+			parent = Some(insert_block(db, parent, i, i as u8, true));
+		}
+	}
+
+	#[test]
+	fn state_is_pruned_outside_of_window() {
+		let db = Backend::new_test(PruningMode::Constrained(2));
+		insert_blocks(&db, 5);
+
+		assert!(db.state_at(BlockId::Number(1)).is_err());
+		for i in 2..5 {
+			let state = db.state_at(BlockId::Number(i)).unwrap();
+			assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![i as u8]));
+		}
+	}
+
+	#[test]
+	fn state_is_kept_in_archive_mode() {
+		let db = Backend::new_test(PruningMode::ArchiveAll);
+		insert_blocks(&db, 5);
+
+		for i in 0..5 {
+			let state = db.state_at(BlockId::Number(i)).unwrap();
+			assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![i as u8]));
+		}
+	}
+
+	#[test]
+	fn revert_within_pruning_window() {
+		let db = Backend::new_test(PruningMode::Constrained(2));
+		insert_blocks(&db, 5);
+
+		assert_eq!(db.revert(5).unwrap(), 2);
+		assert_eq!(db.blockchain().info().unwrap().best_number, 2);
+		assert!(db.blockchain().header(BlockId::Number(3)).unwrap().is_none());
+		let state = db.state_at(BlockId::Number(2)).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![2]));
+	}
+
+	#[test]
+	fn reorg_switches_canonical_chain() {
+		let db = Backend::new_test(PruningMode::Constrained(10));
+		let a0 = insert_block(&db, None, 0, 0, true);
+		let a1 = insert_block(&db, Some(a0), 1, 1, true);
+		let a2 = insert_block(&db, Some(a1), 2, 2, true);
+		let b1 = insert_block(&db, Some(a0), 1, 11, false);
+		let b2 = insert_block(&db, Some(b1), 2, 12, false);
+		assert_eq!(db.blockchain().hash(1).unwrap(), Some(a1));
+
+		let b3 = insert_block(&db, Some(b2), 3, 13, true);
+		assert_eq!(db.blockchain().info().unwrap().best_hash, b3);
+		assert_eq!(db.blockchain().hash(1).unwrap(), Some(b1));
+		assert_eq!(db.blockchain().hash(2).unwrap(), Some(b2));
+		assert_eq!(db.blockchain().header(BlockId::Hash(a2)).unwrap().unwrap().number, 2);
+		assert_eq!(db.blockchain().leaves().unwrap(), vec![a2, b3]);
+
+		let state = db.state_at(BlockId::Hash(a2)).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![2]));
+		let state = db.state_at(BlockId::Number(2)).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![12]));
+	}
+
+	#[test]
+	fn non_canonical_state_is_pruned() {
+		let db = Backend::new_test(PruningMode::Constrained(1));
+		let a0 = insert_block(&db, None, 0, 0, true);
+		let a1 = insert_block(&db, Some(a0), 1, 1, true);
+		let b1 = insert_block(&db, Some(a0), 1, 11, false);
+		assert!(db.state_at(BlockId::Hash(b1)).is_ok());
+
+		insert_block(&db, Some(a1), 2, 2, true);
+		assert!(db.state_at(BlockId::Hash(b1)).is_err());
+		let state = db.state_at(BlockId::Hash(a1)).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![1]));
+	}
+
+	#[test]
+	fn database_of_other_version_is_refused() {
+		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::ArchiveAll).is_ok());
+		assert_eq!(db.get(columns::META, meta::VERSION).unwrap().map(|v| v.to_vec()), Some(DB_VERSION.encode()));
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::ArchiveAll).is_ok());
+
+		let mut transaction = DBTransaction::new();
+		transaction.put(columns::META, meta::VERSION, &(DB_VERSION + 1).encode());
+		db.write(transaction).unwrap();
+		assert!(Backend::from_kvdb(db as Arc<_>, PruningMode::ArchiveAll).is_err());
+	}
+
+	#[test]
+	fn database_is_refused_in_other_pruning_mode() {
+		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let backend = Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::ArchiveAll).unwrap();
+		insert_blocks(&backend, 3);
+		drop(backend);
+
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::Constrained(1)).is_err());
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::default()).is_err());
+		let backend = Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::ArchiveAll).unwrap();
+		assert!(backend.state_at(BlockId::Number(0)).is_ok());
+		drop(backend);
+
+		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::Constrained(2)).is_ok());
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::Constrained(3)).is_err());
+		assert!(Backend::from_kvdb(db.clone() as Arc<_>, PruningMode::ArchiveAll).is_err());
+		assert!(Backend::from_kvdb(db as Arc<_>, PruningMode::Constrained(2)).is_ok());
+	}
+
+	#[test]
+	fn block_below_pruning_window_is_refused() {
+		let db = Backend::new_test(PruningMode::Constrained(1));
+		let a0 = insert_block(&db, None, 0, 0, true);
+		let a1 = insert_block(&db, Some(a0), 1, 1, true);
+		insert_block(&db, Some(a1), 2, 2, true);
+
+		let mut op = db.begin_operation(BlockId::Hash(a1)).unwrap();
+		let header = block::Header {
+			number: 1,
+			parent_hash: a0,
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		op.set_block_data(header, Some(vec![]), None, false).unwrap();
+		assert!(db.commit_operation(op).is_err());
+		assert_eq!(db.blockchain().leaves().unwrap().len(), 1);
+	}
+
+	#[test]
+	fn unversioned_database_is_refused() {
+		let db = Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS));
+		let mut transaction = DBTransaction::new();
+		transaction.put(columns::META, meta::BEST_BLOCK, &[1u8; 32]);
+		db.write(transaction).unwrap();
+		assert!(Backend::from_kvdb(db as Arc<_>, PruningMode::ArchiveAll).is_err());
+	}
 }
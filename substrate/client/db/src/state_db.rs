@@ -0,0 +1,540 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State database maintenance. Trie nodes are reference-counted and every block
+//! journals the nodes it has inserted and deleted. Deletions are only applied once
+//! the block leaves the pruning window, so any state within the window is readable
+//! and the most recent blocks can be reverted. When a block number leaves the window,
+//! the canonical block has its deletions applied and the blocks of all other forks
+//! have their insertions discarded. The insertions of a discarded block whose children are
+//! still within the window are handed down to those children, and only discarded along
+//! with the last of its descendants.
+
+use std::collections::HashMap;
+
+use codec::{Input, Slicable};
+use ethereum_types::H256 as TrieH256;
+use hashdb::DBValue;
+use kvdb::{KeyValueDB, DBTransaction};
+use memorydb::MemoryDB;
+use primitives::block::{self, HeaderHash};
+
+use {columns, db_err, number_to_db_key};
+
+/// Default number of blocks to keep the state for.
+pub const DEFAULT_PRUNING_BLOCKS: block::Number = 256;
+
+/// State pruning mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruningMode {
+	/// Keep the state of the given number of most recent blocks. Older states are discarded.
+	Constrained(block::Number),
+	/// Keep the state of all blocks.
+	ArchiveAll,
+}
+
+impl Default for PruningMode {
+	fn default() -> PruningMode {
+		PruningMode::Constrained(DEFAULT_PRUNING_BLOCKS)
+	}
+}
+
+impl Slicable for PruningMode {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(PruningMode::Constrained(Slicable::decode(input)?)),
+			1 => Some(PruningMode::ArchiveAll),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		match *self {
+			PruningMode::Constrained(keep) => {
+				v.push(0);
+				keep.using_encoded(|s| v.extend(s));
+			}
+			PruningMode::ArchiveAll => v.push(1),
+		}
+
+		v
+	}
+}
+
+/// Trie nodes inserted and deleted by a single block.
+#[derive(Default)]
+struct JournalRecord {
+	parent: HeaderHash,
+	inserted: Vec<TrieH256>,
+	deleted: Vec<TrieH256>,
+	/// Nodes inserted by discarded ancestors of the block, which its state still refers to.
+	inherited: Vec<TrieH256>,
+}
+
+impl JournalRecord {
+	fn encode(&self) -> Vec<u8> {
+		let inserted: Vec<[u8; 32]> = self.inserted.iter().map(|k| k.0).collect();
+		let deleted: Vec<[u8; 32]> = self.deleted.iter().map(|k| k.0).collect();
+		let inherited: Vec<[u8; 32]> = self.inherited.iter().map(|k| k.0).collect();
+		(self.parent, inserted, deleted, inherited).encode()
+	}
+
+	fn decode(data: &[u8]) -> Option<JournalRecord> {
+		<(HeaderHash, Vec<[u8; 32]>, Vec<[u8; 32]>, Vec<[u8; 32]>)>::decode(&mut &data[..])
+			.map(|(parent, inserted, deleted, inherited)| JournalRecord {
+				parent,
+				inserted: inserted.into_iter().map(Into::into).collect(),
+				deleted: deleted.into_iter().map(Into::into).collect(),
+				inherited: inherited.into_iter().map(Into::into).collect(),
+			})
+	}
+}
+
+/// Pending reference count changes of the trie nodes.
+#[derive(Default)]
+struct RefChanges {
+	values: HashMap<TrieH256, DBValue>,
+	deltas: HashMap<TrieH256, i64>,
+}
+
+impl RefChanges {
+	fn insert(&mut self, key: TrieH256, value: DBValue, count: i64) {
+		self.values.entry(key).or_insert(value);
+		*self.deltas.entry(key).or_insert(0) += count;
+	}
+
+	fn add(&mut self, key: TrieH256) {
+		*self.deltas.entry(key).or_insert(0) += 1;
+	}
+
+	fn remove(&mut self, key: TrieH256) {
+		*self.deltas.entry(key).or_insert(0) -= 1;
+	}
+
+	fn apply(self, db: &KeyValueDB, transaction: &mut DBTransaction) -> Result<(), ::client::error::Error> {
+		for (key, delta) in self.deltas {
+			if delta == 0 {
+				continue;
+			}
+
+			let refs = db.get(columns::STATE_REFS, &key.0[..]).map_err(db_err)?
+				.and_then(|refs| u32::decode(&mut &refs[..]))
+				.unwrap_or(0) as i64;
+			let new_refs = refs + delta;
+			if new_refs <= 0 {
+				transaction.delete(columns::STATE, &key.0[..]);
+				transaction.delete(columns::STATE_REFS, &key.0[..]);
+				continue;
+			}
+
+			if refs == 0 {
+				match self.values.get(&key) {
+					Some(value) => transaction.put(columns::STATE, &key.0[..], value),
+					None => {
+						warn!("Trie node {:?} is referenced, but its value is unknown", key);
+						continue;
+					},
+				}
+			}
+			transaction.put(columns::STATE_REFS, &key.0[..], &(new_refs as u32).encode());
+		}
+
+		Ok(())
+	}
+}
+
+/// State database maintenance.
+pub struct StateDb {
+	mode: PruningMode,
+}
+
+impl StateDb {
+	/// Create new state database maintenance with given pruning mode.
+	pub fn new(mode: PruningMode) -> Self {
+		StateDb { mode }
+	}
+
+	/// Returns true if the state of the block `number` is kept when the best block is `best`.
+	/// Only the canonical block is kept for the number at the edge of the pruning window.
+	pub fn is_available(&self, number: block::Number, best: block::Number, is_canonical: bool) -> bool {
+		match self.mode {
+			PruningMode::ArchiveAll => true,
+			PruningMode::Constrained(keep) => {
+				let number = number.saturating_add(keep);
+				number > best || (number == best && is_canonical)
+			},
+		}
+	}
+
+	/// Returns the number of the block which leaves the pruning window when the best block becomes `best`.
+	pub fn pruned_number(&self, best: block::Number) -> Option<block::Number> {
+		match self.mode {
+			PruningMode::Constrained(keep) if keep > 0 && best >= keep => Some(best - keep),
+			_ => None,
+		}
+	}
+
+	/// Add trie node changes of the block `number` with hash `hash` and parent `parent` to the
+	/// transaction. `pruned` lists the numbers of blocks leaving the pruning window, in ascending
+	/// order, along with the hashes of the canonical blocks.
+	pub fn insert_block(
+		&self,
+		db: &KeyValueDB,
+		transaction: &mut DBTransaction,
+		number: block::Number,
+		hash: &HeaderHash,
+		parent: &HeaderHash,
+		mut changes: MemoryDB,
+		pruned: &[(block::Number, HeaderHash)],
+	) -> Result<(), ::client::error::Error> {
+		let keep = match self.mode {
+			PruningMode::ArchiveAll => {
+				for (key, (value, rc)) in changes.drain() {
+					if rc > 0 {
+						transaction.put(columns::STATE, &key.0[..], &value);
+					}
+				}
+				return Ok(());
+			},
+			PruningMode::Constrained(keep) => keep,
+		};
+
+		let mut ref_changes = RefChanges::default();
+		let mut record = JournalRecord { parent: *parent, ..Default::default() };
+		for (key, (value, rc)) in changes.drain() {
+			if rc > 0 {
+				ref_changes.insert(key, value, rc as i64);
+				record.inserted.extend(::std::iter::repeat(key).take(rc as usize));
+			} else if rc < 0 {
+				record.deleted.extend(::std::iter::repeat(key).take((-rc) as usize));
+			}
+		}
+
+		if keep == 0 {
+			// deletions are applied immediately
+			record.deleted.into_iter().for_each(|key| ref_changes.remove(key));
+		} else {
+			let mut journaled = read_journaled_hashes(db, number)?;
+			if !journaled.contains(hash) {
+				journaled.push(*hash);
+			}
+			transaction.put(columns::JOURNAL, &number_to_db_key(number), &journaled.encode());
+			transaction.put(columns::JOURNAL, &hash, &record.encode());
+
+			// journal records rewritten by this transaction.
+			let mut rewritten = HashMap::new();
+			for &(number, ref canonical) in pruned {
+				self.prune(db, transaction, &mut ref_changes, &mut rewritten, number, canonical)?;
+			}
+		}
+
+		ref_changes.apply(db, transaction)
+	}
+
+	// Prune the state of all blocks with given number. Deletions of the canonical block are
+	// applied, insertions of the blocks from other forks are discarded once none of their
+	// descendants is left within the pruning window.
+	fn prune(
+		&self,
+		db: &KeyValueDB,
+		transaction: &mut DBTransaction,
+		ref_changes: &mut RefChanges,
+		rewritten: &mut HashMap<HeaderHash, JournalRecord>,
+		number: block::Number,
+		canonical: &HeaderHash,
+	) -> Result<(), ::client::error::Error> {
+		let next_hashes = read_journaled_hashes(db, number + 1)?;
+		for hash in read_journaled_hashes(db, number)? {
+			let record = match rewritten.remove(&hash) {
+				Some(record) => Some(record),
+				None => read_journal(db, &hash)?,
+			};
+			if let Some(record) = record {
+				if hash == *canonical {
+					trace!("Pruning state of block {} ({})", hash, number);
+					record.deleted.into_iter().for_each(|key| ref_changes.remove(key));
+				} else {
+					let mut nodes = record.inserted;
+					nodes.extend(record.inherited);
+
+					let mut children = Vec::new();
+					for child in &next_hashes {
+						let child_record = match rewritten.remove(child) {
+							Some(record) => Some(record),
+							None => read_journal(db, child)?,
+						};
+						match child_record {
+							Some(child_record) => if child_record.parent == hash {
+								children.push((*child, child_record));
+							} else {
+								rewritten.insert(*child, child_record);
+							},
+							None => {},
+						}
+					}
+
+					if children.is_empty() {
+						trace!("Discarding state of non-canonical block {} ({})", hash, number);
+						nodes.into_iter().for_each(|key| ref_changes.remove(key));
+					} else {
+						trace!("Handing state of non-canonical block {} ({}) down to its children", hash, number);
+						// each child holds its own reference to the nodes.
+						for _ in 1..children.len() {
+							nodes.iter().for_each(|key| ref_changes.add(*key));
+						}
+						for (child, mut child_record) in children {
+							child_record.inherited.extend(nodes.iter().cloned());
+							transaction.put(columns::JOURNAL, &child, &child_record.encode());
+							rewritten.insert(child, child_record);
+						}
+					}
+				}
+			}
+			transaction.delete(columns::JOURNAL, &hash);
+		}
+		transaction.delete(columns::JOURNAL, &number_to_db_key(number));
+		Ok(())
+	}
+
+	/// Revert trie node changes, made by the block `number` with hash `hash`. Returns false if
+	/// the changes can not be reverted, because the block has already left the pruning window.
+	pub fn revert_block(&self, db: &KeyValueDB, transaction: &mut DBTransaction, number: block::Number, hash: &HeaderHash) -> Result<bool, ::client::error::Error> {
+		match self.mode {
+			// all states are kept => nothing to revert
+			PruningMode::ArchiveAll => Ok(true),
+			PruningMode::Constrained(_) => {
+				let record = match read_journal(db, hash)? {
+					Some(record) => record,
+					None => return Ok(false),
+				};
+
+				let mut ref_changes = RefChanges::default();
+				record.inserted.into_iter()
+					.chain(record.inherited.into_iter())
+					.for_each(|key| ref_changes.remove(key));
+				transaction.delete(columns::JOURNAL, &hash);
+
+				let mut journaled = read_journaled_hashes(db, number)?;
+				journaled.retain(|journaled| journaled != hash);
+				match journaled.is_empty() {
+					true => transaction.delete(columns::JOURNAL, &number_to_db_key(number)),
+					false => transaction.put(columns::JOURNAL, &number_to_db_key(number), &journaled.encode()),
+				}
+
+				ref_changes.apply(db, transaction)?;
+				Ok(true)
+			},
+		}
+	}
+}
+
+fn read_journaled_hashes(db: &KeyValueDB, number: block::Number) -> Result<Vec<HeaderHash>, ::client::error::Error> {
+	match db.get(columns::JOURNAL, &number_to_db_key(number)).map_err(db_err)? {
+		Some(hashes) => Vec::<HeaderHash>::decode(&mut &hashes[..])
+			.ok_or_else(|| ::client::error::ErrorKind::Backend("Error decoding state journal index".into()).into()),
+		None => Ok(Vec::new()),
+	}
+}
+
+fn read_journal(db: &KeyValueDB, hash: &HeaderHash) -> Result<Option<JournalRecord>, ::client::error::Error> {
+	match db.get(columns::JOURNAL, &hash).map_err(db_err)? {
+		Some(record) => JournalRecord::decode(&record)
+			.map(Some)
+			.ok_or_else(|| ::client::error::ErrorKind::Backend("Error decoding state journal record".into()).into()),
+		None => Ok(None),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use hashdb::HashDB;
+	use super::*;
+
+	fn test_db() -> Arc<KeyValueDB> {
+		Arc::new(::kvdb_memorydb::create(columns::NUM_COLUMNS))
+	}
+
+	// blocks of the canonical chain have hashes equal to their numbers
+	fn commit_fork(db: &KeyValueDB, state_db: &StateDb, number: block::Number, hash: HeaderHash, parent: HeaderHash, changes: MemoryDB) {
+		let pruned: Vec<_> = state_db.pruned_number(number).into_iter()
+			.map(|pruned| (pruned, HeaderHash::from(pruned)))
+			.collect();
+		let mut transaction = DBTransaction::new();
+		state_db.insert_block(db, &mut transaction, number, &hash, &parent, changes, &pruned).unwrap();
+		db.write(transaction).unwrap();
+	}
+
+	fn commit(db: &KeyValueDB, state_db: &StateDb, number: block::Number, changes: MemoryDB) {
+		let parent = HeaderHash::from(number.saturating_sub(1));
+		commit_fork(db, state_db, number, HeaderHash::from(number), parent, changes)
+	}
+
+	fn contains(db: &KeyValueDB, key: &TrieH256) -> bool {
+		db.get(columns::STATE, &key.0[..]).unwrap().is_some()
+	}
+
+	#[test]
+	fn deleted_nodes_are_kept_within_pruning_window() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::Constrained(2));
+
+		let mut changes = MemoryDB::default();
+		let key = changes.insert(b"hello");
+		commit(&*db, &state_db, 0, changes);
+
+		let mut changes = MemoryDB::default();
+		changes.remove(&key);
+		commit(&*db, &state_db, 1, changes);
+		assert!(contains(&*db, &key));
+
+		commit(&*db, &state_db, 2, MemoryDB::default());
+		assert!(contains(&*db, &key));
+
+		commit(&*db, &state_db, 3, MemoryDB::default());
+		assert!(!contains(&*db, &key));
+	}
+
+	#[test]
+	fn node_is_kept_while_referenced() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::Constrained(0));
+
+		let mut changes = MemoryDB::default();
+		let key = changes.insert(b"hello");
+		commit(&*db, &state_db, 0, changes);
+
+		let mut changes = MemoryDB::default();
+		changes.insert(b"hello");
+		commit(&*db, &state_db, 1, changes);
+
+		let mut changes = MemoryDB::default();
+		changes.remove(&key);
+		commit(&*db, &state_db, 2, changes);
+		assert!(contains(&*db, &key));
+
+		let mut changes = MemoryDB::default();
+		changes.remove(&key);
+		commit(&*db, &state_db, 3, changes);
+		assert!(!contains(&*db, &key));
+	}
+
+	#[test]
+	fn nodes_are_never_deleted_in_archive_mode() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::ArchiveAll);
+
+		let mut changes = MemoryDB::default();
+		let key = changes.insert(b"hello");
+		commit(&*db, &state_db, 0, changes);
+
+		let mut changes = MemoryDB::default();
+		changes.remove(&key);
+		commit(&*db, &state_db, 1, changes);
+		commit(&*db, &state_db, 2, MemoryDB::default());
+		assert!(contains(&*db, &key));
+		assert!(state_db.is_available(0, 2, false));
+	}
+
+	#[test]
+	fn block_is_reverted_within_pruning_window() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::Constrained(1));
+
+		let mut changes = MemoryDB::default();
+		let key1 = changes.insert(b"hello");
+		commit(&*db, &state_db, 0, changes);
+
+		let mut changes = MemoryDB::default();
+		let key2 = changes.insert(b"world");
+		changes.remove(&key1);
+		commit(&*db, &state_db, 1, changes);
+
+		let mut transaction = DBTransaction::new();
+		assert!(state_db.revert_block(&*db, &mut transaction, 1, &HeaderHash::from(1)).unwrap());
+		db.write(transaction).unwrap();
+		assert!(contains(&*db, &key1));
+		assert!(!contains(&*db, &key2));
+
+		// journal of the block 0 has been pruned when the block 1 was inserted
+		let mut transaction = DBTransaction::new();
+		assert!(!state_db.revert_block(&*db, &mut transaction, 0, &HeaderHash::from(0)).unwrap());
+	}
+
+	#[test]
+	fn non_canonical_nodes_are_discarded() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::Constrained(1));
+
+		let mut changes = MemoryDB::default();
+		let key1 = changes.insert(b"hello");
+		commit(&*db, &state_db, 0, changes);
+
+		let mut changes = MemoryDB::default();
+		let canonical_key = changes.insert(b"canonical");
+		commit(&*db, &state_db, 1, changes);
+
+		let mut changes = MemoryDB::default();
+		let fork_key = changes.insert(b"fork");
+		changes.remove(&key1);
+		commit_fork(&*db, &state_db, 1, HeaderHash::from(100), HeaderHash::from(0), changes);
+		assert!(contains(&*db, &fork_key));
+
+		commit(&*db, &state_db, 2, MemoryDB::default());
+		assert!(contains(&*db, &key1));
+		assert!(contains(&*db, &canonical_key));
+		assert!(!contains(&*db, &fork_key));
+	}
+
+	#[test]
+	fn non_canonical_nodes_are_kept_for_descendants_within_window() {
+		let db = test_db();
+		let state_db = StateDb::new(PruningMode::Constrained(1));
+
+		commit(&*db, &state_db, 0, MemoryDB::default());
+		commit(&*db, &state_db, 1, MemoryDB::default());
+
+		let mut changes = MemoryDB::default();
+		let fork_key = changes.insert(b"fork");
+		commit_fork(&*db, &state_db, 1, HeaderHash::from(100), HeaderHash::from(0), changes);
+
+		// two children of the fork block, both referring to its node.
+		let mut changes = MemoryDB::default();
+		let child_key = changes.insert(b"child");
+		commit_fork(&*db, &state_db, 2, HeaderHash::from(101), HeaderHash::from(100), changes);
+		commit_fork(&*db, &state_db, 2, HeaderHash::from(102), HeaderHash::from(100), MemoryDB::default());
+
+		// the fork block leaves the window, its children are still within it.
+		commit(&*db, &state_db, 2, MemoryDB::default());
+		assert!(contains(&*db, &fork_key));
+		assert!(contains(&*db, &child_key));
+
+		commit(&*db, &state_db, 3, MemoryDB::default());
+		assert!(!contains(&*db, &fork_key));
+		assert!(!contains(&*db, &child_key));
+	}
+
+	#[test]
+	fn state_availability_depends_on_pruning_window() {
+		let state_db = StateDb::new(PruningMode::Constrained(10));
+		assert!(state_db.is_available(91, 100, false));
+		assert!(state_db.is_available(90, 100, true));
+		assert!(!state_db.is_available(90, 100, false));
+		assert!(!state_db.is_available(89, 100, true));
+	}
+}
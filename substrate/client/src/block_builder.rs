@@ -66,14 +66,22 @@ impl<B, E> BlockBuilder<B, E> where
 	}
 
 	/// Push a transaction onto the block's list of transactions. This will ensure the transaction
-	/// can be validly executed (by executing it); if it is invalid, it'll be returned along with
-	/// the error. Otherwise, it will return a mutable reference to self (in order to chain).
+	/// can be validly executed (by executing it); if it is invalid, its changes are discarded, it
+	/// is left out of the block and the error is returned. The builder remains usable either way,
+	/// so a failing transaction can simply be skipped.
 	pub fn push(&mut self, tx: Extrinsic) -> error::Result<()> {
-		let (output, _) = self.executor.call_at_state(
+		let (output, _) = match self.executor.call_at_state(
 			&self.state,
 			&mut self.changes,
 			"execute_transaction",
-			&vec![].and(&self.header).and(&tx))?;
+			&vec![].and(&self.header).and(&tx),
+		) {
+			Ok(result) => result,
+			Err(e) => {
+				self.changes.discard_prospective();
+				return Err(e);
+			}
+		};
 		self.header = Header::decode(&mut &output[..]).expect("Header came straight out of runtime so must be valid");
 		self.transactions.push(tx);
 		Ok(())
@@ -16,9 +16,10 @@
 
 //! Polkadot blockchain trait
 
-use primitives::block::{self, Id as BlockId};
+use primitives::block::{self, Id as BlockId, HeaderHash};
 use primitives;
-use error::Result;
+use runtime_support::Hashable;
+use error::{ErrorKind, Result};
 
 /// Blockchain database backend. Does not perform any validation.
 pub trait Backend: Send + Sync {
@@ -34,6 +35,8 @@ pub trait Backend: Send + Sync {
 	fn status(&self, id: BlockId) -> Result<BlockStatus>;
 	/// Get block hash by number. Returns `None` if the header is not in the chain.
 	fn hash(&self, number: block::Number) -> Result<Option<block::HeaderHash>>;
+	/// Get hashes of all blocks that have no children, i.e. heads of all known forks.
+	fn leaves(&self) -> Result<Vec<block::HeaderHash>>;
 }
 
 /// Block import outcome
@@ -67,3 +70,87 @@ pub enum BlockStatus {
 	/// Not in the queue or the blockchain.
 	Unknown,
 }
+
+/// Block in the tree route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteEntry {
+	/// Block number.
+	pub number: block::Number,
+	/// Block hash.
+	pub hash: HeaderHash,
+}
+
+/// A tree-route from one block to another in the chain.
+///
+/// All blocks prior to the pivot in the route are retracted, ordered from `from` towards
+/// the common ancestor. The pivot is the common ancestor itself. All blocks after the pivot
+/// are enacted, ordered from the common ancestor towards `to`.
+#[derive(Debug)]
+pub struct TreeRoute {
+	route: Vec<RouteEntry>,
+	pivot: usize,
+}
+
+impl TreeRoute {
+	/// Get the blocks to retract, starting with `from`.
+	pub fn retracted(&self) -> &[RouteEntry] {
+		&self.route[..self.pivot]
+	}
+
+	/// Get the common ancestor of both blocks.
+	pub fn common_block(&self) -> &RouteEntry {
+		&self.route[self.pivot]
+	}
+
+	/// Get the blocks to enact, ending with `to`.
+	pub fn enacted(&self) -> &[RouteEntry] {
+		&self.route[self.pivot + 1..]
+	}
+}
+
+/// Compute a tree-route between two blocks.
+pub fn tree_route<B: Backend + ?Sized>(backend: &B, from: BlockId, to: BlockId) -> Result<TreeRoute> {
+	let load_header = |id: BlockId| match backend.header(id)? {
+		Some(header) => Ok(header),
+		None => Err(ErrorKind::UnknownBlock(id).into()),
+	};
+	let entry = |header: &block::Header| RouteEntry {
+		number: header.number,
+		hash: header.blake2_256().into(),
+	};
+
+	let mut from = load_header(from)?;
+	let mut to = load_header(to)?;
+
+	let mut from_branch = Vec::new();
+	let mut to_branch = Vec::new();
+
+	while to.number > from.number {
+		to_branch.push(entry(&to));
+		to = load_header(BlockId::Hash(to.parent_hash))?;
+	}
+
+	while from.number > to.number {
+		from_branch.push(entry(&from));
+		from = load_header(BlockId::Hash(from.parent_hash))?;
+	}
+
+	// numbers are equal now. walk backwards until the block is the same
+	while entry(&to) != entry(&from) {
+		to_branch.push(entry(&to));
+		to = load_header(BlockId::Hash(to.parent_hash))?;
+
+		from_branch.push(entry(&from));
+		from = load_header(BlockId::Hash(from.parent_hash))?;
+	}
+
+	// add the pivot block and the enacted blocks, in ascending order
+	let pivot = from_branch.len();
+	from_branch.push(entry(&to));
+	from_branch.extend(to_branch.into_iter().rev());
+
+	Ok(TreeRoute {
+		route: from_branch,
+		pivot,
+	})
+}
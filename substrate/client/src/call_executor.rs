@@ -18,8 +18,6 @@ use std::sync::Arc;
 use futures::{IntoFuture, Future};
 use primitives::block::Id as BlockId;
 use state_machine::{self, OverlayedChanges, Backend as StateBackend, CodeExecutor};
-use state_machine::backend::InMemory as InMemoryStateBackend;
-use triehash::trie_root;
 
 use backend;
 use blockchain::Backend as ChainBackend;
@@ -49,6 +47,11 @@ pub trait CallExecutor {
 	///
 	/// No changes are made.
 	fn call_at_state<S: state_machine::Backend>(&self, state: &S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, S::Transaction), error::Error>;
+
+	/// Execute a call to a contract on top of given state, gathering execution proof.
+	///
+	/// No changes are made.
+	fn prove_at_state<S: state_machine::Backend>(&self, state: S, overlay: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error>;
 }
 
 /// Call executor that executes methods locally, querying all required
@@ -104,6 +107,16 @@ impl<B, E> CallExecutor for LocalCallExecutor<B, E>
 			call_data,
 		).map_err(Into::into)
 	}
+
+	fn prove_at_state<S: state_machine::Backend>(&self, state: S, changes: &mut OverlayedChanges, method: &str, call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
+		state_machine::prove_execution(
+			state,
+			changes,
+			&self.executor,
+			method,
+			call_data,
+		).map_err(Into::into)
+	}
 }
 
 impl<B, F> RemoteCallExecutor<B, F> {
@@ -138,9 +151,13 @@ impl<B, F> CallExecutor for RemoteCallExecutor<B, F>
 	fn call_at_state<S: state_machine::Backend>(&self, _state: &S, _changes: &mut OverlayedChanges, _method: &str, _call_data: &[u8]) -> error::Result<(Vec<u8>, S::Transaction)> {
 		Err(error::ErrorKind::NotAvailableOnLightClient.into())
 	}
+
+	fn prove_at_state<S: state_machine::Backend>(&self, _state: S, _changes: &mut OverlayedChanges, _method: &str, _call_data: &[u8]) -> Result<(Vec<u8>, Vec<Vec<u8>>), error::Error> {
+		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	}
 }
 
-/// Check remote execution proof.
+/// Check remote execution proof using given backend.
 pub fn check_execution_proof<B, E>(backend: &B, executor: &E, request: &RemoteCallRequest, remote_proof: (Vec<u8>, Vec<Vec<u8>>)) -> Result<CallResult, error::Error>
 	where
 		B: backend::RemoteBackend,
@@ -149,25 +166,19 @@ pub fn check_execution_proof<B, E>(backend: &B, executor: &E, request: &RemoteCa
 {
 	let (remote_result, remote_proof) = remote_proof;
 
-	let remote_state = state_from_execution_proof(remote_proof);
-	let remote_state_root = trie_root(remote_state.pairs().into_iter()).0;
-
 	let local_header = backend.blockchain().header(BlockId::Hash(request.block))?;
 	let local_header = local_header.ok_or_else(|| error::ErrorKind::UnknownBlock(BlockId::Hash(request.block)))?;
 	let local_state_root = local_header.state_root;
 
-	if remote_state_root != *local_state_root {
-		return Err(error::ErrorKind::InvalidExecutionProof.into());
-	}
-
 	let mut changes = OverlayedChanges::default();
-	let (local_result, _) = state_machine::execute(
-		&remote_state,
+	let (local_result, _) = state_machine::execution_proof_check(
+		local_state_root.0,
+		remote_proof,
 		&mut changes,
 		executor,
 		&request.method,
 		&request.call_data,
-	)?;
+	).map_err(|_| error::ErrorKind::InvalidExecutionProof)?;
 
 	if local_result != remote_result {
 		return Err(error::ErrorKind::InvalidExecutionProof.into());
@@ -176,28 +187,40 @@ pub fn check_execution_proof<B, E>(backend: &B, executor: &E, request: &RemoteCa
 	Ok(CallResult { return_data: local_result, changes })
 }
 
-/// Convert state to execution proof. Proof is simple the whole state (temporary).
-// TODO [light]: this method must be removed after trie-based proofs are landed.
-pub fn state_to_execution_proof<B: state_machine::Backend>(state: &B) -> Vec<Vec<u8>> {
-	state.pairs().into_iter()
-		.flat_map(|(k, v)| ::std::iter::once(k).chain(::std::iter::once(v)))
-		.collect()
-}
-
-/// Convert execution proof to in-memory state for check. Reverse function for state_to_execution_proof.
-// TODO [light]: this method must be removed after trie-based proofs are landed.
-fn state_from_execution_proof(proof: Vec<Vec<u8>>) -> InMemoryStateBackend {
-	let mut changes = Vec::new();
-	let mut proof_iter = proof.into_iter();
-	loop {
-		let key = proof_iter.next();
-		let value = proof_iter.next();
-		if let (Some(key), Some(value)) = (key, value) {
-			changes.push((key, Some(value)));
-		} else {
-			break;
-		}
+#[cfg(test)]
+mod tests {
+	use primitives::block::Id as BlockId;
+	use state_machine::{Backend, execution_proof_check};
+	use test_client;
+
+	#[test]
+	fn execution_proof_is_generated_and_checked() {
+		// prepare remote client
+		let remote_client = test_client::new();
+		let remote_block_id = BlockId::Number(0);
+		let remote_state_root = remote_client.state_at(&remote_block_id).unwrap()
+			.storage_root(::std::iter::empty()).0;
+
+		// 'fetch' execution proof from remote node
+		let (remote_result, remote_execution_proof) = remote_client.execution_proof(&remote_block_id,
+			"authorities", &[]).unwrap();
+
+		// check remote execution proof locally
+		let local_executor = test_client::NativeExecutor::new();
+		let (local_result, _) = execution_proof_check(remote_state_root, remote_execution_proof,
+			&mut Default::default(), &local_executor, "authorities", &[]).unwrap();
+
+		assert_eq!(remote_result, local_result);
 	}
 
-	InMemoryStateBackend::default().update(changes)
+	#[test]
+	fn execution_proof_check_fails_on_wrong_root() {
+		let remote_client = test_client::new();
+		let (_, remote_execution_proof) = remote_client.execution_proof(&BlockId::Number(0),
+			"authorities", &[]).unwrap();
+
+		let local_executor = test_client::NativeExecutor::new();
+		assert!(execution_proof_check([0; 32], remote_execution_proof,
+			&mut Default::default(), &local_executor, "authorities", &[]).is_err());
+	}
 }
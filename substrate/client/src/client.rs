@@ -129,6 +129,12 @@ pub struct BlockImportNotification {
 	pub header: block::Header,
 	/// Is this the new best block.
 	pub is_new_best: bool,
+	/// Blocks that have been removed from the best chain by this import, starting with the
+	/// previous best block. Empty unless the import caused a reorganisation.
+	pub retracted: Vec<block::HeaderHash>,
+	/// Blocks that have been added to the best chain by this import, in ascending order and
+	/// ending with the imported block. Empty if the imported block is not the new best.
+	pub enacted: Vec<block::HeaderHash>,
 }
 
 /// A header paired with a justification which has already been checked.
@@ -230,12 +236,16 @@ impl<B, E> Client<B, E> where
 	///
 	/// No changes are made.
 	pub fn execution_proof(&self, id: &BlockId, method: &str, call_data: &[u8]) -> error::Result<(Vec<u8>, Vec<Vec<u8>>)> {
-		use call_executor::state_to_execution_proof;
+		self.state_at(id).and_then(|state| self.executor.prove_at_state(state, &mut Default::default(), method, call_data))
+	}
 
-		let result = self.executor.call(id, method, call_data);
-		let result = result?.return_data;
-		let proof = self.backend.state_at(*id).map(|state| state_to_execution_proof(&state))?;
-		Ok((result, proof))
+	/// Read storage entry under given key in state in a block of given hash
+	/// AND returning the storage read proof.
+	pub fn read_proof(&self, id: &BlockId, key: &[u8]) -> error::Result<Vec<Vec<u8>>> {
+		self.state_at(id)
+			.and_then(|state| state_machine::prove_read(state, key)
+				.map(|(_, proof)| proof)
+				.map_err(Into::into))
 	}
 
 	/// Set up the native execution environment to call into a native runtime code.
@@ -330,7 +340,24 @@ impl<B, E> Client<B, E> where
 			None => None,
 		};
 
-		let is_new_best = header.number == self.backend.blockchain().info()?.best_number + 1;
+		let chain_info = self.backend.blockchain().info()?;
+		let is_new_best = header.number > chain_info.best_number;
+		let (retracted, enacted) = if !is_new_best {
+			(Vec::new(), Vec::new())
+		} else if header.parent_hash == chain_info.best_hash {
+			(Vec::new(), vec![hash])
+		} else {
+			let route = blockchain::tree_route(
+				self.backend.blockchain(),
+				BlockId::Hash(chain_info.best_hash),
+				BlockId::Hash(header.parent_hash),
+			)?;
+			debug!("Reorganising chain from {} to {}: {} blocks retracted", chain_info.best_hash, hash, route.retracted().len());
+			(
+				route.retracted().iter().map(|entry| entry.hash).collect(),
+				route.enacted().iter().map(|entry| entry.hash).chain(::std::iter::once(hash)).collect(),
+			)
+		};
 		trace!("Imported {}, (#{}), best={}, origin={:?}", hash, header.number, is_new_best, origin);
 		transaction.set_block_data(header.clone(), body, Some(justification.uncheck().into()), is_new_best)?;
 		if let Some(storage_update) = storage_update {
@@ -343,6 +370,8 @@ impl<B, E> Client<B, E> where
 				origin: origin,
 				header: header,
 				is_new_best: is_new_best,
+				retracted,
+				enacted,
 			};
 			self.import_notification_sinks.lock()
 				.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
@@ -374,6 +403,16 @@ impl<B, E> Client<B, E> where
 		}
 	}
 
+	/// Get hashes of the heads of all known forks.
+	pub fn leaves(&self) -> error::Result<Vec<block::HeaderHash>> {
+		self.backend.blockchain().leaves()
+	}
+
+	/// Compute a tree-route between two blocks. See `blockchain::TreeRoute` for details.
+	pub fn tree_route(&self, from: &BlockId, to: &BlockId) -> error::Result<blockchain::TreeRoute> {
+		blockchain::tree_route(self.backend.blockchain(), *from, *to)
+	}
+
 	/// Get block hash by number.
 	pub fn block_hash(&self, block_number: block::Number) -> error::Result<Option<block::HeaderHash>> {
 		self.backend.blockchain().hash(block_number)
@@ -544,4 +583,89 @@ mod tests {
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public())).unwrap(), 958);
 		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public())).unwrap(), 42);
 	}
+
+	#[test]
+	fn block_builder_skips_failing_transactions() {
+		let client = test_client::new();
+
+		let mut builder = client.new_block().unwrap();
+
+		assert!(builder.push(Transaction {
+			from: Keyring::Alice.to_raw_public(),
+			to: Keyring::Ferdie.to_raw_public(),
+			amount: 42,
+			nonce: 1
+		}.signed()).is_err());
+		builder.push(Transaction {
+			from: Keyring::Alice.to_raw_public(),
+			to: Keyring::Ferdie.to_raw_public(),
+			amount: 42,
+			nonce: 0
+		}.signed()).unwrap();
+
+		let block = builder.bake().unwrap();
+		assert_eq!(block.transactions.len(), 1);
+		client.justify_and_import(BlockOrigin::Own, block).unwrap();
+
+		assert_eq!(client.info().unwrap().chain.best_number, 1);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Alice.to_raw_public())).unwrap(), 958);
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public())).unwrap(), 42);
+	}
+
+	#[test]
+	fn reorg_retracts_and_enacts_blocks() {
+		use futures::{Future, Stream};
+
+		let client = test_client::new();
+		let notifications = client.import_notification_stream();
+		let genesis_hash = client.genesis_hash();
+
+		// G -> A1 -> A2
+		let a1 = client.new_block().unwrap().bake().unwrap();
+		let a1_hash: HeaderHash = a1.header.blake2_256().into();
+		client.justify_and_import(BlockOrigin::Own, a1).unwrap();
+		let a2 = client.new_block().unwrap().bake().unwrap();
+		let a2_hash: HeaderHash = a2.header.blake2_256().into();
+		client.justify_and_import(BlockOrigin::Own, a2).unwrap();
+
+		// G -> B1 -> B2 -> B3
+		let mut builder = client.new_block_at(&BlockId::Hash(genesis_hash)).unwrap();
+		builder.push(Transaction {
+			from: Keyring::Alice.to_raw_public(),
+			to: Keyring::Ferdie.to_raw_public(),
+			amount: 42,
+			nonce: 0
+		}.signed()).unwrap();
+		let b1 = builder.bake().unwrap();
+		let b1_hash: HeaderHash = b1.header.blake2_256().into();
+		client.justify_and_import(BlockOrigin::Own, b1).unwrap();
+		let b2 = client.new_block_at(&BlockId::Hash(b1_hash)).unwrap().bake().unwrap();
+		let b2_hash: HeaderHash = b2.header.blake2_256().into();
+		client.justify_and_import(BlockOrigin::Own, b2).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, a2_hash);
+
+		let b3 = client.new_block_at(&BlockId::Hash(b2_hash)).unwrap().bake().unwrap();
+		let b3_hash: HeaderHash = b3.header.blake2_256().into();
+		client.justify_and_import(BlockOrigin::Own, b3).unwrap();
+		assert_eq!(client.info().unwrap().chain.best_hash, b3_hash);
+		assert_eq!(client.block_hash(1).unwrap(), Some(b1_hash));
+		assert_eq!(client.using_environment(|| test_runtime::system::balance_of(Keyring::Ferdie.to_raw_public())).unwrap(), 42);
+
+		let mut leaves = client.leaves().unwrap();
+		leaves.sort();
+		let mut expected_leaves = vec![a2_hash, b3_hash];
+		expected_leaves.sort();
+		assert_eq!(leaves, expected_leaves);
+
+		let route = client.tree_route(&BlockId::Hash(a2_hash), &BlockId::Hash(b3_hash)).unwrap();
+		assert_eq!(route.common_block().hash, genesis_hash);
+		assert_eq!(route.retracted().iter().map(|e| e.hash).collect::<Vec<_>>(), vec![a2_hash, a1_hash]);
+		assert_eq!(route.enacted().iter().map(|e| e.hash).collect::<Vec<_>>(), vec![b1_hash, b2_hash, b3_hash]);
+
+		let notifications: Vec<_> = notifications.take(5).collect().wait().unwrap();
+		assert!(!notifications[2].is_new_best);
+		assert!(notifications[2].enacted.is_empty());
+		assert_eq!(notifications[4].retracted, vec![a2_hash, a1_hash]);
+		assert_eq!(notifications[4].enacted, vec![b1_hash, b2_hash, b3_hash]);
+	}
 }
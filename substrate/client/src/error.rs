@@ -94,6 +94,18 @@ error_chain! {
 			display("Remote node has responded with invalid execution proof"),
 		}
 
+		/// Invalid remote storage read proof.
+		InvalidReadProof {
+			description("invalid read proof"),
+			display("Remote node has responded with invalid storage read proof"),
+		}
+
+		/// Invalid remote block body.
+		InvalidBodyProof {
+			description("invalid body proof"),
+			display("Remote node has responded with block body which does not match the header"),
+		}
+
 		/// Invalid remote proof.
 		RemoteFetchCancelled {
 			description("remote fetch cancelled"),
@@ -42,6 +42,7 @@ struct Block {
 struct BlockchainStorage {
 	blocks: HashMap<HeaderHash, Block>,
 	hashes: HashMap<block::Number, HeaderHash>,
+	leaves: Vec<HeaderHash>,
 	best_hash: HeaderHash,
 	best_number: block::Number,
 	genesis_hash: HeaderHash,
@@ -68,6 +69,7 @@ impl Blockchain {
 				BlockchainStorage {
 					blocks: HashMap::new(),
 					hashes: HashMap::new(),
+					leaves: Vec::new(),
 					best_hash: HeaderHash::default(),
 					best_number: 0,
 					genesis_hash: HeaderHash::default(),
@@ -86,14 +88,19 @@ impl Blockchain {
 	/// Insert block.
 	pub fn insert(&self, hash: HeaderHash, header: block::Header, justification: Option<primitives::bft::Justification>, body: Option<block::Body>, is_new_best: bool) {
 		let number = header.number;
+		let parent_hash = header.parent_hash;
 		let mut storage = self.storage.write();
 		storage.blocks.insert(hash, Block {
 			header: header,
 			body: body,
 			justification: justification,
 		});
-		storage.hashes.insert(number, hash);
+		storage.leaves.retain(|leaf| *leaf != parent_hash);
+		if !storage.leaves.contains(&hash) {
+			storage.leaves.push(hash);
+		}
 		if is_new_best {
+			Self::canonicalize(&mut storage, hash, number, parent_hash);
 			storage.best_hash = hash;
 			storage.best_number = number;
 		}
@@ -102,6 +109,28 @@ impl Blockchain {
 		}
 	}
 
+	/// Make the chain, ending with the given block, canonical: rewrite the number-to-hash index
+	/// down to the common ancestor with the current canonical chain.
+	fn canonicalize(storage: &mut BlockchainStorage, hash: HeaderHash, number: block::Number, parent_hash: HeaderHash) {
+		storage.hashes.retain(|n, _| *n < number);
+		storage.hashes.insert(number, hash);
+
+		let mut number = number;
+		let mut hash = parent_hash;
+		while number > 0 {
+			number -= 1;
+			if storage.hashes.get(&number) == Some(&hash) {
+				break;
+			}
+
+			storage.hashes.insert(number, hash);
+			hash = match storage.blocks.get(&hash) {
+				Some(block) => block.header.parent_hash,
+				None => break,
+			};
+		}
+	}
+
 	/// Compare this blockchain with another in-mem blockchain
 	pub fn equals_to(&self, other: &Blockchain) -> bool {
 		self.canon_equals_to(other) && self.storage.read().blocks == other.storage.read().blocks
@@ -150,6 +179,10 @@ impl blockchain::Backend for Blockchain {
 	fn hash(&self, number: block::Number) -> error::Result<Option<block::HeaderHash>> {
 		Ok(self.id(BlockId::Number(number)))
 	}
+
+	fn leaves(&self) -> error::Result<Vec<block::HeaderHash>> {
+		Ok(self.storage.read().leaves.clone())
+	}
 }
 
 /// In-memory operation.
@@ -17,14 +17,18 @@
 //! Light client backend. Only stores headers and justifications of blocks.
 //! Everything else is requested from full nodes on demand.
 
-use std::sync::Arc;
-use futures::future::IntoFuture;
+use std::marker::PhantomData;
+use std::sync::{Arc, Weak};
+use futures::{Future, IntoFuture};
+use parking_lot::RwLock;
 use primitives;
 use primitives::block::{self, Id as BlockId, HeaderHash};
 use runtime_support::Hashable;
-use state_machine::CodeExecutor;
+use codec::Slicable;
+use state_machine::{self, CodeExecutor, TrieBackend};
+use triehash::ordered_trie_root;
 use state_machine::backend::Backend as StateBackend;
-use blockchain::{self, BlockStatus};
+use blockchain::{self, Backend as BlockchainBackend, BlockStatus};
 use backend;
 use call_executor::{CallResult, RemoteCallExecutor, check_execution_proof};
 use client::{Client, GenesisBuilder};
@@ -41,48 +45,78 @@ pub struct RemoteCallRequest {
 	pub call_data: Vec<u8>,
 }
 
+/// Remote block body request.
+pub struct RemoteBodyRequest {
+	/// Hash of the block, body of which is requested.
+	pub block: HeaderHash,
+}
+
+/// Remote storage read request.
+pub struct RemoteReadRequest {
+	/// Read at state of given block.
+	pub block: HeaderHash,
+	/// Storage key to read.
+	pub key: Vec<u8>,
+}
+
 /// Light client data fetcher. Implementations of this trait must check if remote data
 /// is correct (see FetchedDataChecker) and return already checked data.
 pub trait Fetcher: Send + Sync {
 	/// Remote call result future.
 	type RemoteCallResult: IntoFuture<Item=CallResult, Error=error::Error>;
 
+	/// Remote storage read result future.
+	type RemoteReadResult: IntoFuture<Item=Option<Vec<u8>>, Error=error::Error>;
+	/// Remote block body result future.
+	type RemoteBodyResult: IntoFuture<Item=block::Body, Error=error::Error>;
+
 	/// Fetch remote call result.
 	fn remote_call(&self, request: RemoteCallRequest) -> Self::RemoteCallResult;
+	/// Fetch remote storage value.
+	fn remote_read(&self, request: RemoteReadRequest) -> Self::RemoteReadResult;
+	/// Fetch remote block body.
+	fn remote_body(&self, request: RemoteBodyRequest) -> Self::RemoteBodyResult;
 }
 
 /// Light client remote data checker.
 pub trait FetchChecker: Send + Sync {
 	/// Check remote method execution proof.
 	fn check_execution_proof(&self, request: &RemoteCallRequest, remote_proof: (Vec<u8>, Vec<Vec<u8>>)) -> error::Result<CallResult>;
+	/// Check remote storage read proof.
+	fn check_read_proof(&self, request: &RemoteReadRequest, remote_proof: Vec<Vec<u8>>) -> error::Result<Option<Vec<u8>>>;
+	/// Check remote block body against the extrinsics root of the block header.
+	fn check_body_proof(&self, request: &RemoteBodyRequest, body: block::Body) -> error::Result<block::Body>;
 }
 
 /// Light client backend.
-pub struct Backend {
-	blockchain: Blockchain,
+pub struct Backend<F> {
+	blockchain: Blockchain<F>,
 }
 
 /// Light client blockchain.
-pub struct Blockchain {
+pub struct Blockchain<F> {
 	storage: InMemBlockchain,
+	fetcher: RwLock<Weak<F>>,
 }
 
 /// Block (header and justification) import operation.
-pub struct BlockImportOperation {
+pub struct BlockImportOperation<F> {
 	pending_block: Option<PendingBlock>,
+	_phantom: PhantomData<F>,
 }
 
 /// On-demand state.
-#[derive(Clone)]
-pub struct OnDemandState {
+pub struct OnDemandState<F> {
+	/// On-demand data fetcher.
+	fetcher: Weak<F>,
 	/// Hash of the block, state is valid for.
-	_block: HeaderHash,
+	block: HeaderHash,
 }
 
 /// Remote data checker.
-pub struct LightDataChecker<E> {
+pub struct LightDataChecker<F, E> {
 	/// Backend reference.
-	backend: Arc<Backend>,
+	backend: Arc<Backend<F>>,
 	/// Executor.
 	executor: E,
 }
@@ -93,14 +127,22 @@ struct PendingBlock {
 	is_best: bool,
 }
 
-impl backend::Backend for Backend {
-	type BlockImportOperation = BlockImportOperation;
-	type Blockchain = Blockchain;
-	type State = OnDemandState;
+impl<F> Backend<F> {
+	/// Set the on-demand data fetcher, used to retrieve state and block data from remote nodes.
+	pub fn set_fetcher(&self, fetcher: Weak<F>) {
+		*self.blockchain.fetcher.write() = fetcher;
+	}
+}
+
+impl<F: Fetcher> backend::Backend for Backend<F> {
+	type BlockImportOperation = BlockImportOperation<F>;
+	type Blockchain = Blockchain<F>;
+	type State = OnDemandState<F>;
 
 	fn begin_operation(&self, _block: BlockId) -> error::Result<Self::BlockImportOperation> {
 		Ok(BlockImportOperation {
 			pending_block: None,
+			_phantom: Default::default(),
 		})
 	}
 
@@ -112,21 +154,22 @@ impl backend::Backend for Backend {
 		Ok(())
 	}
 
-	fn blockchain(&self) -> &Blockchain {
+	fn blockchain(&self) -> &Blockchain<F> {
 		&self.blockchain
 	}
 
 	fn state_at(&self, block: BlockId) -> error::Result<Self::State> {
 		Ok(OnDemandState {
-			_block: self.blockchain.storage.id(block).ok_or(error::ErrorKind::UnknownBlock(block))?,
+			fetcher: self.blockchain.fetcher.read().clone(),
+			block: self.blockchain.storage.id(block).ok_or(error::ErrorKind::UnknownBlock(block))?,
 		})
 	}
 }
 
-impl backend::RemoteBackend for Backend {}
+impl<F: Fetcher> backend::RemoteBackend for Backend<F> {}
 
-impl backend::BlockImportOperation for BlockImportOperation {
-	type State = OnDemandState;
+impl<F: Fetcher> backend::BlockImportOperation for BlockImportOperation<F> {
+	type State = OnDemandState<F>;
 
 	fn state(&self) -> error::Result<Option<&Self::State>> {
 		// None means 'locally-stateless' backend
@@ -154,14 +197,23 @@ impl backend::BlockImportOperation for BlockImportOperation {
 	}
 }
 
-impl blockchain::Backend for Blockchain {
+impl<F: Fetcher> blockchain::Backend for Blockchain<F> {
 	fn header(&self, id: BlockId) -> error::Result<Option<block::Header>> {
 		self.storage.header(id)
 	}
 
-	fn body(&self, _id: BlockId) -> error::Result<Option<block::Body>> {
-		// TODO [light]: fetch from remote node
-		Ok(None)
+	fn body(&self, id: BlockId) -> error::Result<Option<block::Body>> {
+		let hash = match self.storage.id(id) {
+			Some(hash) => hash,
+			None => return Ok(None),
+		};
+
+		self.fetcher.read().upgrade().ok_or(error::ErrorKind::NotAvailableOnLightClient)?
+			.remote_body(RemoteBodyRequest {
+				block: hash,
+			})
+			.into_future().wait()
+			.map(Some)
 	}
 
 	fn justification(&self, id: BlockId) -> error::Result<Option<primitives::bft::Justification>> {
@@ -179,15 +231,32 @@ impl blockchain::Backend for Blockchain {
 	fn hash(&self, number: block::Number) -> error::Result<Option<block::HeaderHash>> {
 		self.storage.hash(number)
 	}
+
+	fn leaves(&self) -> error::Result<Vec<block::HeaderHash>> {
+		self.storage.leaves()
+	}
 }
 
-impl StateBackend for OnDemandState {
+impl<F> Clone for OnDemandState<F> {
+	fn clone(&self) -> Self {
+		OnDemandState {
+			fetcher: self.fetcher.clone(),
+			block: self.block,
+		}
+	}
+}
+
+impl<F: Fetcher> StateBackend for OnDemandState<F> {
 	type Error = error::Error;
 	type Transaction = ();
 
-	fn storage(&self, _key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-		// TODO [light]: fetch from remote node
-		Err(error::ErrorKind::NotAvailableOnLightClient.into())
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.fetcher.upgrade().ok_or(error::ErrorKind::NotAvailableOnLightClient)?
+			.remote_read(RemoteReadRequest {
+				block: self.block,
+				key: key.to_vec(),
+			})
+			.into_future().wait()
 	}
 
 	fn storage_root<I>(&self, _delta: I) -> ([u8; 32], Self::Transaction)
@@ -199,45 +268,174 @@ impl StateBackend for OnDemandState {
 		// whole state is not available on light node
 		Vec::new()
 	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		None
+	}
 }
 
-impl<E> FetchChecker for LightDataChecker<E>
+impl<F, E> FetchChecker for LightDataChecker<F, E>
 	where
+		F: Fetcher,
 		E: CodeExecutor,
 {
 	fn check_execution_proof(&self, request: &RemoteCallRequest, remote_proof: (Vec<u8>, Vec<Vec<u8>>)) -> error::Result<CallResult> {
 		check_execution_proof(&*self.backend, &self.executor, request, remote_proof)
 	}
+
+	fn check_read_proof(&self, request: &RemoteReadRequest, remote_proof: Vec<Vec<u8>>) -> error::Result<Option<Vec<u8>>> {
+		check_read_proof(&self.backend.blockchain.storage, request, remote_proof)
+	}
+
+	fn check_body_proof(&self, request: &RemoteBodyRequest, body: block::Body) -> error::Result<block::Body> {
+		check_body_proof(&self.backend.blockchain.storage, request, body)
+	}
+}
+
+/// Get locally known header of the block, remote data has been requested for.
+fn local_header(blockchain: &InMemBlockchain, block: HeaderHash) -> error::Result<block::Header> {
+	blockchain.header(BlockId::Hash(block))?
+		.ok_or_else(|| error::ErrorKind::UnknownBlock(BlockId::Hash(block)).into())
+}
+
+/// Check remote storage read proof against the state root of the locally known header.
+fn check_read_proof(blockchain: &InMemBlockchain, request: &RemoteReadRequest, remote_proof: Vec<Vec<u8>>) -> error::Result<Option<Vec<u8>>> {
+	let local_state_root = local_header(blockchain, request.block)?.state_root;
+
+	state_machine::read_proof_check(local_state_root.0, remote_proof, &request.key)
+		.map_err(|_| error::ErrorKind::InvalidReadProof.into())
+}
+
+/// Check remote block body against the extrinsics root of the locally known header.
+fn check_body_proof(blockchain: &InMemBlockchain, request: &RemoteBodyRequest, body: block::Body) -> error::Result<block::Body> {
+	let local_extrinsics_root = local_header(blockchain, request.block)?.extrinsics_root;
+
+	let extrinsics_root = ordered_trie_root(body.iter().map(Slicable::encode)).0;
+	if extrinsics_root != local_extrinsics_root.0 {
+		return Err(error::ErrorKind::InvalidBodyProof.into());
+	}
+
+	Ok(body)
 }
 
 /// Create an instance of light client backend.
-pub fn new_light_backend() -> Arc<Backend> {
+pub fn new_light_backend<F>() -> Arc<Backend<F>> {
 	let storage = InMemBlockchain::new();
-	let blockchain = Blockchain { storage };
+	let blockchain = Blockchain { storage, fetcher: RwLock::new(Weak::new()) };
 	Arc::new(Backend { blockchain })
 }
 
 /// Create an instance of light client.
 pub fn new_light<F, B>(
-	backend: Arc<Backend>,
+	backend: Arc<Backend<F>>,
 	fetcher: Arc<F>,
 	genesis_builder: B,
-) -> error::Result<Client<Backend, RemoteCallExecutor<Backend, F>>>
+) -> error::Result<Client<Backend<F>, RemoteCallExecutor<Backend<F>, F>>>
 	where
 		F: Fetcher,
 		B: GenesisBuilder,
 {
+	backend.set_fetcher(Arc::downgrade(&fetcher));
 	let executor = RemoteCallExecutor::new(backend.clone(), fetcher);
 	Client::new(backend, executor, genesis_builder)
 }
 
 /// Create an instance of fetch data checker.
-pub fn new_fetch_checker<E>(
-	backend: Arc<Backend>,
+pub fn new_fetch_checker<F, E>(
+	backend: Arc<Backend<F>>,
 	executor: E,
-) -> LightDataChecker<E>
+) -> LightDataChecker<F, E>
 	where
 		E: CodeExecutor,
 {
 	LightDataChecker { backend, executor }
 }
+
+#[cfg(test)]
+mod tests {
+	use primitives::block::{Extrinsic, Id as BlockId};
+	use runtime_support::Hashable;
+	use test_client;
+	use in_mem::Blockchain as InMemBlockchain;
+	use super::*;
+
+	fn prepare_for_read_proof_check() -> (InMemBlockchain, HeaderHash, Vec<u8>, Vec<Vec<u8>>) {
+		// prepare remote client
+		let remote_client = test_client::new();
+		let remote_block_id = BlockId::Number(0);
+		let remote_block_header = remote_client.header(&remote_block_id).unwrap().unwrap();
+		let remote_block_hash: HeaderHash = remote_block_header.blake2_256().into();
+		let remote_code = remote_client.code_at(&remote_block_id).unwrap();
+
+		// 'fetch' read proof from remote node
+		let remote_read_proof = remote_client.read_proof(&remote_block_id, b":code").unwrap();
+
+		// import remote block header locally
+		let local_storage = InMemBlockchain::new();
+		local_storage.insert(remote_block_hash, remote_block_header, None, None, true);
+		(local_storage, remote_block_hash, remote_code, remote_read_proof)
+	}
+
+	fn prepare_for_body_check() -> (InMemBlockchain, HeaderHash, block::Body) {
+		let body = vec![Extrinsic(vec![1, 2, 3]), Extrinsic(vec![4, 5, 6])];
+		let header = block::Header {
+			parent_hash: Default::default(),
+			number: 0,
+			state_root: Default::default(),
+			extrinsics_root: ordered_trie_root(body.iter().map(Slicable::encode)).0.into(),
+			digest: Default::default(),
+		};
+		let hash: HeaderHash = header.blake2_256().into();
+
+		let local_storage = InMemBlockchain::new();
+		local_storage.insert(hash, header, None, None, true);
+		(local_storage, hash, body)
+	}
+
+	#[test]
+	fn storage_read_proof_is_generated_and_checked() {
+		let (local_blockchain, block, remote_code, remote_read_proof) = prepare_for_read_proof_check();
+		assert_eq!(check_read_proof(&local_blockchain, &RemoteReadRequest {
+			block,
+			key: b":code".to_vec(),
+		}, remote_read_proof).unwrap(), Some(remote_code));
+	}
+
+	#[test]
+	fn storage_read_proof_check_fails_for_unknown_block() {
+		let (local_blockchain, _, _, remote_read_proof) = prepare_for_read_proof_check();
+		assert!(check_read_proof(&local_blockchain, &RemoteReadRequest {
+			block: [1; 32].into(),
+			key: b":code".to_vec(),
+		}, remote_read_proof).is_err());
+	}
+
+	#[test]
+	fn storage_read_proof_check_fails_for_empty_proof() {
+		let (local_blockchain, block, _, _) = prepare_for_read_proof_check();
+		assert!(check_read_proof(&local_blockchain, &RemoteReadRequest {
+			block,
+			key: b":code".to_vec(),
+		}, Vec::new()).is_err());
+	}
+
+	#[test]
+	fn body_is_checked_against_extrinsics_root() {
+		let (local_blockchain, block, remote_body) = prepare_for_body_check();
+		assert_eq!(check_body_proof(&local_blockchain, &RemoteBodyRequest { block }, remote_body.clone()).unwrap(),
+			remote_body);
+	}
+
+	#[test]
+	fn body_check_fails_for_wrong_body() {
+		let (local_blockchain, block, mut remote_body) = prepare_for_body_check();
+		remote_body.pop();
+		assert!(check_body_proof(&local_blockchain, &RemoteBodyRequest { block }, remote_body).is_err());
+	}
+
+	#[test]
+	fn body_check_fails_for_unknown_block() {
+		let (local_blockchain, _, remote_body) = prepare_for_body_check();
+		assert!(check_body_proof(&local_blockchain, &RemoteBodyRequest { block: [1; 32].into() }, remote_body).is_err());
+	}
+}
@@ -14,6 +14,8 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.?
 
+use std::sync::Arc;
+use ed25519;
 pub use service::Role;
 
 /// Protocol configuration
@@ -21,12 +23,18 @@ pub use service::Role;
 pub struct ProtocolConfig {
 	/// Assigned roles.
 	pub roles: Role,
+	/// Parachain id to collate on. Required for the collator role.
+	pub parachain_id: Option<u64>,
+	/// Authority key to announce to the peers and sign the status with. Used by the validators.
+	pub validator_key: Option<Arc<ed25519::Pair>>,
 }
 
 impl Default for ProtocolConfig {
 	fn default() -> ProtocolConfig {
 		ProtocolConfig {
 			roles: Role::FULL,
+			parachain_id: None,
+			validator_key: None,
 		}
 	}
 }
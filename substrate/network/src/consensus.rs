@@ -16,18 +16,22 @@
 
 //! Consensus related bits of the network service.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use futures::sync::{oneshot, mpsc};
 use std::time::{Instant, Duration};
 use io::SyncIo;
 use protocol::Protocol;
 use network::PeerId;
-use primitives::{Hash, block::Id as BlockId, block::Header};
+use primitives::{AuthorityId, Hash, block::Id as BlockId, block::Header};
 use message::{self, Message};
 use runtime_support::Hashable;
 
 // TODO: Add additional spam/DoS attack protection.
 const MESSAGE_LIFETIME: Duration = Duration::from_secs(600);
+// Maximum number of collations kept for a single parachain. The oldest one is evicted first.
+const MAX_COLLATIONS_PER_PARACHAIN: usize = 16;
+// Maximum number of collations kept from a single collator.
+const MAX_COLLATIONS_PER_COLLATOR: usize = 4;
 
 struct CandidateRequest {
 	id: message::RequestId,
@@ -35,19 +39,26 @@ struct CandidateRequest {
 }
 
 struct PeerConsensus {
+	validator_id: Option<AuthorityId>,
 	candidate_fetch: Option<CandidateRequest>,
 	candidate_available: Option<Hash>,
 	known_messages: HashSet<Hash>,
 }
 
-/// Consensus network protocol handler. Manages statements and candidate requests.
+/// Consensus network protocol handler. Manages statements, candidate requests and collations.
 pub struct Consensus {
 	peers: HashMap<PeerId, PeerConsensus>,
+	// Connected collators and their parachain ids.
+	collators: HashMap<PeerId, u64>,
+	// Parachains registered on the relay chain.
+	parachains: HashSet<u64>,
 	our_candidate: Option<(Hash, Vec<u8>)>,
 	statement_sink: Option<mpsc::UnboundedSender<message::Statement>>,
 	bft_message_sink: Option<(mpsc::UnboundedSender<message::LocalizedBftMessage>, Hash)>,
+	collation_sinks: Vec<(mpsc::UnboundedSender<message::Collation>, u64, Hash)>,
 	messages: Vec<(Hash, Instant, message::Message)>,
 	message_hashes: HashSet<Hash>,
+	collations: HashMap<u64, VecDeque<(Instant, PeerId, message::Collation)>>,
 }
 
 impl Consensus {
@@ -55,11 +66,15 @@ impl Consensus {
 	pub fn new() -> Consensus {
 		Consensus {
 			peers: HashMap::new(),
+			collators: HashMap::new(),
+			parachains: HashSet::new(),
 			our_candidate: None,
 			statement_sink: None,
 			bft_message_sink: None,
+			collation_sinks: Vec::new(),
 			messages: Default::default(),
 			message_hashes: Default::default(),
+			collations: Default::default(),
 		}
 	}
 
@@ -67,10 +82,22 @@ impl Consensus {
 	pub fn restart(&mut self) {
 		self.statement_sink = None;
 		self.bft_message_sink = None;
+		self.collation_sinks.clear();
 	}
 
-	/// Handle new connected peer.
-	pub fn new_peer(&mut self, io: &mut SyncIo, protocol: &Protocol, peer_id: PeerId, roles: &[message::Role]) {
+	/// Handle new connected peer. The `validator_id` must have been checked against the status
+	/// signature. The claimed parachain of a collator is not authenticated: its collations are
+	/// only relayed to the subscribers, which check the collator signatures.
+	pub fn new_peer(&mut self, io: &mut SyncIo, protocol: &Protocol, peer_id: PeerId, roles: &[message::Role], validator_id: Option<AuthorityId>, parachain_id: Option<u64>) {
+		if roles.iter().any(|r| *r == message::Role::Collator) {
+			match parachain_id {
+				Some(parachain_id) => {
+					trace!(target:"sync", "Registering collator {} for parachain {}", peer_id, parachain_id);
+					self.collators.insert(peer_id, parachain_id);
+				},
+				None => trace!(target:"sync", "Ignored collator {} without parachain id", peer_id),
+			}
+		}
 		if roles.iter().any(|r| *r == message::Role::Validator) {
 			trace!(target:"sync", "Registering validator {}", peer_id);
 			// Send out all known messages.
@@ -81,6 +108,7 @@ impl Consensus {
 				protocol.send_message(io, peer_id, message.clone());
 			}
 			self.peers.insert(peer_id, PeerConsensus {
+				validator_id,
 				candidate_fetch: None,
 				candidate_available: None,
 				known_messages,
@@ -198,6 +226,84 @@ impl Consensus {
 		stream
 	}
 
+	pub fn on_collation(&mut self, io: &mut SyncIo, peer_id: PeerId, collation: message::Collation) {
+		if !self.parachains.contains(&collation.parachain_id) {
+			trace!(target:"sync", "Ignored collation from {} for unregistered parachain {}", peer_id, collation.parachain_id);
+			return;
+		}
+
+		match self.collators.get(&peer_id) {
+			Some(parachain_id) if *parachain_id == collation.parachain_id => {},
+			Some(parachain_id) => {
+				trace!(target:"sync", "Collator {} sent collation for parachain {} while registered for {}", peer_id, collation.parachain_id, parachain_id);
+				io.disable_peer(peer_id);
+				return;
+			},
+			None => {
+				trace!(target:"sync", "Ignored collation from unregistered collator {}", peer_id);
+				return;
+			},
+		}
+
+		{
+			// a collator can only push out its own collations, honest ones are never starved.
+			let collations = self.collations.entry(collation.parachain_id).or_insert_with(VecDeque::new);
+			if collations.iter().filter(|&&(_, from, _)| from == peer_id).count() >= MAX_COLLATIONS_PER_COLLATOR {
+				trace!(target:"sync", "Evicting oldest collation of {}: too many collations from the collator", peer_id);
+				if let Some(oldest) = collations.iter().position(|&(_, from, _)| from == peer_id) {
+					collations.remove(oldest);
+				}
+			} else if collations.len() >= MAX_COLLATIONS_PER_PARACHAIN {
+				trace!(target:"sync", "Evicting oldest collation: too many collations for parachain {}", collation.parachain_id);
+				collations.pop_front();
+			}
+			collations.push_back((Instant::now(), peer_id, collation.clone()));
+		}
+
+		self.collation_sinks.retain(|&(ref sink, parachain_id, relay_parent)| {
+			if collation.parachain_id != parachain_id || collation.relay_parent != relay_parent {
+				return true;
+			}
+			match sink.unbounded_send(collation.clone()) {
+				Ok(()) => true,
+				Err(e) => {
+					trace!(target:"sync", "Error broadcasting collation notification: {:?}", e);
+					false
+				}
+			}
+		});
+	}
+
+	pub fn collations(&mut self, parachain_id: u64, relay_parent: Hash) -> mpsc::UnboundedReceiver<message::Collation> {
+		let (sink, stream) = mpsc::unbounded();
+
+		for &(_, _, ref collation) in self.collations.get(&parachain_id).into_iter().flat_map(|c| c.iter()) {
+			if collation.relay_parent == relay_parent {
+				sink.unbounded_send(collation.clone()).expect("receiving end known to be open; qed");
+			}
+		}
+
+		self.collation_sinks.push((sink, parachain_id, relay_parent));
+		stream
+	}
+
+	pub fn send_collation(&mut self, io: &mut SyncIo, protocol: &Protocol, collation: message::Collation, validators: &[AuthorityId]) {
+		// Send to the connected validators assigned to the parachain.
+		trace!(target:"sync", "Sending collation for parachain {} on {}", collation.parachain_id, collation.relay_parent);
+		let message = Message::Collation(collation);
+		for (id, peer) in self.peers.iter() {
+			if peer.validator_id.as_ref().map_or(false, |v| validators.contains(v)) {
+				protocol.send_message(io, *id, message.clone());
+			}
+		}
+	}
+
+	pub fn set_parachains(&mut self, parachains: Vec<u64>) {
+		self.parachains = parachains.into_iter().collect();
+		let parachains = &self.parachains;
+		self.collations.retain(|id, _| parachains.contains(id));
+	}
+
 	pub fn fetch_candidate(&mut self, io: &mut SyncIo, protocol: &Protocol, hash: &Hash) -> oneshot::Receiver<Vec<u8>> {
 		// Request from the first peer that has it available.
 		// TODO: random peer selection.
@@ -279,6 +385,7 @@ impl Consensus {
 
 	pub fn peer_disconnected(&mut self, _io: &mut SyncIo, _protocol: &Protocol, peer_id: PeerId) {
 		self.peers.remove(&peer_id);
+		self.collators.remove(&peer_id);
 	}
 
 	pub fn collect_garbage(&mut self, best_header: Option<&Header>) {
@@ -303,6 +410,16 @@ impl Consensus {
 		if self.messages.len() != before {
 			trace!(target:"sync", "Cleaned up {} stale messages", before - self.messages.len());
 		}
+		for collations in self.collations.values_mut() {
+			collations.retain(|&(timestamp, _, ref collation)|
+				timestamp >= now - MESSAGE_LIFETIME &&
+					best_header.map_or(true, |header| collation.relay_parent != header.parent_hash)
+			);
+		}
+		self.collations.retain(|_, collations| !collations.is_empty());
+		self.collation_sinks.retain(|&(_, _, relay_parent)|
+			best_header.map_or(true, |header| relay_parent != header.parent_hash)
+		);
 		for (_, ref mut peer) in self.peers.iter_mut() {
 			peer.known_messages.retain(|h| hashes.contains(h));
 		}
@@ -19,6 +19,7 @@
 use primitives::{AuthorityId, Hash};
 use primitives::block::{Number as BlockNumber, HeaderHash, Header, Body, Block};
 use primitives::bft::Justification;
+use codec::{Slicable, Input};
 use service::Role as RoleFlags;
 use ed25519;
 
@@ -251,12 +252,25 @@ pub enum Message {
 	RemoteCallRequest(RemoteCallRequest),
 	/// Remote method call response.
 	RemoteCallResponse(RemoteCallResponse),
+	/// Remote storage read request.
+	RemoteReadRequest(RemoteReadRequest),
+	/// Remote storage read response.
+	RemoteReadResponse(RemoteReadResponse),
+	/// Remote block body request.
+	RemoteBodyRequest(RemoteBodyRequest),
+	/// Remote block body response.
+	RemoteBodyResponse(RemoteBodyResponse),
+	/// Parachain collation.
+	Collation(Collation),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Status {
-	/// Protocol version.
+	/// Protocol version. Set to the legacy JSON version so that legacy peers accept the status.
 	pub version: u32,
+	/// Highest protocol version supported. Absent for legacy peers, which ignore it.
+	#[serde(default)]
+	pub max_version: Option<u32>,
 	/// Supported roles.
 	pub roles: Vec<Role>,
 	/// Best block number.
@@ -265,7 +279,7 @@ pub struct Status {
 	pub best_hash: HeaderHash,
 	/// Genesis block hash.
 	pub genesis_hash: HeaderHash,
-	/// Signatue of `best_hash` made with validator address. Required for the validator role.
+	/// Signature of the `signing_payload` made with the validator key. Required for `validator_id`.
 	pub validator_signature: Option<ed25519::Signature>,
 	/// Validator address. Required for the validator role.
 	pub validator_id: Option<AuthorityId>,
@@ -273,6 +287,15 @@ pub struct Status {
 	pub parachain_id: Option<u64>,
 }
 
+impl Status {
+	/// The data signed by the validator announcing `validator_id`.
+	pub fn signing_payload(&self) -> Vec<u8> {
+		let mut v = (self.genesis_hash, self.best_hash).encode();
+		encode_option(&mut v, &self.validator_id);
+		v
+	}
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 /// Request block data from a peer.
 pub struct BlockRequest {
@@ -347,3 +370,794 @@ pub struct RemoteCallResponse {
 	/// Execution proof.
 	pub proof: Vec<Vec<u8>>,
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Remote storage read request.
+pub struct RemoteReadRequest {
+	/// Unique request id.
+	pub id: RequestId,
+	/// Block at which to perform read.
+	pub block: HeaderHash,
+	/// Storage key.
+	pub key: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Remote storage read response.
+pub struct RemoteReadResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Read proof.
+	pub proof: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Remote block body request.
+pub struct RemoteBodyRequest {
+	/// Unique request id.
+	pub id: RequestId,
+	/// Block, body of which is requested.
+	pub block: HeaderHash,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// Remote block body response.
+pub struct RemoteBodyResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Block body. Empty if the peer does not have the block body.
+	pub body: Body,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+/// A parachain candidate produced by a collator, sent to the relay chain validators.
+pub struct Collation {
+	/// Relay chain block the collation builds on.
+	pub relay_parent: HeaderHash,
+	/// Parachain id the collation is for.
+	pub parachain_id: u64,
+	/// Encoded candidate receipt.
+	pub receipt: Bytes,
+	/// Candidate block data.
+	pub block_data: Bytes,
+	/// Encoded ingress queue the candidate was built upon.
+	pub ingress: Bytes,
+	/// Collator's signature on the block data.
+	pub signature: ed25519::Signature,
+}
+
+// Binary wire encoding of the messages. Enum variants are prefixed with a single byte
+// discriminant; optional values are prefixed with 0 for `None` and 1 for `Some`.
+
+fn encode_option<T: Slicable>(dest: &mut Vec<u8>, value: &Option<T>) {
+	match *value {
+		Some(ref value) => {
+			dest.push(1);
+			value.using_encoded(|s| dest.extend(s));
+		},
+		None => dest.push(0),
+	}
+}
+
+fn decode_option<T: Slicable, I: Input>(input: &mut I) -> Option<Option<T>> {
+	match input.read_byte()? {
+		0 => Some(None),
+		1 => Some(Some(T::decode(input)?)),
+		_ => None,
+	}
+}
+
+impl Slicable for Role {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(Role::Full),
+			1 => Some(Role::Light),
+			2 => Some(Role::Validator),
+			3 => Some(Role::Collator),
+			_ => None,
+		}
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[match *self {
+			Role::Full => 0u8,
+			Role::Light => 1,
+			Role::Validator => 2,
+			Role::Collator => 3,
+		}])
+	}
+}
+
+impl Slicable for BlockAttribute {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(BlockAttribute::Header),
+			1 => Some(BlockAttribute::Body),
+			2 => Some(BlockAttribute::Receipt),
+			3 => Some(BlockAttribute::MessageQueue),
+			4 => Some(BlockAttribute::Justification),
+			_ => None,
+		}
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[match *self {
+			BlockAttribute::Header => 0u8,
+			BlockAttribute::Body => 1,
+			BlockAttribute::Receipt => 2,
+			BlockAttribute::MessageQueue => 3,
+			BlockAttribute::Justification => 4,
+		}])
+	}
+}
+
+impl Slicable for BlockData {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(BlockData {
+			hash: Slicable::decode(input)?,
+			header: decode_option(input)?,
+			body: decode_option(input)?,
+			receipt: decode_option(input)?,
+			message_queue: decode_option(input)?,
+			justification: decode_option(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.hash.encode();
+		encode_option(&mut v, &self.header);
+		encode_option(&mut v, &self.body);
+		encode_option(&mut v, &self.receipt);
+		encode_option(&mut v, &self.message_queue);
+		encode_option(&mut v, &self.justification);
+		v
+	}
+}
+
+impl Slicable for FromBlock {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(FromBlock::Hash(Slicable::decode(input)?)),
+			1 => Some(FromBlock::Number(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			FromBlock::Hash(ref hash) => {
+				v.push(0);
+				hash.using_encoded(|s| v.extend(s));
+			},
+			FromBlock::Number(ref number) => {
+				v.push(1);
+				number.using_encoded(|s| v.extend(s));
+			},
+		}
+		v
+	}
+}
+
+impl Slicable for Direction {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(Direction::Ascending),
+			1 => Some(Direction::Descending),
+			_ => None,
+		}
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[match *self {
+			Direction::Ascending => 0u8,
+			Direction::Descending => 1,
+		}])
+	}
+}
+
+impl Slicable for UnsignedStatement {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(UnsignedStatement::Candidate(Slicable::decode(input)?)),
+			1 => Some(UnsignedStatement::Valid(Slicable::decode(input)?)),
+			2 => Some(UnsignedStatement::Available(Slicable::decode(input)?)),
+			3 => Some(UnsignedStatement::Invalid(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			UnsignedStatement::Candidate(ref data) => {
+				v.push(0);
+				data.using_encoded(|s| v.extend(s));
+			},
+			UnsignedStatement::Valid(ref hash) => {
+				v.push(1);
+				hash.using_encoded(|s| v.extend(s));
+			},
+			UnsignedStatement::Available(ref hash) => {
+				v.push(2);
+				hash.using_encoded(|s| v.extend(s));
+			},
+			UnsignedStatement::Invalid(ref hash) => {
+				v.push(3);
+				hash.using_encoded(|s| v.extend(s));
+			},
+		}
+		v
+	}
+}
+
+impl Slicable for Statement {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Statement {
+			parent_hash: Slicable::decode(input)?,
+			statement: Slicable::decode(input)?,
+			signature: Slicable::decode(input)?,
+			sender: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.parent_hash.encode();
+		self.statement.using_encoded(|s| v.extend(s));
+		self.signature.using_encoded(|s| v.extend(s));
+		self.sender.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for BftMessage {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(BftMessage::Consensus(Slicable::decode(input)?)),
+			1 => Some(BftMessage::Auxiliary(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			BftMessage::Consensus(ref message) => {
+				v.push(0);
+				message.using_encoded(|s| v.extend(s));
+			},
+			BftMessage::Auxiliary(ref justification) => {
+				v.push(1);
+				justification.using_encoded(|s| v.extend(s));
+			},
+		}
+		v
+	}
+}
+
+impl Slicable for LocalizedBftMessage {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(LocalizedBftMessage {
+			message: Slicable::decode(input)?,
+			parent_hash: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.message.encode();
+		self.parent_hash.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for SignedConsensusProposal {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(SignedConsensusProposal {
+			round_number: Slicable::decode(input)?,
+			proposal: Slicable::decode(input)?,
+			digest: Slicable::decode(input)?,
+			sender: Slicable::decode(input)?,
+			digest_signature: Slicable::decode(input)?,
+			full_signature: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.round_number.encode();
+		self.proposal.using_encoded(|s| v.extend(s));
+		self.digest.using_encoded(|s| v.extend(s));
+		self.sender.using_encoded(|s| v.extend(s));
+		self.digest_signature.using_encoded(|s| v.extend(s));
+		self.full_signature.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for SignedConsensusVote {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(SignedConsensusVote {
+			vote: Slicable::decode(input)?,
+			sender: Slicable::decode(input)?,
+			signature: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.vote.encode();
+		self.sender.using_encoded(|s| v.extend(s));
+		self.signature.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for ConsensusVote {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => {
+				let (round, digest) = Slicable::decode(input)?;
+				Some(ConsensusVote::Prepare(round, digest))
+			},
+			1 => {
+				let (round, digest) = Slicable::decode(input)?;
+				Some(ConsensusVote::Commit(round, digest))
+			},
+			2 => Some(ConsensusVote::AdvanceRound(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			ConsensusVote::Prepare(ref round, ref digest) => {
+				v.push(0);
+				round.using_encoded(|s| v.extend(s));
+				digest.using_encoded(|s| v.extend(s));
+			},
+			ConsensusVote::Commit(ref round, ref digest) => {
+				v.push(1);
+				round.using_encoded(|s| v.extend(s));
+				digest.using_encoded(|s| v.extend(s));
+			},
+			ConsensusVote::AdvanceRound(ref round) => {
+				v.push(2);
+				round.using_encoded(|s| v.extend(s));
+			},
+		}
+		v
+	}
+}
+
+impl Slicable for SignedConsensusMessage {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(SignedConsensusMessage::Propose(Slicable::decode(input)?)),
+			1 => Some(SignedConsensusMessage::Vote(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+		match *self {
+			SignedConsensusMessage::Propose(ref proposal) => {
+				v.push(0);
+				proposal.using_encoded(|s| v.extend(s));
+			},
+			SignedConsensusMessage::Vote(ref vote) => {
+				v.push(1);
+				vote.using_encoded(|s| v.extend(s));
+			},
+		}
+		v
+	}
+}
+
+impl Slicable for Status {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Status {
+			version: Slicable::decode(input)?,
+			max_version: decode_option(input)?,
+			roles: Slicable::decode(input)?,
+			best_number: Slicable::decode(input)?,
+			best_hash: Slicable::decode(input)?,
+			genesis_hash: Slicable::decode(input)?,
+			validator_signature: decode_option(input)?,
+			validator_id: decode_option(input)?,
+			parachain_id: decode_option(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.version.encode();
+		encode_option(&mut v, &self.max_version);
+		self.roles.using_encoded(|s| v.extend(s));
+		self.best_number.using_encoded(|s| v.extend(s));
+		self.best_hash.using_encoded(|s| v.extend(s));
+		self.genesis_hash.using_encoded(|s| v.extend(s));
+		encode_option(&mut v, &self.validator_signature);
+		encode_option(&mut v, &self.validator_id);
+		encode_option(&mut v, &self.parachain_id);
+		v
+	}
+}
+
+impl Slicable for BlockRequest {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(BlockRequest {
+			id: Slicable::decode(input)?,
+			fields: Slicable::decode(input)?,
+			from: Slicable::decode(input)?,
+			to: decode_option(input)?,
+			direction: Slicable::decode(input)?,
+			max: decode_option(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.fields.using_encoded(|s| v.extend(s));
+		self.from.using_encoded(|s| v.extend(s));
+		encode_option(&mut v, &self.to);
+		self.direction.using_encoded(|s| v.extend(s));
+		encode_option(&mut v, &self.max);
+		v
+	}
+}
+
+impl Slicable for CandidateRequest {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(CandidateRequest {
+			id: Slicable::decode(input)?,
+			hash: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.hash.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for CandidateResponse {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(CandidateResponse {
+			id: Slicable::decode(input)?,
+			data: decode_option(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		encode_option(&mut v, &self.data);
+		v
+	}
+}
+
+impl Slicable for BlockResponse {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(BlockResponse {
+			id: Slicable::decode(input)?,
+			blocks: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.blocks.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for BlockAnnounce {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(BlockAnnounce {
+			header: Slicable::decode(input)?,
+		})
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		self.header.using_encoded(f)
+	}
+}
+
+impl Slicable for RemoteCallRequest {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteCallRequest {
+			id: Slicable::decode(input)?,
+			block: Slicable::decode(input)?,
+			method: String::from_utf8(Slicable::decode(input)?).ok()?,
+			data: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.block.using_encoded(|s| v.extend(s));
+		self.method.as_bytes().to_vec().using_encoded(|s| v.extend(s));
+		self.data.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for RemoteCallResponse {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteCallResponse {
+			id: Slicable::decode(input)?,
+			value: Slicable::decode(input)?,
+			proof: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.value.using_encoded(|s| v.extend(s));
+		self.proof.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for RemoteReadRequest {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteReadRequest {
+			id: Slicable::decode(input)?,
+			block: Slicable::decode(input)?,
+			key: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.block.using_encoded(|s| v.extend(s));
+		self.key.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for RemoteReadResponse {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteReadResponse {
+			id: Slicable::decode(input)?,
+			proof: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.proof.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for RemoteBodyRequest {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteBodyRequest {
+			id: Slicable::decode(input)?,
+			block: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.block.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for RemoteBodyResponse {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(RemoteBodyResponse {
+			id: Slicable::decode(input)?,
+			body: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.id.encode();
+		self.body.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+impl Slicable for Collation {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Collation {
+			relay_parent: Slicable::decode(input)?,
+			parachain_id: Slicable::decode(input)?,
+			receipt: Slicable::decode(input)?,
+			block_data: Slicable::decode(input)?,
+			ingress: Slicable::decode(input)?,
+			signature: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = self.relay_parent.encode();
+		self.parachain_id.using_encoded(|s| v.extend(s));
+		self.receipt.using_encoded(|s| v.extend(s));
+		self.block_data.using_encoded(|s| v.extend(s));
+		self.ingress.using_encoded(|s| v.extend(s));
+		self.signature.using_encoded(|s| v.extend(s));
+		v
+	}
+}
+
+// Message discriminants start with 1, so that a binary message never starts with `{`,
+// which is the first byte of a JSON-encoded message.
+#[repr(u8)]
+enum MessageKind {
+	Status = 1,
+	BlockRequest = 2,
+	BlockResponse = 3,
+	BlockAnnounce = 4,
+	Transactions = 5,
+	Statement = 6,
+	CandidateRequest = 7,
+	CandidateResponse = 8,
+	BftMessage = 9,
+	RemoteCallRequest = 10,
+	RemoteCallResponse = 11,
+	RemoteReadRequest = 12,
+	RemoteReadResponse = 13,
+	RemoteBodyRequest = 14,
+	RemoteBodyResponse = 15,
+	Collation = 16,
+}
+
+impl Slicable for Message {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			x if x == MessageKind::Status as u8 => Some(Message::Status(Slicable::decode(input)?)),
+			x if x == MessageKind::BlockRequest as u8 => Some(Message::BlockRequest(Slicable::decode(input)?)),
+			x if x == MessageKind::BlockResponse as u8 => Some(Message::BlockResponse(Slicable::decode(input)?)),
+			x if x == MessageKind::BlockAnnounce as u8 => Some(Message::BlockAnnounce(Slicable::decode(input)?)),
+			x if x == MessageKind::Transactions as u8 => Some(Message::Transactions(Slicable::decode(input)?)),
+			x if x == MessageKind::Statement as u8 => Some(Message::Statement(Slicable::decode(input)?)),
+			x if x == MessageKind::CandidateRequest as u8 => Some(Message::CandidateRequest(Slicable::decode(input)?)),
+			x if x == MessageKind::CandidateResponse as u8 => Some(Message::CandidateResponse(Slicable::decode(input)?)),
+			x if x == MessageKind::BftMessage as u8 => Some(Message::BftMessage(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteCallRequest as u8 => Some(Message::RemoteCallRequest(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteCallResponse as u8 => Some(Message::RemoteCallResponse(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteReadRequest as u8 => Some(Message::RemoteReadRequest(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteReadResponse as u8 => Some(Message::RemoteReadResponse(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteBodyRequest as u8 => Some(Message::RemoteBodyRequest(Slicable::decode(input)?)),
+			x if x == MessageKind::RemoteBodyResponse as u8 => Some(Message::RemoteBodyResponse(Slicable::decode(input)?)),
+			x if x == MessageKind::Collation as u8 => Some(Message::Collation(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		fn with_kind<T: Slicable>(kind: MessageKind, payload: &T) -> Vec<u8> {
+			let mut v = vec![kind as u8];
+			payload.using_encoded(|s| v.extend(s));
+			v
+		}
+
+		match *self {
+			Message::Status(ref m) => with_kind(MessageKind::Status, m),
+			Message::BlockRequest(ref m) => with_kind(MessageKind::BlockRequest, m),
+			Message::BlockResponse(ref m) => with_kind(MessageKind::BlockResponse, m),
+			Message::BlockAnnounce(ref m) => with_kind(MessageKind::BlockAnnounce, m),
+			Message::Transactions(ref m) => with_kind(MessageKind::Transactions, m),
+			Message::Statement(ref m) => with_kind(MessageKind::Statement, m),
+			Message::CandidateRequest(ref m) => with_kind(MessageKind::CandidateRequest, m),
+			Message::CandidateResponse(ref m) => with_kind(MessageKind::CandidateResponse, m),
+			Message::BftMessage(ref m) => with_kind(MessageKind::BftMessage, m),
+			Message::RemoteCallRequest(ref m) => with_kind(MessageKind::RemoteCallRequest, m),
+			Message::RemoteCallResponse(ref m) => with_kind(MessageKind::RemoteCallResponse, m),
+			Message::RemoteReadRequest(ref m) => with_kind(MessageKind::RemoteReadRequest, m),
+			Message::RemoteReadResponse(ref m) => with_kind(MessageKind::RemoteReadResponse, m),
+			Message::RemoteBodyRequest(ref m) => with_kind(MessageKind::RemoteBodyRequest, m),
+			Message::RemoteBodyResponse(ref m) => with_kind(MessageKind::RemoteBodyResponse, m),
+			Message::Collation(ref m) => with_kind(MessageKind::Collation, m),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use primitives::block::Extrinsic;
+
+	fn roundtrip(message: Message) {
+		let encoded = message.encode();
+		assert_ne!(encoded[0], b'{');
+		assert_eq!(Message::decode(&mut &encoded[..]), Some(message));
+	}
+
+	#[test]
+	fn status_roundtrip() {
+		roundtrip(Message::Status(Status {
+			version: 0,
+			max_version: Some(1),
+			roles: vec![Role::Full, Role::Validator],
+			best_number: 42,
+			best_hash: [1; 32].into(),
+			genesis_hash: [2; 32].into(),
+			validator_signature: Some(Default::default()),
+			validator_id: None,
+			parachain_id: Some(100),
+		}));
+	}
+
+	#[test]
+	fn block_messages_roundtrip() {
+		roundtrip(Message::BlockRequest(BlockRequest {
+			id: 10,
+			fields: vec![BlockAttribute::Header, BlockAttribute::Body, BlockAttribute::Justification],
+			from: FromBlock::Number(5),
+			to: Some([3; 32].into()),
+			direction: Direction::Descending,
+			max: None,
+		}));
+		roundtrip(Message::BlockResponse(BlockResponse {
+			id: 10,
+			blocks: vec![BlockData {
+				hash: [4; 32].into(),
+				header: Some(Header {
+					parent_hash: [5; 32].into(),
+					number: 5,
+					state_root: [6; 32].into(),
+					extrinsics_root: [7; 32].into(),
+					digest: Default::default(),
+				}),
+				body: Some(vec![Extrinsic(vec![1, 2, 3])]),
+				receipt: None,
+				message_queue: Some(vec![8]),
+				justification: None,
+			}],
+		}));
+	}
+
+	#[test]
+	fn consensus_messages_roundtrip() {
+		roundtrip(Message::BftMessage(LocalizedBftMessage {
+			message: BftMessage::Consensus(SignedConsensusMessage::Vote(SignedConsensusVote {
+				vote: ConsensusVote::Commit(3, [9; 32].into()),
+				sender: [1; 32],
+				signature: Default::default(),
+			})),
+			parent_hash: [2; 32].into(),
+		}));
+		roundtrip(Message::Statement(Statement {
+			parent_hash: [3; 32].into(),
+			statement: UnsignedStatement::Candidate(vec![1, 2, 3]),
+			signature: Default::default(),
+			sender: [4; 32],
+		}));
+	}
+
+	#[test]
+	fn remote_messages_roundtrip() {
+		roundtrip(Message::RemoteCallRequest(RemoteCallRequest {
+			id: 1,
+			block: [1; 32].into(),
+			method: "authorities".into(),
+			data: vec![1, 2],
+		}));
+		roundtrip(Message::RemoteReadResponse(RemoteReadResponse {
+			id: 2,
+			proof: vec![vec![1], vec![2, 3]],
+		}));
+	}
+
+	#[test]
+	fn collation_roundtrip() {
+		roundtrip(Message::Collation(Collation {
+			relay_parent: [1; 32].into(),
+			parachain_id: 5,
+			receipt: vec![1, 2, 3],
+			block_data: vec![4, 5],
+			ingress: vec![6],
+			signature: Default::default(),
+		}));
+	}
+
+	#[test]
+	fn invalid_message_is_rejected() {
+		assert_eq!(Message::decode(&mut &[0u8, 1, 2][..]), None);
+		assert_eq!(Message::decode(&mut &[MessageKind::BlockAnnounce as u8, 1, 2][..]), None);
+	}
+}
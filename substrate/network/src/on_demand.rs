@@ -25,7 +25,8 @@ use linked_hash_map::LinkedHashMap;
 use linked_hash_map::Entry;
 use parking_lot::Mutex;
 use client;
-use client::light::{Fetcher, FetchChecker, RemoteCallRequest};
+use client::light::{Fetcher, FetchChecker, RemoteBodyRequest, RemoteCallRequest, RemoteReadRequest};
+use primitives::block;
 use io::SyncIo;
 use message;
 use network::PeerId;
@@ -45,8 +46,14 @@ pub trait OnDemandService: Send + Sync {
 	/// Maintain peers requests.
 	fn maintain_peers(&self, io: &mut SyncIo);
 
-	/// When response is received from remote node.
-	fn on_remote_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteCallResponse);
+	/// When call response is received from remote node.
+	fn on_remote_call_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteCallResponse);
+
+	/// When read response is received from remote node.
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteReadResponse);
+
+	/// When body response is received from remote node.
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteBodyResponse);
 }
 
 /// On-demand requests service. Dispatches requests to appropriate peers.
@@ -56,8 +63,8 @@ pub struct OnDemand<E: service::ExecuteInContext> {
 }
 
 /// On-demand response.
-pub struct Response {
-	receiver: Receiver<client::CallResult>,
+pub struct Response<T> {
+	receiver: Receiver<T>,
 }
 
 #[derive(Default)]
@@ -72,12 +79,23 @@ struct OnDemandCore<E: service::ExecuteInContext> {
 struct Request {
 	id: u64,
 	timestamp: Instant,
-	sender: Sender<client::CallResult>,
-	request: RemoteCallRequest,
+	data: RequestData,
+}
+
+enum RequestData {
+	RemoteCall(RemoteCallRequest, Sender<client::CallResult>),
+	RemoteRead(RemoteReadRequest, Sender<Option<Vec<u8>>>),
+	RemoteBody(RemoteBodyRequest, Sender<block::Body>),
 }
 
-impl Future for Response {
-	type Item = client::CallResult;
+enum Accept {
+	Ok,
+	CheckFailed(client::error::Error, RequestData),
+	Unexpected(RequestData),
+}
+
+impl<T> Future for Response<T> {
+	type Item = T;
 	type Error = client::error::Error;
 
 	fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
@@ -107,19 +125,69 @@ impl<E> OnDemand<E> where E: service::ExecuteInContext {
 	}
 
 	/// Execute method call on remote node, returning execution result and proof.
-	pub fn remote_call(&self, request: RemoteCallRequest) -> Response {
+	pub fn remote_call(&self, request: RemoteCallRequest) -> Response<client::CallResult> {
+		let (sender, receiver) = channel();
+		self.schedule_request(RequestData::RemoteCall(request, sender), Response {
+			receiver,
+		})
+	}
+
+	/// Read storage value at remote node, returning the value checked against the read proof.
+	pub fn remote_read(&self, request: RemoteReadRequest) -> Response<Option<Vec<u8>>> {
 		let (sender, receiver) = channel();
-		let result = Response {
-			receiver: receiver,
+		self.schedule_request(RequestData::RemoteRead(request, sender), Response {
+			receiver,
+		})
+	}
+
+	/// Fetch block body from remote node, returning the body checked against the header.
+	pub fn remote_body(&self, request: RemoteBodyRequest) -> Response<block::Body> {
+		let (sender, receiver) = channel();
+		self.schedule_request(RequestData::RemoteBody(request, sender), Response {
+			receiver,
+		})
+	}
+
+	/// Schedule && dispatch request.
+	fn schedule_request<R>(&self, data: RequestData, result: R) -> R {
+		let mut core = self.core.lock();
+		core.insert(data);
+		core.dispatch();
+		result
+	}
+
+	/// Try to accept response from given peer.
+	fn accept_response<F: FnOnce(Request) -> Accept>(&self, rtype: &str, io: &mut SyncIo, peer: PeerId, request_id: u64, try_accept: F) {
+		let mut core = self.core.lock();
+		let request = match core.remove(peer, request_id) {
+			Some(request) => request,
+			None => {
+				trace!(target: "sync", "Invalid remote {} response from peer {}", rtype, peer);
+				io.disconnect_peer(peer);
+				core.remove_peer(peer);
+				return;
+			},
+		};
+
+		let retry_request_data = match try_accept(request) {
+			Accept::Ok => None,
+			Accept::CheckFailed(error, retry_request_data) => {
+				trace!(target: "sync", "Failed to check remote {} response from peer {}: {}", rtype, peer, error);
+				Some(retry_request_data)
+			},
+			Accept::Unexpected(retry_request_data) => {
+				trace!(target: "sync", "Unexpected response to remote {} from peer {}", rtype, peer);
+				Some(retry_request_data)
+			},
 		};
 
-		{
-			let mut core = self.core.lock();
-			core.insert(sender, request);
-			core.dispatch();
+		if let Some(request_data) = retry_request_data {
+			io.disconnect_peer(peer);
+			core.remove_peer(peer);
+			core.insert(request_data);
 		}
 
-		result
+		core.dispatch();
 	}
 }
 
@@ -149,38 +217,65 @@ impl<E> OnDemandService for OnDemand<E> where E: service::ExecuteInContext {
 		core.dispatch();
 	}
 
-	fn on_remote_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteCallResponse) {
-		let mut core = self.core.lock();
-		match core.remove(peer, response.id) {
-			Some(request) => match self.checker.check_execution_proof(&request.request, (response.value, response.proof)) {
+	fn on_remote_call_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteCallResponse) {
+		self.accept_response("call", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteCall(request, sender) => match self.checker.check_execution_proof(&request, (response.value, response.proof)) {
 				Ok(response) => {
 					// we do not bother if receiver has been dropped already
-					let _ = request.sender.send(response);
-				},
-				Err(error) => {
-					trace!(target: "sync", "Failed to check remote response from peer {}: {}", peer, error);
-					io.disconnect_peer(peer);
-					core.remove_peer(peer);
-					core.insert(request.sender, request.request);
+					let _ = sender.send(response);
+					Accept::Ok
 				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteCall(request, sender)),
 			},
-			None => {
-				trace!(target: "sync", "Invalid remote response from peer {}", peer);
-				io.disconnect_peer(peer);
-				core.remove_peer(peer);
+			data => Accept::Unexpected(data),
+		})
+	}
+
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteReadResponse) {
+		self.accept_response("read", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteRead(request, sender) => match self.checker.check_read_proof(&request, response.proof) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(response);
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteRead(request, sender)),
 			},
-		}
+			data => Accept::Unexpected(data),
+		})
+	}
 
-		core.dispatch();
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer: PeerId, response: message::RemoteBodyResponse) {
+		self.accept_response("body", io, peer, response.id, |request| match request.data {
+			RequestData::RemoteBody(request, sender) => match self.checker.check_body_proof(&request, response.body) {
+				Ok(response) => {
+					// we do not bother if receiver has been dropped already
+					let _ = sender.send(response);
+					Accept::Ok
+				},
+				Err(error) => Accept::CheckFailed(error, RequestData::RemoteBody(request, sender)),
+			},
+			data => Accept::Unexpected(data),
+		})
 	}
 }
 
 impl<E> Fetcher for OnDemand<E> where E: service::ExecuteInContext {
-	type RemoteCallResult = Response;
+	type RemoteCallResult = Response<client::CallResult>;
+	type RemoteReadResult = Response<Option<Vec<u8>>>;
+	type RemoteBodyResult = Response<block::Body>;
 
 	fn remote_call(&self, request: RemoteCallRequest) -> Self::RemoteCallResult {
 		self.remote_call(request)
 	}
+
+	fn remote_read(&self, request: RemoteReadRequest) -> Self::RemoteReadResult {
+		self.remote_read(request)
+	}
+
+	fn remote_body(&self, request: RemoteBodyRequest) -> Self::RemoteBodyResult {
+		self.remote_body(request)
+	}
 }
 
 impl<E> OnDemandCore<E> where E: service::ExecuteInContext {
@@ -214,15 +309,14 @@ impl<E> OnDemandCore<E> where E: service::ExecuteInContext {
 		}
 	}
 
-	pub fn insert(&mut self, sender: Sender<client::CallResult>, request: RemoteCallRequest) {
+	pub fn insert(&mut self, data: RequestData) {
 		let request_id = self.next_request_id;
 		self.next_request_id += 1;
 
 		self.pending_requests.push_back(Request {
 			id: request_id,
 			timestamp: Instant::now(),
-			sender,
-			request,
+			data,
 		});
 	}
 
@@ -255,21 +349,34 @@ impl<E> OnDemandCore<E> where E: service::ExecuteInContext {
 			request.timestamp = Instant::now();
 			trace!(target: "sync", "Dispatching remote request {} to peer {}", request.id, peer);
 
-			service.execute_in_context(|ctx, protocol| {
-				let message = message::RemoteCallRequest {
-					id: request.id,
-					block: request.request.block,
-					method: request.request.method.clone(),
-					data: request.request.call_data.clone(),
-				};
-
-				protocol.send_message(ctx, peer, message::Message::RemoteCallRequest(message))
-			});
+			service.execute_in_context(|ctx, protocol| protocol.send_message(ctx, peer, request.message()));
 			self.active_peers.insert(peer, request);
 		}
 	}
 }
 
+impl Request {
+	pub fn message(&self) -> message::Message {
+		match self.data {
+			RequestData::RemoteCall(ref data, _) => message::Message::RemoteCallRequest(message::RemoteCallRequest {
+				id: self.id,
+				block: data.block,
+				method: data.method.clone(),
+				data: data.call_data.clone(),
+			}),
+			RequestData::RemoteRead(ref data, _) => message::Message::RemoteReadRequest(message::RemoteReadRequest {
+				id: self.id,
+				block: data.block,
+				key: data.key.clone(),
+			}),
+			RequestData::RemoteBody(ref data, _) => message::Message::RemoteBodyRequest(message::RemoteBodyRequest {
+				id: self.id,
+				block: data.block,
+			}),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::VecDeque;
@@ -278,7 +385,8 @@ mod tests {
 	use futures::Future;
 	use parking_lot::RwLock;
 	use client;
-	use client::light::{FetchChecker, RemoteCallRequest};
+	use client::light::{FetchChecker, RemoteBodyRequest, RemoteCallRequest, RemoteReadRequest};
+	use primitives::block::{self, Extrinsic};
 	use io::NetSyncIo;
 	use message;
 	use network::PeerId;
@@ -304,6 +412,20 @@ mod tests {
 				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
 			}
 		}
+
+		fn check_read_proof(&self, _request: &RemoteReadRequest, remote_proof: Vec<Vec<u8>>) -> client::error::Result<Option<Vec<u8>>> {
+			match self.ok {
+				true => Ok(remote_proof.into_iter().next()),
+				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
+
+		fn check_body_proof(&self, _request: &RemoteBodyRequest, body: block::Body) -> client::error::Result<block::Body> {
+			match self.ok {
+				true => Ok(body),
+				false => Err(client::error::ErrorKind::Backend("Test error".into()).into()),
+			}
+		}
 	}
 
 	fn dummy(ok: bool) -> (Arc<DummyExecutor>, Arc<OnDemand<DummyExecutor>>) {
@@ -319,7 +441,7 @@ mod tests {
 	}
 
 	fn receive_response(on_demand: &OnDemand<DummyExecutor>, network: &mut TestIo, peer: PeerId, id: message::RequestId) {
-		on_demand.on_remote_response(network, peer, message::RemoteCallResponse {
+		on_demand.on_remote_call_response(network, peer, message::RemoteCallResponse {
 			id: id,
 			value: vec![1],
 			proof: vec![vec![2]],
@@ -420,4 +542,60 @@ mod tests {
 		receive_response(&*on_demand, &mut network, 0, 0);
 		thread.join().unwrap();
 	}
+
+	#[test]
+	fn disconnects_from_peer_on_wrong_response_type() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+
+		on_demand.remote_call(RemoteCallRequest { block: Default::default(), method: "test".into(), call_data: vec![] });
+		on_demand.on_remote_read_response(&mut network, 0, message::RemoteReadResponse {
+			id: 0,
+			proof: vec![vec![2]],
+		});
+		assert!(network.to_disconnect.contains(&0));
+		assert_eq!(on_demand.core.lock().pending_requests.len(), 1);
+	}
+
+	#[test]
+	fn receives_remote_read_response() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+
+		let response = on_demand.remote_read(RemoteReadRequest { block: Default::default(), key: b":key".to_vec() });
+		let thread = ::std::thread::spawn(move || {
+			let result = response.wait().unwrap();
+			assert_eq!(result, Some(vec![42]));
+		});
+
+		on_demand.on_remote_read_response(&mut network, 0, message::RemoteReadResponse {
+			id: 0,
+			proof: vec![vec![42]],
+		});
+		thread.join().unwrap();
+	}
+
+	#[test]
+	fn receives_remote_body_response() {
+		let (_x, on_demand) = dummy(true);
+		let queue = RwLock::new(VecDeque::new());
+		let mut network = TestIo::new(&queue, None);
+		on_demand.on_connect(0, Role::FULL);
+
+		let response = on_demand.remote_body(RemoteBodyRequest { block: Default::default() });
+		let thread = ::std::thread::spawn(move || {
+			let result = response.wait().unwrap();
+			assert_eq!(result, vec![Extrinsic(vec![42])]);
+		});
+
+		on_demand.on_remote_body_response(&mut network, 0, message::RemoteBodyResponse {
+			id: 0,
+			body: vec![Extrinsic(vec![42])],
+		});
+		thread.join().unwrap();
+	}
 }
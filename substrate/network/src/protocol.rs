@@ -21,15 +21,18 @@ use std::time;
 use parking_lot::{RwLock, Mutex};
 use futures::sync::oneshot;
 use serde_json;
-use primitives::block::{HeaderHash, ExtrinsicHash, Number as BlockNumber, Header, Id as BlockId};
-use primitives::{Hash, blake2_256};
+use primitives::block::{HeaderHash, ExtrinsicHash, Number as BlockNumber, Header, Body, Id as BlockId};
+use primitives::{AuthorityId, Hash, blake2_256};
+use primitives::hexdisplay::HexDisplay;
+use codec::Slicable;
+use ed25519;
 use runtime_support::Hashable;
 use network::PeerId;
 
 use message::{self, Message};
 use sync::{ChainSync, Status as SyncStatus, SyncState};
 use consensus::Consensus;
-use service::{Role, TransactionPool, StatementStream, BftMessageStream};
+use service::{Role, TransactionPool, StatementStream, BftMessageStream, CollationStream};
 use config::ProtocolConfig;
 use chain::Client;
 use on_demand::OnDemandService;
@@ -38,7 +41,10 @@ use error;
 use super::header_hash;
 
 const REQUEST_TIMEOUT_SEC: u64 = 40;
-const PROTOCOL_VERSION: u32 = 0;
+/// Current protocol version. Messages are encoded with the binary codec.
+const PROTOCOL_VERSION: u32 = 1;
+/// Legacy protocol version, kept as a debug fallback. Messages are encoded as JSON.
+const JSON_PROTOCOL_VERSION: u32 = 0;
 
 // Maximum allowed entries in `BlockResponse`
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
@@ -56,6 +62,8 @@ pub struct Protocol {
 	// Connected peers pending Status message.
 	handshaking_peers: RwLock<HashMap<PeerId, time::Instant>>,
 	transaction_pool: Arc<TransactionPool>,
+	// Connected peers that negotiated JSON encoding.
+	json_peers: RwLock<HashSet<PeerId>>,
 }
 
 /// Syncing status and statistics
@@ -118,6 +126,7 @@ impl Protocol {
 			peers: RwLock::new(HashMap::new()),
 			handshaking_peers: RwLock::new(HashMap::new()),
 			transaction_pool: transaction_pool,
+			json_peers: RwLock::new(HashSet::new()),
 		};
 		Ok(protocol)
 	}
@@ -134,14 +143,33 @@ impl Protocol {
 	}
 
 	pub fn handle_packet(&self, io: &mut SyncIo, peer_id: PeerId, data: &[u8]) {
-		let message: Message = match serde_json::from_slice(data) {
-			Ok(m) => m,
-			Err(e) => {
-				debug!("Invalid packet from {}: {}", peer_id, e);
-				io.disable_peer(peer_id);
-				return;
+		// Binary messages never start with `{`, so JSON is detected by the first byte.
+		let is_json = data.first() == Some(&b'{');
+		let message: Message = if is_json {
+			match serde_json::from_slice(data) {
+				Ok(m) => m,
+				Err(e) => {
+					debug!("Invalid packet from {}: {}", peer_id, e);
+					io.disable_peer(peer_id);
+					return;
+				}
+			}
+		} else {
+			match Slicable::decode(&mut &data[..]) {
+				Some(m) => m,
+				None => {
+					debug!("Invalid packet from {}", peer_id);
+					io.disable_peer(peer_id);
+					return;
+				}
 			}
 		};
+		// Messages are identified by the hash of their binary encoding.
+		let message_hash = if is_json {
+			Protocol::hash_message(&message)
+		} else {
+			blake2_256(data).into()
+		};
 
 		match message {
 			Message::Status(s) => self.on_status_message(io, peer_id, s),
@@ -174,13 +202,18 @@ impl Protocol {
 			Message::BlockAnnounce(announce) => {
 				self.on_block_announce(io, peer_id, announce);
 			},
-			Message::Statement(s) => self.on_statement(io, peer_id, s, blake2_256(data).into()),
+			Message::Statement(s) => self.on_statement(io, peer_id, s, message_hash),
 			Message::CandidateRequest(r) => self.on_candidate_request(io, peer_id, r),
 			Message::CandidateResponse(r) => self.on_candidate_response(io, peer_id, r),
-			Message::BftMessage(m) => self.on_bft_message(io, peer_id, m, blake2_256(data).into()),
+			Message::BftMessage(m) => self.on_bft_message(io, peer_id, m, message_hash),
 			Message::Transactions(m) => self.on_transactions(io, peer_id, m),
 			Message::RemoteCallRequest(request) => self.on_remote_call_request(io, peer_id, request),
-			Message::RemoteCallResponse(response) => self.on_remote_call_response(io, peer_id, response)
+			Message::RemoteCallResponse(response) => self.on_remote_call_response(io, peer_id, response),
+			Message::RemoteReadRequest(request) => self.on_remote_read_request(io, peer_id, request),
+			Message::RemoteReadResponse(response) => self.on_remote_read_response(io, peer_id, response),
+			Message::RemoteBodyRequest(request) => self.on_remote_body_request(io, peer_id, request),
+			Message::RemoteBodyResponse(response) => self.on_remote_body_response(io, peer_id, response),
+			Message::Collation(collation) => self.on_collation(io, peer_id, collation),
 		}
 	}
 
@@ -197,7 +230,17 @@ impl Protocol {
 			},
 			_ => (),
 		}
-		let data = serde_json::to_vec(&message).expect("Serializer is infallible; qed");
+		// The status is sent before the peer's version is known, so it is encoded as JSON, which
+		// peers of every version decode.
+		let as_json = match message {
+			Message::Status(_) => true,
+			_ => self.json_peers.read().contains(&peer_id),
+		};
+		let data = if as_json {
+			serde_json::to_vec(&message).expect("Serializer is infallible; qed")
+		} else {
+			message.encode()
+		};
 		if let Err(e) = io.send(peer_id, data) {
 			debug!(target:"sync", "Error sending message: {:?}", e);
 			io.disconnect_peer(peer_id);
@@ -205,8 +248,7 @@ impl Protocol {
 	}
 
 	pub fn hash_message(message: &Message) -> Hash {
-		let data = serde_json::to_vec(&message).expect("Serializer is infallible; qed");
-		blake2_256(&data).into()
+		blake2_256(&message.encode()).into()
 	}
 
 	/// Called when a new peer is connected
@@ -223,6 +265,7 @@ impl Protocol {
 			let mut peers = self.peers.write();
 			let mut handshaking_peers = self.handshaking_peers.write();
 			handshaking_peers.remove(&peer);
+			self.json_peers.write().remove(&peer);
 			peers.remove(&peer).is_some()
 		};
 		if removed {
@@ -260,7 +303,7 @@ impl Protocol {
 			let block_data = message::BlockData {
 				hash: hash,
 				header: if get_header { Some(header) } else { None },
-				body: if get_body { self.chain.body(&BlockId::Hash(hash)).unwrap_or(None) } else { None },
+				body: if get_body { self.local_body(&BlockId::Hash(hash)).unwrap_or(None) } else { None },
 				receipt: None,
 				message_queue: None,
 				justification: if get_justification { self.chain.justification(&BlockId::Hash(hash)).unwrap_or(None) } else { None },
@@ -309,6 +352,11 @@ impl Protocol {
 		self.consensus.lock().on_bft_message(io, self, peer, message, hash);
 	}
 
+	fn on_collation(&self, io: &mut SyncIo, peer: PeerId, collation: message::Collation) {
+		trace!(target: "sync", "Collation from {} for parachain {} on {}", peer, collation.parachain_id, collation.relay_parent);
+		self.consensus.lock().on_collation(io, peer, collation);
+	}
+
 	/// See `ConsensusService` trait.
 	pub fn send_bft_message(&self, io: &mut SyncIo, message: message::LocalizedBftMessage) {
 		self.consensus.lock().send_bft_message(io, self, message)
@@ -334,6 +382,21 @@ impl Protocol {
 		self.consensus.lock().send_statement(io, self, statement)
 	}
 
+	/// See `ConsensusService` trait.
+	pub fn collations(&self, parachain_id: u64, relay_parent: Hash) -> CollationStream {
+		self.consensus.lock().collations(parachain_id, relay_parent)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn send_collation(&self, io: &mut SyncIo, collation: message::Collation, validators: &[AuthorityId]) {
+		self.consensus.lock().send_collation(io, self, collation, validators)
+	}
+
+	/// See `ConsensusService` trait.
+	pub fn set_parachains(&self, parachains: Vec<u64>) {
+		self.consensus.lock().set_parachains(parachains)
+	}
+
 	/// See `ConsensusService` trait.
 	pub fn set_local_candidate(&self, candidate: Option<(Hash, Vec<u8>)>) {
 		self.consensus.lock().set_local_candidate(candidate)
@@ -398,14 +461,32 @@ impl Protocol {
 				trace!(target: "sync", "Peer {} genesis hash mismatch (ours: {}, theirs: {})", peer_id, self.genesis_hash, status.genesis_hash);
 				return;
 			}
-			if status.version != PROTOCOL_VERSION {
-				io.disable_peer(peer_id);
-				trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, status.version);
-				return;
+			let version = cmp::min(PROTOCOL_VERSION, status.max_version.unwrap_or(status.version));
+			match version {
+				PROTOCOL_VERSION => (),
+				JSON_PROTOCOL_VERSION => {
+					trace!(target: "sync", "Peer {} uses JSON encoding", peer_id);
+					self.json_peers.write().insert(peer_id);
+				},
+				_ => {
+					io.disable_peer(peer_id);
+					trace!(target: "sync", "Peer {} unsupported eth protocol ({})", peer_id, status.version);
+					return;
+				}
+			}
+			if let Some(validator_id) = status.validator_id {
+				let valid = status.validator_signature.as_ref().map_or(false, |signature|
+					ed25519::verify_strong(signature, &status.signing_payload(), ed25519::Public(validator_id))
+				);
+				if !valid {
+					io.disable_peer(peer_id);
+					trace!(target: "sync", "Peer {} bad validator signature", peer_id);
+					return;
+				}
 			}
 
 			let peer = Peer {
-				protocol_version: status.version,
+				protocol_version: version,
 				roles: message::Role::as_flags(&status.roles),
 				best_hash: status.best_hash,
 				best_number: status.best_number,
@@ -421,7 +502,7 @@ impl Protocol {
 		}
 
 		self.sync.write().new_peer(io, self, peer_id);
-		self.consensus.lock().new_peer(io, self, peer_id, &status.roles);
+		self.consensus.lock().new_peer(io, self, peer_id, &status.roles, status.validator_id, status.parachain_id);
 		self.on_demand.as_ref().map(|s| s.on_connect(peer_id, message::Role::as_flags(&status.roles)));
 	}
 
@@ -484,16 +565,20 @@ impl Protocol {
 	/// Send Status message
 	fn send_status(&self, io: &mut SyncIo, peer_id: PeerId) {
 		if let Ok(info) = self.chain.info() {
-			let status = message::Status {
-				version: PROTOCOL_VERSION,
+			let mut status = message::Status {
+				version: JSON_PROTOCOL_VERSION,
+				max_version: Some(PROTOCOL_VERSION),
 				genesis_hash: info.chain.genesis_hash,
 				roles: self.config.roles.into(),
 				best_number: info.chain.best_number,
 				best_hash: info.chain.best_hash,
 				validator_signature: None,
-				validator_id: None,
-				parachain_id: None,
+				validator_id: self.config.validator_key.as_ref().map(|key| key.public().0),
+				parachain_id: self.config.parachain_id,
 			};
+			if let Some(ref key) = self.config.validator_key {
+				status.validator_signature = Some(key.sign(&status.signing_payload()));
+			}
 			self.send_message(io, peer_id, Message::Status(status))
 		}
 	}
@@ -555,7 +640,64 @@ impl Protocol {
 
 	fn on_remote_call_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteCallResponse) {
 		trace!(target: "sync", "Remote response {} from {}", response.id, peer_id);
-		self.on_demand.as_ref().map(|s| s.on_remote_response(io, peer_id, response));
+		self.on_demand.as_ref().map(|s| s.on_remote_call_response(io, peer_id, response));
+	}
+
+	fn on_remote_read_request(&self, io: &mut SyncIo, peer_id: PeerId, request: message::RemoteReadRequest) {
+		trace!(target: "sync", "Remote read request {} from {} ({} at {})",
+			request.id, peer_id, HexDisplay::from(&request.key), request.block);
+		let proof = match self.chain.read_proof(&request.block, &request.key) {
+			Ok(proof) => proof,
+			Err(error) => {
+				trace!(target: "sync", "Remote read request {} from {} ({} at {}) failed with: {}",
+					request.id, peer_id, HexDisplay::from(&request.key), request.block, error);
+				Default::default()
+			},
+		};
+
+		self.send_message(io, peer_id, message::Message::RemoteReadResponse(message::RemoteReadResponse {
+			id: request.id, proof,
+		}));
+	}
+
+	fn on_remote_read_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteReadResponse) {
+		trace!(target: "sync", "Remote read response {} from {}", response.id, peer_id);
+		self.on_demand.as_ref().map(|s| s.on_remote_read_response(io, peer_id, response));
+	}
+
+	fn on_remote_body_request(&self, io: &mut SyncIo, peer_id: PeerId, request: message::RemoteBodyRequest) {
+		trace!(target: "sync", "Remote body request {} from {} ({})", request.id, peer_id, request.block);
+		let body = match self.local_body(&BlockId::Hash(request.block)) {
+			Ok(Some(body)) => body,
+			Ok(None) => {
+				trace!(target: "sync", "Remote body request {} from {} ({}) failed: no body", request.id, peer_id, request.block);
+				Default::default()
+			},
+			Err(error) => {
+				trace!(target: "sync", "Remote body request {} from {} ({}) failed with: {}",
+					request.id, peer_id, request.block, error);
+				Default::default()
+			},
+		};
+
+		self.send_message(io, peer_id, message::Message::RemoteBodyResponse(message::RemoteBodyResponse {
+			id: request.id, body,
+		}));
+	}
+
+	fn on_remote_body_response(&self, io: &mut SyncIo, peer_id: PeerId, response: message::RemoteBodyResponse) {
+		trace!(target: "sync", "Remote body response {} from {}", response.id, peer_id);
+		self.on_demand.as_ref().map(|s| s.on_remote_body_response(io, peer_id, response));
+	}
+
+	/// Read block body from the local database. Light nodes do not store bodies
+	/// and must not fetch them from the network while serving a peer request.
+	fn local_body(&self, id: &BlockId) -> Result<Option<Body>, ::client::error::Error> {
+		if self.config.roles.contains(Role::LIGHT) {
+			return Ok(None);
+		}
+
+		self.chain.body(id)
 	}
 
 	pub fn chain(&self) -> &Client {
@@ -23,14 +23,14 @@ use network::{NetworkProtocolHandler, NetworkContext, HostInfo, PeerId, Protocol
 NetworkConfiguration , NonReservedPeerMode, ErrorKind};
 use network_devp2p::{NetworkService};
 use primitives::block::{ExtrinsicHash, Header, HeaderHash};
-use primitives::Hash;
+use primitives::{AuthorityId, Hash};
 use core_io::{TimerToken};
 use io::NetSyncIo;
 use protocol::{Protocol, ProtocolStatus, PeerInfo as ProtocolPeerInfo};
 use config::{ProtocolConfig};
 use error::Error;
 use chain::Client;
-use message::{Statement, LocalizedBftMessage};
+use message::{Statement, LocalizedBftMessage, Collation};
 use on_demand::OnDemandService;
 
 /// Polkadot devp2p protocol id
@@ -44,6 +44,8 @@ pub type FetchFuture = oneshot::Receiver<Vec<u8>>;
 pub type StatementStream = mpsc::UnboundedReceiver<Statement>;
 /// Type that represents bft messages stream.
 pub type BftMessageStream = mpsc::UnboundedReceiver<LocalizedBftMessage>;
+/// Type that represents collation stream.
+pub type CollationStream = mpsc::UnboundedReceiver<Collation>;
 
 const TICK_TOKEN: TimerToken = 0;
 const TICK_TIMEOUT: Duration = Duration::from_millis(1000);
@@ -106,6 +108,15 @@ pub trait ConsensusService: Send + Sync {
 	fn bft_messages(&self, parent_hash: Hash) -> BftMessageStream;
 	/// Send out a BFT message.
 	fn send_bft_message(&self, message: LocalizedBftMessage);
+
+	/// Get stream of collations for given parachain, building on given relay chain
+	/// parent hash.
+	fn collations(&self, parachain_id: u64, relay_parent: Hash) -> CollationStream;
+	/// Send out a collation to the connected validators among `validators`.
+	fn send_collation(&self, collation: Collation, validators: Vec<AuthorityId>);
+	/// Set the parachains registered on the relay chain. Collations for other parachains
+	/// are ignored.
+	fn set_parachains(&self, parachains: Vec<u64>);
 }
 
 /// Service able to execute closure in the network context.
@@ -286,6 +297,20 @@ impl ConsensusService for Service {
 			self.handler.protocol.send_bft_message(&mut NetSyncIo::new(context), message);
 		});
 	}
+
+	fn collations(&self, parachain_id: u64, relay_parent: Hash) -> CollationStream {
+		self.handler.protocol.collations(parachain_id, relay_parent)
+	}
+
+	fn send_collation(&self, collation: Collation, validators: Vec<AuthorityId>) {
+		self.network.with_context(DOT_PROTOCOL_ID, |context| {
+			self.handler.protocol.send_collation(&mut NetSyncIo::new(context), collation, &validators);
+		});
+	}
+
+	fn set_parachains(&self, parachains: Vec<u64>) {
+		self.handler.protocol.set_parachains(parachains)
+	}
 }
 
 impl NetworkProtocolHandler for ProtocolHandler {
@@ -16,6 +16,7 @@
 
 use super::*;
 use message::*;
+use codec::Slicable;
 use futures::Stream;
 
 #[test]
@@ -42,10 +43,153 @@ fn bft_messages_include_those_sent_before_asking_for_stream() {
 	};
 
 
-	let as_bytes = ::serde_json::to_vec(&Message::BftMessage(localized.clone())).unwrap();
+	let as_bytes = Message::BftMessage(localized.clone()).encode();
 	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
 
 	let stream = peer.sync.bft_messages(parent_hash);
 
 	assert_eq!(stream.wait().next(), Some(Ok(localized)));
 }
+
+#[test]
+fn collations_are_delivered_from_registered_collators() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::VALIDATOR | ::service::Role::COLLATOR | ::service::Role::FULL;
+	config.parachain_id = Some(5);
+
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	let relay_parent = peer.genesis_hash();
+
+	let collation = |parachain_id| Collation {
+		relay_parent: relay_parent,
+		parachain_id: parachain_id,
+		receipt: vec![1, 2, 3],
+		block_data: vec![4, 5, 6],
+		ingress: Vec::new(),
+		signature: Default::default(),
+	};
+
+	// collations for unregistered parachains are ignored.
+	let as_bytes = Message::Collation(collation(5)).encode();
+	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+	peer.sync.set_parachains(vec![5, 6]);
+	let mut early = peer.sync.collations(5, relay_parent).wait();
+
+	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+	let mut stream = peer.sync.collations(5, relay_parent).wait();
+	assert_eq!(early.next(), Some(Ok(collation(5))));
+	assert_eq!(stream.next(), Some(Ok(collation(5))));
+
+	// collations for another parachain get the collator disabled.
+	let as_bytes = Message::Collation(collation(6)).encode();
+	peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+	assert!(io.to_disconnect.contains(&1));
+}
+
+#[test]
+fn collations_are_sent_to_assigned_validators_only() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::VALIDATOR | ::service::Role::FULL;
+	let key = Arc::new(::ed25519::Pair::from_seed(&[1; 32]));
+	let validator_id = key.public().0;
+	config.validator_key = Some(key);
+
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let collation = Collation {
+		relay_parent: peer.genesis_hash(),
+		parachain_id: 5,
+		receipt: vec![1, 2, 3],
+		block_data: vec![4, 5, 6],
+		ingress: Vec::new(),
+		signature: Default::default(),
+	};
+
+	let mut io = TestIo::new(&peer.queue, None);
+	peer.sync.send_collation(&mut io, collation.clone(), &[[2; 32]]);
+	assert!(io.packets.is_empty());
+
+	peer.sync.send_collation(&mut io, collation, &[validator_id]);
+	assert_eq!(io.packets.len(), 1);
+	assert_eq!(io.packets[0].recipient, 1);
+}
+
+#[test]
+fn forged_validator_status_is_refused() {
+	let net = TestNet::new(1);
+	let peer = net.peer(0);
+	peer.start();
+	peer.on_connect(1);
+	peer.pending_message().unwrap();
+
+	let key = ::ed25519::Pair::from_seed(&[1; 32]);
+	let status = |validator_id: [u8; 32]| {
+		let mut status = Status {
+			version: 0,
+			max_version: Some(1),
+			roles: vec![Role::Full, Role::Validator],
+			best_number: 0,
+			best_hash: peer.genesis_hash(),
+			genesis_hash: peer.genesis_hash(),
+			validator_signature: None,
+			validator_id: Some(validator_id),
+			parachain_id: None,
+		};
+		status.validator_signature = Some(key.sign(&status.signing_payload()));
+		Message::Status(status).encode()
+	};
+
+	// the status is signed with a key other than the announced one.
+	let disconnected = peer.receive_message(1, TestPacket { data: status([2; 32]), recipient: 0 });
+	assert!(disconnected.contains(&1));
+
+	peer.on_connect(2);
+	peer.pending_message().unwrap();
+	let disconnected = peer.receive_message(2, TestPacket { data: status(key.public().0), recipient: 0 });
+	assert!(disconnected.is_empty());
+}
+
+#[test]
+fn collator_flood_does_not_block_other_collators() {
+	let mut config = ::config::ProtocolConfig::default();
+	config.roles = ::service::Role::VALIDATOR | ::service::Role::COLLATOR | ::service::Role::FULL;
+	config.parachain_id = Some(5);
+
+	let mut net = TestNet::new_with_config(3, config);
+	net.sync(); // necessary for handshaking
+
+	let peer = net.peer(0);
+	let mut io = TestIo::new(&peer.queue, None);
+	peer.sync.set_parachains(vec![5]);
+	let relay_parent = peer.genesis_hash();
+
+	let collation = |relay_parent| Collation {
+		relay_parent: relay_parent,
+		parachain_id: 5,
+		receipt: vec![1, 2, 3],
+		block_data: vec![4, 5, 6],
+		ingress: Vec::new(),
+		signature: Default::default(),
+	};
+
+	// collator 1 floods collations on unknown relay parents.
+	for i in 0..32u64 {
+		let as_bytes = Message::Collation(collation(i.into())).encode();
+		peer.sync.handle_packet(&mut io, 1, &as_bytes[..]);
+	}
+	let as_bytes = Message::Collation(collation(relay_parent)).encode();
+	peer.sync.handle_packet(&mut io, 2, &as_bytes[..]);
+	assert!(io.to_disconnect.is_empty());
+
+	let mut stream = peer.sync.collations(5, relay_parent).wait();
+	assert_eq!(stream.next(), Some(Ok(collation(relay_parent))));
+	// only the most recent collations of the flooding collator are kept.
+	let mut stream = peer.sync.collations(5, 31.into()).wait();
+	assert_eq!(stream.next(), Some(Ok(collation(31.into()))));
+}
@@ -85,3 +85,44 @@ fn sync_after_fork_works() {
 	assert!(net.peer(2).client.backend().blockchain().canon_equals_to(&peer1_chain));
 }
 
+
+#[test]
+fn legacy_json_peer_is_served_in_json() {
+	use message::{Message, Role, Status};
+
+	::env_logger::init().ok();
+	let net = TestNet::new(1);
+	let peer = net.peer(0);
+	peer.start();
+	peer.on_connect(1);
+
+	// the status is sent before the version of the peer is known, in the encoding every version reads.
+	let packet = peer.pending_message().unwrap();
+	let status: ::serde_json::Value = ::serde_json::from_slice(&packet.data).unwrap();
+	assert_eq!(status["Status"]["version"], 0);
+
+	// a legacy peer knows neither `max_version` nor the binary codec.
+	let legacy_status = Message::Status(Status {
+		version: 0,
+		max_version: None,
+		roles: vec![Role::Full],
+		best_number: 5,
+		best_hash: [1; 32].into(),
+		genesis_hash: peer.genesis_hash(),
+		validator_signature: None,
+		validator_id: None,
+		parachain_id: None,
+	});
+	let mut legacy_status = ::serde_json::to_value(&legacy_status).unwrap();
+	legacy_status["Status"].as_object_mut().unwrap().remove("max_version");
+	let data = ::serde_json::to_vec(&legacy_status).unwrap();
+	assert!(peer.receive_message(1, TestPacket { data, recipient: 0 }).is_empty());
+
+	// the block request for the peer's better chain is sent as JSON.
+	let packet = peer.pending_message().unwrap();
+	assert_eq!(packet.data[0], b'{');
+	match ::serde_json::from_slice(&packet.data).unwrap() {
+		Message::BlockRequest(_) => (),
+		message => panic!("Unexpected message {:?}", message),
+	}
+}
@@ -72,6 +72,14 @@ impl TypedValue {
 			_ => None,
 		}
 	}
+
+	/// Returns `Some` if this value of type `I64`.
+	pub fn as_i64(&self) -> Option<i64> {
+		match *self {
+			TypedValue::I64(v) => Some(v),
+			_ => None,
+		}
+	}
 }
 
 #[cfg(feature = "std")]
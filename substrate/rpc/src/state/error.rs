@@ -28,6 +28,11 @@ error_chain! {
 			description("not implemented yet"),
 			display("Method Not Implemented"),
 		}
+		/// The runtime returned logs which could not be decoded.
+		InvalidLogs {
+			description("invalid logs"),
+			display("Runtime returned logs which could not be decoded"),
+		}
 	}
 }
 
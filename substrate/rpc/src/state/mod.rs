@@ -23,12 +23,50 @@ mod tests;
 
 use std::sync::Arc;
 use client::{self, Client, CallExecutor};
-use primitives::{block, Hash, blake2_256};
+use codec::{Slicable, Input};
+use primitives::{block, bytes, Hash, H256, blake2_256};
 use primitives::storage::{StorageKey, StorageData};
 use primitives::hexdisplay::HexDisplay;
 use state_machine;
 
-use self::error::Result;
+use self::error::{ErrorKind, Result};
+
+/// A contract log deposited in a block, as returned by the runtime's `logs` call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+	/// The index of the extrinsic which deposited the log.
+	pub extrinsic_index: u32,
+	/// The contract which deposited the log.
+	pub address: H256,
+	/// The topics the log was deposited under.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	#[serde(with = "bytes")]
+	pub data: Vec<u8>,
+}
+
+impl Slicable for LogRecord {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(LogRecord {
+			extrinsic_index: Slicable::decode(input)?,
+			address: Slicable::decode(input)?,
+			topics: Slicable::decode(input)?,
+			data: Slicable::decode(input)?,
+		})
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.extrinsic_index.using_encoded(|s| v.extend(s));
+		self.address.using_encoded(|s| v.extend(s));
+		self.topics.using_encoded(|s| v.extend(s));
+		self.data.using_encoded(|s| v.extend(s));
+
+		v
+	}
+}
 
 build_rpc_trait! {
 	/// Polkadot state API
@@ -64,6 +102,16 @@ build_rpc_trait! {
 		/// Call a contract.
 		#[rpc(name = "state_call")]
 		fn call(&self, String, Vec<u8>) -> Result<Vec<u8>>;
+
+		/// Returns the contract logs of a block deposited by any of the given addresses under any
+		/// of the given topics. An empty list matches everything.
+		#[rpc(name = "state_getLogsAt")]
+		fn logs_at(&self, Vec<H256>, Vec<H256>, block::HeaderHash) -> Result<Vec<LogRecord>>;
+
+		/// Returns the contract logs of the best block deposited by any of the given addresses
+		/// under any of the given topics. An empty list matches everything.
+		#[rpc(name = "state_getLogs")]
+		fn logs(&self, Vec<H256>, Vec<H256>) -> Result<Vec<LogRecord>>;
 	}
 }
 
@@ -105,4 +153,13 @@ impl<B, E> StateApi for Arc<Client<B, E>> where
 	fn call(&self, method: String, data: Vec<u8>) -> Result<Vec<u8>> {
 		self.call_at(method, data, self.as_ref().info()?.chain.best_hash)
 	}
+
+	fn logs_at(&self, addresses: Vec<H256>, topics: Vec<H256>, block: block::HeaderHash) -> Result<Vec<LogRecord>> {
+		let encoded = self.call_at("logs".into(), (addresses, topics).encode(), block)?;
+		Slicable::decode(&mut &encoded[..]).ok_or_else(|| ErrorKind::InvalidLogs.into())
+	}
+
+	fn logs(&self, addresses: Vec<H256>, topics: Vec<H256>) -> Result<Vec<LogRecord>> {
+		self.logs_at(addresses, topics, self.as_ref().info()?.chain.best_hash)
+	}
 }
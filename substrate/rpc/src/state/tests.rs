@@ -39,3 +39,30 @@ fn should_call_contract() {
 		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
 	)
 }
+
+#[test]
+fn should_decode_runtime_log_records() {
+	let runtime_logs = vec![(0u32, H256::from(1), vec![H256::from(2), H256::from(3)], vec![5u8, 6])].encode();
+
+	assert_eq!(
+		Vec::<LogRecord>::decode(&mut &runtime_logs[..]),
+		Some(vec![LogRecord {
+			extrinsic_index: 0,
+			address: H256::from(1),
+			topics: vec![H256::from(2), H256::from(3)],
+			data: vec![5, 6],
+		}])
+	);
+	assert_eq!(Vec::<LogRecord>::decode(&mut &runtime_logs[..runtime_logs.len() - 1]), None);
+}
+
+#[test]
+fn should_fail_to_get_logs_without_runtime_support() {
+	let client = Arc::new(test_client::new());
+	let genesis_hash = client.genesis_hash();
+
+	assert_matches!(
+		StateApi::logs_at(&client, vec![], vec![], genesis_hash),
+		Err(Error(ErrorKind::Client(client::error::ErrorKind::Execution(_)), _))
+	)
+}
@@ -0,0 +1,214 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate Demo.
+
+// Substrate Demo is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate Demo is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate Demo.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Macros for declaring the events of a runtime module and combining them into the outer event
+//! type of a runtime.
+
+pub use codec::{Slicable, Input};
+pub use rstd::prelude::Vec;
+
+/// Declare the events of a module.
+///
+/// Each variant has named fields and an explicit index used for its encoding:
+///
+/// ```ignore
+/// decl_event! {
+/// 	pub enum RawEvent<AccountId, Balance> {
+/// 		Transfer { from: AccountId, to: AccountId, value: Balance } = 0,
+/// 	}
+/// }
+/// ```
+///
+/// The events may be ignored by a runtime by using `()` as the module's event type.
+#[macro_export]
+macro_rules! decl_event {
+	(
+		$(#[$attr:meta])*
+		pub enum $name:ident<$( $param:ident ),*> {
+			$(
+				$(#[$variant_attr:meta])*
+				$variant:ident { $( $field:ident : $field_ty:ty ),* } = $id:expr,
+			)*
+		}
+	) => {
+		$(#[$attr])*
+		#[derive(Clone, PartialEq, Eq)]
+		#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+		pub enum $name<$( $param ),*> {
+			$(
+				$(#[$variant_attr])*
+				$variant { $( $field : $field_ty ),* },
+			)*
+		}
+
+		impl<$( $param: $crate::event::Slicable ),*> $crate::event::Slicable for $name<$( $param ),*> {
+			__decl_event_slicable!{ $name; $( $variant { $( $field ),* } = $id, )* }
+		}
+
+		impl<$( $param ),*> From<$name<$( $param ),*>> for () {
+			fn from(_: $name<$( $param ),*>) -> () { () }
+		}
+	};
+	(
+		$(#[$attr:meta])*
+		pub enum $name:ident {
+			$(
+				$(#[$variant_attr:meta])*
+				$variant:ident { $( $field:ident : $field_ty:ty ),* } = $id:expr,
+			)*
+		}
+	) => {
+		$(#[$attr])*
+		#[derive(Clone, PartialEq, Eq)]
+		#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+		pub enum $name {
+			$(
+				$(#[$variant_attr])*
+				$variant { $( $field : $field_ty ),* },
+			)*
+		}
+
+		impl $crate::event::Slicable for $name {
+			__decl_event_slicable!{ $name; $( $variant { $( $field ),* } = $id, )* }
+		}
+
+		impl From<$name> for () {
+			fn from(_: $name) -> () { () }
+		}
+	}
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __decl_event_slicable {
+	(
+		$name:ident; $( $variant:ident { $( $field:ident ),* } = $id:expr, )*
+	) => {
+		fn decode<I: $crate::event::Input>(input: &mut I) -> Option<Self> {
+			match input.read_byte()? {
+				$(
+					$id => Some($name::$variant {
+						$( $field: $crate::event::Slicable::decode(input)? ),*
+					}),
+				)*
+				_ => None,
+			}
+		}
+
+		fn encode(&self) -> $crate::event::Vec<u8> {
+			let mut v = $crate::event::Vec::new();
+			match *self {
+				$(
+					$name::$variant { $( ref $field ),* } => {
+						v.push($id as u8);
+						$( $crate::event::Slicable::using_encoded($field, |s| v.extend(s)); )*
+					}
+				)*
+			}
+			v
+		}
+	}
+}
+
+/// Implement the outer event type of a runtime, combining the events of its modules. Modules
+/// whose events are generic over the runtime are marked with `<T>`.
+///
+/// ```ignore
+/// impl_outer_event! {
+/// 	pub enum Event for Concrete {
+/// 		staking<T> = 0,
+/// 		parachains = 1,
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_outer_event {
+	(
+		pub enum $name:ident for $runtime:ident {
+			$( $rest:tt )*
+		}
+	) => {
+		__impl_outer_event! { $name; $runtime; {}; $( $rest )* }
+	}
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_outer_event {
+	(
+		$name:ident; $runtime:ident; { $( $parsed:tt )* };
+		$module:ident<T> = $id:expr, $( $rest:tt )*
+	) => {
+		__impl_outer_event! {
+			$name; $runtime; { $( $parsed )* $module [ $module::Event<$runtime> ] = $id, };
+			$( $rest )*
+		}
+	};
+	(
+		$name:ident; $runtime:ident; { $( $parsed:tt )* };
+		$module:ident = $id:expr, $( $rest:tt )*
+	) => {
+		__impl_outer_event! {
+			$name; $runtime; { $( $parsed )* $module [ $module::Event ] = $id, };
+			$( $rest )*
+		}
+	};
+	(
+		$name:ident; $runtime:ident; { $( $module:ident [ $event:ty ] = $id:expr, )* };
+	) => {
+		#[derive(Clone, PartialEq, Eq)]
+		#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+		#[allow(non_camel_case_types)]
+		#[allow(missing_docs)]
+		pub enum $name {
+			$(
+				$module($event),
+			)*
+		}
+
+		$(
+			impl From<$event> for $name {
+				fn from(x: $event) -> Self {
+					$name::$module(x)
+				}
+			}
+		)*
+
+		impl $crate::event::Slicable for $name {
+			fn decode<I: $crate::event::Input>(input: &mut I) -> Option<Self> {
+				match input.read_byte()? {
+					$(
+						$id => Some($name::$module($crate::event::Slicable::decode(input)?)),
+					)*
+					_ => None,
+				}
+			}
+
+			fn encode(&self) -> $crate::event::Vec<u8> {
+				let mut v = $crate::event::Vec::new();
+				match *self {
+					$(
+						$name::$module(ref sub) => {
+							v.push($id as u8);
+							$crate::event::Slicable::using_encoded(sub, |s| v.extend(s));
+						}
+					)*
+				}
+				v
+			}
+		}
+	}
+}
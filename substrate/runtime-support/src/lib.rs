@@ -38,6 +38,7 @@ pub extern crate substrate_codec as codec;
 pub use self::storage::generator::Storage as GenericStorage;
 
 pub mod dispatch;
+pub mod event;
 pub mod storage;
 mod hashable;
 
@@ -29,13 +29,18 @@ extern crate substrate_runtime_io as runtime_io;
 extern crate substrate_runtime_primitives as primitives;
 extern crate substrate_codec as codec;
 extern crate substrate_runtime_system as system;
+extern crate substrate_misbehavior_check as misbehavior_check;
 extern crate substrate_primitives;
 
+#[cfg(test)]
+extern crate substrate_keyring as keyring;
+
 use rstd::prelude::*;
-use runtime_support::{storage, Parameter};
+use codec::Slicable;
+use runtime_support::{storage, Parameter, StorageValue, StorageMap};
 use runtime_support::dispatch::Result;
 use runtime_support::storage::unhashed::StorageVec;
-use primitives::traits::RefInto;
+use primitives::traits::{RefInto, As, MaybeEmpty};
 use substrate_primitives::bft::MisbehaviorReport;
 
 
@@ -52,15 +57,41 @@ pub const CODE: &'static [u8] = b":code";
 
 pub type KeyValue = (Vec<u8>, Vec<u8>);
 
+/// Something which can be told about an authority whose misbehaviour has been proven.
+pub trait OnMisbehavior<SessionKey, AccountId> {
+	/// Punish the authority `offender`, whose misbehaviour was reported by `reporter`.
+	fn on_misbehavior(offender: &SessionKey, reporter: &AccountId);
+}
+
+impl<SessionKey, AccountId> OnMisbehavior<SessionKey, AccountId> for () {
+	fn on_misbehavior(_offender: &SessionKey, _reporter: &AccountId) {}
+}
+
+/// Something which can be told about an authority which was noted as offline.
+pub trait OnOfflineValidator {
+	/// Note that the authority at `validator_index` in the current set was offline.
+	fn on_offline_validator(validator_index: usize);
+}
+
+impl OnOfflineValidator for () {
+	fn on_offline_validator(_validator_index: usize) {}
+}
+
 pub trait Trait: system::Trait {
-	type PublicAux: RefInto<Self::AccountId>;
+	/// The position of the note_offline call in the block.
+	const NOTE_OFFLINE_POSITION: u32;
+
+	type PublicAux: RefInto<Self::AccountId> + MaybeEmpty;
  	type SessionKey: Parameter + Default;
+	type OnMisbehavior: OnMisbehavior<Self::SessionKey, Self::AccountId>;
+	type OnOfflineValidator: OnOfflineValidator;
 }
 
 decl_module! {
 	pub struct Module<T: Trait>;
 	pub enum Call where aux: T::PublicAux {
 		fn report_misbehavior(aux, report: MisbehaviorReport) -> Result = 0;
+		fn note_offline(aux, offline_val_indices: Vec<u32>) -> Result = 1;
 	}
 	pub enum PrivCall {
 		fn set_code(new: Vec<u8>) -> Result = 0;
@@ -68,6 +99,15 @@ decl_module! {
 	}
 }
 
+decl_storage! {
+	trait Store for Module<T: Trait>;
+
+	// The number of the block during which the current authority set was last changed.
+	pub AuthoritiesChangedAt get(authorities_changed_at): b"con:ach" => default T::BlockNumber;
+	// The authorities of the current set who have already been punished for misbehaviour.
+	pub Punished get(punished): b"con:pun" => default Vec<T::SessionKey>;
+}
+
 impl<T: Trait> Module<T> {
 	/// Get the current set of authorities. These are the session keys.
 	pub fn authorities() -> Vec<T::SessionKey> {
@@ -89,8 +129,57 @@ impl<T: Trait> Module<T> {
 	}
 
 	/// Report some misbehaviour.
-	fn report_misbehavior(_aux: &T::PublicAux, _report: MisbehaviorReport) -> Result {
-		// TODO.
+	///
+	/// The report must concern a round on top of a block of this chain which was imported since
+	/// the current authority set came into effect, and the target must be part of that set.
+	fn report_misbehavior(aux: &T::PublicAux, report: MisbehaviorReport) -> Result {
+		let parent_number = T::BlockNumber::sa(report.parent_number);
+		ensure!(parent_number >= Self::authorities_changed_at(), "misbehavior report predates the current authority set");
+
+		let parent_hash = T::Hash::decode(&mut &report.parent_hash[..]).ok_or("misbehavior report has a bad parent hash")?;
+		ensure!(
+			<system::BlockHash<T>>::exists(parent_number) && <system::Module<T>>::block_hash(parent_number) == parent_hash,
+			"misbehavior report is not for a block of this chain"
+		);
+
+		// session keys are the keys which authorities use to sign consensus messages.
+		let target = T::SessionKey::decode(&mut &report.target[..]).ok_or("misbehavior report has a bad target")?;
+		ensure!(Self::authorities().contains(&target), "misbehavior report target is not an authority");
+
+		let mut punished = Self::punished();
+		ensure!(!punished.contains(&target), "misbehaving authority has already been punished");
+		ensure!(
+			misbehavior_check::evaluate_misbehavior(&report.target, report.parent_hash, &report.misbehavior),
+			"misbehavior report has bad proof"
+		);
+
+		punished.push(target.clone());
+		<Punished<T>>::put(punished);
+		T::OnMisbehavior::on_misbehavior(&target, aux.ref_into());
+		Ok(())
+	}
+
+	/// Note the authorities at the given indices of the current set as offline, in ascending
+	/// order. Only the block proposer may include this, as an unsigned extrinsic.
+	fn note_offline(aux: &T::PublicAux, offline_val_indices: Vec<u32>) -> Result {
+		ensure!(aux.is_empty(), "note_offline must not be signed");
+		ensure!(
+			<system::Module<T>>::extrinsic_index() == T::NOTE_OFFLINE_POSITION,
+			"note_offline extrinsic must be at position NOTE_OFFLINE_POSITION in the block"
+		);
+
+		let authority_count = Self::authorities().len();
+		let mut last = None;
+		for &index in &offline_val_indices {
+			ensure!((index as usize) < authority_count, "offline validator index out of range");
+			ensure!(last.map_or(true, |l| l < index), "offline validator indices must be ascending and unique");
+			last = Some(index);
+		}
+
+		for index in offline_val_indices {
+			T::OnOfflineValidator::on_offline_validator(index as usize);
+		}
+
 		Ok(())
 	}
 
@@ -98,12 +187,26 @@ impl<T: Trait> Module<T> {
 	///
 	/// Called by `next_session` only.
 	pub fn set_authorities(authorities: &[T::SessionKey]) {
-		AuthorityStorageVec::<T::SessionKey>::set_items(authorities);
+		if &Self::authorities()[..] != authorities {
+			AuthorityStorageVec::<T::SessionKey>::set_items(authorities);
+			Self::note_authorities_changed();
+		}
 	}
 
 	/// Set a single authority by index.
 	pub fn set_authority(index: u32, key: &T::SessionKey) {
-		AuthorityStorageVec::<T::SessionKey>::set_item(index, key);
+		let current = Self::authorities();
+		if current.get(index as usize).map_or(false, |k| k != key) {
+			AuthorityStorageVec::<T::SessionKey>::set_item(index, key);
+			Self::note_authorities_changed();
+		}
+	}
+
+	/// Note that the authority set has changed in this block: reports concerning earlier blocks
+	/// are no longer accepted and the new set starts out unpunished.
+	fn note_authorities_changed() {
+		<AuthoritiesChangedAt<T>>::put(<system::Module<T>>::block_number());
+		<Punished<T>>::kill();
 	}
 }
 
@@ -137,3 +240,161 @@ impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 		r
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::with_externalities;
+	use substrate_primitives::{H256, AuthorityId};
+	use substrate_primitives::block::HeaderHash;
+	use substrate_primitives::bft::{Action, Message, MisbehaviorKind};
+	use primitives::BuildExternalities;
+	use primitives::traits::HasPublicAux;
+	use primitives::testing::{Digest, Header};
+	use keyring::Keyring;
+
+	pub struct Test;
+	impl HasPublicAux for Test {
+		type PublicAux = u64;
+	}
+	impl system::Trait for Test {
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = runtime_io::BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Header = Header;
+		type Event = ();
+	}
+	impl Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 0;
+
+		type PublicAux = <Self as HasPublicAux>::PublicAux;
+		type SessionKey = AuthorityId;
+		type OnMisbehavior = ();
+		type OnOfflineValidator = ();
+	}
+
+	type System = system::Module<Test>;
+	type Consensus = Module<Test>;
+
+	fn new_test_ext() -> runtime_io::TestExternalities {
+		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		t.extend(GenesisConfig::<Test>{
+			code: vec![],
+			authorities: vec![Keyring::Alice.to_raw_public(), Keyring::Bob.to_raw_public()],
+		}.build_externalities());
+		t
+	}
+
+	fn prepare(who: Keyring, parent: HeaderHash, round: u32, hash: HeaderHash) -> (HeaderHash, substrate_primitives::Signature) {
+		let message = Message { parent, action: Action::Prepare(round, hash) };
+		(hash, who.sign(&message.encode()))
+	}
+
+	fn double_prepare(who: Keyring, parent_number: u64) -> MisbehaviorReport {
+		let parent_hash = [69u8; 32].into();
+		MisbehaviorReport {
+			parent_hash,
+			parent_number,
+			target: who.to_raw_public(),
+			misbehavior: MisbehaviorKind::BftDoublePrepare(
+				1,
+				prepare(who, parent_hash, 1, [1; 32].into()),
+				prepare(who, parent_hash, 1, [2; 32].into()),
+			),
+		}
+	}
+
+	#[test]
+	fn reporting_misbehavior_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Consensus::report_misbehavior(&1, double_prepare(Keyring::Alice, 0)));
+			assert_eq!(Consensus::punished(), vec![Keyring::Alice.to_raw_public()]);
+		});
+	}
+
+	#[test]
+	fn reporting_misbehavior_twice_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Consensus::report_misbehavior(&1, double_prepare(Keyring::Alice, 0)));
+			assert_noop!(
+				Consensus::report_misbehavior(&2, double_prepare(Keyring::Alice, 0)),
+				"misbehaving authority has already been punished"
+			);
+		});
+	}
+
+	#[test]
+	fn reporting_misbehavior_of_non_authority_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Consensus::report_misbehavior(&1, double_prepare(Keyring::Charlie, 0)),
+				"misbehavior report target is not an authority"
+			);
+		});
+	}
+
+	#[test]
+	fn reporting_misbehavior_with_bad_proof_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			let mut report = double_prepare(Keyring::Alice, 0);
+			report.target = Keyring::Bob.to_raw_public();
+			assert_noop!(Consensus::report_misbehavior(&1, report), "misbehavior report has bad proof");
+		});
+	}
+
+	#[test]
+	fn reporting_misbehavior_for_unknown_block_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_noop!(
+				Consensus::report_misbehavior(&1, double_prepare(Keyring::Alice, 1)),
+				"misbehavior report is not for a block of this chain"
+			);
+		});
+	}
+
+	#[test]
+	fn noting_offline_validators_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Consensus::note_offline(&0, vec![0, 1]));
+			assert_noop!(Consensus::note_offline(&1, vec![0]), "note_offline must not be signed");
+			assert_noop!(Consensus::note_offline(&0, vec![2]), "offline validator index out of range");
+			assert_noop!(
+				Consensus::note_offline(&0, vec![1, 0]),
+				"offline validator indices must be ascending and unique"
+			);
+		});
+	}
+
+	#[test]
+	fn reporting_misbehavior_before_authority_change_should_not_work() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(2);
+			Consensus::set_authorities(&[Keyring::Alice.to_raw_public(), Keyring::Charlie.to_raw_public()]);
+			assert_noop!(
+				Consensus::report_misbehavior(&1, double_prepare(Keyring::Alice, 0)),
+				"misbehavior report predates the current authority set"
+			);
+		});
+	}
+
+	#[test]
+	fn only_changing_the_authorities_should_reset_punishments() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_ok!(Consensus::report_misbehavior(&1, double_prepare(Keyring::Alice, 0)));
+			System::set_block_number(2);
+
+			// rotating into the same set changes nothing.
+			Consensus::set_authorities(&[Keyring::Alice.to_raw_public(), Keyring::Bob.to_raw_public()]);
+			Consensus::set_authority(1, &Keyring::Bob.to_raw_public());
+			assert_eq!(Consensus::authorities_changed_at(), 0);
+			assert_eq!(Consensus::punished(), vec![Keyring::Alice.to_raw_public()]);
+
+			Consensus::set_authority(1, &Keyring::Charlie.to_raw_public());
+			assert_eq!(Consensus::authorities_changed_at(), 2);
+			assert!(Consensus::punished().is_empty());
+		});
+	}
+}
@@ -0,0 +1,26 @@
+;; Copies as many bytes as its input data holds, read from offset `$skip` of the input data,
+;; into its memory and returns them. Any non-zero `$skip` thus reads past the end of the
+;; input data and traps.
+(module
+	;; ext_input_size() -> u32
+	(import "env" "ext_input_size" (func $ext_input_size (result i32)))
+	;; ext_input_copy(dest_ptr: u32, offset: u32, len: u32)
+	(import "env" "ext_input_copy" (func $ext_input_copy (param i32 i32 i32)))
+	;; ext_return(data_ptr: u32, data_len: u32)
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(call $ext_input_copy
+			(i32.const 0)  ;; Pointer where to place the input data.
+			(i32.const $skip)  ;; Offset within the input data.
+			(call $ext_input_size)  ;; Length of the input data.
+		)
+		(call $ext_return
+			(i32.const 0)  ;; Pointer to the data to return.
+			(call $ext_input_size)  ;; Length of the data to return.
+		)
+		;; Execution must have stopped at `ext_return`.
+		unreachable
+	)
+)
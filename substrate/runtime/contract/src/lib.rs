@@ -17,7 +17,7 @@
 //! Crate for executing smart-contracts.
 //!
 //! It provides an means for executing contracts represented in WebAssembly (Wasm for short).
-//! Contracts are able to create other contracts, transfer funds to each other and operate on a simple key-value storage.
+//! Contracts are able to create other contracts, call each other and transfer funds to each other, operate on a simple key-value storage and deposit events.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
@@ -37,6 +37,7 @@ extern crate assert_matches;
 extern crate wabt;
 
 use rstd::prelude::*;
+use rstd::cmp;
 use codec::Slicable;
 
 use parity_wasm::elements::{self, External, MemoryType};
@@ -52,6 +53,8 @@ pub trait Ext {
 	type AccountId: Slicable + Clone;
 	/// The balance of an account.
 	type Balance: Slicable;
+	/// The topic under which an event may be looked up.
+	type Hash: Slicable;
 
 	/// Returns the storage entry of the executing account by the given key.
 	fn get_storage(&self, key: &[u8]) -> Option<Vec<u8>>;
@@ -59,15 +62,59 @@ pub trait Ext {
 	/// Sets the storage entry by the given key to the specified value.
 	fn set_storage(&mut self, key: &[u8], value: Option<Vec<u8>>);
 
-	// TODO: Return the address of the created contract.
 	/// Create a new account for a contract.
 	///
 	/// The newly created account will be associated with the `code`. `value` specifies the amount of value
-	/// transfered from this to the newly created account.
-	fn create(&mut self, code: &[u8], value: Self::Balance);
+	/// transfered from this to the newly created account. The address of the account is derived from
+	/// `code`, `salt` and this account. The constructor of the code, if any, is run with `init_data` and
+	/// at most `gas_limit` gas.
+	///
+	/// None of the changes made by a failed creation are kept.
+	fn create(
+		&mut self,
+		code: &[u8],
+		value: Self::Balance,
+		gas_limit: u64,
+		salt: &[u8],
+		init_data: &[u8],
+	) -> CreateReceipt<Self::AccountId>;
+
+	/// Call the specified account, transferring `value` to it and running its code, if any, with
+	/// `input_data` and at most `gas_limit` gas. Any data returned by the code is placed into
+	/// `output_data`.
+	///
+	/// None of the changes made by a failed call are kept.
+	fn call(
+		&mut self,
+		to: &Self::AccountId,
+		value: Self::Balance,
+		gas_limit: u64,
+		input_data: &[u8],
+		output_data: &mut Vec<u8>,
+	) -> CallReceipt;
+
+	/// Deposit an event by this account with the given `topics` and `data`.
+	///
+	/// Events deposited by a failed call or creation are discarded along with its other changes.
+	fn deposit_event(&mut self, topics: Vec<Self::Hash>, data: Vec<u8>);
+}
+
+/// The receipt of a creation made through `Ext::create`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CreateReceipt<AccountId> {
+	/// The address of the created contract, or `None` if the creation failed.
+	pub address: Option<AccountId>,
+	/// The amount of gas used by the constructor of the contract.
+	pub gas_used: u64,
+}
 
-	/// Transfer some funds to the specified account.
-	fn transfer(&mut self, to: &Self::AccountId, value: Self::Balance);
+/// The receipt of a call made through `Ext::call`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallReceipt {
+	/// The amount of gas used by the code of the callee.
+	pub gas_used: u64,
+	/// Whether the call, including the code of the callee, succeeded.
+	pub success: bool,
 }
 
 /// Error that can occur while preparing or executing wasm smart-contract.
@@ -110,13 +157,24 @@ pub enum Error {
 	Memory,
 }
 
-struct Runtime<'a, T: Ext + 'a> {
+/// A reason for a host function to stop the execution of a contract other than an error.
+enum SpecialTrap {
+	/// The contract returned through `ext_return`.
+	Return,
+}
+
+struct Runtime<'a, 'data, T: Ext + 'a> {
 	ext: &'a mut T,
+	input_data: &'data [u8],
+	output_data: &'data mut Vec<u8>,
 	memory: sandbox::Memory,
 	gas_used: u64,
 	gas_limit: u64,
+	special_trap: Option<SpecialTrap>,
+	scratch_buf: Vec<u8>,
+	config: Config,
 }
-impl<'a, T: Ext + 'a> Runtime<'a, T> {
+impl<'a, 'data, T: Ext + 'a> Runtime<'a, 'data, T> {
 	fn memory(&self) -> &sandbox::Memory {
 		&self.memory
 	}
@@ -126,6 +184,28 @@ impl<'a, T: Ext + 'a> Runtime<'a, T> {
 	fn ext_mut(&mut self) -> &mut T {
 		self.ext
 	}
+	/// Read `len` bytes of the linear memory at `ptr`, charging gas for each of them.
+	fn read_memory(&mut self, ptr: u32, len: u32) -> Result<Vec<u8>, sandbox::HostError> {
+		// the length is chosen by the contract, so nothing is allocated before it is paid for.
+		self.charge_memory_access(len)?;
+		let mut buf = Vec::new();
+		buf.resize(len as usize, 0);
+		self.memory.get(ptr, &mut buf)?;
+		Ok(buf)
+	}
+	/// Account for `len` bytes being copied from or into the linear memory. Fails if that is
+	/// more than the linear memory may hold or if there is not enough gas.
+	fn charge_memory_access(&mut self, len: u32) -> Result<(), sandbox::HostError> {
+		if len as u64 > self.config.max_memory_pages as u64 * WASM_PAGE_SIZE {
+			return Err(sandbox::HostError);
+		}
+		let cost = (len as u64).saturating_mul(self.config.memory_per_byte_cost as u64);
+		if self.charge_gas(cost) {
+			Ok(())
+		} else {
+			Err(sandbox::HostError)
+		}
+	}
 	/// Account for used gas.
 	///
 	/// Returns `false` if there is not enough gas or addition of the specified
@@ -144,12 +224,51 @@ impl<'a, T: Ext + 'a> Runtime<'a, T> {
 	}
 }
 
-/// Execute the given code as a contract.
+/// Execute the given code as a contract, called with `input_data`.
+///
+/// Any data returned by the contract is placed into `output_data`. Returns the amount of gas used
+/// by a successful execution. A failed execution is considered to have used all of `gas_limit`.
 pub fn execute<'a, T: Ext>(
 	code: &[u8],
+	input_data: &[u8],
+	output_data: &mut Vec<u8>,
+	ext: &'a mut T,
+	gas_limit: u64,
+) -> Result<u64, Error> {
+	invoke(code, b"call", input_data, output_data, ext, gas_limit)
+}
+
+/// Run the constructor of the given code, its `deploy` export, with `input_data`.
+///
+/// Behaves like `execute`, except that code without a constructor succeeds without using any gas.
+pub fn deploy<'a, T: Ext>(
+	code: &[u8],
+	input_data: &[u8],
+	output_data: &mut Vec<u8>,
 	ext: &'a mut T,
 	gas_limit: u64,
-) -> Result<(), Error> {
+) -> Result<u64, Error> {
+	let module: elements::Module =
+		elements::deserialize_buffer(code).map_err(|_| Error::Deserialization)?;
+	let has_constructor = module
+		.export_section()
+		.map_or(false, |exports| exports.entries().iter().any(|e| e.field() == "deploy"));
+	if !has_constructor {
+		output_data.clear();
+		return Ok(0);
+	}
+	invoke(code, b"deploy", input_data, output_data, ext, gas_limit)
+}
+
+/// Invoke the given export of the code.
+fn invoke<'a, T: Ext>(
+	code: &[u8],
+	export: &[u8],
+	input_data: &[u8],
+	output_data: &mut Vec<u8>,
+	ext: &'a mut T,
+	gas_limit: u64,
+) -> Result<u64, Error> {
 	// ext_gas(amount: u32)
 	//
 	// Account for used gas. Traps if gas used is greater than gas limit.
@@ -227,49 +346,223 @@ pub fn execute<'a, T: Ext>(
 		Ok(sandbox::ReturnValue::Unit)
 	}
 
-	// ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32)
+	// ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32) -> u32
+	//
+	// Transfer value to the given account, running its code, if any, without input data.
+	// Returns 0 if the transfer succeeded and 1 if it failed, in which case none of its changes
+	// are kept.
+	//
+	// - transfer_to, transfer_to_len: the account to transfer to.
+	// - value_ptr, value_len: the value to transfer.
 	fn ext_transfer<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
 		let transfer_to_ptr = args[0].as_i32().unwrap() as u32;
 		let transfer_to_len = args[1].as_i32().unwrap() as u32;
 		let value_ptr = args[2].as_i32().unwrap() as u32;
 		let value_len = args[3].as_i32().unwrap() as u32;
 
-		let mut transfer_to = Vec::new();
-		transfer_to.resize(transfer_to_len as usize, 0);
-		e.memory().get(transfer_to_ptr, &mut transfer_to)?;
-		let transfer_to = T::AccountId::decode(&mut &transfer_to[..]).unwrap();
+		let transfer_to_buf = e.read_memory(transfer_to_ptr, transfer_to_len)?;
+		let transfer_to = T::AccountId::decode(&mut &transfer_to_buf[..]).ok_or(sandbox::HostError)?;
+		let value_buf = e.read_memory(value_ptr, value_len)?;
+		let value = T::Balance::decode(&mut &value_buf[..]).ok_or(sandbox::HostError)?;
+
+		// the code of the recipient may use all of the gas which is left.
+		let gas_left = e.gas_limit - e.gas_used;
+		let receipt = e.ext_mut().call(&transfer_to, value, gas_left, &[], &mut Vec::new());
+		if !e.charge_gas(receipt.gas_used) {
+			return Err(sandbox::HostError);
+		}
+
+		let status = if receipt.success { 0 } else { 1 };
+		Ok(sandbox::ReturnValue::Value(sandbox::TypedValue::I32(status)))
+	}
+
+	// ext_call(callee_ptr: u32, callee_len: u32, gas: u64, value_ptr: u32, value_len: u32, input_data_ptr: u32, input_data_len: u32) -> u32
+	//
+	// Call the given account, transferring value to it and running its code with the given
+	// input data. Any data returned by the callee is placed into the scratch buffer. Returns 0
+	// if the call succeeded and 1 if it failed, in which case none of its changes are kept.
+	//
+	// - callee_ptr, callee_len: the account to call.
+	// - gas: how much of the gas left may be used by the callee; 0 means all of it.
+	// - value_ptr, value_len: the value to transfer.
+	// - input_data_ptr, input_data_len: the input data of the call.
+	fn ext_call<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		let callee_ptr = args[0].as_i32().unwrap() as u32;
+		let callee_len = args[1].as_i32().unwrap() as u32;
+		let gas = args[2].as_i64().unwrap() as u64;
+		let value_ptr = args[3].as_i32().unwrap() as u32;
+		let value_len = args[4].as_i32().unwrap() as u32;
+		let input_data_ptr = args[5].as_i32().unwrap() as u32;
+		let input_data_len = args[6].as_i32().unwrap() as u32;
+
+		let callee_buf = e.read_memory(callee_ptr, callee_len)?;
+		let callee = T::AccountId::decode(&mut &callee_buf[..]).ok_or(sandbox::HostError)?;
+		let value_buf = e.read_memory(value_ptr, value_len)?;
+		let value = T::Balance::decode(&mut &value_buf[..]).ok_or(sandbox::HostError)?;
+		let input_data = e.read_memory(input_data_ptr, input_data_len)?;
+
+		let gas_left = e.gas_limit - e.gas_used;
+		let gas_limit = if gas == 0 { gas_left } else { cmp::min(gas, gas_left) };
+		let mut output_data = Vec::new();
+		let receipt = e.ext_mut().call(&callee, value, gas_limit, &input_data, &mut output_data);
+		e.scratch_buf = output_data;
+		if !e.charge_gas(receipt.gas_used) {
+			return Err(sandbox::HostError);
+		}
+
+		let status = if receipt.success { 0 } else { 1 };
+		Ok(sandbox::ReturnValue::Value(sandbox::TypedValue::I32(status)))
+	}
 
-		let mut value_buf = Vec::new();
-		value_buf.resize(value_len as usize, 0);
-		e.memory().get(value_ptr, &mut value_buf)?;
-		let value = T::Balance::decode(&mut &value_buf[..]).unwrap();
+	// ext_scratch_size() -> u32
+	//
+	// Returns the size of the scratch buffer, which holds the data returned by the last call.
+	fn ext_scratch_size<T: Ext>(e: &mut Runtime<T>, _args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		Ok(sandbox::ReturnValue::Value(sandbox::TypedValue::I32(e.scratch_buf.len() as i32)))
+	}
 
-		e.ext_mut().transfer(&transfer_to, value);
+	// ext_scratch_copy(dest_ptr: u32, offset: u32, len: u32)
+	//
+	// Copy a part of the scratch buffer into the linear memory. Traps if the part doesn't lie
+	// within the scratch buffer.
+	//
+	// - dest_ptr: pointer into the linear memory where the data should be placed.
+	// - offset: offset of the part within the scratch buffer.
+	// - len: length of the part.
+	fn ext_scratch_copy<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		let dest_ptr = args[0].as_i32().unwrap() as u32;
+		let offset = args[1].as_i32().unwrap() as u32 as usize;
+		let len = args[2].as_i32().unwrap() as u32 as usize;
+
+		let end = offset.checked_add(len).ok_or(sandbox::HostError)?;
+		if end > e.scratch_buf.len() {
+			return Err(sandbox::HostError);
+		}
+		e.charge_memory_access(len as u32)?;
+		e.memory.set(dest_ptr, &e.scratch_buf[offset..end])?;
 
 		Ok(sandbox::ReturnValue::Unit)
 	}
 
-	// ext_create(code_ptr: u32, code_len: u32, value_ptr: u32, value_len: u32)
+	// ext_create(code_ptr: u32, code_len: u32, gas: u64, value_ptr: u32, value_len: u32, salt_ptr: u32, salt_len: u32, init_data_ptr: u32, init_data_len: u32) -> u32
+	//
+	// Create a contract with the given code, transferring value to it and running its
+	// constructor, if any, with the given init data. Returns 0 if the creation succeeded, in
+	// which case the address of the contract is placed into the scratch buffer, and 1 if it
+	// failed, in which case none of its changes are kept.
+	//
+	// - code_ptr, code_len: the code of the contract.
+	// - gas: how much of the gas left may be used by the constructor; 0 means all of it.
+	// - value_ptr, value_len: the value to transfer.
+	// - salt_ptr, salt_len: the salt from which, together with the code and the creator, the
+	//   address of the contract is derived.
+	// - init_data_ptr, init_data_len: the input data of the constructor.
 	fn ext_create<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
 		let code_ptr = args[0].as_i32().unwrap() as u32;
 		let code_len = args[1].as_i32().unwrap() as u32;
-		let value_ptr = args[2].as_i32().unwrap() as u32;
-		let value_len = args[3].as_i32().unwrap() as u32;
+		let gas = args[2].as_i64().unwrap() as u64;
+		let value_ptr = args[3].as_i32().unwrap() as u32;
+		let value_len = args[4].as_i32().unwrap() as u32;
+		let salt_ptr = args[5].as_i32().unwrap() as u32;
+		let salt_len = args[6].as_i32().unwrap() as u32;
+		let init_data_ptr = args[7].as_i32().unwrap() as u32;
+		let init_data_len = args[8].as_i32().unwrap() as u32;
+
+		let code = e.read_memory(code_ptr, code_len)?;
+		let value_buf = e.read_memory(value_ptr, value_len)?;
+		let value = T::Balance::decode(&mut &value_buf[..]).ok_or(sandbox::HostError)?;
+		let salt = e.read_memory(salt_ptr, salt_len)?;
+		let init_data = e.read_memory(init_data_ptr, init_data_len)?;
+
+		let gas_left = e.gas_limit - e.gas_used;
+		let gas_limit = if gas == 0 { gas_left } else { cmp::min(gas, gas_left) };
+		let receipt = e.ext_mut().create(&code, value, gas_limit, &salt, &init_data);
+		e.scratch_buf = receipt.address.as_ref().map_or_else(Vec::new, |address| address.encode());
+		if !e.charge_gas(receipt.gas_used) {
+			return Err(sandbox::HostError);
+		}
+
+		let status = if receipt.address.is_some() { 0 } else { 1 };
+		Ok(sandbox::ReturnValue::Value(sandbox::TypedValue::I32(status)))
+	}
 
-		let mut value_buf = Vec::new();
-		value_buf.resize(value_len as usize, 0);
-		e.memory().get(value_ptr, &mut value_buf)?;
-		let value = T::Balance::decode(&mut &value_buf[..]).unwrap();
+	// ext_deposit_event(topics_ptr: u32, topics_len: u32, data_ptr: u32, data_len: u32)
+	//
+	// Deposit an event with the given topics and data. The event is recorded in the block and
+	// may be looked up by the address of the depositing contract and by any of its topics.
+	//
+	// - topics_ptr, topics_len: the encoded list of topics.
+	// - data_ptr, data_len: the data of the event.
+	fn ext_deposit_event<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		let topics_ptr = args[0].as_i32().unwrap() as u32;
+		let topics_len = args[1].as_i32().unwrap() as u32;
+		let data_ptr = args[2].as_i32().unwrap() as u32;
+		let data_len = args[3].as_i32().unwrap() as u32;
+
+		let topics_buf = e.read_memory(topics_ptr, topics_len)?;
+		let topics = Vec::<T::Hash>::decode(&mut &topics_buf[..]).ok_or(sandbox::HostError)?;
+		let data = e.read_memory(data_ptr, data_len)?;
+
+		let cost = (topics.len() as u64)
+			.saturating_mul(e.config.event_per_topic_cost as u64)
+			.saturating_add((data.len() as u64).saturating_mul(e.config.event_data_per_byte_cost as u64));
+		if !e.charge_gas(cost) {
+			return Err(sandbox::HostError);
+		}
+		e.ext_mut().deposit_event(topics, data);
+
+		Ok(sandbox::ReturnValue::Unit)
+	}
 
-		let mut code = Vec::new();
-		code.resize(code_len as usize, 0u8);
-		e.memory().get(code_ptr, &mut code)?;
+	// ext_input_size() -> u32
+	//
+	// Returns the size of the input data of the call.
+	fn ext_input_size<T: Ext>(e: &mut Runtime<T>, _args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		Ok(sandbox::ReturnValue::Value(sandbox::TypedValue::I32(e.input_data.len() as i32)))
+	}
 
-		e.ext_mut().create(&code, value);
+	// ext_input_copy(dest_ptr: u32, offset: u32, len: u32)
+	//
+	// Copy a part of the input data of the call into the linear memory. Traps if the part
+	// doesn't lie within the input data.
+	//
+	// - dest_ptr: pointer into the linear memory where the data should be placed.
+	// - offset: offset of the part within the input data.
+	// - len: length of the part.
+	fn ext_input_copy<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		let dest_ptr = args[0].as_i32().unwrap() as u32;
+		let offset = args[1].as_i32().unwrap() as u32 as usize;
+		let len = args[2].as_i32().unwrap() as u32 as usize;
+
+		let end = offset.checked_add(len).ok_or(sandbox::HostError)?;
+		if end > e.input_data.len() {
+			return Err(sandbox::HostError);
+		}
+		e.charge_memory_access(len as u32)?;
+		let input_data = e.input_data;
+		e.memory().set(dest_ptr, &input_data[offset..end])?;
 
 		Ok(sandbox::ReturnValue::Unit)
 	}
 
+	// ext_return(data_ptr: u32, data_len: u32)
+	//
+	// Stop the execution of the contract successfully, returning the given data to the caller.
+	//
+	// - data_ptr: pointer into the linear memory where the data to return is placed.
+	// - data_len: length of the data to return.
+	fn ext_return<T: Ext>(e: &mut Runtime<T>, args: &[sandbox::TypedValue]) -> Result<sandbox::ReturnValue, sandbox::HostError> {
+		let data_ptr = args[0].as_i32().unwrap() as u32;
+		let data_len = args[1].as_i32().unwrap() as u32;
+
+		let data = e.read_memory(data_ptr, data_len)?;
+		*e.output_data = data;
+
+		// stop the execution by trapping; `execute` tells this apart from an error.
+		e.special_trap = Some(SpecialTrap::Return);
+		Err(sandbox::HostError)
+	}
+
 	let PreparedContract {
 		instrumented_code,
 		memory,
@@ -281,25 +574,44 @@ pub fn execute<'a, T: Ext>(
 	imports.add_host_func("env", "ext_get_storage", ext_get_storage::<T>);
 	imports.add_host_func("env", "ext_transfer", ext_transfer::<T>);
 	imports.add_host_func("env", "ext_create", ext_create::<T>);
+	imports.add_host_func("env", "ext_call", ext_call::<T>);
+	imports.add_host_func("env", "ext_scratch_size", ext_scratch_size::<T>);
+	imports.add_host_func("env", "ext_scratch_copy", ext_scratch_copy::<T>);
+	imports.add_host_func("env", "ext_input_size", ext_input_size::<T>);
+	imports.add_host_func("env", "ext_input_copy", ext_input_copy::<T>);
+	imports.add_host_func("env", "ext_return", ext_return::<T>);
+	imports.add_host_func("env", "ext_deposit_event", ext_deposit_event::<T>);
 	// TODO: ext_balance, ext_address, ext_callvalue, etc.
 	imports.add_memory("env", "memory", memory.clone());
 
+	output_data.clear();
 	let mut runtime = Runtime {
 		ext,
+		input_data,
+		output_data,
 		memory,
 		gas_limit,
 		gas_used: 0,
+		special_trap: None,
+		scratch_buf: Vec::new(),
+		config: Config::default(),
 	};
 
 	let mut instance =
 		sandbox::Instance::new(&instrumented_code, &imports, &mut runtime)
 			.map_err(|_| Error::Instantiate)?;
-	instance
-		.invoke(b"call", &[], &mut runtime)
-		.map(|_| ())
-		.map_err(|_| Error::Invoke)
+	match (instance.invoke(export, &[], &mut runtime), runtime.special_trap) {
+		(Ok(_), _) | (Err(_), Some(SpecialTrap::Return)) => Ok(runtime.gas_used),
+		(Err(_), None) => {
+			runtime.output_data.clear();
+			Err(Error::Invoke)
+		}
+	}
 }
 
+/// The size of a page of the linear memory in bytes.
+const WASM_PAGE_SIZE: u64 = 64 * 1024;
+
 #[derive(Clone)]
 struct Config {
 	/// Gas cost of a growing memory by single page.
@@ -317,6 +629,15 @@ struct Config {
 	//// What is the maximal memory pages amount is allowed to have for
 	/// a contract.
 	max_memory_pages: u32,
+
+	/// Gas cost of each topic of a deposited event.
+	event_per_topic_cost: u32,
+
+	/// Gas cost of each byte of the data of a deposited event.
+	event_data_per_byte_cost: u32,
+
+	/// Gas cost of each byte copied between the linear memory and the host.
+	memory_per_byte_cost: u32,
 }
 
 impl Default for Config {
@@ -326,6 +647,9 @@ impl Default for Config {
 			regular_op_cost: 1,
 			max_stack_height: 64 * 1024,
 			max_memory_pages: 16,
+			event_per_topic_cost: 1,
+			event_data_per_byte_cost: 1,
+			memory_per_byte_cost: 1,
 		}
 	}
 }
@@ -481,21 +805,31 @@ mod tests {
 	struct CreateEntry {
 		code: Vec<u8>,
 		endownment: u64,
+		salt: Vec<u8>,
+		data: Vec<u8>,
 	}
 	#[derive(Debug, PartialEq, Eq)]
 	struct TransferEntry {
 		to: u64,
 		value: u64,
+		data: Vec<u8>,
+	}
+	#[derive(Debug, PartialEq, Eq)]
+	struct EventEntry {
+		topics: Vec<u64>,
+		data: Vec<u8>,
 	}
 	#[derive(Default)]
 	struct MockExt {
 		storage: HashMap<Vec<u8>, Vec<u8>>,
 		creates: Vec<CreateEntry>,
 		transfers: Vec<TransferEntry>,
+		events: Vec<EventEntry>,
 	}
 	impl Ext for MockExt {
 		type AccountId = u64;
 		type Balance = u64;
+		type Hash = u64;
 
 		fn get_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 			self.storage.get(key).cloned()
@@ -503,21 +837,51 @@ mod tests {
 		fn set_storage(&mut self, key: &[u8], value: Option<Vec<u8>>) {
 			*self.storage.entry(key.to_vec()).or_insert(Vec::new()) = value.unwrap_or(Vec::new());
 		}
-		fn create(&mut self, code: &[u8], value: Self::Balance) {
+		fn create(
+			&mut self,
+			code: &[u8],
+			value: Self::Balance,
+			_gas_limit: u64,
+			salt: &[u8],
+			init_data: &[u8],
+		) -> CreateReceipt<Self::AccountId> {
 			self.creates.push(
 				CreateEntry {
 					code: code.to_vec(),
 					endownment: value,
+					salt: salt.to_vec(),
+					data: init_data.to_vec(),
 				}
 			);
+			CreateReceipt {
+				address: Some(42),
+				gas_used: 0,
+			}
 		}
-		fn transfer(&mut self, to: &Self::AccountId, value: Self::Balance) {
+		fn call(
+			&mut self,
+			to: &Self::AccountId,
+			value: Self::Balance,
+			_gas_limit: u64,
+			input_data: &[u8],
+			output_data: &mut Vec<u8>,
+		) -> CallReceipt {
 			self.transfers.push(
 				TransferEntry {
 					to: *to,
 					value,
+					data: input_data.to_vec(),
 				}
 			);
+			// echo the input data back to the caller.
+			*output_data = input_data.to_vec();
+			CallReceipt {
+				gas_used: 0,
+				success: true,
+			}
+		}
+		fn deposit_event(&mut self, topics: Vec<Self::Hash>, data: Vec<u8>) {
+			self.events.push(EventEntry { topics, data });
 		}
 	}
 
@@ -587,17 +951,21 @@ mod tests {
 
 	const CODE_TRANSFER: &str = r#"
 (module
-    ;; ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32)
-    (import "env" "ext_transfer" (func $ext_transfer (param i32 i32 i32 i32)))
+    ;; ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32) -> u32
+    (import "env" "ext_transfer" (func $ext_transfer (param i32 i32 i32 i32) (result i32)))
 
     (import "env" "memory" (memory 1 1))
 
     (func (export "call")
-        (call $ext_transfer
-            (i32.const 4)  ;; Pointer to "Transfer to" address.
-            (i32.const 8)  ;; Length of "Transfer to" address.
-            (i32.const 12)  ;; Pointer to the buffer with value to transfer
-			(i32.const 8)   ;; Length of the buffer with value to transfer.
+        (if
+            (call $ext_transfer
+                (i32.const 4)  ;; Pointer to "Transfer to" address.
+                (i32.const 8)  ;; Length of "Transfer to" address.
+                (i32.const 12)  ;; Pointer to the buffer with value to transfer
+                (i32.const 8)   ;; Length of the buffer with value to transfer.
+            )
+            ;; The transfer must succeed.
+            (then unreachable)
         )
     )
 
@@ -616,11 +984,12 @@ mod tests {
 		let code_transfer = wabt::wat2wasm(CODE_TRANSFER).unwrap();
 
 		let mut mock_ext = MockExt::default();
-		execute(&code_transfer, &mut mock_ext, 50_000).unwrap();
+		execute(&code_transfer, &[], &mut Vec::new(), &mut mock_ext, 50_000).unwrap();
 
 		assert_eq!(&mock_ext.transfers, &[TransferEntry {
 			to: 2,
 			value: 6,
+			data: vec![],
 		}]);
 	}
 
@@ -643,25 +1012,54 @@ mod tests {
 		format!(
 r#"
 (module
-    ;; ext_create(code_ptr: u32, code_len: u32, value_ptr: u32, value_len: u32)
-    (import "env" "ext_create" (func $ext_create (param i32 i32 i32 i32)))
+    ;; ext_create(code_ptr: u32, code_len: u32, gas: u64, value_ptr: u32, value_len: u32, salt_ptr: u32, salt_len: u32, init_data_ptr: u32, init_data_len: u32) -> u32
+    (import "env" "ext_create" (func $ext_create (param i32 i32 i64 i32 i32 i32 i32 i32 i32) (result i32)))
+    ;; ext_scratch_copy(dest_ptr: u32, offset: u32, len: u32)
+    (import "env" "ext_scratch_copy" (func $ext_scratch_copy (param i32 i32 i32)))
+    ;; ext_return(data_ptr: u32, data_len: u32)
+    (import "env" "ext_return" (func $ext_return (param i32 i32)))
 
     (import "env" "memory" (memory 1 1))
 
     (func (export "call")
-        (call $ext_create
-            (i32.const 12)   ;; Pointer to `code`
-            (i32.const {code_len}) ;; Length of `code`
-            (i32.const 4)   ;; Pointer to the buffer with value to transfer
-			(i32.const 8)   ;; Length of the buffer with value to transfer
+        (if
+            (call $ext_create
+                (i32.const 20)   ;; Pointer to `code`
+                (i32.const {code_len}) ;; Length of `code`
+                (i64.const 0)   ;; How much gas to devote to the constructor; 0 means all of it.
+                (i32.const 4)   ;; Pointer to the buffer with value to transfer
+                (i32.const 8)   ;; Length of the buffer with value to transfer
+                (i32.const 12)  ;; Pointer to the salt
+                (i32.const 4)   ;; Length of the salt
+                (i32.const 16)  ;; Pointer to the init data
+                (i32.const 4)   ;; Length of the init data
+            )
+            ;; The creation must succeed.
+            (then unreachable)
+        )
+        ;; Return the address of the created contract.
+        (call $ext_scratch_copy
+            (i32.const 4)   ;; Pointer where to place the address.
+            (i32.const 0)   ;; Offset within the scratch buffer.
+            (i32.const 8)   ;; Length of the address.
+        )
+        (call $ext_return
+            (i32.const 4)   ;; Pointer to the address.
+            (i32.const 8)   ;; Length of the address.
         )
     )
 	;; Amount of value to transfer.
 	;; Represented by u64 (8 bytes long) in little endian.
 	(data (i32.const 4) "\03\00\00\00\00\00\00\00")
 
+	;; Salt of the address.
+	(data (i32.const 12) "\01\02\03\04")
+
+	;; Init data of the constructor.
+	(data (i32.const 16) "\05\06\07\08")
+
 	;; Embedded wasm code.
-    (data (i32.const 12) "{escaped_bytecode}")
+    (data (i32.const 20) "{escaped_bytecode}")
 )
 "#,
 			escaped_bytecode = escaped_bytestring(&child_bytecode),
@@ -675,14 +1073,68 @@ r#"
 		let code_create = wabt::wat2wasm(&code_create(&code_transfer)).unwrap();
 
 		let mut mock_ext = MockExt::default();
-		execute(&code_create, &mut mock_ext, 50_000).unwrap();
+		let mut output_data = Vec::new();
+		execute(&code_create, &[], &mut output_data, &mut mock_ext, 50_000).unwrap();
 
 		assert_eq!(&mock_ext.creates, &[
 			CreateEntry {
 				code: code_transfer,
 				endownment: 3,
+				salt: vec![1, 2, 3, 4],
+				data: vec![5, 6, 7, 8],
 			}
 		]);
+		// the mock gives the created contract the address 42.
+		assert_eq!(output_data, vec![42, 0, 0, 0, 0, 0, 0, 0]);
+	}
+
+	/// This code increments a counter in its storage when deployed and does nothing when called.
+	const CODE_CONSTRUCTOR: &str =
+r#"
+(module
+	;; ext_set_storage(location_ptr: i32, value_non_null: bool, value_ptr: i32)
+	(import "env" "ext_set_storage" (func $ext_set_storage (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "deploy")
+		(call $ext_set_storage
+			(i32.const 4)  ;; Pointer to a location of the storage.
+			(i32.const 1)  ;; Value is not null.
+			(i32.const 36) ;; Pointer to a data we want to put in the storage.
+		)
+	)
+	(func (export "call")
+		nop
+	)
+
+	;; Location of storage to store the data. 32 bytes.
+	(data (i32.const 4) "\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01")
+	;; Data to store. 32 bytes.
+	(data (i32.const 36) "\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02")
+)
+"#;
+
+	#[test]
+	fn contract_deploy() {
+		let code_constructor = wabt::wat2wasm(CODE_CONSTRUCTOR).unwrap();
+		let code_transfer = wabt::wat2wasm(CODE_TRANSFER).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		execute(&code_constructor, &[], &mut Vec::new(), &mut mock_ext, 50_000).unwrap();
+		assert!(mock_ext.storage.is_empty());
+
+		assert_matches!(
+			deploy(&code_constructor, &[], &mut Vec::new(), &mut mock_ext, 50_000),
+			Ok(gas_used) if gas_used > 0
+		);
+		assert_eq!(&mock_ext.storage.get(&[1u8; 32][..]).unwrap()[..], &[2u8; 32][..]);
+
+		// code without a constructor is deployed without running any of it.
+		assert_matches!(
+			deploy(&code_transfer, &[], &mut Vec::new(), &mut mock_ext, 50_000),
+			Ok(0)
+		);
+		assert!(mock_ext.transfers.is_empty());
 	}
 
 	/// This code a value from the storage, increment it's first byte
@@ -730,8 +1182,8 @@ r#"
 		let mut mock_ext = MockExt::default();
 
 		// Execute the test twice.
-		execute(&code_adder, &mut mock_ext, 50_000).unwrap();
-		execute(&code_adder, &mut mock_ext, 50_000).unwrap();
+		execute(&code_adder, &[], &mut Vec::new(), &mut mock_ext, 50_000).unwrap();
+		execute(&code_adder, &[], &mut Vec::new(), &mut mock_ext, 50_000).unwrap();
 
 		let storage_addr = [0x01u8; 32];
 		assert_eq!(
@@ -780,13 +1232,192 @@ r#"
 		let mut mock_ext = MockExt::default();
 
 		assert_matches!(
-			execute(&code_loop, &mut mock_ext, 900_000),
+			execute(&code_loop, &[], &mut Vec::new(), &mut mock_ext, 900_000),
 			Err(_)
 		);
 		assert_matches!(
-			execute(&code_loop, &mut mock_ext, 937_000),
-			Ok(_)
+			execute(&code_loop, &[], &mut Vec::new(), &mut mock_ext, 937_000),
+			Ok(gas_used) if gas_used <= 937_000 && gas_used > 900_000
+		);
+	}
+
+	/// This code returns its input data if `$skip` is 0, and traps otherwise. Shared with the
+	/// tests of the staking module.
+	const CODE_ECHO: &str = include_str!("../fixtures/echo.wat");
+
+	#[test]
+	fn contract_input_and_return() {
+		let code_echo = wabt::wat2wasm(CODE_ECHO.replace("$skip", "0")).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		let mut output_data = Vec::new();
+		execute(&code_echo, &[1, 2, 3, 4], &mut output_data, &mut mock_ext, 50_000).unwrap();
+
+		assert_eq!(output_data, vec![1, 2, 3, 4]);
+	}
+
+	#[test]
+	fn contract_input_copy_out_of_bounds() {
+		let code_echo = wabt::wat2wasm(CODE_ECHO.replace("$skip", "1")).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		let mut output_data = vec![1];
+
+		assert_matches!(
+			execute(&code_echo, &[1, 2, 3, 4], &mut output_data, &mut mock_ext, 50_000),
+			Err(Error::Invoke)
+		);
+		assert!(output_data.is_empty());
+	}
+
+	/// This code returns nearly 4 GiB of data, far more than its memory holds.
+	const CODE_RETURN_HUGE: &str =
+r#"
+(module
+	;; ext_return(data_ptr: u32, data_len: u32)
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(call $ext_return
+			(i32.const 0)  ;; Pointer to the data to return.
+			(i32.const -1)  ;; Length of the data to return.
+		)
+	)
+)
+"#;
+
+	#[test]
+	fn contract_return_beyond_memory() {
+		let code_return_huge = wabt::wat2wasm(CODE_RETURN_HUGE).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		let mut output_data = Vec::new();
+
+		// the length is refused before anything is allocated for it.
+		assert_matches!(
+			execute(&code_return_huge, &[], &mut output_data, &mut mock_ext, u64::max_value()),
+			Err(Error::Invoke)
+		);
+		assert!(output_data.is_empty());
+	}
+
+	/// This code calls the account 9 with some input data and returns the data returned by it.
+	const CODE_CALL: &str =
+r#"
+(module
+	;; ext_call(callee_ptr: u32, callee_len: u32, gas: u64, value_ptr: u32, value_len: u32, input_data_ptr: u32, input_data_len: u32) -> u32
+	(import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+	;; ext_scratch_size() -> u32
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	;; ext_scratch_copy(dest_ptr: u32, offset: u32, len: u32)
+	(import "env" "ext_scratch_copy" (func $ext_scratch_copy (param i32 i32 i32)))
+	;; ext_return(data_ptr: u32, data_len: u32)
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(if
+			(call $ext_call
+				(i32.const 4)  ;; Pointer to "callee" address.
+				(i32.const 8)  ;; Length of "callee" address.
+				(i64.const 0)  ;; How much gas to devote to the call; 0 means all of it.
+				(i32.const 12)  ;; Pointer to the buffer with value to transfer.
+				(i32.const 8)   ;; Length of the buffer with value to transfer.
+				(i32.const 20)  ;; Pointer to the input data.
+				(i32.const 4)   ;; Length of the input data.
+			)
+			;; The call must succeed.
+			(then unreachable)
+		)
+		(call $ext_scratch_copy
+			(i32.const 24)  ;; Pointer where to place the returned data.
+			(i32.const 0)  ;; Offset within the scratch buffer.
+			(call $ext_scratch_size)  ;; Length of the returned data.
+		)
+		(call $ext_return
+			(i32.const 24)  ;; Pointer to the data to return.
+			(call $ext_scratch_size)  ;; Length of the data to return.
+		)
+	)
+
+	;; Callee AccountId.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 4) "\09\00\00\00\00\00\00\00")
+	;; Amount of value to transfer.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 12) "\06\00\00\00\00\00\00\00")
+	;; Input data of the call.
+	(data (i32.const 20) "\01\02\03\04")
+)
+"#;
+
+	#[test]
+	fn contract_call() {
+		let code_call = wabt::wat2wasm(CODE_CALL).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		let mut output_data = Vec::new();
+		execute(&code_call, &[], &mut output_data, &mut mock_ext, 50_000).unwrap();
+
+		assert_eq!(&mock_ext.transfers, &[TransferEntry {
+			to: 9,
+			value: 6,
+			data: vec![1, 2, 3, 4],
+		}]);
+		// the mock callee echoes its input data.
+		assert_eq!(output_data, vec![1, 2, 3, 4]);
+	}
+
+	/// This code deposits an event with the topics 1 and 2 and some data. The length of the
+	/// encoded topics is given by `$topics_len`.
+	const CODE_DEPOSIT_EVENT: &str =
+r#"
+(module
+	;; ext_deposit_event(topics_ptr: u32, topics_len: u32, data_ptr: u32, data_len: u32)
+	(import "env" "ext_deposit_event" (func $ext_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(call $ext_deposit_event
+			(i32.const 4)  ;; Pointer to the encoded topics.
+			(i32.const $topics_len)  ;; Length of the encoded topics.
+			(i32.const 24)  ;; Pointer to the data.
+			(i32.const 4)   ;; Length of the data.
+		)
+	)
+
+	;; Topics, a list of two u64s prefixed with its length as u32, all in little endian.
+	(data (i32.const 4) "\02\00\00\00\01\00\00\00\00\00\00\00\02\00\00\00\00\00\00\00")
+	;; Data of the event.
+	(data (i32.const 24) "\05\06\07\08")
+)
+"#;
+
+	#[test]
+	fn contract_deposit_event() {
+		let code_deposit_event = wabt::wat2wasm(CODE_DEPOSIT_EVENT.replace("$topics_len", "20")).unwrap();
+
+		let mut mock_ext = MockExt::default();
+		execute(&code_deposit_event, &[], &mut Vec::new(), &mut mock_ext, 50_000).unwrap();
+
+		assert_eq!(&mock_ext.events, &[EventEntry {
+			topics: vec![1, 2],
+			data: vec![5, 6, 7, 8],
+		}]);
+	}
+
+	#[test]
+	fn contract_deposit_event_with_bad_topics() {
+		let code_deposit_event = wabt::wat2wasm(CODE_DEPOSIT_EVENT.replace("$topics_len", "12")).unwrap();
+
+		let mut mock_ext = MockExt::default();
+
+		assert_matches!(
+			execute(&code_deposit_event, &[], &mut Vec::new(), &mut mock_ext, 50_000),
+			Err(Error::Invoke)
 		);
+		assert!(mock_ext.events.is_empty());
 	}
 
 	const CODE_MEM: &str =
@@ -808,7 +1439,7 @@ r#"
 		let mut mock_ext = MockExt::default();
 
 		assert_matches!(
-			execute(&code_mem, &mut mock_ext, 100_000),
+			execute(&code_mem, &[], &mut Vec::new(), &mut mock_ext, 100_000),
 			Err(_)
 		);
 	}
@@ -32,6 +32,7 @@ extern crate substrate_runtime_consensus as consensus;
 extern crate substrate_runtime_democracy as democracy;
 extern crate substrate_runtime_session as session;
 extern crate substrate_runtime_staking as staking;
+#[macro_use]
 extern crate substrate_runtime_system as system;
 
 use rstd::prelude::*;
@@ -97,7 +98,22 @@ pub mod voting;
 
 pub type VoteIndex = u32;
 
-pub trait Trait: democracy::Trait {}
+pub trait Trait: democracy::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_event! {
+	/// An event in this module.
+	pub enum RawEvent<AccountId> {
+		/// A tally has been finalised: `incoming` members have joined the council in place of
+		/// the `outgoing` members whose terms expired.
+		TallyFinalised { incoming: Vec<AccountId>, outgoing: Vec<AccountId> } = 0,
+	}
+}
+
+/// The event type of this module, specialised to a runtime.
+pub type Event<T> = RawEvent<<T as system::Trait>::AccountId>;
 
 decl_module! {
 	pub struct Module<T: Trait>;
@@ -344,7 +360,7 @@ impl<T: Trait> Module<T> {
 		let (_, _, expiring) = Self::next_finalise().ok_or("cannot present outside of presentation period")?;
 		let stakes = Self::snapshoted_stakes();
 		let voters = Self::voters();
-		let bad_presentation_punishment = Self::present_slash_per_voter() * T::Balance::sa(voters.len());
+		let bad_presentation_punishment = Self::present_slash_per_voter() * T::Balance::sa(voters.len() as u64);
 		ensure!(<staking::Module<T>>::can_slash(aux.ref_into(), bad_presentation_punishment), "presenter must have sufficient slashable funds");
 
 		let mut leaderboard = Self::leaderboard().ok_or("leaderboard must exist while present phase active")?;
@@ -482,15 +498,16 @@ impl<T: Trait> Module<T> {
 		}
 
 		// set the new council.
+		let incoming: Vec<T::AccountId> = leaderboard.iter()
+			.rev()
+			.take_while(|&&(b, _)| !b.is_zero())
+			.take(coming as usize)
+			.map(|&(_, ref a)| a.clone())
+			.collect();
 		let mut new_council: Vec<_> = Self::active_council()
 			.into_iter()
 			.skip(expiring.len())
-			.chain(leaderboard.iter()
-				.rev()
-				.take_while(|&&(b, _)| !b.is_zero())
-				.take(coming as usize)
-				.cloned()
-				.map(|(_, a)| (a, new_expiry)))
+			.chain(incoming.iter().cloned().map(|a| (a, new_expiry)))
 			.collect();
 		new_council.sort_by_key(|&(_, expiry)| expiry);
 		<ActiveCouncil<T>>::put(new_council);
@@ -521,10 +538,13 @@ impl<T: Trait> Module<T> {
 		<Candidates<T>>::put(new_candidates);
 		<CandidateCount<T>>::put(count);
 		<VoteCount<T>>::put(Self::vote_index() + 1);
+		Self::deposit_event(RawEvent::TallyFinalised { incoming, outgoing: expiring });
 		Ok(())
 	}
 }
 
+impl_deposit_event!(Module<T>, Event<T>);
+
 #[cfg(any(feature = "std", test))]
 pub struct GenesisConfig<T: Trait> {
 	// for the voting onto the  council
@@ -607,13 +627,28 @@ mod tests {
 		}
 	}
 
+	mod council {
+		pub use ::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			staking<T> = 0,
+			democracy<T> = 1,
+			council<T> = 2,
+		}
+	}
+
 	pub struct Test;
 	impl HasPublicAux for Test {
 		type PublicAux = u64;
 	}
 	impl consensus::Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehavior = ();
+		type OnOfflineValidator = ();
 	}
 	impl system::Trait for Test {
 		type Index = u64;
@@ -623,6 +658,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = TestEvent;
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -630,11 +666,16 @@ mod tests {
 	impl staking::Trait for Test {
 		type Balance = u64;
 		type DetermineContractAddress = staking::DummyContractAddressFor;
+		type OnAccountKill = ();
+		type Event = TestEvent;
 	}
 	impl democracy::Trait for Test {
 		type Proposal = Proposal;
+		type Event = TestEvent;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
 	}
-	impl Trait for Test {}
 
 	pub fn new_test_ext(with_council: bool) -> runtime_io::TestExternalities {
 		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
@@ -655,10 +696,18 @@ mod tests {
 			bonding_duration: 0,
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			creation_fee: 0,
+			misbehavior_slash: 0,
+			reporter_reward: 0,
+			session_reward: 0,
+			offline_slash: 0,
+			offline_slash_grace: 0,
 		}.build_externalities());
 		t.extend(democracy::GenesisConfig::<Test>{
 			launch_period: 1,
 			voting_period: 3,
+			enactment_period: 2,
 			minimum_deposit: 1,
 		}.build_externalities());
 		t.extend(GenesisConfig::<Test>{
@@ -988,6 +1037,11 @@ mod tests {
 			assert_eq!(Council::vote_index(), 1);
 			assert_eq!(Council::voter_last_active(2), Some(0));
 			assert_eq!(Council::voter_last_active(5), Some(0));
+
+			assert_eq!(System::events(), vec![system::EventRecord {
+				extrinsic_index: 0,
+				event: TestEvent::council(RawEvent::TallyFinalised { incoming: vec![5, 2], outgoing: vec![] }),
+			}]);
 		});
 	}
 
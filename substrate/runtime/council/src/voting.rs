@@ -182,6 +182,12 @@ impl<T: Trait> Module<T> {
 				if let (_, 0, 0) = tally {
 					<democracy::Module<T>>::internal_cancel_referendum(ref_index);
 				}
+			} else if let Some(&democracy::PrivCall::cancel_queued(when, ref_index)) = IsSubType::<democracy::Module<T>>::is_sub_type(&proposal) {
+				if let (_, 0, 0) = tally {
+					if let Err(e) = <democracy::Module<T>>::internal_cancel_queued(when, ref_index) {
+						print(e);
+					}
+				}
 			} else {
 				if tally.0 > tally.1 + tally.2 {
 					Self::kill_veto_of(&proposal_hash);
@@ -215,7 +221,8 @@ mod tests {
 	use super::*;
 	use ::tests::*;
 	use runtime_support::Hashable;
-	use democracy::VoteThreshold;
+	use runtime_support::dispatch::AuxDispatchable;
+	use democracy::{VoteThreshold, Conviction};
 
 	type CouncilVoting = super::Module<Test>;
 
@@ -270,6 +277,39 @@ mod tests {
 		});
 	}
 
+	fn cancel_queued_proposal(when: u64, id: u32) -> Proposal {
+		Proposal::Democracy(democracy::PrivCall::cancel_queued(when, id))
+	}
+
+	#[test]
+	fn queued_cancellation_should_work_when_unanimous() {
+		with_externalities(&mut new_test_ext(true), || {
+			System::set_block_number(1);
+			let proposal = bonding_duration_proposal(42);
+			assert_ok!(Democracy::internal_start_referendum(proposal.clone(), VoteThreshold::SuperMajorityApprove));
+			assert_ok!(democracy::Call::<Test>::vote(0, true, Conviction::None).dispatch(&4));
+
+			System::set_block_number(4);
+			<Democracy as Executable>::execute();
+			assert_eq!(Democracy::scheduled(6), vec![(proposal, 0)]);
+
+			let cancellation = cancel_queued_proposal(6, 0);
+			let hash = cancellation.blake2_256().into();
+			assert_ok!(CouncilVoting::propose(&1, Box::new(cancellation)));
+			assert_ok!(CouncilVoting::vote(&2, hash, true));
+			assert_ok!(CouncilVoting::vote(&3, hash, true));
+			assert_ok!(CouncilVoting::end_block(System::block_number()));
+
+			System::set_block_number(5);
+			assert_ok!(CouncilVoting::end_block(System::block_number()));
+			assert_eq!(Democracy::scheduled(6), vec![]);
+
+			System::set_block_number(6);
+			<Democracy as Executable>::execute();
+			assert_eq!(Staking::bonding_duration(), 0);
+		});
+	}
+
 	#[test]
 	fn referendum_cancellation_should_fail_when_not_unanimous() {
 		with_externalities(&mut new_test_ext(true), || {
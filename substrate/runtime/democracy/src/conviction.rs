@@ -0,0 +1,89 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate Demo.
+
+// Substrate Demo is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate Demo is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate Demo.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Voting conviction.
+
+use primitives::traits::{As, Bounded, CheckedMul};
+use codec::{Input, Slicable};
+
+/// The conviction of a vote or delegation. A voter's balance is always locked until the
+/// referendum is enacted; greater convictions multiply the weight of the vote in exchange for
+/// keeping the balance locked for additional voting periods beyond that.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+pub enum Conviction {
+	/// Weight of 1x; locked only until enactment.
+	None,
+	/// Weight of 2x; locked for one voting period after enactment.
+	Locked2x,
+	/// Weight of 3x; locked for two voting periods after enactment.
+	Locked3x,
+	/// Weight of 4x; locked for three voting periods after enactment.
+	Locked4x,
+	/// Weight of 5x; locked for four voting periods after enactment.
+	Locked5x,
+	/// Weight of 6x; locked for five voting periods after enactment.
+	Locked6x,
+}
+
+impl Default for Conviction {
+	fn default() -> Self {
+		Conviction::None
+	}
+}
+
+impl Conviction {
+	/// The factor by which the voter's balance is multiplied to give the weight of its vote.
+	pub fn multiplier(&self) -> usize {
+		match *self {
+			Conviction::None => 1,
+			Conviction::Locked2x => 2,
+			Conviction::Locked3x => 3,
+			Conviction::Locked4x => 4,
+			Conviction::Locked5x => 5,
+			Conviction::Locked6x => 6,
+		}
+	}
+
+	/// The number of voting periods after enactment for which the voter's balance stays locked.
+	pub fn lock_periods(&self) -> usize {
+		self.multiplier() - 1
+	}
+
+	/// The weight of a vote backed by `balance` at this conviction, saturating at the greatest
+	/// balance.
+	pub fn votes<Balance: As<u64> + CheckedMul + Bounded>(&self, balance: Balance) -> Balance {
+		balance.checked_mul(&Balance::sa(self.multiplier() as u64)).unwrap_or_else(Balance::max_value)
+	}
+}
+
+impl Slicable for Conviction {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		input.read_byte().and_then(|v| match v {
+			0 => Some(Conviction::None),
+			1 => Some(Conviction::Locked2x),
+			2 => Some(Conviction::Locked3x),
+			3 => Some(Conviction::Locked4x),
+			4 => Some(Conviction::Locked5x),
+			5 => Some(Conviction::Locked6x),
+			_ => None,
+		})
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[self.lock_periods() as u8])
+	}
+}
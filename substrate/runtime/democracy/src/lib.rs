@@ -36,16 +36,19 @@ extern crate substrate_runtime_primitives as primitives;
 extern crate substrate_runtime_consensus as consensus;
 extern crate substrate_runtime_session as session;
 extern crate substrate_runtime_staking as staking;
+#[macro_use]
 extern crate substrate_runtime_system as system;
 
 use rstd::prelude::*;
 use rstd::result;
-use primitives::traits::{Zero, Executable, RefInto, As};
+use primitives::traits::{Zero, Bounded, CheckedAdd, Executable, RefInto, As};
 use runtime_support::{StorageValue, StorageMap, Parameter, Dispatchable, IsSubType};
 use runtime_support::dispatch::Result;
 
 mod vote_threshold;
+mod conviction;
 pub use vote_threshold::{Approved, VoteThreshold};
+pub use conviction::Conviction;
 
 /// A proposal index.
 pub type PropIndex = u32;
@@ -54,18 +57,48 @@ pub type ReferendumIndex = u32;
 
 pub trait Trait: staking::Trait + Sized {
 	type Proposal: Parameter + Dispatchable + IsSubType<Module<Self>>;
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
 
+decl_event! {
+	/// An event in this module.
+	pub enum RawEvent<Balance, AccountId> {
+		/// A public proposal has been tabled for referendum vote.
+		Tabled { proposal_index: PropIndex, deposit: Balance, depositors: Vec<AccountId> } = 0,
+		/// A referendum has begun.
+		Started { ref_index: ReferendumIndex, threshold: VoteThreshold } = 1,
+		/// A referendum has passed; its proposal is scheduled for enactment.
+		Passed { ref_index: ReferendumIndex } = 2,
+		/// A referendum has not passed.
+		NotPassed { ref_index: ReferendumIndex } = 3,
+		/// The proposal of a passed referendum has been dispatched, with whether it succeeded.
+		Executed { ref_index: ReferendumIndex, result: bool } = 4,
+		/// An account has delegated its votes to another account.
+		Delegated { who: AccountId, target: AccountId } = 5,
+		/// An account has stopped delegating its votes.
+		Undelegated { who: AccountId } = 6,
+		/// The proposal of a passed referendum has been cancelled before its enactment.
+		Cancelled { ref_index: ReferendumIndex } = 7,
+	}
+}
+
+/// The event type of this module, specialised to a runtime.
+pub type Event<T> = RawEvent<<T as staking::Trait>::Balance, <T as system::Trait>::AccountId>;
+
 decl_module! {
 	pub struct Module<T: Trait>;
 	pub enum Call where aux: T::PublicAux {
 		fn propose(aux, proposal: Box<T::Proposal>, value: T::Balance) -> Result = 0;
 		fn second(aux, proposal: PropIndex) -> Result = 1;
-		fn vote(aux, ref_index: ReferendumIndex, approve_proposal: bool) -> Result = 2;
+		fn vote(aux, ref_index: ReferendumIndex, approve_proposal: bool, conviction: Conviction) -> Result = 2;
+		fn delegate(aux, to: T::AccountId, conviction: Conviction) -> Result = 3;
+		fn undelegate(aux) -> Result = 4;
 	}
 	pub enum PrivCall {
 		fn start_referendum(proposal: Box<T::Proposal>, vote_threshold: VoteThreshold) -> Result = 0;
 		fn cancel_referendum(ref_index: ReferendumIndex) -> Result = 1;
+		fn cancel_queued(when: T::BlockNumber, ref_index: ReferendumIndex) -> Result = 2;
 	}
 }
 
@@ -85,6 +118,8 @@ decl_storage! {
 
 	// How often (in blocks) to check for new votes.
 	pub VotingPeriod get(voting_period): b"dem:per" => required T::BlockNumber;
+	// The delay (in blocks) between a referendum passing and its proposal being enacted.
+	pub EnactmentPeriod get(enactment_period): b"dem:enp" => required T::BlockNumber;
 
 	// The next free referendum index, aka the number of referendums started so far.
 	pub ReferendumCount get(referendum_count): b"dem:rco" => required ReferendumIndex;
@@ -92,12 +127,19 @@ decl_storage! {
 	pub NextTally get(next_tally): b"dem:nxt" => required ReferendumIndex;
 	// Information concerning any given referendum.
 	pub ReferendumInfoOf get(referendum_info): b"dem:pro:" => map [ ReferendumIndex => (T::BlockNumber, T::Proposal, VoteThreshold) ];
+	// The proposals of passed referenda to be enacted at a given block, along with their referendum index.
+	pub Scheduled get(scheduled): b"dem:sch:" => default map [ T::BlockNumber => Vec<(T::Proposal, ReferendumIndex)> ];
 
 	// Get the voters for the current proposal.
 	pub VotersFor get(voters_for): b"dem:vtr:" => default map [ ReferendumIndex => Vec<T::AccountId> ];
 
-	// Get the vote, if Some, of `who`.
-	pub VoteOf get(vote_of): b"dem:vot:" => map [ (ReferendumIndex, T::AccountId) => bool ];
+	// Get the vote, if Some, of `who`, along with its conviction.
+	pub VoteOf get(vote_of): b"dem:vot:" => map [ (ReferendumIndex, T::AccountId) => (bool, Conviction) ];
+
+	// The account to which `who` delegates its votes, if any, and the conviction of the delegation.
+	pub Delegations get(delegations): b"dem:dlg:" => map [ T::AccountId => (T::AccountId, Conviction) ];
+	// The accounts which directly delegate their votes to `who`.
+	pub DelegatorsOf get(delegators_of): b"dem:dls:" => default map [ T::AccountId => Vec<T::AccountId> ];
 }
 
 impl<T: Trait> Module<T> {
@@ -107,7 +149,7 @@ impl<T: Trait> Module<T> {
 	/// Get the amount locked in support of `proposal`; `None` if proposal isn't a valid proposal
 	/// index.
 	pub fn locked_for(proposal: PropIndex) -> Option<T::Balance> {
-		Self::deposit_of(proposal).map(|(d, l)| d * T::Balance::sa(l.len()))
+		Self::deposit_of(proposal).map(|(d, l)| d * T::Balance::sa(l.len() as u64))
 	}
 
 	/// Return true if `ref_index` is an on-going referendum.
@@ -134,12 +176,30 @@ impl<T: Trait> Module<T> {
 			.collect()
 	}
 
-	/// Get the voters for the current proposal.
-	pub fn tally(ref_index: ReferendumIndex) -> (T::Balance, T::Balance) {
-		Self::voters_for(ref_index).iter()
-			.map(|a| (<staking::Module<T>>::balance(a), Self::vote_of((ref_index, a.clone())).unwrap_or(false)/*defensive only: all items come from `voters`; for an item to be in `voters` there must be a vote registered; qed*/))
-			.map(|(bal, vote)| if vote { (bal, Zero::zero()) } else { (Zero::zero(), bal) })
-			.fold((Zero::zero(), Zero::zero()), |(a, b), (c, d)| (a + c, b + d))
+	/// Get the conviction-weighted votes for and against `ref_index`, including those delegated to
+	/// its voters, along with the combined balance of all accounts counted (the turnout).
+	pub fn tally(ref_index: ReferendumIndex) -> (T::Balance, T::Balance, T::Balance) {
+		let mut approve = Zero::zero();
+		let mut against = Zero::zero();
+		let mut turnout = Zero::zero();
+		for voter in Self::voters_for(ref_index) {
+			let (approve_proposal, conviction) = Self::vote_of((ref_index, voter.clone()))
+				.unwrap_or((false, Conviction::None))/*defensive only: all items come from `voters`; for an item to be in `voters` there must be a vote registered; qed*/;
+			let balance = <staking::Module<T>>::balance(&voter);
+			let mut votes = conviction.votes(balance);
+			turnout = saturating_add(turnout, balance);
+			Self::for_each_delegator(ref_index, &voter, |delegator, conviction| {
+				let balance = <staking::Module<T>>::balance(delegator);
+				votes = saturating_add(votes, conviction.votes(balance));
+				turnout = saturating_add(turnout, balance);
+			});
+			if approve_proposal {
+				approve = saturating_add(approve, votes);
+			} else {
+				against = saturating_add(against, votes);
+			}
+		}
+		(approve, against, turnout)
 	}
 
 	// dispatching.
@@ -172,9 +232,10 @@ impl<T: Trait> Module<T> {
 	}
 
 	/// Vote in a referendum. If `approve_proposal` is true, the vote is to enact the proposal;
-	/// false would be a vote to keep the status quo..
-	fn vote(aux: &T::PublicAux, ref_index: ReferendumIndex, approve_proposal: bool) -> Result {
-		ensure!(Self::is_active_referendum(ref_index), "vote given for invalid referendum.");
+	/// false would be a vote to keep the status quo. The transactor's balance is locked until the
+	/// referendum is enacted, and for longer with greater `conviction`.
+	fn vote(aux: &T::PublicAux, ref_index: ReferendumIndex, approve_proposal: bool, conviction: Conviction) -> Result {
+		let (end, _, _) = Self::referendum_info(ref_index).ok_or("vote given for invalid referendum.")?;
 		ensure!(!<staking::Module<T>>::balance(aux.ref_into()).is_zero(),
 			"transactor must have balance to signal approval.");
 		if !<VoteOf<T>>::exists(&(ref_index, aux.ref_into().clone())) {
@@ -182,7 +243,35 @@ impl<T: Trait> Module<T> {
 			voters.push(aux.ref_into().clone());
 			<VotersFor<T>>::insert(ref_index, voters);
 		}
-		<VoteOf<T>>::insert(&(ref_index, aux.ref_into().clone()), approve_proposal);
+		<VoteOf<T>>::insert(&(ref_index, aux.ref_into().clone()), (approve_proposal, conviction));
+		Self::lock_for(aux.ref_into(), end, conviction);
+		Ok(())
+	}
+
+	/// Delegate the transactor's votes to `to` in all referenda in which the transactor doesn't
+	/// vote itself. The transactor's balance is locked as though it voted with `conviction` in each
+	/// referendum in which the delegation is counted.
+	fn delegate(aux: &T::PublicAux, to: T::AccountId, conviction: Conviction) -> Result {
+		let who = aux.ref_into();
+		ensure!(who != &to, "cannot delegate to self");
+		if let Some((old, _)) = Self::delegations(who) {
+			Self::remove_delegator(&old, who);
+		}
+		let mut delegators = Self::delegators_of(&to);
+		delegators.push(who.clone());
+		<DelegatorsOf<T>>::insert(&to, delegators);
+		<Delegations<T>>::insert(who, (to.clone(), conviction));
+		Self::deposit_event(RawEvent::Delegated { who: who.clone(), target: to });
+		Ok(())
+	}
+
+	/// Stop delegating the transactor's votes. Any locks from referenda in which the delegation
+	/// was counted remain.
+	fn undelegate(aux: &T::PublicAux) -> Result {
+		let who = aux.ref_into();
+		let (to, _) = <Delegations<T>>::take(who).ok_or("not delegating")?;
+		Self::remove_delegator(&to, who);
+		Self::deposit_event(RawEvent::Undelegated { who: who.clone() });
 		Ok(())
 	}
 
@@ -201,6 +290,11 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
+	/// Cancel the proposal of the passed referendum `ref_index`, scheduled for enactment at `when`.
+	fn cancel_queued(when: T::BlockNumber, ref_index: ReferendumIndex) -> Result {
+		Self::internal_cancel_queued(when, ref_index)
+	}
+
 	// exposed mutables.
 
 	/// Start a referendum. Can be called directly by the council.
@@ -213,6 +307,21 @@ impl<T: Trait> Module<T> {
 		<Module<T>>::clear_referendum(ref_index);
 	}
 
+	/// Cancel a scheduled proposal before its enactment. Can be called directly by the council.
+	pub fn internal_cancel_queued(when: T::BlockNumber, ref_index: ReferendumIndex) -> Result {
+		let mut scheduled = Self::scheduled(when);
+		let len = scheduled.len();
+		scheduled.retain(|&(_, i)| i != ref_index);
+		ensure!(scheduled.len() < len, "proposal not scheduled");
+		if scheduled.is_empty() {
+			<Scheduled<T>>::remove(when);
+		} else {
+			<Scheduled<T>>::insert(when, scheduled);
+		}
+		Self::deposit_event(RawEvent::Cancelled { ref_index });
+		Ok(())
+	}
+
 	// private.
 
 	/// Start a referendum
@@ -228,9 +337,58 @@ impl<T: Trait> Module<T> {
 
 		<ReferendumCount<T>>::put(ref_index + 1);
 		<ReferendumInfoOf<T>>::insert(ref_index, (end, proposal, vote_threshold));
+		Self::deposit_event(RawEvent::Started { ref_index, threshold: vote_threshold });
 		Ok(ref_index)
 	}
 
+	/// Call `f` with each account, and the conviction of its delegation, whose votes in `ref_index`
+	/// are delegated directly or transitively to `who`. Accounts which voted themselves are skipped,
+	/// along with those delegating through them.
+	fn for_each_delegator<F: FnMut(&T::AccountId, Conviction)>(ref_index: ReferendumIndex, who: &T::AccountId, mut f: F) {
+		// every account delegates to at most one other, so starting from a voter each account can be
+		// reached at most once; a cycle would have to lead back through the voter, which is skipped.
+		let mut pending = vec![who.clone()];
+		while let Some(target) = pending.pop() {
+			for delegator in Self::delegators_of(&target) {
+				if <VoteOf<T>>::exists(&(ref_index, delegator.clone())) {
+					continue;
+				}
+				if let Some((_, conviction)) = Self::delegations(&delegator) {
+					f(&delegator, conviction);
+				}
+				pending.push(delegator);
+			}
+		}
+	}
+
+	/// Remove `who` from the delegators of `target`.
+	fn remove_delegator(target: &T::AccountId, who: &T::AccountId) {
+		let mut delegators = Self::delegators_of(target);
+		delegators.retain(|d| d != who);
+		if delegators.is_empty() {
+			<DelegatorsOf<T>>::remove(target);
+		} else {
+			<DelegatorsOf<T>>::insert(target, delegators);
+		}
+	}
+
+	/// Lock the balance of `who` for a vote with `conviction` on a referendum ending at `end`.
+	fn lock_for(who: &T::AccountId, end: T::BlockNumber, conviction: Conviction) {
+		let until = end + Self::enactment_period()
+			+ Self::voting_period() * T::BlockNumber::sa(conviction.lock_periods() as u64);
+		<staking::Module<T>>::extend_bondage(who, until);
+	}
+
+	/// Lock the balances of all accounts whose delegated votes are counted in `ref_index`, which
+	/// ends at `end`.
+	fn lock_delegators(ref_index: ReferendumIndex, end: T::BlockNumber) {
+		for voter in Self::voters_for(ref_index) {
+			Self::for_each_delegator(ref_index, &voter, |delegator, conviction|
+				Self::lock_for(delegator, end, conviction)
+			);
+		}
+	}
+
 	/// Remove all info on a referendum.
 	fn clear_referendum(ref_index: ReferendumIndex) {
 		<ReferendumInfoOf<T>>::remove(ref_index);
@@ -256,6 +414,7 @@ impl<T: Trait> Module<T> {
 						<staking::Module<T>>::refund(d, deposit);
 					}
 					<PublicProps<T>>::put(public_props);
+					Self::deposit_event(RawEvent::Tabled { proposal_index: prop_index, deposit, depositors });
 					Self::inject_referendum(now + Self::voting_period(), proposal, VoteThreshold::SuperMajorityApprove)?;
 				} else {
 					return Err("depositors always exist for current proposals")
@@ -264,19 +423,37 @@ impl<T: Trait> Module<T> {
 		}
 
 		// tally up votes for any expiring referenda.
-		for (index, _, proposal, vote_threshold) in Self::maturing_referendums_at(now) {
-			let (approve, against) = Self::tally(index);
+		for (index, end, proposal, vote_threshold) in Self::maturing_referendums_at(now) {
+			let (approve, against, turnout) = Self::tally(index);
 			let total_stake = <staking::Module<T>>::total_stake();
+			Self::lock_delegators(index, end);
 			Self::clear_referendum(index);
-			if vote_threshold.approved(approve, against, total_stake) {
-				proposal.dispatch()?;
+			if vote_threshold.approved(approve, against, turnout, total_stake) {
+				Self::deposit_event(RawEvent::Passed { ref_index: index });
+				let when = now + Self::enactment_period();
+				let mut scheduled = Self::scheduled(when);
+				scheduled.push((proposal, index));
+				<Scheduled<T>>::insert(when, scheduled);
+			} else {
+				Self::deposit_event(RawEvent::NotPassed { ref_index: index });
 			}
 			<NextTally<T>>::put(index + 1);
 		}
+
+		// enact any proposals whose enactment period is over.
+		for (proposal, index) in <Scheduled<T>>::take(now) {
+			let result = proposal.dispatch();
+			Self::deposit_event(RawEvent::Executed { ref_index: index, result: result.is_ok() });
+			if let Err(e) = result {
+				runtime_io::print(e);
+			}
+		}
 		Ok(())
 	}
 }
 
+impl_deposit_event!(Module<T>, Event<T>);
+
 impl<T: Trait> Executable for Module<T> {
 	fn execute() {
 		if let Err(e) = Self::end_block(<system::Module<T>>::block_number()) {
@@ -289,6 +466,7 @@ impl<T: Trait> Executable for Module<T> {
 pub struct GenesisConfig<T: Trait> {
 	pub launch_period: T::BlockNumber,
 	pub voting_period: T::BlockNumber,
+	pub enactment_period: T::BlockNumber,
 	pub minimum_deposit: T::Balance,
 }
 
@@ -298,6 +476,7 @@ impl<T: Trait> GenesisConfig<T> {
 		GenesisConfig {
 			launch_period: T::BlockNumber::sa(1),
 			voting_period: T::BlockNumber::sa(1),
+			enactment_period: T::BlockNumber::sa(0),
 			minimum_deposit: T::Balance::sa(1),
 		}
 	}
@@ -306,6 +485,7 @@ impl<T: Trait> GenesisConfig<T> {
 		GenesisConfig {
 			launch_period: T::BlockNumber::sa(1),
 			voting_period: T::BlockNumber::sa(3),
+			enactment_period: T::BlockNumber::sa(1),
 			minimum_deposit: T::Balance::sa(1),
 		}
 	}
@@ -317,6 +497,7 @@ impl<T: Trait> Default for GenesisConfig<T> {
 		GenesisConfig {
 			launch_period: T::BlockNumber::sa(1000),
 			voting_period: T::BlockNumber::sa(1000),
+			enactment_period: T::BlockNumber::sa(1000),
 			minimum_deposit: T::Balance::sa(0),
 		}
 	}
@@ -332,6 +513,7 @@ impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 		map![
 			twox_128(<LaunchPeriod<T>>::key()).to_vec() => self.launch_period.encode(),
 			twox_128(<VotingPeriod<T>>::key()).to_vec() => self.voting_period.encode(),
+			twox_128(<EnactmentPeriod<T>>::key()).to_vec() => self.enactment_period.encode(),
 			twox_128(<MinimumDeposit<T>>::key()).to_vec() => self.minimum_deposit.encode(),
 			twox_128(<ReferendumCount<T>>::key()).to_vec() => (0 as ReferendumIndex).encode(),
 			twox_128(<NextTally<T>>::key()).to_vec() => (0 as ReferendumIndex).encode(),
@@ -340,6 +522,11 @@ impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T>
 	}
 }
 
+/// `a + b`, or the greatest balance if that would overflow.
+fn saturating_add<Balance: CheckedAdd + Bounded>(a: Balance, b: Balance) -> Balance {
+	a.checked_add(&b).unwrap_or_else(Balance::max_value)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -357,13 +544,27 @@ mod tests {
 		}
 	}
 
+	mod democracy {
+		pub use ::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			staking<T> = 0,
+			democracy<T> = 1,
+		}
+	}
+
 	pub struct Test;
 	impl HasPublicAux for Test {
 		type PublicAux = u64;
 	}
 	impl consensus::Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehavior = ();
+		type OnOfflineValidator = ();
 	}
 	impl system::Trait for Test {
 		type Index = u64;
@@ -373,6 +574,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = TestEvent;
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -380,9 +582,12 @@ mod tests {
 	impl staking::Trait for Test {
 		type Balance = u64;
 		type DetermineContractAddress = staking::DummyContractAddressFor;
+		type OnAccountKill = ();
+		type Event = TestEvent;
 	}
 	impl Trait for Test {
 		type Proposal = Proposal;
+		type Event = TestEvent;
 	}
 
 	fn new_test_ext() -> runtime_io::TestExternalities {
@@ -404,10 +609,18 @@ mod tests {
 			bonding_duration: 3,
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			creation_fee: 0,
+			misbehavior_slash: 0,
+			reporter_reward: 0,
+			session_reward: 0,
+			offline_slash: 0,
+			offline_slash_grace: 0,
 		}.build_externalities());
 		t.extend(GenesisConfig::<Test>{
 			launch_period: 1,
 			voting_period: 1,
+			enactment_period: 0,
 			minimum_deposit: 1,
 		}.build_externalities());
 		t
@@ -456,12 +669,12 @@ mod tests {
 
 			System::set_block_number(2);
 			let r = 0;
-			assert_ok!(Democracy::vote(&1, r, true));
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
 
 			assert_eq!(Democracy::referendum_count(), 1);
 			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), Some(true));
-			assert_eq!(Democracy::tally(r), (10, 0));
+			assert_eq!(Democracy::vote_of((r, 1)), Some((true, Conviction::None)));
+			assert_eq!(Democracy::tally(r), (10, 0, 10));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
@@ -540,19 +753,19 @@ mod tests {
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 
 			System::set_block_number(1);
-			assert_ok!(Democracy::vote(&1, 0, true));
+			assert_ok!(Democracy::vote(&1, 0, true, Conviction::None));
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
 			assert_eq!(Staking::bonding_duration(), 4);
 
 			System::set_block_number(2);
-			assert_ok!(Democracy::vote(&1, 1, true));
+			assert_ok!(Democracy::vote(&1, 1, true, Conviction::None));
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
 			assert_eq!(Staking::bonding_duration(), 3);
 
 			System::set_block_number(3);
-			assert_ok!(Democracy::vote(&1, 2, true));
+			assert_ok!(Democracy::vote(&1, 2, true, Conviction::None));
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
 			assert_eq!(Staking::bonding_duration(), 2);
@@ -568,25 +781,84 @@ mod tests {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&1, r, true));
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
 
 			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), Some(true));
-			assert_eq!(Democracy::tally(r), (10, 0));
+			assert_eq!(Democracy::vote_of((r, 1)), Some((true, Conviction::None)));
+			assert_eq!(Democracy::tally(r), (10, 0, 10));
+
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			Staking::check_new_era();
+
+			assert_eq!(Staking::era_length(), 2);
+		});
+	}
+
+	#[test]
+	fn referendum_should_deposit_events() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+
+			let events = System::events().into_iter().map(|r| r.event).collect::<Vec<_>>();
+			assert_eq!(events, vec![
+				TestEvent::democracy(RawEvent::Started { ref_index: r, threshold: VoteThreshold::SuperMajorityApprove }),
+				TestEvent::democracy(RawEvent::Passed { ref_index: r }),
+				TestEvent::democracy(RawEvent::Executed { ref_index: r, result: true }),
+			]);
+		});
+	}
+
+	#[test]
+	fn passed_proposal_should_be_enacted_after_delay() {
+		with_externalities(&mut new_test_ext(), || {
+			<EnactmentPeriod<Test>>::put(2);
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
+			assert_eq!(Staking::bondage(&1), 3);
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			assert_eq!(Democracy::scheduled(3), vec![(sessions_per_era_proposal(2), r)]);
 			Staking::check_new_era();
+			assert_eq!(Staking::era_length(), 1);
 
+			System::set_block_number(3);
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			assert_eq!(Democracy::scheduled(3), vec![]);
+			Staking::check_new_era();
 			assert_eq!(Staking::era_length(), 2);
 		});
 	}
 
+	#[test]
+	fn cancel_queued_should_work() {
+		with_externalities(&mut new_test_ext(), || {
+			<EnactmentPeriod<Test>>::put(2);
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+
+			assert_noop!(Democracy::cancel_queued(3, r + 1), "proposal not scheduled");
+			assert_ok!(Democracy::cancel_queued(3, r));
+			assert_eq!(Democracy::scheduled(3), vec![]);
+
+			System::set_block_number(3);
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			Staking::check_new_era();
+			assert_eq!(Staking::era_length(), 1);
+		});
+	}
+
 	#[test]
 	fn cancel_referendum_should_work() {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&1, r, true));
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
 			assert_ok!(Democracy::cancel_referendum(r));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
@@ -601,11 +873,11 @@ mod tests {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&1, r, false));
+			assert_ok!(Democracy::vote(&1, r, false, Conviction::None));
 
 			assert_eq!(Democracy::voters_for(r), vec![1]);
-			assert_eq!(Democracy::vote_of((r, 1)), Some(false));
-			assert_eq!(Democracy::tally(r), (0, 10));
+			assert_eq!(Democracy::vote_of((r, 1)), Some((false, Conviction::None)));
+			assert_eq!(Democracy::tally(r), (0, 10, 10));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
@@ -619,14 +891,14 @@ mod tests {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&1, r, true));
-			assert_ok!(Democracy::vote(&2, r, false));
-			assert_ok!(Democracy::vote(&3, r, false));
-			assert_ok!(Democracy::vote(&4, r, true));
-			assert_ok!(Democracy::vote(&5, r, false));
-			assert_ok!(Democracy::vote(&6, r, true));
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
+			assert_ok!(Democracy::vote(&2, r, false, Conviction::None));
+			assert_ok!(Democracy::vote(&3, r, false, Conviction::None));
+			assert_ok!(Democracy::vote(&4, r, true, Conviction::None));
+			assert_ok!(Democracy::vote(&5, r, false, Conviction::None));
+			assert_ok!(Democracy::vote(&6, r, true, Conviction::None));
 
-			assert_eq!(Democracy::tally(r), (110, 100));
+			assert_eq!(Democracy::tally(r), (110, 100, 210));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
@@ -640,10 +912,10 @@ mod tests {
 		with_externalities(&mut new_test_ext(), || {
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&5, r, false));
-			assert_ok!(Democracy::vote(&6, r, true));
+			assert_ok!(Democracy::vote(&5, r, false, Conviction::None));
+			assert_ok!(Democracy::vote(&6, r, true, Conviction::None));
 
-			assert_eq!(Democracy::tally(r), (60, 50));
+			assert_eq!(Democracy::tally(r), (60, 50, 110));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
@@ -660,11 +932,11 @@ mod tests {
 
 			System::set_block_number(1);
 			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
-			assert_ok!(Democracy::vote(&4, r, true));
-			assert_ok!(Democracy::vote(&5, r, false));
-			assert_ok!(Democracy::vote(&6, r, true));
+			assert_ok!(Democracy::vote(&4, r, true, Conviction::None));
+			assert_ok!(Democracy::vote(&5, r, false, Conviction::None));
+			assert_ok!(Democracy::vote(&6, r, true, Conviction::None));
 
-			assert_eq!(Democracy::tally(r), (100, 50));
+			assert_eq!(Democracy::tally(r), (100, 50, 150));
 
 			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
 			Staking::check_new_era();
@@ -672,4 +944,96 @@ mod tests {
 			assert_eq!(Staking::era_length(), 2);
 		});
 	}
+
+	#[test]
+	fn tally_should_saturate_rather_than_overflow() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			Staking::set_free_balance(&1, u64::max_value() - 1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SimpleMajority).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::Locked6x));
+			assert_ok!(Democracy::vote(&2, r, true, Conviction::None));
+			assert_ok!(Democracy::vote(&3, r, false, Conviction::None));
+
+			assert_eq!(Conviction::Locked6x.votes(u64::max_value() - 1), u64::max_value());
+			assert_eq!(Democracy::tally(r), (u64::max_value(), 30, u64::max_value()));
+		});
+	}
+
+	#[test]
+	fn conviction_should_weight_votes_and_extend_lock() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SimpleMajority).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::Locked3x));
+			assert_ok!(Democracy::vote(&2, r, false, Conviction::None));
+
+			assert_eq!(Democracy::vote_of((r, 1)), Some((true, Conviction::Locked3x)));
+			assert_eq!(Democracy::tally(r), (30, 20, 30));
+			assert_eq!(Staking::bondage(&1), 3);
+			assert_eq!(Staking::bondage(&2), 1);
+
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			Staking::check_new_era();
+
+			assert_eq!(Staking::era_length(), 2);
+		});
+	}
+
+	#[test]
+	fn locked_voter_should_not_move_funds() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::Locked3x));
+
+			System::set_block_number(2);
+			assert_noop!(Staking::deduct_unbonded(&1, 1), "not enough liquid funds");
+
+			System::set_block_number(3);
+			assert_ok!(Staking::deduct_unbonded(&1, 1));
+		});
+	}
+
+	#[test]
+	fn delegated_votes_should_be_counted() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			assert_ok!(Democracy::delegate(&2, 1, Conviction::Locked2x));
+			assert_ok!(Democracy::delegate(&3, 2, Conviction::None));
+			let r = Democracy::inject_referendum(1, sessions_per_era_proposal(2), VoteThreshold::SuperMajorityApprove).unwrap();
+			assert_ok!(Democracy::vote(&1, r, true, Conviction::None));
+
+			assert_eq!(Democracy::tally(r), (80, 0, 60));
+
+			// a direct vote overrides the delegation, along with those delegating through it.
+			assert_ok!(Democracy::vote(&3, r, false, Conviction::None));
+			assert_eq!(Democracy::tally(r), (50, 30, 60));
+
+			assert_eq!(Democracy::end_block(System::block_number()), Ok(()));
+			assert_eq!(Staking::bondage(&2), 2);
+		});
+	}
+
+	#[test]
+	fn delegation_should_be_changeable() {
+		with_externalities(&mut new_test_ext(), || {
+			System::set_block_number(1);
+			assert_noop!(Democracy::undelegate(&2), "not delegating");
+			assert_noop!(Democracy::delegate(&1, 1, Conviction::None), "cannot delegate to self");
+
+			assert_ok!(Democracy::delegate(&2, 1, Conviction::None));
+			assert_eq!(Democracy::delegations(&2), Some((1, Conviction::None)));
+			assert_eq!(Democracy::delegators_of(&1), vec![2]);
+
+			assert_ok!(Democracy::delegate(&2, 3, Conviction::Locked2x));
+			assert_eq!(Democracy::delegations(&2), Some((3, Conviction::Locked2x)));
+			assert!(Democracy::delegators_of(&1).is_empty());
+			assert_eq!(Democracy::delegators_of(&3), vec![2]);
+
+			assert_ok!(Democracy::undelegate(&2));
+			assert_eq!(Democracy::delegations(&2), None);
+			assert!(Democracy::delegators_of(&3).is_empty());
+		});
+	}
 }
@@ -18,7 +18,7 @@
 
 use primitives::traits::{Zero, IntegerSquareRoot};
 use codec::{Input, Slicable};
-use rstd::ops::{Add, Mul, Div, Rem};
+use rstd::ops::{Mul, Div, Rem};
 
 /// A means of determining if a vote is past pass threshold.
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -52,10 +52,10 @@ impl Slicable for VoteThreshold {
 }
 
 pub trait Approved<Balance> {
-	/// Given `approve` votes for and `against` votes against from a total electorate size of
-	/// `electorate` (`electorate - (approve + against)` are abstainers), then returns true if the
-	/// overall outcome is in favour of approval.
-	fn approved(&self, approve: Balance, against: Balance, electorate: Balance) -> bool;
+	/// Given `approve` votes for and `against` votes against, cast by voters with a combined
+	/// balance of `turnout` from a total electorate size of `electorate` (`electorate - turnout`
+	/// are abstainers), then returns true if the overall outcome is in favour of approval.
+	fn approved(&self, approve: Balance, against: Balance, turnout: Balance, electorate: Balance) -> bool;
 }
 
 /// Return `true` iff `n1 / d1 < n2 / d2`. `d1` and `d2` may not be zero.
@@ -86,13 +86,12 @@ fn compare_rationals<T: Zero + Mul<T, Output = T> + Div<T, Output = T> + Rem<T,
 	}
 }
 
-impl<Balance: IntegerSquareRoot + Zero + Ord + Add<Balance, Output = Balance> + Mul<Balance, Output = Balance> + Div<Balance, Output = Balance> + Rem<Balance, Output = Balance> + Copy> Approved<Balance> for VoteThreshold {
-	/// Given `approve` votes for and `against` votes against from a total electorate size of
-	/// `electorate` (`electorate - (approve + against)` are abstainers), then returns true if the
-	/// overall outcome is in favour of approval.
-	fn approved(&self, approve: Balance, against: Balance, electorate: Balance) -> bool {
-		let voters = approve + against;
-		let sqrt_voters = voters.integer_sqrt();
+impl<Balance: IntegerSquareRoot + Zero + Ord + Mul<Balance, Output = Balance> + Div<Balance, Output = Balance> + Rem<Balance, Output = Balance> + Copy> Approved<Balance> for VoteThreshold {
+	/// Given `approve` votes for and `against` votes against, cast by voters with a combined
+	/// balance of `turnout` from a total electorate size of `electorate` (`electorate - turnout`
+	/// are abstainers), then returns true if the overall outcome is in favour of approval.
+	fn approved(&self, approve: Balance, against: Balance, turnout: Balance, electorate: Balance) -> bool {
+		let sqrt_voters = turnout.integer_sqrt();
 		let sqrt_electorate = electorate.integer_sqrt();
 		if sqrt_voters.is_zero() { return false; }
 		match *self {
@@ -111,7 +110,7 @@ mod tests {
 
 	#[test]
 	fn should_work() {
-		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(60, 50, 210), false);
-		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(100, 50, 210), true);
+		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(60, 50, 110, 210), false);
+		assert_eq!(VoteThreshold::SuperMajorityApprove.approved(100, 50, 150, 210), true);
 	}
 }
@@ -48,6 +48,7 @@ use rstd::marker::PhantomData;
 use runtime_io::Hashing;
 use runtime_support::StorageValue;
 use primitives::traits::{self, Header, Zero, One, Checkable, Applyable, CheckEqual, Executable, MakePayment};
+use primitives::{ApplyOutcome, ApplyError, ApplyResult};
 use codec::Slicable;
 use system::extrinsics_root;
 
@@ -64,8 +65,8 @@ impl<
 	Payment: MakePayment<System::AccountId>,
 	Finalisation: Executable,
 > Executive<System, Block, Payment, Finalisation> where
-	Block::Extrinsic: Checkable + Slicable,
-	<Block::Extrinsic as Checkable>::Checked: Applyable<Index = System::Index, AccountId = System::AccountId>
+	Block::Extrinsic: Checkable<system::ChainContext<System>> + Slicable,
+	<Block::Extrinsic as Checkable<system::ChainContext<System>>>::Checked: Applyable<Index = System::Index, AccountId = System::AccountId>
 {
 	/// Start the execution of a particular block.
 	pub fn initialise_block(header: &System::Header) {
@@ -118,35 +119,45 @@ impl<
 
 	/// Apply extrinsic outside of the block execution function.
 	/// This doesn't attempt to validate anything regarding the block, but it builds a list of uxt
-	/// hashes.
-	pub fn apply_extrinsic(uxt: Block::Extrinsic) {
+	/// hashes. An extrinsic which returns an `ApplyError` leaves no trace in storage and must not
+	/// be included in the block.
+	pub fn apply_extrinsic(uxt: Block::Extrinsic) -> ApplyResult {
 		let encoded = uxt.encode();
 		let encoded_len = encoded.len();
-		<system::Module<System>>::note_extrinsic(encoded);
-		Self::apply_extrinsic_no_note_with_len(uxt, encoded_len);
+		Self::apply_extrinsic_with_len(uxt, encoded_len, Some(encoded))
 	}
 
 	/// Apply an extrinsic inside the block execution function.
 	fn apply_extrinsic_no_note(uxt: Block::Extrinsic) {
 		let l = uxt.encode().len();
-		Self::apply_extrinsic_no_note_with_len(uxt, l);
+		match Self::apply_extrinsic_with_len(uxt, l, None) {
+			Ok(_) => (),
+			Err(ApplyError::BadSignature) => panic!("All extrinsics should be properly signed"),
+			Err(ApplyError::Stale) | Err(ApplyError::Future) => panic!("All extrinsics should have the correct nonce"),
+			Err(ApplyError::CantPay) => panic!("All extrinsics should have sender able to pay their fees"),
+			Err(ApplyError::Expired) => panic!("All extrinsics should be within their era"),
+		}
 	}
 
-	/// Actually apply an extrinsic given its `encoded_len`; this doesn't note its hash.
-	fn apply_extrinsic_no_note_with_len(uxt: Block::Extrinsic, encoded_len: usize) {
-		// Verify the signature is good.
-		let xt = match uxt.check() {
-			Ok(xt) => xt,
-			Err(_) => panic!("All extrinsics should be properly signed"),
-		};
+	/// Actually apply an extrinsic given its `encoded_len`; this doesn't note its hash unless
+	/// `to_note` is given.
+	fn apply_extrinsic_with_len(uxt: Block::Extrinsic, encoded_len: usize, to_note: Option<Vec<u8>>) -> ApplyResult {
+		// Verify the signature is good. This fails for extrinsics which are outside of their era.
+		let context = system::ChainContext::<System>::default();
+		let xt = uxt.check(&context).map_err(|uxt| if uxt.is_expired(&context) {
+			ApplyError::Expired
+		} else {
+			ApplyError::BadSignature
+		})?;
 
 		if xt.sender() != &Default::default() {
 			// check index
 			let expected_index = <system::Module<System>>::account_index(xt.sender());
-			assert!(xt.index() == &expected_index, "All extrinsics should have the correct nonce");
+			if xt.index() < &expected_index { return Err(ApplyError::Stale) }
+			if xt.index() > &expected_index { return Err(ApplyError::Future) }
 
 			// pay any fees.
-			assert!(Payment::make_payment(xt.sender(), encoded_len), "All extrinsics should have sender able to pay their fees");
+			if !Payment::make_payment(xt.sender(), encoded_len) { return Err(ApplyError::CantPay) }
 
 			// AUDIT: Under no circumstances may this function panic from here onwards.
 
@@ -154,18 +165,27 @@ impl<
 			<system::Module<System>>::inc_account_index(xt.sender());
 		}
 
+		// make sure to `note_extrinsic` only after we know it's going to be executed
+		// to prevent it from leaking in storage.
+		if let Some(encoded) = to_note {
+			<system::Module<System>>::note_extrinsic(encoded);
+		}
+
 		// decode parameters and dispatch
-		xt.apply();
+		let r = xt.apply();
 
-		<system::ExtrinsicIndex<System>>::put(<system::ExtrinsicIndex<System>>::get() + 1u32);
+		<system::Module<System>>::note_applied_extrinsic(r.is_ok());
+
+		Ok(match r {
+			Ok(_) => ApplyOutcome::Success,
+			Err(e) => { runtime_io::print(e); ApplyOutcome::Fail },
+		})
 	}
 
 	fn final_checks(header: &System::Header) {
-		// check digest
-		assert!(header.digest() == &<system::Module<System>>::digest());
-
-		// remove temporaries.
-		<system::Module<System>>::finalise();
+		// remove temporaries and check digest, which gains a log bloom on finalisation.
+		let finalised = <system::Module<System>>::finalise();
+		assert!(header.digest() == finalised.digest());
 
 		// check storage root.
 		let storage_root = System::Hashing::storage_root();
@@ -177,6 +197,7 @@ impl<
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use runtime_support::StorageMap;
 	use staking::Call;
 	use runtime_io::with_externalities;
 	use substrate_primitives::H256;
@@ -189,8 +210,11 @@ mod tests {
 		type PublicAux = u64;
 	}
 	impl consensus::Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehavior = ();
+		type OnOfflineValidator = ();
 	}
 	impl system::Trait for Test {
 		type Index = u64;
@@ -200,6 +224,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -207,6 +232,8 @@ mod tests {
 	impl staking::Trait for Test {
 		type Balance = u64;
 		type DetermineContractAddress = staking::DummyContractAddressFor;
+		type OnAccountKill = ();
+		type Event = ();
 	}
 
 	type TestXt = primitives::testing::TestXt<Call<Test>>;
@@ -224,11 +251,18 @@ mod tests {
 			bonding_duration: 0,
 			transaction_base_fee: 10,
 			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			creation_fee: 0,
+			misbehavior_slash: 0,
+			reporter_reward: 0,
+			session_reward: 0,
+			offline_slash: 0,
+			offline_slash_grace: 0,
 		}.build_externalities());
-		let xt = primitives::testing::TestXt((1, 0, Call::transfer(2, 69)));
+		let xt = primitives::testing::TestXt((1, 0, Call::transfer(2, 69, 0, 0, vec![])));
 		with_externalities(&mut t, || {
 			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
-			Executive::apply_extrinsic(xt);
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Success));
 			assert_eq!(<staking::Module<Test>>::balance(&1), 32);
 			assert_eq!(<staking::Module<Test>>::balance(&2), 69);
 		});
@@ -242,6 +276,29 @@ mod tests {
 		t
 	}
 
+	#[test]
+	fn bad_extrinsic_not_inserted() {
+		let xt = primitives::testing::TestXt((1, 42, Call::transfer(33, 69, 0, 0, vec![])));
+		with_externalities(&mut new_test_ext(), || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+			assert_eq!(Executive::apply_extrinsic(xt), Err(ApplyError::Future));
+			assert_eq!(<system::Module<Test>>::extrinsic_index(), 0);
+			assert!(!<system::ExtrinsicData<Test>>::exists(0));
+		});
+	}
+
+	#[test]
+	fn failed_dispatch_is_recorded() {
+		let xt = primitives::testing::TestXt((1, 0, Call::transfer(33, 69, 0, 0, vec![])));
+		with_externalities(&mut new_test_ext(), || {
+			Executive::initialise_block(&Header::new(1, H256::default(), H256::default(), [69u8; 32].into(), Digest::default()));
+			assert_eq!(Executive::apply_extrinsic(xt), Ok(ApplyOutcome::Fail));
+			assert_eq!(<system::Module<Test>>::extrinsic_index(), 1);
+			assert_eq!(<system::Module<Test>>::account_index(&1), 1);
+			assert_eq!(<system::Module<Test>>::extrinsic_success(), vec![false]);
+		});
+	}
+
 	#[test]
 	fn block_import_works() {
 		with_externalities(&mut new_test_ext(), || {
@@ -0,0 +1,163 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A Bloom filter over the addresses and topics of the logs deposited in a block, allowing
+//! light clients to skip blocks which cannot contain the logs they are looking for.
+
+use rstd::prelude::*;
+use codec::{Input, Slicable};
+use runtime_io;
+use substrate_primitives::block::Log;
+use traits::DigestItem;
+
+/// The size of the filter in bytes.
+pub const BLOOM_SIZE: usize = 256;
+
+/// The number of bits set in the filter for each accrued input.
+const BITS_PER_INPUT: usize = 3;
+
+/// The prefix which marks an opaque digest log as carrying a `LogBloom`.
+pub const LOG_BLOOM_TAG: &'static [u8] = b":log_bloom:";
+
+/// A 2048-bit Bloom filter. Each input sets three bits, chosen by consecutive pairs of bytes of
+/// its Blake2 hash.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize))]
+pub struct LogBloom(Vec<u8>);
+
+impl LogBloom {
+	/// Create an empty filter.
+	pub fn new() -> Self {
+		let mut bits = Vec::new();
+		bits.resize(BLOOM_SIZE, 0);
+		LogBloom(bits)
+	}
+
+	/// Add `input` to the filter.
+	pub fn accrue(&mut self, input: &[u8]) {
+		for &(byte, mask) in Self::positions(input).iter() {
+			self.0[byte] |= mask;
+		}
+	}
+
+	/// Whether `input` may have been added to the filter. False positives are possible, false
+	/// negatives are not.
+	pub fn contains(&self, input: &[u8]) -> bool {
+		Self::positions(input).iter().all(|&(byte, mask)| self.0[byte] & mask == mask)
+	}
+
+	/// The raw bytes of the filter.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.0
+	}
+
+	/// The filter as an opaque digest log: `LOG_BLOOM_TAG` followed by its raw bytes.
+	pub fn to_tagged(&self) -> Vec<u8> {
+		let mut tagged = LOG_BLOOM_TAG.to_vec();
+		tagged.extend_from_slice(&self.0);
+		tagged
+	}
+
+	/// Read the filter from an opaque digest log, if it is one made by `to_tagged`.
+	pub fn from_tagged(log: &[u8]) -> Option<Self> {
+		if log.len() == LOG_BLOOM_TAG.len() + BLOOM_SIZE && log.starts_with(LOG_BLOOM_TAG) {
+			Some(LogBloom(log[LOG_BLOOM_TAG.len()..].to_vec()))
+		} else {
+			None
+		}
+	}
+
+	fn positions(input: &[u8]) -> [(usize, u8); BITS_PER_INPUT] {
+		let hash = runtime_io::blake2_256(input);
+		let mut positions = [(0, 0); BITS_PER_INPUT];
+		for (i, position) in positions.iter_mut().enumerate() {
+			let bit = ((hash[2 * i] as usize) << 8 | hash[2 * i + 1] as usize) % (BLOOM_SIZE * 8);
+			*position = (bit / 8, 1u8 << (bit % 8));
+		}
+		positions
+	}
+}
+
+impl Default for LogBloom {
+	fn default() -> Self {
+		LogBloom::new()
+	}
+}
+
+impl Slicable for LogBloom {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Vec::<u8>::decode(input)
+			.and_then(|v| if v.len() == BLOOM_SIZE { Some(LogBloom(v)) } else { None })
+	}
+
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		self.0.using_encoded(f)
+	}
+}
+
+impl DigestItem for Vec<u8> {
+	fn from_log_bloom(bloom: LogBloom) -> Self {
+		bloom.to_tagged()
+	}
+	fn as_log_bloom(&self) -> Option<LogBloom> {
+		LogBloom::from_tagged(self)
+	}
+}
+
+impl DigestItem for Log {
+	fn from_log_bloom(bloom: LogBloom) -> Self {
+		Log(bloom.to_tagged())
+	}
+	fn as_log_bloom(&self) -> Option<LogBloom> {
+		LogBloom::from_tagged(&self.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn accrued_inputs_are_contained() {
+		let mut bloom = LogBloom::new();
+		assert!(!bloom.contains(b"address"));
+
+		bloom.accrue(b"address");
+		bloom.accrue(b"topic");
+		assert!(bloom.contains(b"address"));
+		assert!(bloom.contains(b"topic"));
+		assert!(!bloom.contains(b"other topic"));
+		assert!(bloom.as_bytes().iter().map(|b| b.count_ones()).sum::<u32>() <= 6);
+	}
+
+	#[test]
+	fn bloom_roundtrips() {
+		let mut bloom = LogBloom::new();
+		bloom.accrue(b"topic");
+		assert_eq!(LogBloom::decode(&mut &bloom.encode()[..]), Some(bloom));
+		assert_eq!(LogBloom::decode(&mut &vec![0u8; 10].encode()[..]), None);
+	}
+
+	#[test]
+	fn bloom_is_told_apart_from_other_logs() {
+		let mut bloom = LogBloom::new();
+		bloom.accrue(b"topic");
+		let log = Log::from_log_bloom(bloom.clone());
+		assert_eq!(log.as_log_bloom(), Some(bloom.clone()));
+		assert_eq!(Log(bloom.as_bytes().to_vec()).as_log_bloom(), None);
+		assert_eq!(Log(vec![1]).as_log_bloom(), None);
+	}
+}
@@ -21,6 +21,7 @@ use rstd::prelude::*;
 use codec::{Slicable, Input};
 use runtime_support::AuxDispatchable;
 use traits;
+use bloom::LogBloom;
 use rstd::ops;
 
 #[cfg(feature = "std")]
@@ -79,6 +80,87 @@ impl<AccountId, Index, Call> Slicable for Extrinsic<AccountId, Index, Call> wher
 	}
 }
 
+/// The period of validity of a signed extrinsic. The hash of the era's checkpoint block is part of
+/// the signed payload, so a mortal extrinsic cannot be replayed on a chain which doesn't share
+/// that block.
+#[derive(PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+pub enum Era {
+	/// Valid forever. The checkpoint is the genesis block.
+	Immortal,
+	/// Valid for the given number of blocks after the checkpoint block with the given number.
+	Mortal(u64, u64),
+}
+
+impl Default for Era {
+	fn default() -> Self {
+		Era::Immortal
+	}
+}
+
+impl Era {
+	/// The number of the checkpoint block whose hash is signed along with the extrinsic.
+	pub fn checkpoint(&self) -> u64 {
+		match *self {
+			Era::Immortal => 0,
+			Era::Mortal(checkpoint, _) => checkpoint,
+		}
+	}
+
+	/// Whether an extrinsic of this era may be included in the block with the given number.
+	pub fn is_valid_at(&self, number: u64) -> bool {
+		match *self {
+			Era::Immortal => true,
+			Era::Mortal(checkpoint, period) =>
+				checkpoint < number && number - checkpoint <= period,
+		}
+	}
+}
+
+impl Slicable for Era {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(Era::Immortal),
+			1 => Some(Era::Mortal(Slicable::decode(input)?, Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		match *self {
+			Era::Immortal => v.push(0),
+			Era::Mortal(checkpoint, period) => {
+				v.push(1);
+				checkpoint.using_encoded(|s| v.extend(s));
+				period.using_encoded(|s| v.extend(s));
+			}
+		}
+
+		v
+	}
+}
+
+impl<AccountId, Index, Call> Extrinsic<AccountId, Index, Call> where
+ 	AccountId: Member + Slicable,
+ 	Index: Member + Slicable,
+ 	Call: Member + Slicable
+{
+	/// The payload which must be signed for this extrinsic to be valid in the given era: the
+	/// extrinsic itself, the era, and the hashes of the era's checkpoint block and of the
+	/// genesis block.
+	pub fn signing_payload<Hash: Slicable>(&self, era: &Era, checkpoint_hash: &Hash, genesis_hash: &Hash) -> Vec<u8> {
+		let mut v = self.encode();
+
+		era.using_encoded(|s| v.extend(s));
+		checkpoint_hash.using_encoded(|s| v.extend(s));
+		genesis_hash.using_encoded(|s| v.extend(s));
+
+		v
+	}
+}
+
 /// A extrinsics right from the external world. Unchecked.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize))]
@@ -90,7 +172,10 @@ pub struct UncheckedExtrinsic<AccountId, Index, Call, Signature> where
 {
 	/// The actual extrinsic information.
 	pub extrinsic: Extrinsic<AccountId, Index, Call>,
-	/// The signature; should be an Ed25519 signature applied to the serialised `extrinsic` field.
+	/// The period of validity of the extrinsic.
+	pub era: Era,
+	/// The signature; should be an Ed25519 signature applied to the `extrinsic` field's
+	/// signing payload for `era`.
 	pub signature: Signature,
 }
 
@@ -122,6 +207,7 @@ impl<AccountId, Index, Call, Signature> Slicable for UncheckedExtrinsic<AccountI
 
 		Some(UncheckedExtrinsic {
 			extrinsic: Slicable::decode(input)?,
+			era: Slicable::decode(input)?,
 			signature: Slicable::decode(input)?,
 		})
 	}
@@ -136,6 +222,7 @@ impl<AccountId, Index, Call, Signature> Slicable for UncheckedExtrinsic<AccountI
 		self.extrinsic.signed.using_encoded(|s| v.extend(s));
 		self.extrinsic.index.using_encoded(|s| v.extend(s));
 		self.extrinsic.function.using_encoded(|s| v.extend(s));
+		self.era.using_encoded(|s| v.extend(s));
 		self.signature.using_encoded(|s| v.extend(s));
 
 		let length = (v.len() - 4) as u32;
@@ -153,32 +240,53 @@ impl<AccountId, Index, Call, Signature> fmt::Debug for UncheckedExtrinsic<Accoun
 	Signature: Member,
 {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "UncheckedExtrinsic({:?})", self.extrinsic)
+		write!(f, "UncheckedExtrinsic({:?}, {:?})", self.extrinsic, self.era)
 	}
 }
 
-impl<AccountId, Index, Call, Signature> traits::Checkable for UncheckedExtrinsic<AccountId, Index, Call, Signature> where
- 	AccountId: Member + Default,
- 	Index: Member,
- 	Call: Member,
+impl<AccountId, Index, Call, Signature, Context> traits::Checkable<Context>
+	for UncheckedExtrinsic<AccountId, Index, Call, Signature>
+where
+ 	AccountId: Member + Default + Slicable,
+ 	Index: Member + Slicable,
+ 	Call: Member + Slicable,
 	Signature: Member + Default + traits::Verify<Signer = AccountId>,
-	Extrinsic<AccountId, Index, Call>: Slicable
+	Context: traits::ChainContext + ?Sized,
+	Context::Hash: Slicable,
 {
 	type Checked = CheckedExtrinsic<AccountId, Index, Call, Signature>;
 
-	fn check(self) -> Result<Self::Checked, Self> {
+	fn check(self, context: &Context) -> Result<Self::Checked, Self> {
 		if !self.is_signed() {
+			return Ok(CheckedExtrinsic(self));
+		}
+
+		if self.is_expired(context) {
+			return Err(self);
+		}
+		let hashes = context.block_hash(self.era.checkpoint())
+			.and_then(|checkpoint| context.block_hash(0).map(|genesis| (checkpoint, genesis)));
+		let (checkpoint_hash, genesis_hash) = match hashes {
+			Some(hashes) => hashes,
+			None => return Err(self),
+		};
+
+		let payload = self.extrinsic.signing_payload(&self.era, &checkpoint_hash, &genesis_hash);
+		if self.signature.verify(&payload, &self.extrinsic.signed) {
 			Ok(CheckedExtrinsic(self))
 		} else {
-			if ::codec::Slicable::using_encoded(&self.extrinsic, |msg|
-				self.signature.verify(msg, &self.extrinsic.signed)
-			) {
-				Ok(CheckedExtrinsic(self))
-			} else {
-				Err(self)
-			}
+			Err(self)
 		}
 	}
+
+	// expired extrinsics and those whose checkpoint isn't an ancestor cannot be checked. One
+	// signed for another chain is only told apart from a forgery by its signature, though.
+	fn is_expired(&self, context: &Context) -> bool {
+		self.is_signed() && (
+			!self.era.is_valid_at(context.current_height())
+				|| context.block_hash(self.era.checkpoint()).is_none()
+		)
+	}
 }
 
 /// A type-safe indicator that a extrinsic has been checked.
@@ -249,9 +357,9 @@ where
 		&self.0.extrinsic.signed
 	}
 
-	fn apply(self) {
+	fn apply(self) -> Result<(), &'static str> {
 		let xt = self.0.extrinsic;
-		xt.function.dispatch(&xt.signed);
+		xt.function.dispatch(&xt.signed)
 	}
 }
 
@@ -271,12 +379,18 @@ impl<Item> Slicable for Digest<Item> where
 	}
 }
 impl<Item> traits::Digest for Digest<Item> where
- 	Item: Member + Slicable
+ 	Item: Member + Slicable + traits::DigestItem
 {
 	type Item = Item;
 	fn push(&mut self, item: Self::Item) {
 		self.logs.push(item);
 	}
+	fn push_log_bloom(&mut self, bloom: LogBloom) {
+		self.logs.push(traits::DigestItem::from_log_bloom(bloom));
+	}
+	fn log_bloom(&self) -> Option<LogBloom> {
+		self.logs.iter().filter_map(traits::DigestItem::as_log_bloom).next()
+	}
 }
 
 /// Abstraction over a block header for a substrate chain.
@@ -423,3 +537,73 @@ where
 		(self.header, self.extrinsics)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use traits::{Checkable, ChainContext, Verify};
+
+	#[derive(Default, PartialEq, Eq, Clone, Debug, Serialize)]
+	struct TestSignature(u64, Vec<u8>);
+
+	impl Verify for TestSignature {
+		type Signer = u64;
+		fn verify(&self, msg: &[u8], signer: &u64) -> bool {
+			self.0 == *signer && self.1 == msg
+		}
+	}
+
+	// a chain whose block `n` has hash `100 + n`.
+	struct TestContext {
+		height: u64,
+	}
+
+	impl ChainContext for TestContext {
+		type Hash = u64;
+
+		fn current_height(&self) -> u64 {
+			self.height
+		}
+
+		fn block_hash(&self, number: u64) -> Option<u64> {
+			if number < self.height { Some(100 + number) } else { None }
+		}
+	}
+
+	type Ex = UncheckedExtrinsic<u64, u64, u64, TestSignature>;
+
+	fn signed(era: Era, checkpoint_hash: u64, genesis_hash: u64) -> Ex {
+		let extrinsic = Extrinsic { signed: 1, index: 0, function: 42 };
+		let payload = extrinsic.signing_payload(&era, &checkpoint_hash, &genesis_hash);
+		UncheckedExtrinsic { extrinsic, era, signature: TestSignature(1, payload) }
+	}
+
+	#[test]
+	fn mortal_extrinsic_checks_within_its_era() {
+		let context = TestContext { height: 5 };
+		assert!(signed(Era::Mortal(2, 4), 102, 100).check(&context).is_ok());
+		assert!(signed(Era::Immortal, 100, 100).check(&context).is_ok());
+	}
+
+	#[test]
+	fn expired_mortal_extrinsic_is_rejected() {
+		let context = TestContext { height: 7 };
+		assert!(signed(Era::Mortal(2, 4), 102, 100).check(&context).is_err());
+		assert!(signed(Era::Mortal(6, 4), 106, 100).check(&TestContext { height: 6 }).is_err());
+
+		assert!(signed(Era::Mortal(2, 4), 102, 100).is_expired(&context));
+		assert!(signed(Era::Mortal(6, 4), 106, 100).is_expired(&TestContext { height: 6 }));
+		assert!(!signed(Era::Mortal(2, 4), 102, 100).is_expired(&TestContext { height: 5 }));
+	}
+
+	#[test]
+	fn extrinsic_signed_for_other_chain_is_rejected() {
+		let context = TestContext { height: 5 };
+		assert!(signed(Era::Mortal(2, 4), 103, 100).check(&context).is_err());
+		assert!(signed(Era::Mortal(2, 4), 102, 101).check(&context).is_err());
+		assert!(signed(Era::Immortal, 100, 101).check(&context).is_err());
+
+		// a bad signature rather than an expired extrinsic.
+		assert!(!signed(Era::Immortal, 100, 101).is_expired(&context));
+	}
+}
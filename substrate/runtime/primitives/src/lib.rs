@@ -44,6 +44,7 @@ pub mod testing;
 
 pub mod traits;
 pub mod generic;
+pub mod bloom;
 
 #[cfg(feature = "std")]
 pub type BuiltExternalities = HashMap<Vec<u8>, Vec<u8>>;
@@ -72,6 +73,71 @@ impl From<H512> for Ed25519Signature {
 		Ed25519Signature(h)
 	}
 }
+impl From<Ed25519Signature> for H512 {
+	fn from(s: Ed25519Signature) -> H512 {
+		s.0
+	}
+}
+
+/// The outcome of applying an extrinsic which was valid for inclusion in the block.
+#[derive(Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize))]
+#[repr(u8)]
+pub enum ApplyOutcome {
+	/// Successful application; the extrinsic's dispatch returned no error.
+	Success = 0,
+	/// The extrinsic's dispatch failed. Fees were paid and the index incremented regardless.
+	Fail = 1,
+}
+
+impl codec::Slicable for ApplyOutcome {
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			x if x == ApplyOutcome::Success as u8 => Some(ApplyOutcome::Success),
+			x if x == ApplyOutcome::Fail as u8 => Some(ApplyOutcome::Fail),
+			_ => None,
+		}
+	}
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[*self as u8])
+	}
+}
+
+/// Reason why an extrinsic could not be applied and so cannot be included in the block.
+#[derive(Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize))]
+#[repr(u8)]
+pub enum ApplyError {
+	/// The signature of the extrinsic is bad.
+	BadSignature = 0,
+	/// The index of the extrinsic is lower than that of its sender's account.
+	Stale = 1,
+	/// The index of the extrinsic is higher than that of its sender's account.
+	Future = 2,
+	/// The sender cannot pay the fees of the extrinsic.
+	CantPay = 3,
+	/// The extrinsic is outside of its era, or its checkpoint is not a block of this chain.
+	Expired = 4,
+}
+
+impl codec::Slicable for ApplyError {
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			x if x == ApplyError::BadSignature as u8 => Some(ApplyError::BadSignature),
+			x if x == ApplyError::Stale as u8 => Some(ApplyError::Stale),
+			x if x == ApplyError::Future as u8 => Some(ApplyError::Future),
+			x if x == ApplyError::CantPay as u8 => Some(ApplyError::CantPay),
+			x if x == ApplyError::Expired as u8 => Some(ApplyError::Expired),
+			_ => None,
+		}
+	}
+	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
+		f(&[*self as u8])
+	}
+}
+
+/// Result of applying an extrinsic.
+pub type ApplyResult = Result<ApplyOutcome, ApplyError>;
 
 #[macro_export]
 macro_rules! __impl_outer_config_types {
@@ -21,24 +21,74 @@ use codec::{Slicable, Input};
 use runtime_support::AuxDispatchable;
 use substrate_primitives::H256;
 use traits::{self, Checkable, Applyable};
+use bloom::LogBloom;
+
+#[derive(PartialEq, Eq, Clone, Serialize, Debug)]
+pub enum DigestItem {
+	Log(u64),
+	LogBloom(LogBloom),
+}
+impl Slicable for DigestItem {
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		match input.read_byte()? {
+			0 => Some(DigestItem::Log(Slicable::decode(input)?)),
+			1 => Some(DigestItem::LogBloom(Slicable::decode(input)?)),
+			_ => None,
+		}
+	}
+
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		match *self {
+			DigestItem::Log(ref log) => {
+				v.push(0);
+				log.using_encoded(|s| v.extend(s));
+			}
+			DigestItem::LogBloom(ref bloom) => {
+				v.push(1);
+				bloom.using_encoded(|s| v.extend(s));
+			}
+		}
+
+		v
+	}
+}
+impl traits::DigestItem for DigestItem {
+	fn from_log_bloom(bloom: LogBloom) -> Self {
+		DigestItem::LogBloom(bloom)
+	}
+	fn as_log_bloom(&self) -> Option<LogBloom> {
+		match *self {
+			DigestItem::LogBloom(ref bloom) => Some(bloom.clone()),
+			DigestItem::Log(_) => None,
+		}
+	}
+}
 
 #[derive(Default, PartialEq, Eq, Clone, Serialize, Debug)]
 pub struct Digest {
-	pub logs: Vec<u64>,
+	pub logs: Vec<DigestItem>,
 }
 impl Slicable for Digest {
 	fn decode<I: Input>(input: &mut I) -> Option<Self> {
-		Vec::<u64>::decode(input).map(|logs| Digest { logs })
+		Vec::<DigestItem>::decode(input).map(|logs| Digest { logs })
 	}
 	fn using_encoded<R, F: FnOnce(&[u8]) -> R>(&self, f: F) -> R {
 		self.logs.using_encoded(f)
 	}
 }
 impl traits::Digest for Digest {
-	type Item = u64;
+	type Item = DigestItem;
 	fn push(&mut self, item: Self::Item) {
 		self.logs.push(item);
 	}
+	fn push_log_bloom(&mut self, bloom: LogBloom) {
+		self.logs.push(DigestItem::LogBloom(bloom));
+	}
+	fn log_bloom(&self) -> Option<LogBloom> {
+		self.logs.iter().filter_map(traits::DigestItem::as_log_bloom).next()
+	}
 }
 
 #[derive(PartialEq, Eq, Clone, Serialize, Debug)]
@@ -137,14 +187,14 @@ impl<Call: AuxDispatchable + Slicable + Sized + serde::Serialize> Slicable for T
 		self.0.encode()
 	}
 }
-impl<Call: AuxDispatchable + Slicable + Sized + serde::Serialize> Checkable for TestXt<Call> {
+impl<Call: AuxDispatchable + Slicable + Sized + serde::Serialize, Context: ?Sized> Checkable<Context> for TestXt<Call> {
 	type Checked = Self;
-	fn check(self) -> Result<Self, Self> { Ok(self) }
+	fn check(self, _context: &Context) -> Result<Self, Self> { Ok(self) }
 }
 impl<Call: AuxDispatchable<Aux = u64> + Slicable + Sized + serde::Serialize> Applyable for TestXt<Call> {
 	type AccountId = u64;
 	type Index = u64;
 	fn sender(&self) -> &u64 { &(self.0).0 }
 	fn index(&self) -> &u64 { &(self.0).1 }
-	fn apply(self) { (self.0).2.dispatch(&(self.0).0); }
+	fn apply(self) -> Result<(), &'static str> { (self.0).2.dispatch(&(self.0).0) }
 }
@@ -21,8 +21,10 @@ use rstd;
 #[cfg(not(feature = "std"))] use runtime_io;
 use substrate_primitives;
 use codec::Slicable;
+use bloom::LogBloom;
 pub use integer_sqrt::IntegerSquareRoot;
 pub use num_traits::{Zero, One, Bounded};
+pub use num_traits::ops::checked::{CheckedAdd, CheckedSub, CheckedMul, CheckedDiv};
 use rstd::ops::{Add, Sub, Mul, Div, Rem, AddAssign, SubAssign, MulAssign, DivAssign, RemAssign};
 
 /// Means of signature verification.
@@ -104,21 +106,23 @@ impl<T> RefInto<T> for T {
 }
 
 pub trait SimpleArithmetic:
-	Zero + One + IntegerSquareRoot + As<usize> +
+	Zero + One + IntegerSquareRoot + As<u64> +
 	Add<Self, Output = Self> + AddAssign<Self> +
 	Sub<Self, Output = Self> + SubAssign<Self> +
 	Mul<Self, Output = Self> + MulAssign<Self> +
 	Div<Self, Output = Self> + DivAssign<Self> +
 	Rem<Self, Output = Self> + RemAssign<Self> +
+	CheckedAdd + CheckedSub + CheckedMul + CheckedDiv +
 	PartialOrd<Self> + Ord
 {}
 impl<T:
-	Zero + One + IntegerSquareRoot + As<usize> +
+	Zero + One + IntegerSquareRoot + As<u64> +
 	Add<Self, Output = Self> + AddAssign<Self> +
 	Sub<Self, Output = Self> + SubAssign<Self> +
 	Mul<Self, Output = Self> + MulAssign<Self> +
 	Div<Self, Output = Self> + DivAssign<Self> +
 	Rem<Self, Output = Self> + RemAssign<Self> +
+	CheckedAdd + CheckedSub + CheckedMul + CheckedDiv +
 	PartialOrd<Self> + Ord
 > SimpleArithmetic for T {}
 
@@ -149,10 +153,19 @@ impl<A: Executable, B: Executable> Executable for (A, B) {
 }
 
 /// Something that acts like a `Digest` - it can have `Log`s `push`ed onto it and these `Log`s are
-/// each `Slicable`.
+/// each `Slicable`. A `LogBloom` of the block's contract logs can be pushed as well.
 pub trait Digest {
 	type Item: Sized;
 	fn push(&mut self, item: Self::Item);
+	fn push_log_bloom(&mut self, bloom: LogBloom);
+	/// The `LogBloom` pushed onto this digest, if any.
+	fn log_bloom(&self) -> Option<LogBloom>;
+}
+
+/// A `Digest` item which can carry a `LogBloom`, distinguishable from the item's other uses.
+pub trait DigestItem: Sized {
+	fn from_log_bloom(bloom: LogBloom) -> Self;
+	fn as_log_bloom(&self) -> Option<LogBloom>;
 }
 
 impl Digest for substrate_primitives::Digest {
@@ -160,6 +173,12 @@ impl Digest for substrate_primitives::Digest {
 	fn push(&mut self, item: Self::Item) {
 		self.logs.push(item);
 	}
+	fn push_log_bloom(&mut self, bloom: LogBloom) {
+		self.logs.push(DigestItem::from_log_bloom(bloom));
+	}
+	fn log_bloom(&self) -> Option<LogBloom> {
+		self.logs.iter().filter_map(DigestItem::as_log_bloom).next()
+	}
 }
 
 /// Something which fulfills the abstract idea of a Substrate header. It has types for a `Number`,
@@ -239,9 +258,28 @@ impl Block for substrate_primitives::Block {
 
 /// A "checkable" piece of information, used by the standard Substrate Executive in order to
 /// check the validity of a piece of extrinsic information, usually by verifying the signature.
-pub trait Checkable: Sized {
+/// The `Context` gives access to any chain information needed for the check.
+pub trait Checkable<Context: ?Sized>: Sized {
 	type Checked: Sized;
-	fn check(self) -> Result<Self::Checked, Self>;
+	fn check(self, context: &Context) -> Result<Self::Checked, Self>;
+
+	/// Whether the information is outside of its period of validity in `context`, in which case
+	/// it fails `check` however it is signed.
+	fn is_expired(&self, _context: &Context) -> bool {
+		false
+	}
+}
+
+/// Information about the chain on which an extrinsic is being checked, used to determine
+/// whether it is still within its era of validity.
+pub trait ChainContext {
+	type Hash;
+
+	/// The number of the block in which the extrinsic would be included.
+	fn current_height(&self) -> u64;
+
+	/// The hash of the ancestor block with the given number, if there is one.
+	fn block_hash(&self, number: u64) -> Option<Self::Hash>;
 }
 
 /// An "executable" piece of information, used by the standard Substrate Executive in order to
@@ -255,7 +293,7 @@ pub trait Applyable {
 	type Index;
 	fn index(&self) -> &Self::Index;
 	fn sender(&self) -> &Self::AccountId;
-	fn apply(self);
+	fn apply(self) -> Result<(), &'static str>;
 }
 
 /// Something that can be checked for equality and printed out to a debug channel if bad.
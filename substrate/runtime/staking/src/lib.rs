@@ -38,6 +38,7 @@ extern crate substrate_runtime_primitives as primitives;
 extern crate substrate_runtime_consensus as consensus;
 extern crate substrate_runtime_sandbox as sandbox;
 extern crate substrate_runtime_session as session;
+#[macro_use]
 extern crate substrate_runtime_system as system;
 
 #[cfg(test)] use std::fmt::Debug;
@@ -45,7 +46,7 @@ use rstd::prelude::*;
 use rstd::{cmp, result};
 use rstd::cell::RefCell;
 use rstd::collections::btree_map::{BTreeMap, Entry};
-use codec::Slicable;
+use codec::{Slicable, Input};
 use runtime_support::{StorageValue, StorageMap, Parameter};
 use runtime_support::dispatch::Result;
 use primitives::traits::{Zero, One, Bounded, RefInto, SimpleArithmetic, Executable, MakePayment, As};
@@ -66,8 +67,127 @@ pub enum LockStatus<BlockNumber: PartialEq + Clone> {
 	Staked,
 }
 
+/// The maximum number of validators a single account may nominate.
+pub const MAX_NOMINATIONS: usize = 16;
+
+/// The number of eras for which the reward history is kept.
+pub const REWARD_HISTORY_DEPTH: usize = 28;
+
+/// The maximum depth of nested contract calls; the call made by a transaction has depth 0.
+pub const MAX_CALL_DEPTH: u32 = 32;
+
+/// The stake backing an elected validator for the current era.
+#[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Exposure<AccountId, Balance> {
+	/// The validator's own stake.
+	pub own: Balance,
+	/// The portion of each nominator's stake which backs the validator.
+	pub others: Vec<(AccountId, Balance)>,
+}
+
+impl<AccountId, Balance: SimpleArithmetic + Copy> Exposure<AccountId, Balance> {
+	/// The total stake backing the validator.
+	pub fn total(&self) -> Balance {
+		self.others.iter().fold(self.own, |acc, &(_, stake)| acc + stake)
+	}
+}
+
+/// `value * part / total` for `part <= total`, without overflowing. Should the product not fit,
+/// `value` is divided first, rounding the result down.
+fn proportion<Balance: SimpleArithmetic + Copy>(value: Balance, part: Balance, total: Balance) -> Balance {
+	match value.checked_mul(&part) {
+		Some(product) => product / total,
+		None => value / total * part,
+	}
+}
+
+impl<AccountId: Slicable, Balance: Slicable> Slicable for Exposure<AccountId, Balance> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.own.using_encoded(|s| v.extend(s));
+		self.others.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(Exposure {
+			own: Slicable::decode(input)?,
+			others: Slicable::decode(input)?,
+		})
+	}
+}
+
+/// A chunk of bonded stake which is being unbonded and may be withdrawn once its era has begun.
+#[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UnlockChunk<Balance, BlockNumber> {
+	/// The amount of stake being unbonded.
+	pub value: Balance,
+	/// The era at which the stake may be withdrawn.
+	pub era: BlockNumber,
+}
+
+impl<Balance: Slicable, BlockNumber: Slicable> Slicable for UnlockChunk<Balance, BlockNumber> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.value.using_encoded(|s| v.extend(s));
+		self.era.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(UnlockChunk {
+			value: Slicable::decode(input)?,
+			era: Slicable::decode(input)?,
+		})
+	}
+}
+
+/// The stake bonded by a stash account, as managed by its controller.
+#[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StakingLedger<AccountId, Balance, BlockNumber> {
+	/// The stash account whose balance is bonded.
+	pub stash: AccountId,
+	/// The total amount of the stash's balance which is bonded, including that being unbonded.
+	pub total: Balance,
+	/// The amount of the stash's balance which counts towards staking.
+	pub active: Balance,
+	/// The stake which is being unbonded.
+	pub unlocking: Vec<UnlockChunk<Balance, BlockNumber>>,
+}
+
+impl<AccountId: Slicable, Balance: Slicable, BlockNumber: Slicable> Slicable for StakingLedger<AccountId, Balance, BlockNumber> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		self.stash.using_encoded(|s| v.extend(s));
+		self.total.using_encoded(|s| v.extend(s));
+		self.active.using_encoded(|s| v.extend(s));
+		self.unlocking.using_encoded(|s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: Input>(input: &mut I) -> Option<Self> {
+		Some(StakingLedger {
+			stash: Slicable::decode(input)?,
+			total: Slicable::decode(input)?,
+			active: Slicable::decode(input)?,
+			unlocking: Slicable::decode(input)?,
+		})
+	}
+}
+
 pub trait ContractAddressFor<AccountId: Sized> {
-	fn contract_address_for(code: &[u8], origin: &AccountId) -> AccountId;
+	/// The address of the contract with `code` created by `origin`, which may vary `salt` to
+	/// create several contracts with the same code.
+	fn contract_address_for(code: &[u8], salt: &[u8], origin: &AccountId) -> AccountId;
 }
 
 impl<Hashing, AccountId> ContractAddressFor<AccountId> for Hashing where
@@ -75,25 +195,59 @@ impl<Hashing, AccountId> ContractAddressFor<AccountId> for Hashing where
 	AccountId: Sized + Slicable + From<Hashing::Output>,
 	Hashing::Output: Slicable
 {
-	fn contract_address_for(code: &[u8], origin: &AccountId) -> AccountId {
+	fn contract_address_for(code: &[u8], salt: &[u8], origin: &AccountId) -> AccountId {
 		let mut dest_pre = Hashing::hash(code).encode();
+		dest_pre.extend(Hashing::hash(salt).encode());
 		origin.using_encoded(|s| dest_pre.extend(s));
 		AccountId::from(Hashing::hash(&dest_pre))
 	}
 }
 
+/// Something which can be told about accounts which have been reaped.
+pub trait OnAccountKill<AccountId> {
+	/// The account `who` has been reaped, its total balance having fallen below the existential
+	/// deposit.
+	fn on_account_kill(who: &AccountId);
+}
+
+impl<AccountId> OnAccountKill<AccountId> for () {
+	fn on_account_kill(_who: &AccountId) {}
+}
+
 pub trait Trait: system::Trait + session::Trait {
 	/// The balance of an account.
-	type Balance: Parameter + SimpleArithmetic + Slicable + Default + Copy;
+	type Balance: Parameter + SimpleArithmetic + Slicable + Default + Copy + Bounded;
 	type DetermineContractAddress: ContractAddressFor<Self::AccountId>;
+	/// Handler for when an account has been reaped.
+	type OnAccountKill: OnAccountKill<Self::AccountId>;
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+}
+
+decl_event! {
+	/// An event in this module.
+	pub enum RawEvent<AccountId, Balance> {
+		/// A transfer of `value` was made from `from` to `to`.
+		Transfer { from: AccountId, to: AccountId, value: Balance } = 0,
+	}
 }
 
+/// The event type of this module, specialised to a runtime.
+pub type Event<T> = RawEvent<<T as system::Trait>::AccountId, <T as Trait>::Balance>;
+
 decl_module! {
 	pub struct Module<T: Trait>;
 	pub enum Call where aux: T::PublicAux {
-		fn transfer(aux, dest: T::AccountId, value: T::Balance) -> Result = 0;
+		fn transfer(aux, dest: T::AccountId, value: T::Balance, gas_limit: u64, gas_price: T::Balance, data: Vec<u8>) -> Result = 0;
 		fn stake(aux) -> Result = 1;
 		fn unstake(aux) -> Result = 2;
+		fn nominate(aux, targets: Vec<T::AccountId>) -> Result = 3;
+		fn unnominate(aux) -> Result = 4;
+		fn bond(aux, controller: T::AccountId, value: T::Balance) -> Result = 5;
+		fn bond_extra(aux, value: T::Balance) -> Result = 6;
+		fn unbond(aux, value: T::Balance) -> Result = 7;
+		fn withdraw_unbonded(aux) -> Result = 8;
+		fn create(aux, code: Vec<u8>, value: T::Balance, gas_limit: u64, gas_price: T::Balance, salt: Vec<u8>, data: Vec<u8>) -> Result = 9;
 	}
 	pub enum PrivCall {
 		fn set_sessions_per_era(new: T::BlockNumber) -> Result = 0;
@@ -112,21 +266,52 @@ decl_storage! {
 	pub ValidatorCount get(validator_count): b"sta:vac" => required u32;
 	// The length of a staking era in sessions.
 	pub SessionsPerEra get(sessions_per_era): b"sta:spe" => required T::BlockNumber;
-	// The total amount of stake on the system.
+	// The total amount of stake on the system: the sum of all free and reserved balances.
 	pub TotalStake get(total_stake): b"sta:tot" => required T::Balance;
+	// The minimum total balance an account may have; accounts falling below it are reaped.
+	pub ExistentialDeposit get(existential_deposit): b"sta:exd" => default T::Balance;
+	// The fee to be paid for creating an account.
+	pub CreationFee get(creation_fee): b"sta:crf" => default T::Balance;
 	// The fee to be paid for making a transaction; the base.
 	pub TransactionBaseFee get(transaction_base_fee): b"sta:basefee" => required T::Balance;
 	// The fee to be paid for making a transaction; the per-byte portion.
 	pub TransactionByteFee get(transaction_byte_fee): b"sta:bytefee" => required T::Balance;
+	// The amount slashed from a validator whose misbehaviour has been proven.
+	pub MisbehaviorSlash get(misbehavior_slash): b"sta:mss" => default T::Balance;
+	// The portion of a misbehaviour slash which is paid to the reporter.
+	pub ReporterReward get(reporter_reward): b"sta:rrw" => default T::Balance;
+	// The reward minted for the validator set at the end of every session.
+	pub SessionReward get(session_reward): b"sta:ssr" => default T::Balance;
+	// The amount slashed from a validator which has been reported offline too many times.
+	pub OfflineSlash get(offline_slash): b"sta:oss" => default T::Balance;
+	// The number of times a validator may be reported offline in an era before being slashed.
+	pub OfflineSlashGrace get(offline_slash_grace): b"sta:osg" => default u32;
 
 	// The current era index.
 	pub CurrentEra get(current_era): b"sta:era" => required T::BlockNumber;
 	// All the accounts with a desire to stake.
 	pub Intentions: b"sta:wil:" => default Vec<T::AccountId>;
+	// The validators each nominator has chosen to back.
+	pub Nominating get(nominating): b"sta:nom:" => default map [ T::AccountId => Vec<T::AccountId> ];
+	// The nominators backing each account.
+	pub NominatorsFor get(nominators_for): b"sta:nof:" => default map [ T::AccountId => Vec<T::AccountId> ];
+	// The stake backing each validator elected for the current era.
+	pub CurrentExposure get(current_exposure): b"sta:exp:" => map [ T::AccountId => Exposure<T::AccountId, T::Balance> ];
 	// The next value of sessions per era.
 	pub NextSessionsPerEra get(next_sessions_per_era): b"sta:nse" => T::BlockNumber;
 	// The block number at which the era length last changed.
 	pub LastEraLengthChange get(last_era_length_change): b"sta:lec" => default T::BlockNumber;
+	// The session rewards and transaction fees accrued during the current era, paid out to
+	// the validators at its end.
+	pub CurrentEraReward get(current_era_reward): b"sta:cer" => default T::Balance;
+	// The reward points earned by each validator during the current era.
+	pub CurrentEraPoints get(current_era_points): b"sta:cep:" => default map [ T::AccountId => u32 ];
+	// The rewards paid to each validator at the end of a given era.
+	pub RewardHistory get(reward_history): b"sta:rwh:" => default map [ T::BlockNumber => Vec<(T::AccountId, T::Balance)> ];
+	// The number of times each validator has been reported offline during the current era.
+	pub OfflineStrikes get(offline_strikes): b"sta:ofs:" => default map [ T::AccountId => u32 ];
+	// Whether a new era should be started at the end of the current session.
+	pub ForcingNewEra get(forcing_new_era): b"sta:fne" => default bool;
 
 	// The balance of a given account.
 	pub FreeBalance get(free_balance): b"sta:bal:" => default map [ T::AccountId => T::Balance ];
@@ -135,14 +320,25 @@ decl_storage! {
 	// slashed, but gets slashed last of all.
 	pub ReservedBalance get(reserved_balance): b"sta:lbo:" => default map [ T::AccountId => T::Balance ];
 
+	// The controller account of each bonded stash account.
+	pub Bonded get(bonded): b"sta:bnd:" => map [ T::AccountId => T::AccountId ];
+	// The staking ledger of each controller account.
+	pub Ledger get(ledger): b"sta:ldg:" => map [ T::AccountId => StakingLedger<T::AccountId, T::Balance, T::BlockNumber> ];
+
 	// The block at which the `who`'s funds become entirely liquid.
 	pub Bondage get(bondage): b"sta:bon:" => default map [ T::AccountId => T::BlockNumber ];
+	// The furthest block until which `who`'s funds were locked through `extend_bondage`, kept
+	// while staking so the lock outlives the stake.
+	pub ExtendedBondage get(extended_bondage): b"sta:ebo:" => default map [ T::AccountId => T::BlockNumber ];
 
 	// The code associated with an account.
 	pub CodeOf: b"sta:cod:" => default map [ T::AccountId => Vec<u8> ];	// TODO Vec<u8> values should be optimised to not do a length prefix.
 
 	// The storage items associated with an account/key.
 	pub StorageOf: b"sta:sto:" => map [ (T::AccountId, Vec<u8>) => Vec<u8> ];	// TODO: keys should also be able to take AsRef<KeyType> to ensure Vec<u8>s can be passed as &[u8]
+
+	// The keys of the storage items associated with an account, so they can be removed when it is reaped.
+	pub StorageKeysOf: b"sta:stk:" => default map [ T::AccountId => Vec<Vec<u8>> ];
 }
 
 impl<T: Trait> Module<T> {
@@ -184,37 +380,124 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
-	/// Create a smart-contract account.
-	pub fn create(aux: &T::PublicAux, code: &[u8], value: T::Balance) -> Result {
-		// commit anything that made it this far to storage
-		if let Some(commit) = Self::effect_create(aux.ref_into(), code, value, &DirectAccountDb)? {
-			<AccountDb<T>>::merge(&mut DirectAccountDb, commit);
+	/// The stake backing `who` for the current era. Validators which were not elected through
+	/// staking are backed only by their own stake.
+	pub fn exposure_of(who: &T::AccountId) -> Exposure<T::AccountId, T::Balance> {
+		Self::current_exposure(who).unwrap_or_else(|| Exposure {
+			own: Self::stake_of(who),
+			others: Vec::new(),
+		})
+	}
+
+	/// The account on whose behalf `who` acts: the stash if `who` is a controller, otherwise
+	/// `who` itself.
+	pub fn stash_of(who: &T::AccountId) -> T::AccountId {
+		Self::ledger(who).map(|l| l.stash).unwrap_or_else(|| who.clone())
+	}
+
+	/// The stake `who` puts forward: the active bonded stake if it is a bonded stash, otherwise
+	/// its entire balance.
+	pub fn stake_of(who: &T::AccountId) -> T::Balance {
+		match Self::bonded(who) {
+			// slashes come out of the reserved balance, which may since have fallen below the ledger.
+			Some(controller) => Self::ledger(&controller)
+				.map_or_else(Zero::zero, |l| cmp::min(l.active, Self::reserved_balance(who))),
+			None => Self::balance(who),
+		}
+	}
+
+	/// The data which the code of `dest` would return if called by `transactor` with `data`,
+	/// `value` and at most `gas_limit` gas. Nothing is changed; the call is only simulated.
+	pub fn call_contract(
+		transactor: &T::AccountId,
+		dest: &T::AccountId,
+		value: T::Balance,
+		gas_limit: u64,
+		data: &[u8]
+	) -> result::Result<Vec<u8>, &'static str> {
+		let outcome = Self::effect_transfer(transactor, dest, value, gas_limit, data, 0, &DirectAccountDb)?;
+		match outcome.state {
+			Some(_) => Ok(outcome.output_data),
+			None => Err("contract execution failed"),
 		}
-		Ok(())
 	}
 
 	// PUBLIC DISPATCH
 
-	/// Transfer some unlocked staking balance to another staker.
-	/// TODO: probably want to state gas-limit and gas-price.
-	fn transfer(aux: &T::PublicAux, dest: T::AccountId, value: T::Balance) -> Result {
-		// commit anything that made it this far to storage
-		if let Some(commit) = Self::effect_transfer(aux.ref_into(), &dest, value, &DirectAccountDb)? {
-			<AccountDb<T>>::merge(&mut DirectAccountDb, commit);
-		}
+	/// Transfer some unlocked staking balance to another staker, calling the code of `dest`, if
+	/// any, with `data` and at most `gas_limit` gas paid for at `gas_price` each.
+	fn transfer(
+		aux: &T::PublicAux,
+		dest: T::AccountId,
+		value: T::Balance,
+		gas_limit: u64,
+		gas_price: T::Balance,
+		data: Vec<u8>
+	) -> Result {
+		let transactor = aux.ref_into();
+		Self::reserve_gas(transactor, gas_limit, gas_price)?;
+		let result = Self::effect_transfer(transactor, &dest, value, gas_limit, &data, 0, &DirectAccountDb)
+			.map(|outcome| {
+				// commit anything that made it this far to storage
+				let committed = outcome.state.is_some();
+				if let Some(commit) = outcome.state {
+					<AccountDb<T>>::merge(&mut DirectAccountDb, commit);
+				}
+				(outcome.gas_used, committed)
+			});
+		Self::settle_gas(transactor, gas_limit, result.as_ref().map_or(0, |r| r.0), gas_price);
+		let (_, committed) = result?;
+		// the gas is paid for either way, but nothing else happened if the code failed.
+		ensure!(committed, "contract execution failed");
+
+		Self::deposit_event(RawEvent::Transfer { from: transactor.clone(), to: dest, value });
+		Ok(())
+	}
+
+	/// Create a smart-contract account with the given `code`, endowed with `value`, at the
+	/// address derived from `salt`. Its constructor, if any, is run with `data` and at most
+	/// `gas_limit` gas paid for at `gas_price` each.
+	fn create(
+		aux: &T::PublicAux,
+		code: Vec<u8>,
+		value: T::Balance,
+		gas_limit: u64,
+		gas_price: T::Balance,
+		salt: Vec<u8>,
+		data: Vec<u8>
+	) -> Result {
+		let transactor = aux.ref_into();
+		Self::reserve_gas(transactor, gas_limit, gas_price)?;
+		let result = Self::effect_create(transactor, &code, value, gas_limit, &salt, &data, 0, &DirectAccountDb)
+			.map(|(_, outcome)| {
+				// commit anything that made it this far to storage
+				let committed = outcome.state.is_some();
+				if let Some(commit) = outcome.state {
+					<AccountDb<T>>::merge(&mut DirectAccountDb, commit);
+				}
+				(outcome.gas_used, committed)
+			});
+		Self::settle_gas(transactor, gas_limit, result.as_ref().map_or(0, |r| r.0), gas_price);
+		let (_, committed) = result?;
+		// the gas is paid for either way, but nothing else happened if the code failed.
+		ensure!(committed, "contract execution failed");
 		Ok(())
 	}
 
 	/// Declare the desire to stake for the transactor.
 	///
 	/// Effects will be felt at the beginning of the next era.
+	/// When sent from a controller, the stake is that bonded by its stash; otherwise the
+	/// transactor's whole balance is locked.
 	fn stake(aux: &T::PublicAux) -> Result {
+		let who = Self::stash_of(aux.ref_into());
 		let mut intentions = <Intentions<T>>::get();
 		// can't be in the list twice.
-		ensure!(intentions.iter().find(|&t| t == aux.ref_into()).is_none(), "Cannot stake if already staked.");
-		intentions.push(aux.ref_into().clone());
+		ensure!(intentions.iter().find(|&t| t == &who).is_none(), "Cannot stake if already staked.");
+		ensure!(Self::nominating(&who).is_empty(), "Cannot stake if already nominating.");
+		intentions.push(who.clone());
 		<Intentions<T>>::put(intentions);
-		<Bondage<T>>::insert(aux.ref_into(), T::BlockNumber::max_value());
+		Self::lock(&who);
 		Ok(())
 	}
 
@@ -222,11 +505,134 @@ impl<T: Trait> Module<T> {
 	///
 	/// Effects will be felt at the beginning of the next era.
 	fn unstake(aux: &T::PublicAux) -> Result {
+		let who = Self::stash_of(aux.ref_into());
 		let mut intentions = <Intentions<T>>::get();
-		let position = intentions.iter().position(|t| t == aux.ref_into()).ok_or("Cannot unstake if not already staked.")?;
+		let position = intentions.iter().position(|t| t == &who).ok_or("Cannot unstake if not already staked.")?;
 		intentions.swap_remove(position);
 		<Intentions<T>>::put(intentions);
-		<Bondage<T>>::insert(aux.ref_into(), Self::current_era() + Self::bonding_duration());
+		Self::unlock(&who);
+		Ok(())
+	}
+
+	/// Back the given validator candidates with the transactor's stake, replacing any previous
+	/// nominations. The stake is split evenly between the targets.
+	///
+	/// Effects will be felt at the beginning of the next era.
+	fn nominate(aux: &T::PublicAux, targets: Vec<T::AccountId>) -> Result {
+		let who = &Self::stash_of(aux.ref_into());
+		ensure!(!targets.is_empty(), "Cannot nominate without targets.");
+		ensure!(targets.len() <= MAX_NOMINATIONS, "Cannot nominate so many targets.");
+		ensure!(<Intentions<T>>::get().iter().find(|&t| t == who).is_none(), "Cannot nominate if already staked.");
+		ensure!(targets.iter().find(|&t| t == who).is_none(), "Cannot nominate self.");
+
+		let mut targets = targets;
+		targets.sort();
+		targets.dedup();
+
+		Self::remove_nominations(who);
+		for target in &targets {
+			let mut nominators = Self::nominators_for(target);
+			nominators.push(who.clone());
+			<NominatorsFor<T>>::insert(target, nominators);
+		}
+		<Nominating<T>>::insert(who, targets);
+		Self::lock(who);
+		Ok(())
+	}
+
+	/// Retract all of the transactor's nominations.
+	///
+	/// Effects will be felt at the beginning of the next era.
+	fn unnominate(aux: &T::PublicAux) -> Result {
+		let who = &Self::stash_of(aux.ref_into());
+		ensure!(!Self::nominating(who).is_empty(), "Cannot unnominate if not nominating.");
+		Self::remove_nominations(who);
+		Self::unlock(who);
+		Ok(())
+	}
+
+	/// Bond `value` of the transactor's free balance, making the transactor a stash account
+	/// whose stake is managed by `controller`.
+	fn bond(aux: &T::PublicAux, controller: T::AccountId, value: T::Balance) -> Result {
+		let stash = aux.ref_into();
+		ensure!(Self::bonded(stash).is_none(), "Stash is already bonded.");
+		ensure!(Self::ledger(&controller).is_none(), "Controller is already paired.");
+		ensure!(!value.is_zero(), "Cannot bond nothing.");
+		ensure!(
+			<Intentions<T>>::get().iter().find(|&t| t == stash).is_none() && Self::nominating(stash).is_empty(),
+			"Cannot bond while staking the whole balance."
+		);
+
+		Self::reserve_balance(stash, value)?;
+		<Bonded<T>>::insert(stash, &controller);
+		<Ledger<T>>::insert(&controller, StakingLedger {
+			stash: stash.clone(),
+			total: value,
+			active: value,
+			unlocking: Vec::new(),
+		});
+		Ok(())
+	}
+
+	/// Bond a further `value` of the transactor's free balance. The transactor must be a stash.
+	fn bond_extra(aux: &T::PublicAux, value: T::Balance) -> Result {
+		let stash = aux.ref_into();
+		let controller = Self::bonded(stash).ok_or("Not a stash.")?;
+		let mut ledger = Self::ledger(&controller).ok_or("Not a controller.")?;
+
+		Self::reserve_balance(stash, value)?;
+		ledger.total += value;
+		ledger.active += value;
+		<Ledger<T>>::insert(&controller, ledger);
+		Ok(())
+	}
+
+	/// Begin unbonding `value` of the stake managed by the transactor, which must be a
+	/// controller. It may be withdrawn after the bonding duration has passed.
+	///
+	/// Effects on the stake backing validators will be felt at the beginning of the next era.
+	fn unbond(aux: &T::PublicAux, value: T::Balance) -> Result {
+		let controller = aux.ref_into();
+		let mut ledger = Self::ledger(controller).ok_or("Not a controller.")?;
+		ensure!(!value.is_zero(), "Cannot unbond nothing.");
+		ensure!(value <= ledger.active, "Cannot unbond more than is bonded.");
+
+		ledger.active -= value;
+		ledger.unlocking.push(UnlockChunk {
+			value,
+			era: Self::current_era() + Self::bonding_duration(),
+		});
+		<Ledger<T>>::insert(controller, ledger);
+		Ok(())
+	}
+
+	/// Return all unbonded stake whose bonding duration has passed to the free balance of the
+	/// stash managed by the transactor, which must be a controller. Once nothing is left bonded,
+	/// the stash and controller are unpaired.
+	fn withdraw_unbonded(aux: &T::PublicAux) -> Result {
+		let controller = aux.ref_into();
+		let mut ledger = Self::ledger(controller).ok_or("Not a controller.")?;
+
+		let current_era = Self::current_era();
+		let mut withdrawn: T::Balance = Zero::zero();
+		ledger.unlocking.retain(|chunk| if chunk.era > current_era {
+			true
+		} else {
+			withdrawn += chunk.value;
+			false
+		});
+		ensure!(!withdrawn.is_zero(), "Nothing to withdraw.");
+
+		// only the stake tracked by the ledger is released, never reservations of other modules.
+		let withdrawn = cmp::min(withdrawn, ledger.total);
+		Self::unreserve_balance(&ledger.stash, withdrawn);
+		ledger.total -= withdrawn;
+		if ledger.total.is_zero() {
+			<Bonded<T>>::remove(&ledger.stash);
+			<Ledger<T>>::remove(controller);
+		} else {
+			<Ledger<T>>::insert(controller, ledger);
+		}
 		Ok(())
 	}
 
@@ -264,23 +670,34 @@ impl<T: Trait> Module<T> {
 		if let LockStatus::Liquid = Self::unlock_block(who) {
 			let b = Self::free_balance(who);
 			if b >= value {
-				<FreeBalance<T>>::insert(who, b - value);
+				Self::set_free_balance(who, b - value);
 				return Ok(())
 			}
 		}
 		Err("not enough liquid funds")
 	}
 
+	/// Lock the whole balance of `who` until at least block `until`. Longer existing locks are
+	/// left in place.
+	pub fn extend_bondage(who: &T::AccountId, until: T::BlockNumber) {
+		if Self::extended_bondage(who) < until {
+			<ExtendedBondage<T>>::insert(who, until);
+		}
+		if Self::bondage(who) < until {
+			<Bondage<T>>::insert(who, until);
+		}
+	}
+
 	/// Refund some balance.
 	pub fn refund(who: &T::AccountId, value: T::Balance) {
-		<FreeBalance<T>>::insert(who, Self::free_balance(who) + value)
+		Self::set_free_balance(who, Self::free_balance(who) + value)
 	}
 
 	/// Will slash any balance, but prefer free over reserved.
 	pub fn slash(who: &T::AccountId, value: T::Balance) -> Result {
 		let free_balance = Self::free_balance(who);
 		let free_slash = cmp::min(free_balance, value);
-		<FreeBalance<T>>::insert(who, &(free_balance - free_slash));
+		Self::set_free_balance(who, free_balance - free_slash);
 		if free_slash < value {
 			Self::slash_reserved(who, value - free_slash)
 				.map_err(|_| "not enough funds")
@@ -295,8 +712,8 @@ impl<T: Trait> Module<T> {
 		if b < value {
 			return Err("not enough free funds")
 		}
-		<FreeBalance<T>>::insert(who, b - value);
-		<ReservedBalance<T>>::insert(who, Self::reserved_balance(who) + value);
+		Self::set_reserved_balance(who, Self::reserved_balance(who) + value);
+		Self::set_free_balance(who, b - value);
 		Ok(())
 	}
 
@@ -304,15 +721,16 @@ impl<T: Trait> Module<T> {
 	pub fn unreserve_balance(who: &T::AccountId, value: T::Balance) {
 		let b = Self::reserved_balance(who);
 		let value = cmp::min(b, value);
-		<ReservedBalance<T>>::insert(who, b - value);
-		<FreeBalance<T>>::insert(who, Self::free_balance(who) + value);
+		Self::set_free_balance(who, Self::free_balance(who) + value);
+		Self::set_reserved_balance(who, b - value);
 	}
 
-	/// Moves `value` from reserved balance to balance.
+	/// Slash up to `value` of the reserved balance of `who`. Bonded stake is slashed first.
 	pub fn slash_reserved(who: &T::AccountId, value: T::Balance) -> Result {
 		let b = Self::reserved_balance(who);
 		let slash = cmp::min(b, value);
-		<ReservedBalance<T>>::insert(who, b - slash);
+		Self::slash_bonded(who, slash);
+		Self::set_reserved_balance(who, b - slash);
 		if value == slash {
 			Ok(())
 		} else {
@@ -324,8 +742,8 @@ impl<T: Trait> Module<T> {
 	pub fn transfer_reserved_balance(slashed: &T::AccountId, beneficiary: &T::AccountId, value: T::Balance) -> Result {
 		let b = Self::reserved_balance(slashed);
 		let slash = cmp::min(b, value);
-		<ReservedBalance<T>>::insert(slashed, b - slash);
-		<FreeBalance<T>>::insert(beneficiary, Self::free_balance(beneficiary) + slash);
+		Self::set_reserved_balance(slashed, b - slash);
+		Self::set_free_balance(beneficiary, Self::free_balance(beneficiary) + slash);
 		if value == slash {
 			Ok(())
 		} else {
@@ -333,19 +751,282 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Lower the ledger of the stash `who` by up to `value` of its bonded stake, taking it from the
+	/// active stake first and then from the chunks unlocking last.
+	fn slash_bonded(who: &T::AccountId, value: T::Balance) {
+		let controller = match Self::bonded(who) {
+			Some(controller) => controller,
+			None => return,
+		};
+		let mut ledger = match Self::ledger(&controller) {
+			Some(ledger) => ledger,
+			None => return,
+		};
+
+		let mut remaining = cmp::min(value, ledger.total);
+		ledger.total -= remaining;
+		let from_active = cmp::min(remaining, ledger.active);
+		ledger.active -= from_active;
+		remaining -= from_active;
+		for chunk in ledger.unlocking.iter_mut().rev() {
+			let from_chunk = cmp::min(remaining, chunk.value);
+			chunk.value -= from_chunk;
+			remaining -= from_chunk;
+		}
+		ledger.unlocking.retain(|chunk| !chunk.value.is_zero());
+		<Ledger<T>>::insert(&controller, ledger);
+	}
+
+	/// Set the free balance of `who`, reaping the account if its total balance falls below the
+	/// existential deposit. Any balance minted or burned is accounted for in the total stake.
+	pub fn set_free_balance(who: &T::AccountId, balance: T::Balance) {
+		if balance + Self::reserved_balance(who) < Self::existential_deposit() {
+			Self::reap(who);
+		} else {
+			Self::adjust_total_stake(Self::free_balance(who), balance);
+			<FreeBalance<T>>::insert(who, balance);
+		}
+	}
+
+	/// Set the reserved balance of `who`, reaping the account if its total balance falls below
+	/// the existential deposit. Any balance minted or burned is accounted for in the total stake.
+	pub fn set_reserved_balance(who: &T::AccountId, balance: T::Balance) {
+		if Self::free_balance(who) + balance < Self::existential_deposit() {
+			Self::reap(who);
+		} else {
+			Self::adjust_total_stake(Self::reserved_balance(who), balance);
+			<ReservedBalance<T>>::insert(who, balance);
+		}
+	}
+
+	/// Account for a balance changing from `old` to `new` in the total stake.
+	fn adjust_total_stake(old: T::Balance, new: T::Balance) {
+		let total = Self::total_stake();
+		let total = if new >= old {
+			total.checked_add(&(new - old)).unwrap_or(total)
+		} else {
+			total.checked_sub(&(old - new)).unwrap_or_else(Zero::zero)
+		};
+		<TotalStake<T>>::put(total);
+	}
+
+	/// Slash a validator and its nominators by up to `value` in total, in proportion to the stake
+	/// each of them has backing the validator. The validator bears any rounding remainder.
+	pub fn slash_validator(who: &T::AccountId, value: T::Balance) {
+		let exposure = Self::exposure_of(who);
+		let total = exposure.total();
+		if total.is_zero() {
+			return;
+		}
+
+		let value = cmp::min(value, total);
+		let mut remaining = value;
+		for &(ref nominator, stake) in &exposure.others {
+			let share = cmp::min(proportion(value, stake, total), remaining);
+			let _ = Self::slash(nominator, share);
+			remaining -= share;
+		}
+		let _ = Self::slash(who, remaining);
+	}
+
+	/// Reward a validator and its nominators with `value` in total, in proportion to the stake
+	/// each of them has backing the validator. The validator receives any rounding remainder.
+	pub fn reward_validator(who: &T::AccountId, value: T::Balance) {
+		let exposure = Self::exposure_of(who);
+		let total = exposure.total();
+
+		let mut remaining = value;
+		if !total.is_zero() {
+			for &(ref nominator, stake) in &exposure.others {
+				let share = cmp::min(proportion(value, stake, total), remaining);
+				Self::refund(nominator, share);
+				remaining -= share;
+			}
+		}
+		Self::refund(who, remaining);
+	}
+
+	/// Punish a validator whose misbehaviour has been proven: slash them and their nominators, pay
+	/// the reporter out of the slash and remove them from the intentions for the next era.
+	fn punish_misbehavior(who: &T::AccountId, reporter: &T::AccountId) {
+		let slash = cmp::min(Self::exposure_of(who).total(), Self::misbehavior_slash());
+		Self::slash_validator(who, slash);
+		Self::refund(reporter, cmp::min(slash, Self::reporter_reward()));
+		Self::eject(who);
+	}
+
+	/// Note that the validator `who` was offline. Once it has been reported more times than the
+	/// grace allows, slash it and eject it from the validator set at the end of the session.
+	fn note_offline(who: &T::AccountId) {
+		// validators which have already been ejected or have unstaked are leaving the set anyway.
+		if !<Intentions<T>>::get().contains(who) {
+			return;
+		}
+
+		let strikes = Self::offline_strikes(who) + 1;
+		if strikes <= Self::offline_slash_grace() {
+			<OfflineStrikes<T>>::insert(who, strikes);
+			return;
+		}
+
+		let slash = cmp::min(Self::exposure_of(who).total(), Self::offline_slash());
+		Self::slash_validator(who, slash);
+		Self::eject(who);
+		<OfflineStrikes<T>>::remove(who);
+		<ForcingNewEra<T>>::put(true);
+	}
+
+	/// Remove all trace of the account `who`: its balances, transaction index, bonding and any
+	/// contract code and storage. Other modules are notified through `OnAccountKill`.
+	///
+	/// NOTE: Since the transaction index is reset, transactions previously sent by `who` could
+	/// be replayed should it be endowed again.
+	fn reap(who: &T::AccountId) {
+		Self::adjust_total_stake(Self::free_balance(who) + Self::reserved_balance(who), Zero::zero());
+		<FreeBalance<T>>::remove(who);
+		<ReservedBalance<T>>::remove(who);
+		<Bondage<T>>::remove(who);
+		<ExtendedBondage<T>>::remove(who);
+		if let Some(controller) = <Bonded<T>>::take(who) {
+			<Ledger<T>>::remove(&controller);
+		}
+		<system::AccountIndex<T>>::remove(who);
+
+		let mut intentions = <Intentions<T>>::get();
+		if let Some(position) = intentions.iter().position(|t| t == who) {
+			intentions.swap_remove(position);
+			<Intentions<T>>::put(intentions);
+		}
+		Self::remove_nominations(who);
+		for nominator in <NominatorsFor<T>>::take(who) {
+			let mut targets = Self::nominating(&nominator);
+			targets.retain(|t| t != who);
+			if targets.is_empty() {
+				<Nominating<T>>::remove(&nominator);
+				Self::unlock(&nominator);
+			} else {
+				<Nominating<T>>::insert(&nominator, targets);
+			}
+		}
+
+		<CodeOf<T>>::remove(who);
+		for key in <StorageKeysOf<T>>::take(who) {
+			<StorageOf<T>>::remove((who.clone(), key));
+		}
+
+		T::OnAccountKill::on_account_kill(who);
+	}
+
+	/// Remove `who` from the intentions and begin unbonding its stake.
+	fn eject(who: &T::AccountId) {
+		let mut intentions = <Intentions<T>>::get();
+		if let Some(position) = intentions.iter().position(|t| t == who) {
+			intentions.swap_remove(position);
+			<Intentions<T>>::put(intentions);
+		}
+		Self::unlock(who);
+	}
+
+	/// Lock the whole balance of `who` while it stakes. Bonded stashes are already locked by
+	/// their ledger.
+	fn lock(who: &T::AccountId) {
+		if Self::bonded(who).is_none() {
+			<Bondage<T>>::insert(who, T::BlockNumber::max_value());
+		}
+	}
+
+	/// Begin unlocking the whole balance of `who` once it stops staking. Bonded stashes are
+	/// unbonded through their ledger instead. Longer locks from `extend_bondage` are kept.
+	fn unlock(who: &T::AccountId) {
+		if Self::bonded(who).is_none() {
+			let until = cmp::max(Self::era_change_block(Self::bonding_duration()), Self::extended_bondage(who));
+			<Bondage<T>>::insert(who, until);
+		}
+	}
+
+	/// The block at which the era `eras` after the current one begins, assuming the era length
+	/// doesn't change in the meantime.
+	fn era_change_block(eras: T::BlockNumber) -> T::BlockNumber {
+		let last_change = Self::last_era_length_change();
+		let era_length = Self::era_length();
+		let eras_begun = (<system::Module<T>>::block_number() - last_change) / era_length;
+		last_change + era_length * (eras_begun + eras)
+	}
+
 	/// Hook to be called after to transaction processing.
 	pub fn check_new_era() {
+		let block_number = <system::Module<T>>::block_number();
+
+		// reward the validators if the session is about to end.
+		let session_length = <session::Module<T>>::length();
+		let session_ends = (block_number - <session::Module<T>>::last_length_change()) % session_length == Zero::zero();
+		if session_ends {
+			Self::reward_session();
+		}
+
+		// start a new era early if a validator was ejected; later eras are counted from here.
+		if session_ends && <ForcingNewEra<T>>::take() {
+			<LastEraLengthChange<T>>::put(&block_number);
+			Self::new_era();
+			return;
+		}
+
 		// check block number and call new_era if necessary.
-		if (<system::Module<T>>::block_number() - Self::last_era_length_change()) % Self::era_length() == Zero::zero() {
+		if (block_number - Self::last_era_length_change()) % Self::era_length() == Zero::zero() {
 			Self::new_era();
 		}
 	}
 
-	/// The era has changed - enact new staking set.
+	/// The session is ending: mint the session reward into the era's reward pot and give each
+	/// of the validators a point for their service.
+	fn reward_session() {
+		<CurrentEraReward<T>>::put(Self::current_era_reward() + Self::session_reward());
+		for v in <session::Module<T>>::validators() {
+			<CurrentEraPoints<T>>::insert(&v, Self::current_era_points(&v) + 1);
+		}
+	}
+
+	/// Pay out the era's reward pot to the validators in proportion to the points they earned,
+	/// record the payouts in the reward history and reset the points. Any remainder left by
+	/// rounding is carried over into the next era.
+	fn payout_era() {
+		let validators = <session::Module<T>>::validators();
+		let points = validators.iter()
+			.map(|v| <CurrentEraPoints<T>>::take(v))
+			.collect::<Vec<_>>();
+		let total_points = points.iter().fold(0u64, |acc, &p| acc + p as u64);
+
+		let pot = Self::current_era_reward();
+		if total_points == 0 || pot.is_zero() {
+			return;
+		}
+
+		let total_points = <T::Balance as As<u64>>::sa(total_points);
+		let mut paid = Vec::with_capacity(validators.len());
+		let mut remaining = pot;
+		for (v, p) in validators.into_iter().zip(points) {
+			let reward = proportion(pot, <T::Balance as As<u64>>::sa(p as u64), total_points);
+			Self::reward_validator(&v, reward);
+			remaining -= reward;
+			paid.push((v, reward));
+		}
+		<CurrentEraReward<T>>::put(remaining);
+
+		let era = Self::current_era();
+		<RewardHistory<T>>::insert(&era, paid);
+		let depth = <T::BlockNumber as As<u64>>::sa(REWARD_HISTORY_DEPTH as u64);
+		if era >= depth {
+			<RewardHistory<T>>::remove(&(era - depth));
+		}
+	}
+
+	/// The era has changed - pay out the rewards for the ending era and enact new staking set.
 	///
 	/// NOTE: This always happens immediately before a session change to ensure that new validators
 	/// get a chance to set their session keys.
 	fn new_era() {
+		Self::payout_era();
+
 		// Increment current era.
 		<CurrentEra<T>>::put(&(<CurrentEra<T>>::get() + One::one()));
 
@@ -357,21 +1038,60 @@ impl<T: Trait> Module<T> {
 			}
 		}
 
-		// evaluate desired staking amounts and nominations and optimise to find the best
-		// combination of validators, then use session::internal::set_validators().
-		// for now, this just orders would-be stakers by their balances and chooses the top-most
-		// <ValidatorCount<T>>::get() of them.
+		// order would-be stakers by their total backing, their own balance plus what has been
+		// nominated to them, and choose the top-most <ValidatorCount<T>>::get() of them.
 		let mut intentions = <Intentions<T>>::get()
 			.into_iter()
-			.map(|v| (Self::balance(&v), v))
+			.map(|v| {
+				let exposure = Self::compute_exposure(&v);
+				(exposure.total(), v, exposure)
+			})
 			.collect::<Vec<_>>();
-		intentions.sort_unstable_by(|&(ref b1, _), &(ref b2, _)| b2.cmp(&b1));
-		<session::Module<T>>::set_validators(
-			&intentions.into_iter()
-				.map(|(_, v)| v)
-				.take(<ValidatorCount<T>>::get() as usize)
-				.collect::<Vec<_>>()
-		);
+		intentions.sort_unstable_by(|&(ref b1, _, _), &(ref b2, _, _)| b2.cmp(&b1));
+		intentions.truncate(<ValidatorCount<T>>::get() as usize);
+
+		for v in <session::Module<T>>::validators() {
+			<CurrentExposure<T>>::remove(&v);
+			<OfflineStrikes<T>>::remove(&v);
+		}
+		let mut validators = Vec::with_capacity(intentions.len());
+		for (_, v, exposure) in intentions {
+			<CurrentExposure<T>>::insert(&v, exposure);
+			validators.push(v);
+		}
+		<session::Module<T>>::set_validators(&validators);
+	}
+
+	/// The stake which would back `who` if elected now: their own stake along with an
+	/// even split of each of their nominators' stakes.
+	fn compute_exposure(who: &T::AccountId) -> Exposure<T::AccountId, T::Balance> {
+		let others = Self::nominators_for(who)
+			.into_iter()
+			.map(|n| {
+				let targets = <T::Balance as As<u64>>::sa(Self::nominating(&n).len() as u64);
+				let stake = Self::stake_of(&n) / targets;
+				(n, stake)
+			})
+			.collect();
+
+		Exposure {
+			own: Self::stake_of(who),
+			others,
+		}
+	}
+
+	/// Remove all nominations made by `who`.
+	fn remove_nominations(who: &T::AccountId) {
+		for target in Self::nominating(who) {
+			let mut nominators = Self::nominators_for(&target);
+			nominators.retain(|n| n != who);
+			if nominators.is_empty() {
+				<NominatorsFor<T>>::remove(&target);
+			} else {
+				<NominatorsFor<T>>::insert(&target, nominators);
+			}
+		}
+		<Nominating<T>>::remove(who);
 	}
 }
 
@@ -381,6 +1101,24 @@ impl<T: Trait> Executable for Module<T> {
 	}
 }
 
+impl<T: Trait> consensus::OnMisbehavior<T::SessionKey, T::AccountId> for Module<T> {
+	fn on_misbehavior(offender: &T::SessionKey, reporter: &T::AccountId) {
+		// authorities are kept in the same order as the validators they belong to.
+		let index = <consensus::Module<T>>::authorities().iter().position(|k| k == offender);
+		if let Some(validator) = index.and_then(|i| <session::Module<T>>::validators().into_iter().nth(i)) {
+			Self::punish_misbehavior(&validator, reporter);
+		}
+	}
+}
+
+impl<T: Trait> consensus::OnOfflineValidator for Module<T> {
+	fn on_offline_validator(validator_index: usize) {
+		if let Some(validator) = <session::Module<T>>::validators().into_iter().nth(validator_index) {
+			Self::note_offline(&validator);
+		}
+	}
+}
+
 // Each identity's stake may be in one of three bondage states, given by an integer:
 // - n | n <= <CurrentEra<T>>::get(): inactive: free to be transferred.
 // - ~0: active: currently representing a validator.
@@ -410,7 +1148,37 @@ impl<T: Trait> ChangeEntry<T> {
 	}
 }
 
-type State<T> = BTreeMap<<T as system::Trait>::AccountId, ChangeEntry<T>>;
+/// A log deposited by the code of a contract.
+struct LogEntry<T: Trait> {
+	address: T::AccountId,
+	topics: Vec<T::Hash>,
+	data: Vec<u8>,
+}
+
+/// Changes made on top of an `AccountDb`, together with the logs deposited while making them.
+struct State<T: Trait> {
+	accounts: BTreeMap<T::AccountId, ChangeEntry<T>>,
+	logs: Vec<LogEntry<T>>,
+}
+
+impl<T: Trait> State<T> {
+	fn new() -> Self {
+		State {
+			accounts: BTreeMap::new(),
+			logs: Vec::new(),
+		}
+	}
+}
+
+/// The outcome of a transfer or creation which passed its checks.
+struct ExecutionOutcome<T: Trait> {
+	/// The changes to commit, unless the code of the destination failed.
+	state: Option<State<T>>,
+	/// The amount of gas used by the code of the destination.
+	gas_used: u64,
+	/// The data returned by the code of the destination.
+	output_data: Vec<u8>,
+}
 
 trait AccountDb<T: Trait> {
 	fn get_storage(&self, account: &T::AccountId, location: &[u8]) -> Option<Vec<u8>>;
@@ -420,6 +1188,7 @@ trait AccountDb<T: Trait> {
 	fn set_storage(&mut self, account: &T::AccountId, location: Vec<u8>, value: Option<Vec<u8>>);
 	fn set_code(&mut self, account: &T::AccountId, code: Vec<u8>);
 	fn set_balance(&mut self, account: &T::AccountId, balance: T::Balance);
+	fn deposit_log(&mut self, log: LogEntry<T>);
 
 	fn merge(&mut self, state: State<T>);
 }
@@ -436,34 +1205,47 @@ impl<T: Trait> AccountDb<T> for DirectAccountDb {
 		<FreeBalance<T>>::get(account)
 	}
 	fn set_storage(&mut self, account: &T::AccountId, location: Vec<u8>, value: Option<Vec<u8>>) {
+		let key = (account.clone(), location);
+		let existed = <StorageOf<T>>::exists(&key);
 		if let Some(value) = value {
-			<StorageOf<T>>::insert(&(account.clone(), location), &value);
-		} else {
-			<StorageOf<T>>::remove(&(account.clone(), location));
+			if !existed {
+				let mut keys = <StorageKeysOf<T>>::get(account);
+				keys.push(key.1.clone());
+				<StorageKeysOf<T>>::insert(account, keys);
+			}
+			<StorageOf<T>>::insert(&key, &value);
+		} else if existed {
+			let mut keys = <StorageKeysOf<T>>::get(account);
+			keys.retain(|k| k != &key.1);
+			<StorageKeysOf<T>>::insert(account, keys);
+			<StorageOf<T>>::remove(&key);
 		}
 	}
 	fn set_code(&mut self, account: &T::AccountId, code: Vec<u8>) {
 		<CodeOf<T>>::insert(account, &code);
 	}
 	fn set_balance(&mut self, account: &T::AccountId, balance: T::Balance) {
-		<FreeBalance<T>>::insert(account, balance);
+		<Module<T>>::set_free_balance(account, balance);
+	}
+	fn deposit_log(&mut self, log: LogEntry<T>) {
+		<system::Module<T>>::deposit_log_record(log.address, log.topics, log.data);
 	}
 	fn merge(&mut self, s: State<T>) {
-		for (address, changed) in s.into_iter() {
-			if let Some(balance) = changed.balance {
-				<FreeBalance<T>>::insert(&address, balance);
-			}
+		for (address, changed) in s.accounts.into_iter() {
 			if let Some(code) = changed.code {
-				<CodeOf<T>>::insert(&address, &code);
+				<Self as AccountDb<T>>::set_code(self, &address, code);
 			}
 			for (k, v) in changed.storage.into_iter() {
-				if let Some(value) = v {
-					<StorageOf<T>>::insert((address.clone(), k), &value);
-				} else {
-					<StorageOf<T>>::remove((address.clone(), k));
-				}
+				<Self as AccountDb<T>>::set_storage(self, &address, k, v);
+			}
+			// the balance goes last, as it may cause the account to be reaped.
+			if let Some(balance) = changed.balance {
+				<Self as AccountDb<T>>::set_balance(self, &address, balance);
 			}
 		}
+		for log in s.logs.into_iter() {
+			<Self as AccountDb<T>>::deposit_log(self, log);
+		}
 	}
 }
 
@@ -487,6 +1269,7 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn get_storage(&self, account: &T::AccountId, location: &[u8]) -> Option<Vec<u8>> {
 		self.local
 			.borrow()
+			.accounts
 			.get(account)
 			.and_then(|a| a.storage.get(location))
 			.cloned()
@@ -495,6 +1278,7 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn get_code(&self, account: &T::AccountId) -> Vec<u8> {
 		self.local
 			.borrow()
+			.accounts
 			.get(account)
 			.and_then(|a| a.code.clone())
 			.unwrap_or_else(|| self.underlying.get_code(account))
@@ -502,6 +1286,7 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn get_balance(&self, account: &T::AccountId) -> T::Balance {
 		self.local
 			.borrow()
+			.accounts
 			.get(account)
 			.and_then(|a| a.balance)
 			.unwrap_or_else(|| self.underlying.get_balance(account))
@@ -509,6 +1294,7 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn set_storage(&mut self, account: &T::AccountId, location: Vec<u8>, value: Option<Vec<u8>>) {
 		self.local
 			.borrow_mut()
+			.accounts
 			.entry(account.clone())
 			.or_insert(Default::default())
 			.storage
@@ -517,6 +1303,7 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn set_code(&mut self, account: &T::AccountId, code: Vec<u8>) {
 		self.local
 			.borrow_mut()
+			.accounts
 			.entry(account.clone())
 			.or_insert(Default::default())
 			.code = Some(code);
@@ -524,15 +1311,19 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 	fn set_balance(&mut self, account: &T::AccountId, balance: T::Balance) {
 		self.local
 			.borrow_mut()
+			.accounts
 			.entry(account.clone())
 			.or_insert(Default::default())
 			.balance = Some(balance);
 	}
+	fn deposit_log(&mut self, log: LogEntry<T>) {
+		self.local.borrow_mut().logs.push(log);
+	}
 	fn merge(&mut self, s: State<T>) {
 		let mut local = self.local.borrow_mut();
 
-		for (address, changed) in s.into_iter() {
-			match local.entry(address) {
+		for (address, changed) in s.accounts.into_iter() {
+			match local.accounts.entry(address) {
 				Entry::Occupied(e) => {
 					let mut value = e.into_mut();
 					if changed.balance.is_some() {
@@ -548,89 +1339,191 @@ impl<'a, T: Trait> AccountDb<T> for OverlayAccountDb<'a, T> {
 				}
 			}
 		}
+		local.logs.extend(s.logs.into_iter());
 	}
 }
 
+impl_deposit_event!(Module<T>, Event<T>);
+
 impl<T: Trait> Module<T> {
+	/// The cost of `gas` at `gas_price`, or `None` if it doesn't fit in the balance type.
+	fn gas_fee(gas: u64, gas_price: T::Balance) -> Option<T::Balance> {
+		let gas_balance = <T::Balance as As<u64>>::sa(gas);
+		if gas_balance.as_() != gas {
+			return None;
+		}
+		gas_price.checked_mul(&gas_balance)
+	}
+
+	/// Reserve the cost of `gas_limit` gas at `gas_price` from the free balance of `transactor`.
+	fn reserve_gas(transactor: &T::AccountId, gas_limit: u64, gas_price: T::Balance) -> Result {
+		let gas_fee = Self::gas_fee(gas_limit, gas_price).ok_or("gas limit too high")?;
+		if gas_fee.is_zero() {
+			return Ok(());
+		}
+		Self::reserve_balance(transactor, gas_fee)
+			.map_err(|_| "not enough free funds to pay for gas")
+	}
+
+	/// Refund the cost of the unused part of `gas_limit` to `transactor` and pay for the
+	/// `gas_used` into the reward of the current era.
+	fn settle_gas(transactor: &T::AccountId, gas_limit: u64, gas_used: u64, gas_price: T::Balance) {
+		// `reserve_gas` has checked that the fee for the whole limit fits.
+		let fees = Self::gas_fee(gas_used, gas_price)
+			.and_then(|used| Self::gas_fee(gas_limit - gas_used, gas_price).map(|unused| (used, unused)));
+		let (used_fee, unused_fee) = match fees {
+			Some(fees) => fees,
+			None => return,
+		};
+		if used_fee.is_zero() && unused_fee.is_zero() {
+			return;
+		}
+		Self::unreserve_balance(transactor, unused_fee);
+		// the fee for the used gas was reserved by `reserve_gas`; only what is left of it is paid.
+		let paid = cmp::min(Self::reserved_balance(transactor), used_fee);
+		let _ = Self::slash_reserved(transactor, paid);
+		<CurrentEraReward<T>>::put(Self::current_era_reward() + paid);
+	}
+
+	/// Create a contract with `code` on top of `account_db`, endowed with `value` by
+	/// `transactor`, running its constructor with `init_data` and at most `gas_limit` gas.
+	/// `depth` is the depth of the creation made by `transactor`.
+	///
+	/// Returns the address of the contract, which is derived from `salt`, together with the
+	/// outcome of the creation.
 	fn effect_create<DB: AccountDb<T>>(
 		transactor: &T::AccountId,
 		code: &[u8],
 		value: T::Balance,
+		gas_limit: u64,
+		salt: &[u8],
+		init_data: &[u8],
+		depth: u32,
 		account_db: &DB,
-	) -> result::Result<Option<State<T>>, &'static str> {
+	) -> result::Result<(T::AccountId, ExecutionOutcome<T>), &'static str> {
+		if depth > MAX_CALL_DEPTH {
+			return Err("reached maximum depth of contract calls");
+		}
+		let dest = T::DetermineContractAddress::contract_address_for(code, salt, transactor);
+		if &dest == transactor || !account_db.get_code(&dest).is_empty() {
+			return Err("contract address already in use");
+		}
+
+		// the address may already hold funds sent to it ahead of the creation.
 		let from_balance = account_db.get_balance(transactor);
-		// TODO: a fee.
-		if from_balance < value {
+		let to_balance = account_db.get_balance(&dest);
+		let would_create = to_balance.is_zero() && Self::reserved_balance(&dest).is_zero();
+		let fee = if would_create { Self::creation_fee() } else { Zero::zero() };
+		let liability = value + fee;
+		if from_balance < liability {
 			return Err("balance too low to send value");
 		}
-
-		let dest = T::DetermineContractAddress::contract_address_for(code, transactor);
-
-		// early-out if degenerate.
-		if &dest == transactor {
-			return Ok(None);
+		if would_create && value < Self::existential_deposit() {
+			return Err("value too low to create account");
+		}
+		if to_balance + value < to_balance {
+			return Err("destination balance too high to receive value");
 		}
 
-		let mut local = BTreeMap::new();
+		// Our local overlay: the constructor runs on top of it and its changes are only kept if
+		// the constructor succeeds.
+		let mut overlay = OverlayAccountDb::new(account_db);
+		overlay.set_balance(transactor, from_balance - liability);
+		overlay.set_balance(&dest, to_balance + value);
+		overlay.set_code(&dest, code.to_vec());
 
-		// two inserts are safe
-		// note that we now know that `&dest != transactor` due to early-out before.
-		local.insert(dest, ChangeEntry { balance: Some(value), code: Some(code.to_vec()), storage: Default::default() });
-		local.insert(transactor.clone(), ChangeEntry::balance_changed(from_balance - value));
+		let mut output_data = Vec::new();
+		let (should_commit, gas_used) = {
+			let mut staking_ext = StakingExt {
+				account_db: &mut overlay,
+				account: dest.clone(),
+				depth,
+			};
+			match contract::deploy(code, init_data, &mut output_data, &mut staking_ext, gas_limit) {
+				Ok(gas_used) => (true, gas_used),
+				Err(_) => (false, gas_limit),
+			}
+		};
 
-		Ok(Some(local))
+		Ok((dest, ExecutionOutcome {
+			state: if should_commit {
+				Some(overlay.into_state())
+			} else {
+				None
+			},
+			gas_used,
+			output_data,
+		}))
 	}
 
+	/// Transfer `value` from `transactor` to `dest` on top of `account_db`, calling the code of
+	/// `dest` with `input_data` and at most `gas_limit` gas. `depth` is the depth of the call
+	/// made by `transactor`.
 	fn effect_transfer<DB: AccountDb<T>>(
 		transactor: &T::AccountId,
 		dest: &T::AccountId,
 		value: T::Balance,
+		gas_limit: u64,
+		input_data: &[u8],
+		depth: u32,
 		account_db: &DB,
-	) -> result::Result<Option<State<T>>, &'static str> {
-		let from_balance = account_db.get_balance(transactor);
-		if from_balance < value {
-			return Err("balance too low to send value");
+	) -> result::Result<ExecutionOutcome<T>, &'static str> {
+		if depth > MAX_CALL_DEPTH {
+			return Err("reached maximum depth of contract calls");
 		}
 
+		let from_balance = account_db.get_balance(transactor);
 		let to_balance = account_db.get_balance(dest);
-		if <Bondage<T>>::get(transactor) > <Bondage<T>>::get(dest) {
+		let would_create = to_balance.is_zero() && Self::reserved_balance(dest).is_zero();
+		let fee = if would_create { Self::creation_fee() } else { Zero::zero() };
+		let liability = value + fee;
+
+		if from_balance < liability {
+			return Err("balance too low to send value");
+		}
+		if would_create && value < Self::existential_deposit() {
+			return Err("value too low to create account");
+		}
+		if !value.is_zero() && Self::unlock_block(transactor) != LockStatus::Liquid {
 			return Err("bondage too high to send value");
 		}
-		if to_balance + value <= to_balance {
+		// calls to contracts needn't carry any value.
+		if to_balance + value < to_balance {
 			return Err("destination balance too high to receive value");
 		}
 
-		// TODO: a fee, based upon gaslimit/gasprice.
-		let gas_limit = 100_000;
-
-		// TODO: consider storing upper-bound for contract's gas limit in fixed-length runtime
-		// code in contract itself and use that.
-
 		// Our local overlay: Should be used for any transfers and creates that happen internally.
 		let mut overlay = OverlayAccountDb::new(account_db);
 
 		if transactor != dest {
-			overlay.set_balance(transactor, from_balance - value);
+			overlay.set_balance(transactor, from_balance - liability);
 			overlay.set_balance(dest, to_balance + value);
 		}
 
 		let dest_code = overlay.get_code(dest);
-		let should_commit = if dest_code.is_empty() {
-			true
+		let mut output_data = Vec::new();
+		let (should_commit, gas_used) = if dest_code.is_empty() {
+			(true, 0)
 		} else {
-			// TODO: logging (logs are just appended into a notable storage-based vector and cleared every
-			// block).
 			let mut staking_ext = StakingExt {
 				account_db: &mut overlay,
 				account: dest.clone(),
+				depth,
 			};
-			contract::execute(&dest_code, &mut staking_ext, gas_limit).is_ok()
+			match contract::execute(&dest_code, input_data, &mut output_data, &mut staking_ext, gas_limit) {
+				Ok(gas_used) => (true, gas_used),
+				Err(_) => (false, gas_limit),
+			}
 		};
 
-		Ok(if should_commit {
-			Some(overlay.into_state())
-		} else {
-			None
+		Ok(ExecutionOutcome {
+			state: if should_commit {
+				Some(overlay.into_state())
+			} else {
+				None
+			},
+			gas_used,
+			output_data,
 		})
 	}
 }
@@ -638,10 +1531,13 @@ impl<T: Trait> Module<T> {
 struct StakingExt<'a, 'b: 'a, T: Trait + 'b> {
 	account_db: &'a mut OverlayAccountDb<'b, T>,
 	account: T::AccountId,
+	// the depth of the call in which the code of `account` runs.
+	depth: u32,
 }
 impl<'a, 'b: 'a, T: Trait> contract::Ext for StakingExt<'a, 'b, T> {
 	type AccountId = T::AccountId;
 	type Balance = T::Balance;
+	type Hash = T::Hash;
 
 	fn get_storage(&self, key: &[u8]) -> Option<Vec<u8>> {
 		self.account_db.get_storage(&self.account, key)
@@ -649,40 +1545,93 @@ impl<'a, 'b: 'a, T: Trait> contract::Ext for StakingExt<'a, 'b, T> {
 	fn set_storage(&mut self, key: &[u8], value: Option<Vec<u8>>) {
 		self.account_db.set_storage(&self.account, key.to_vec(), value);
 	}
-	fn create(&mut self, code: &[u8], value: Self::Balance) {
-		if let Ok(Some(commit_state)) =
-			Module::<T>::effect_create(&self.account, code, value, self.account_db)
-		{
-			self.account_db.merge(commit_state);
+	fn create(
+		&mut self,
+		code: &[u8],
+		value: Self::Balance,
+		gas_limit: u64,
+		salt: &[u8],
+		init_data: &[u8],
+	) -> contract::CreateReceipt<Self::AccountId> {
+		let result = Module::<T>::effect_create(
+			&self.account,
+			code,
+			value,
+			gas_limit,
+			salt,
+			init_data,
+			self.depth + 1,
+			self.account_db,
+		);
+		match result {
+			Ok((address, ExecutionOutcome { state: Some(commit_state), gas_used, .. })) => {
+				self.account_db.merge(commit_state);
+				contract::CreateReceipt { address: Some(address), gas_used }
+			}
+			Ok((_, ExecutionOutcome { state: None, gas_used, .. })) =>
+				contract::CreateReceipt { address: None, gas_used },
+			Err(_) => contract::CreateReceipt { address: None, gas_used: 0 },
 		}
 	}
-	fn transfer(&mut self, to: &Self::AccountId, value: Self::Balance) {
-		if let Ok(Some(commit_state)) =
-			Module::<T>::effect_transfer(&self.account, to, value, self.account_db)
-		{
-			self.account_db.merge(commit_state);
+	fn call(
+		&mut self,
+		to: &Self::AccountId,
+		value: Self::Balance,
+		gas_limit: u64,
+		input_data: &[u8],
+		output_data: &mut Vec<u8>,
+	) -> contract::CallReceipt {
+		// the callee's changes live in an overlay of their own on top of ours, and only make it
+		// into ours if the call succeeds.
+		let result = Module::<T>::effect_transfer(
+			&self.account,
+			to,
+			value,
+			gas_limit,
+			input_data,
+			self.depth + 1,
+			self.account_db,
+		);
+		match result {
+			Ok(ExecutionOutcome { state: Some(commit_state), gas_used, output_data: data }) => {
+				self.account_db.merge(commit_state);
+				*output_data = data;
+				contract::CallReceipt { gas_used, success: true }
+			}
+			Ok(ExecutionOutcome { state: None, gas_used, .. }) =>
+				contract::CallReceipt { gas_used, success: false },
+			Err(_) => contract::CallReceipt { gas_used: 0, success: false },
 		}
 	}
+	fn deposit_event(&mut self, topics: Vec<Self::Hash>, data: Vec<u8>) {
+		self.account_db.deposit_log(LogEntry {
+			address: self.account.clone(),
+			topics,
+			data,
+		});
+	}
 }
 
 impl<T: Trait> MakePayment<T::AccountId> for Module<T> {
 	fn make_payment(transactor: &T::AccountId, encoded_len: usize) -> bool {
 		let b = Self::free_balance(transactor);
-		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<usize>>::sa(encoded_len);
+		let transaction_fee = Self::transaction_base_fee() + Self::transaction_byte_fee() * <T::Balance as As<u64>>::sa(encoded_len as u64);
 		if b < transaction_fee {
 			return false;
 		}
-		<FreeBalance<T>>::insert(transactor, b - transaction_fee);
+		Self::set_free_balance(transactor, b - transaction_fee);
+		<CurrentEraReward<T>>::put(Self::current_era_reward() + transaction_fee);
 		true
 	}
 }
 
+/// Places contracts at the address of their origin plus one plus the length of their salt.
 #[cfg(any(feature = "std", test))]
 pub struct DummyContractAddressFor;
 #[cfg(any(feature = "std", test))]
 impl ContractAddressFor<u64> for DummyContractAddressFor {
-	fn contract_address_for(_code: &[u8], origin: &u64) -> u64 {
-		origin + 1
+	fn contract_address_for(_code: &[u8], salt: &[u8], origin: &u64) -> u64 {
+		origin + 1 + salt.len() as u64
 	}
 }
 
@@ -696,6 +1645,13 @@ pub struct GenesisConfig<T: Trait> {
 	pub bonding_duration: T::BlockNumber,
 	pub transaction_base_fee: T::Balance,
 	pub transaction_byte_fee: T::Balance,
+	pub existential_deposit: T::Balance,
+	pub creation_fee: T::Balance,
+	pub misbehavior_slash: T::Balance,
+	pub reporter_reward: T::Balance,
+	pub session_reward: T::Balance,
+	pub offline_slash: T::Balance,
+	pub offline_slash_grace: u32,
 }
 
 #[cfg(any(feature = "std", test))]
@@ -711,6 +1667,13 @@ impl<T: Trait> GenesisConfig<T> where T::AccountId: From<u64> {
 			bonding_duration: T::BlockNumber::sa(0),
 			transaction_base_fee: T::Balance::sa(0),
 			transaction_byte_fee: T::Balance::sa(0),
+			existential_deposit: T::Balance::sa(0),
+			creation_fee: T::Balance::sa(0),
+			misbehavior_slash: T::Balance::sa(0),
+			reporter_reward: T::Balance::sa(0),
+			session_reward: T::Balance::sa(0),
+			offline_slash: T::Balance::sa(0),
+			offline_slash_grace: 0,
 		}
 	}
 
@@ -733,6 +1696,13 @@ impl<T: Trait> GenesisConfig<T> where T::AccountId: From<u64> {
 			bonding_duration: T::BlockNumber::sa(0),
 			transaction_base_fee: T::Balance::sa(1),
 			transaction_byte_fee: T::Balance::sa(0),
+			existential_deposit: T::Balance::sa(0),
+			creation_fee: T::Balance::sa(0),
+			misbehavior_slash: T::Balance::sa(0),
+			reporter_reward: T::Balance::sa(0),
+			session_reward: T::Balance::sa(0),
+			offline_slash: T::Balance::sa(0),
+			offline_slash_grace: 0,
 		}
 	}
 }
@@ -750,6 +1720,13 @@ impl<T: Trait> Default for GenesisConfig<T> {
 			bonding_duration: T::BlockNumber::sa(1000),
 			transaction_base_fee: T::Balance::sa(0),
 			transaction_byte_fee: T::Balance::sa(0),
+			existential_deposit: T::Balance::sa(0),
+			creation_fee: T::Balance::sa(0),
+			misbehavior_slash: T::Balance::sa(0),
+			reporter_reward: T::Balance::sa(0),
+			session_reward: T::Balance::sa(0),
+			offline_slash: T::Balance::sa(0),
+			offline_slash_grace: 0,
 		}
 	}
 }
@@ -769,6 +1746,13 @@ impl<T: Trait> primitives::BuildExternalities for GenesisConfig<T> {
 			twox_128(<BondingDuration<T>>::key()).to_vec() => self.bonding_duration.encode(),
 			twox_128(<TransactionBaseFee<T>>::key()).to_vec() => self.transaction_base_fee.encode(),
 			twox_128(<TransactionByteFee<T>>::key()).to_vec() => self.transaction_byte_fee.encode(),
+			twox_128(<ExistentialDeposit<T>>::key()).to_vec() => self.existential_deposit.encode(),
+			twox_128(<CreationFee<T>>::key()).to_vec() => self.creation_fee.encode(),
+			twox_128(<MisbehaviorSlash<T>>::key()).to_vec() => self.misbehavior_slash.encode(),
+			twox_128(<ReporterReward<T>>::key()).to_vec() => self.reporter_reward.encode(),
+			twox_128(<SessionReward<T>>::key()).to_vec() => self.session_reward.encode(),
+			twox_128(<OfflineSlash<T>>::key()).to_vec() => self.offline_slash.encode(),
+			twox_128(<OfflineSlashGrace<T>>::key()).to_vec() => self.offline_slash_grace.encode(),
 			twox_128(<CurrentEra<T>>::key()).to_vec() => self.current_era.encode(),
 			twox_128(<TotalStake<T>>::key()).to_vec() => total_stake.encode()
 		];
@@ -786,7 +1770,7 @@ mod tests {
 	use runtime_io::with_externalities;
 	use substrate_primitives::H256;
 	use primitives::BuildExternalities;
-	use primitives::traits::{HasPublicAux, Identity};
+	use primitives::traits::{HasPublicAux, Identity, Header as HeaderT, Digest as DigestT};
 	use primitives::testing::{Digest, Header};
 
 	pub struct Test;
@@ -794,8 +1778,11 @@ mod tests {
 		type PublicAux = u64;
 	}
 	impl consensus::Trait for Test {
+		const NOTE_OFFLINE_POSITION: u32 = 1;
 		type PublicAux = <Self as HasPublicAux>::PublicAux;
 		type SessionKey = u64;
+		type OnMisbehavior = Module<Test>;
+		type OnOfflineValidator = Module<Test>;
 	}
 	impl system::Trait for Test {
 		type Index = u64;
@@ -805,6 +1792,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = RawEvent<u64, u64>;
 	}
 	impl session::Trait for Test {
 		type ConvertAccountIdToSessionKey = Identity;
@@ -812,6 +1800,8 @@ mod tests {
 	impl Trait for Test {
 		type Balance = u64;
 		type DetermineContractAddress = DummyContractAddressFor;
+		type OnAccountKill = ();
+		type Event = RawEvent<u64, u64>;
 	}
 
 	fn new_test_ext(session_length: u64, sessions_per_era: u64, current_era: u64, monied: bool) -> runtime_io::TestExternalities {
@@ -833,6 +1823,13 @@ mod tests {
 			bonding_duration: 3,
 			transaction_base_fee: 0,
 			transaction_byte_fee: 0,
+			existential_deposit: 0,
+			creation_fee: 0,
+			misbehavior_slash: 15,
+			reporter_reward: 5,
+			session_reward: 0,
+			offline_slash: 10,
+			offline_slash_grace: 1,
 		}.build_externalities());
 		t
 	}
@@ -864,8 +1861,10 @@ mod tests {
 
 			// Block 3: Unstake highest, introduce another staker. No change yet.
 			System::set_block_number(3);
+			<BondingDuration<Test>>::put(1);
 			assert_ok!(Staking::stake(&3));
 			assert_ok!(Staking::unstake(&4));
+			assert_eq!(Staking::unlock_block(&4), LockStatus::LockedUntil(4));
 			Staking::check_new_era();
 
 			// Block 4: New era - validators change.
@@ -875,7 +1874,7 @@ mod tests {
 
 			// Block 5: Transfer stake from highest to lowest. No change yet.
 			System::set_block_number(5);
-			assert_ok!(Staking::transfer(&4, 1, 40));
+			assert_ok!(Staking::transfer(&4, 1, 40, 0, 0, vec![]));
 			Staking::check_new_era();
 
 			// Block 6: Lowest now validator.
@@ -973,18 +1972,55 @@ mod tests {
 	fn staking_balance_transfer_works() {
 		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
 			<FreeBalance<Test>>::insert(1, 111);
-			assert_ok!(Staking::transfer(&1, 2, 69));
+			assert_ok!(Staking::transfer(&1, 2, 69, 0, 0, vec![]));
 			assert_eq!(Staking::balance(&1), 42);
 			assert_eq!(Staking::balance(&2), 69);
 		});
 	}
 
+	#[test]
+	fn staking_balance_transfer_should_deposit_event() {
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(1, 111);
+			assert_ok!(Staking::transfer(&1, 2, 69, 0, 0, vec![]));
+			assert_eq!(System::events(), vec![system::EventRecord {
+				extrinsic_index: 0,
+				event: RawEvent::Transfer { from: 1, to: 2, value: 69 },
+			}]);
+		});
+	}
+
 	#[test]
 	fn staking_balance_transfer_when_bonded_should_not_work() {
 		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
 			<FreeBalance<Test>>::insert(1, 111);
 			assert_ok!(Staking::stake(&1));
-			assert_noop!(Staking::transfer(&1, 2, 69), "bondage too high to send value");
+			assert_noop!(Staking::transfer(&1, 2, 69, 0, 0, vec![]), "bondage too high to send value");
+		});
+	}
+
+	#[test]
+	fn staking_balance_transfer_between_bonded_accounts_should_not_work() {
+		with_externalities(&mut new_test_ext(1, 3, 1, true), || {
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::stake(&2));
+			assert_noop!(Staking::transfer(&1, 2, 5, 0, 0, vec![]), "bondage too high to send value");
+		});
+	}
+
+	#[test]
+	fn extended_bondage_should_outlive_stake() {
+		with_externalities(&mut new_test_ext(1, 3, 1, true), || {
+			Staking::extend_bondage(&1, 10);
+			Staking::extend_bondage(&1, 8);
+			assert_eq!(Staking::bondage(&1), 10);
+
+			assert_ok!(Staking::stake(&1));
+			Staking::extend_bondage(&1, 12);
+			assert_eq!(Staking::unlock_block(&1), LockStatus::Staked);
+
+			assert_ok!(Staking::unstake(&1));
+			assert_eq!(Staking::bondage(&1), 12);
 		});
 	}
 
@@ -1010,7 +2046,7 @@ mod tests {
 		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
 			<FreeBalance<Test>>::insert(1, 111);
 			assert_ok!(Staking::reserve_balance(&1, 69));
-			assert_noop!(Staking::transfer(&1, 2, 69), "balance too low to send value");
+			assert_noop!(Staking::transfer(&1, 2, 69, 0, 0, vec![]), "balance too low to send value");
 		});
 	}
 
@@ -1124,17 +2160,417 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn misbehaving_validator_should_be_punished() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::stake(&3));
+			assert_ok!(Staking::stake(&4));
+			Session::set_validators(&[4, 3]);
+
+			<Staking as consensus::OnMisbehavior<u64, u64>>::on_misbehavior(&3, &1);
+			assert_eq!(Staking::balance(&3), 15);
+			assert_eq!(Staking::balance(&1), 15);
+			assert_eq!(Staking::unlock_block(&3), LockStatus::LockedUntil(6));
+
+			// Block 2: New era - the misbehaving validator is not chosen again.
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![4, 2]);
+		});
+	}
+
+	#[test]
+	fn misbehavior_slash_should_be_capped_at_balance() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			<FreeBalance<Test>>::insert(3, 2);
+			Session::set_validators(&[4, 3]);
+
+			<Staking as consensus::OnMisbehavior<u64, u64>>::on_misbehavior(&3, &1);
+			assert_eq!(Staking::balance(&3), 0);
+			assert_eq!(Staking::balance(&1), 12);
+		});
+	}
+
+	#[test]
+	fn offline_validator_should_be_ejected_after_grace() {
+		with_externalities(&mut new_test_ext(1, 3, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::stake(&3));
+			assert_ok!(Staking::stake(&4));
+			Session::set_validators(&[4, 3]);
+
+			// The first report is within the grace.
+			<Staking as consensus::OnOfflineValidator>::on_offline_validator(1);
+			assert_eq!(Staking::offline_strikes(&3), 1);
+			assert_eq!(Staking::balance(&3), 30);
+			Staking::check_new_era();
+			assert_eq!(Staking::current_era(), 0);
+
+			// The second is not.
+			<Staking as consensus::OnOfflineValidator>::on_offline_validator(1);
+			assert_eq!(Staking::offline_strikes(&3), 0);
+			assert_eq!(Staking::balance(&3), 20);
+			assert_eq!(Staking::unlock_block(&3), LockStatus::LockedUntil(9));
+
+			// Further reports are ignored once the validator has been ejected.
+			<Staking as consensus::OnOfflineValidator>::on_offline_validator(1);
+			<Staking as consensus::OnOfflineValidator>::on_offline_validator(1);
+			assert_eq!(Staking::balance(&3), 20);
+
+			// Block 2: A new era is forced at the end of the session, without the offline validator.
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Staking::current_era(), 1);
+			assert_eq!(Session::validators(), vec![4, 2]);
+
+			// Later eras are counted from the forced one.
+			System::set_block_number(4);
+			Staking::check_new_era();
+			assert_eq!(Staking::current_era(), 1);
+			System::set_block_number(5);
+			Staking::check_new_era();
+			assert_eq!(Staking::current_era(), 2);
+		});
+	}
+
+	#[test]
+	fn bonded_stake_should_unbond_in_chunks() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::bond(&3, 5, 20));
+			assert_noop!(Staking::bond(&3, 6, 5), "Stash is already bonded.");
+			assert_noop!(Staking::bond(&4, 5, 5), "Controller is already paired.");
+			assert_eq!(Staking::free_balance(&3), 10);
+			assert_eq!(Staking::reserved_balance(&3), 20);
+			assert_eq!(Staking::stash_of(&5), 3);
+			assert_eq!(Staking::stake_of(&3), 20);
+
+			// the stash's free balance stays liquid while the controller stakes on its behalf.
+			assert_ok!(Staking::stake(&5));
+			assert_eq!(<Intentions<Test>>::get(), vec![3]);
+			assert_eq!(Staking::unlock_block(&3), LockStatus::Liquid);
+			assert_ok!(Staking::transfer(&3, 1, 5, 0, 0, vec![]));
+
+			assert_noop!(Staking::unbond(&5, 21), "Cannot unbond more than is bonded.");
+			assert_ok!(Staking::unbond(&5, 15));
+			assert_eq!(Staking::stake_of(&3), 5);
+			assert_noop!(Staking::withdraw_unbonded(&5), "Nothing to withdraw.");
+
+			<CurrentEra<Test>>::put(3);
+			assert_ok!(Staking::unbond(&5, 5));
+			assert_ok!(Staking::withdraw_unbonded(&5));
+			assert_eq!(Staking::free_balance(&3), 20);
+			assert_eq!(Staking::reserved_balance(&3), 5);
+			assert_eq!(Staking::ledger(&5).unwrap().total, 5);
+
+			<CurrentEra<Test>>::put(6);
+			assert_ok!(Staking::withdraw_unbonded(&5));
+			assert_eq!(Staking::free_balance(&3), 25);
+			assert_eq!(Staking::reserved_balance(&3), 0);
+			assert_eq!(Staking::ledger(&5), None);
+			assert_eq!(Staking::bonded(&3), None);
+		});
+	}
+
+	#[test]
+	fn slashed_bonded_stake_should_not_release_other_reservations() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::bond(&3, 5, 20));
+			// another module reserves some of the stash's balance.
+			assert_ok!(Staking::reserve_balance(&3, 5));
+			assert_ok!(Staking::unbond(&5, 20));
+
+			// the free balance is slashed first, then the bonded stake.
+			assert_ok!(Staking::slash(&3, 15));
+			assert_eq!(Staking::free_balance(&3), 0);
+			assert_eq!(Staking::reserved_balance(&3), 15);
+			let ledger = Staking::ledger(&5).unwrap();
+			assert_eq!(ledger.total, 10);
+			assert_eq!(ledger.active, 0);
+			assert_eq!(ledger.unlocking.iter().map(|chunk| chunk.value).collect::<Vec<_>>(), vec![10]);
+
+			<CurrentEra<Test>>::put(3);
+			assert_ok!(Staking::withdraw_unbonded(&5));
+			assert_eq!(Staking::free_balance(&3), 10);
+			assert_eq!(Staking::reserved_balance(&3), 5);
+			assert_eq!(Staking::ledger(&5), None);
+		});
+	}
+
+	#[test]
+	fn bonded_stake_should_be_used_for_election() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			// Block 1: Two bonded stashes, one only partially bonded, and one whole-balance staker.
+			System::set_block_number(1);
+			assert_ok!(Staking::bond(&1, 11, 10));
+			assert_ok!(Staking::bond(&2, 12, 5));
+			assert_ok!(Staking::stake(&11));
+			assert_ok!(Staking::stake(&12));
+			assert_ok!(Staking::stake(&3));
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![10, 20]);
+
+			// Block 2: New era - the stashes stand in for their controllers.
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![3, 1]);
+			assert_eq!(Staking::exposure_of(&1).own, 10);
+		});
+	}
+
+	#[test]
+	fn account_below_existential_deposit_should_be_reaped() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			<ExistentialDeposit<Test>>::put(10);
+
+			<system::AccountIndex<Test>>::insert(1, 3);
+			assert_ok!(Staking::transfer(&1, 2, 5, 0, 0, vec![]));
+			assert_eq!(Staking::free_balance(&1), 0);
+			assert_eq!(System::account_index(&1), 0);
+
+			<CodeOf<Test>>::insert(3, b"code".to_vec());
+			<DirectAccountDb as AccountDb<Test>>::set_storage(&mut DirectAccountDb, &3, b"foo".to_vec(), Some(b"1".to_vec()));
+			assert_eq!(<StorageKeysOf<Test>>::get(3), vec![b"foo".to_vec()]);
+			assert_ok!(Staking::reserve_balance(&3, 10));
+			assert_ok!(Staking::slash(&3, 25));
+			assert_eq!(Staking::balance(&3), 0);
+			assert!(<CodeOf<Test>>::get(3).is_empty());
+			assert_eq!(<StorageOf<Test>>::get((3, b"foo".to_vec())), None);
+			assert!(<StorageKeysOf<Test>>::get(3).is_empty());
+		});
+	}
+
+	#[test]
+	fn reaped_account_should_stop_staking_and_nominating() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			<ExistentialDeposit<Test>>::put(10);
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::nominate(&3, vec![1]));
+			assert_ok!(Staking::nominate(&4, vec![1, 2]));
+
+			assert_ok!(Staking::slash(&1, 10));
+			assert_eq!(Staking::balance(&1), 0);
+			assert_eq!(<Intentions<Test>>::get(), vec![2]);
+			assert!(Staking::nominators_for(&1).is_empty());
+			assert!(Staking::nominating(&3).is_empty());
+			assert_eq!(Staking::nominating(&4), vec![2]);
+
+			assert_ok!(Staking::slash(&4, 40));
+			assert!(Staking::nominators_for(&2).is_empty());
+			assert!(Staking::nominating(&4).is_empty());
+		});
+	}
+
+	#[test]
+	fn creating_account_should_respect_existential_deposit_and_fee() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			<ExistentialDeposit<Test>>::put(10);
+			<CreationFee<Test>>::put(3);
+
+			assert_noop!(Staking::transfer(&4, 5, 9, 0, 0, vec![]), "value too low to create account");
+			assert_ok!(Staking::transfer(&4, 5, 10, 0, 0, vec![]));
+			assert_eq!(Staking::free_balance(&4), 27);
+			assert_eq!(Staking::free_balance(&5), 10);
+
+			// no fee is due once the account exists.
+			assert_ok!(Staking::transfer(&4, 5, 1, 0, 0, vec![]));
+			assert_eq!(Staking::free_balance(&4), 26);
+			assert_eq!(Staking::free_balance(&5), 11);
+		});
+	}
+
+	#[test]
+	fn nominators_should_back_validators() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			// Block 1: Three intentions and a nominator backing the poorest of them.
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::stake(&3));
+			assert_ok!(Staking::nominate(&4, vec![1]));
+			assert_eq!(Staking::nominating(&4), vec![1]);
+			assert_eq!(Staking::nominators_for(&1), vec![4]);
+			assert_eq!(Staking::unlock_block(&4), LockStatus::Staked);
+			Staking::check_new_era();
+
+			// Block 2: New era - the nominated validator has the most backing.
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![1, 3]);
+			assert_eq!(Staking::current_exposure(&1), Some(Exposure { own: 10, others: vec![(4, 40)] }));
+			assert_eq!(Staking::current_exposure(&3), Some(Exposure { own: 30, others: vec![] }));
+
+			// Block 3: Nominator withdraws. No change yet.
+			System::set_block_number(3);
+			assert_ok!(Staking::unnominate(&4));
+			assert!(Staking::nominators_for(&1).is_empty());
+			// the nominator's funds unlock three eras of two blocks after the current one began.
+			assert_eq!(Staking::unlock_block(&4), LockStatus::LockedUntil(8));
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![1, 3]);
+
+			// Block 4: New era - back to balance ordering.
+			System::set_block_number(4);
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![3, 2]);
+			assert_eq!(Staking::current_exposure(&1), None);
+		});
+	}
+
+	#[test]
+	fn nominations_should_be_split_between_targets() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::nominate(&4, vec![2, 1, 2]));
+			assert_eq!(Staking::nominating(&4), vec![1, 2]);
+
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Staking::exposure_of(&1).total(), 30);
+			assert_eq!(Staking::exposure_of(&2).total(), 40);
+
+			// renominating replaces the previous targets.
+			assert_ok!(Staking::nominate(&4, vec![1]));
+			assert_eq!(Staking::nominating(&4), vec![1]);
+			assert!(Staking::nominators_for(&2).is_empty());
+		});
+	}
+
+	#[test]
+	fn invalid_nominations_should_not_work() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			assert_ok!(Staking::stake(&1));
+			assert_noop!(Staking::nominate(&2, vec![]), "Cannot nominate without targets.");
+			assert_noop!(Staking::nominate(&2, vec![2]), "Cannot nominate self.");
+			assert_noop!(Staking::nominate(&1, vec![3]), "Cannot nominate if already staked.");
+			assert_noop!(Staking::unnominate(&2), "Cannot unnominate if not nominating.");
+
+			assert_ok!(Staking::nominate(&2, vec![1]));
+			assert_noop!(Staking::stake(&2), "Cannot stake if already nominating.");
+		});
+	}
+
+	#[test]
+	fn slashes_and_rewards_should_be_shared_with_nominators() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::stake(&2));
+			assert_ok!(Staking::nominate(&4, vec![1]));
+
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Session::validators(), vec![1, 2]);
+
+			// 1 is backed 10 by itself and 40 by its nominator.
+			Staking::slash_validator(&1, 15);
+			assert_eq!(Staking::balance(&1), 7);
+			assert_eq!(Staking::balance(&4), 28);
+
+			Staking::reward_validator(&1, 10);
+			assert_eq!(Staking::balance(&1), 9);
+			assert_eq!(Staking::balance(&4), 36);
+		});
+	}
+
+	#[test]
+	fn total_stake_should_follow_slashes_and_rewards() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			System::set_block_number(1);
+			assert_ok!(Staking::stake(&1));
+			assert_ok!(Staking::nominate(&4, vec![1]));
+
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Staking::total_stake(), 100);
+
+			Staking::slash_validator(&1, 15);
+			assert_eq!(Staking::total_stake(), 85);
+
+			Staking::reward_validator(&1, 10);
+			assert_eq!(Staking::total_stake(), 95);
+
+			// reaped dust is burned.
+			<ExistentialDeposit<Test>>::put(5);
+			assert_ok!(Staking::slash(&2, 16));
+			assert_eq!(Staking::balance(&2), 0);
+			assert_eq!(Staking::total_stake(), 75);
+		});
+	}
+
+	#[test]
+	fn proportional_shares_should_not_overflow() {
+		assert_eq!(proportion(10u64, 3, 4), 7);
+		assert_eq!(proportion(u64::max_value(), 1, 2), u64::max_value() / 2);
+		assert_eq!(proportion(u64::max_value(), 3, 3), u64::max_value());
+	}
+
+	#[test]
+	fn rewards_should_be_paid_out_at_era_end() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			<SessionReward<Test>>::put(10);
+			<TransactionBaseFee<Test>>::put(3);
+			Session::set_validators(&[1, 2]);
+
+			// Block 1: Session ends, the validators earn a point each and the reward is accrued.
+			System::set_block_number(1);
+			assert!(<Staking as MakePayment<u64>>::make_payment(&3, 0));
+			Staking::check_new_era();
+			assert_eq!(Staking::current_era_points(&1), 1);
+			assert_eq!(Staking::current_era_reward(), 13);
+			assert_eq!(Staking::balance(&1), 10);
+			assert_eq!(Staking::total_stake(), 97);
+
+			// Block 2: Era ends and the accrued reward is split by points.
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert_eq!(Staking::balance(&1), 21);
+			assert_eq!(Staking::balance(&2), 31);
+			assert_eq!(Staking::current_era_points(&1), 0);
+			assert_eq!(Staking::current_era_reward(), 1);
+			assert_eq!(Staking::reward_history(&0), vec![(1, 11), (2, 11)]);
+			assert_eq!(Staking::total_stake(), 119);
+		});
+	}
+
+	#[test]
+	fn reward_history_should_be_pruned() {
+		with_externalities(&mut new_test_ext(1, 2, 0, true), || {
+			<SessionReward<Test>>::put(2);
+			<RewardHistory<Test>>::insert(&0, vec![(1, 1)]);
+			<CurrentEra<Test>>::put(REWARD_HISTORY_DEPTH as u64);
+			Session::set_validators(&[1, 2]);
+
+			System::set_block_number(2);
+			Staking::check_new_era();
+			assert!(Staking::reward_history(&0).is_empty());
+			assert_eq!(Staking::reward_history(&(REWARD_HISTORY_DEPTH as u64)), vec![(1, 1), (2, 1)]);
+		});
+	}
+
 	const CODE_TRANSFER: &str = r#"
 (module
-	;; ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32)
-	(import "env" "ext_transfer" (func $ext_transfer (param i32 i32 i32 i32)))
+	;; ext_transfer(transfer_to: u32, transfer_to_len: u32, value_ptr: u32, value_len: u32) -> u32
+	(import "env" "ext_transfer" (func $ext_transfer (param i32 i32 i32 i32) (result i32)))
 	(import "env" "memory" (memory 1 1))
 	(func (export "call")
-		(call $ext_transfer
-			(i32.const 4)  ;; Pointer to "Transfer to" address.
-			(i32.const 8)  ;; Length of "Transfer to" address.
-			(i32.const 12)  ;; Pointer to the buffer with value to transfer
-			(i32.const 8)   ;; Length of the buffer with value to transfer.
+		(if
+			(call $ext_transfer
+				(i32.const 4)  ;; Pointer to "Transfer to" address.
+				(i32.const 8)  ;; Length of "Transfer to" address.
+				(i32.const 12)  ;; Pointer to the buffer with value to transfer
+				(i32.const 8)   ;; Length of the buffer with value to transfer.
+			)
+			;; The transfer must succeed.
+			(then unreachable)
 		)
 	)
 	;; Destination AccountId to transfer the funds.
@@ -1157,7 +2593,7 @@ mod tests {
 
 			<CodeOf<Test>>::insert(1, code_transfer.to_vec());
 
-			assert_ok!(Staking::transfer(&0, 1, 11));
+			assert_ok!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]));
 
 			assert_eq!(Staking::balance(&0), 100);
 			assert_eq!(Staking::balance(&1), 5);
@@ -1165,6 +2601,387 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn contract_transfer_to_failing_code_should_report_failure() {
+		let code_transfer = wabt::wat2wasm(CODE_TRANSFER).unwrap();
+		let code_trap = wabt::wat2wasm(CODE_TRAP).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 0);
+			<FreeBalance<Test>>::insert(2, 30);
+
+			<CodeOf<Test>>::insert(1, code_transfer.to_vec());
+			<CodeOf<Test>>::insert(2, code_trap.to_vec());
+
+			// the failed transfer is reported to the caller, whose code traps on it.
+			assert_eq!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]), Err("contract execution failed"));
+
+			assert_eq!(Staking::balance(&0), 111);
+			assert_eq!(Staking::balance(&1), 0);
+			assert_eq!(Staking::balance(&2), 30);
+		});
+	}
+
+	#[test]
+	fn contract_transfer_should_pay_for_used_gas() {
+		let code_transfer = wabt::wat2wasm(CODE_TRANSFER).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 1_111);
+			<FreeBalance<Test>>::insert(1, 0);
+			<FreeBalance<Test>>::insert(2, 30);
+
+			<CodeOf<Test>>::insert(1, code_transfer.to_vec());
+
+			let reward = Staking::current_era_reward();
+			assert_noop!(Staking::transfer(&0, 1, 11, u64::max_value(), 2, vec![]), "gas limit too high");
+			assert_noop!(Staking::transfer(&0, 1, 11, 2_000, 1, vec![]), "not enough free funds to pay for gas");
+			assert_ok!(Staking::transfer(&0, 1, 11, 1_000, 1, vec![]));
+
+			// the unused gas is refunded and the used gas is paid into the era's reward.
+			let gas_used = Staking::current_era_reward() - reward;
+			assert!(gas_used > 0 && gas_used < 1_000);
+			assert_eq!(Staking::free_balance(&0), 1_100 - gas_used);
+			assert_eq!(Staking::reserved_balance(&0), 0);
+			assert_eq!(Staking::balance(&1), 5);
+			assert_eq!(Staking::balance(&2), 36);
+		});
+	}
+
+	#[test]
+	fn contract_transfer_out_of_gas_should_consume_gas_limit() {
+		let code_transfer = wabt::wat2wasm(CODE_TRANSFER).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 0);
+			<FreeBalance<Test>>::insert(2, 30);
+
+			<CodeOf<Test>>::insert(1, code_transfer.to_vec());
+
+			let reward = Staking::current_era_reward();
+			assert_eq!(Staking::transfer(&0, 1, 11, 1, 10, vec![]), Err("contract execution failed"));
+
+			// the transfer is reverted but the whole gas limit is paid for.
+			assert_eq!(Staking::current_era_reward(), reward + 10);
+			assert_eq!(Staking::balance(&0), 101);
+			assert_eq!(Staking::balance(&1), 0);
+			assert_eq!(Staking::balance(&2), 30);
+		});
+	}
+
+	const CODE_TRAP: &str = r#"
+(module
+	(func (export "call")
+		unreachable
+	)
+)
+"#;
+
+	#[test]
+	fn contract_transfer_to_trapping_code_should_fail() {
+		let code_trap = wabt::wat2wasm(CODE_TRAP).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 10);
+
+			<CodeOf<Test>>::insert(1, code_trap.to_vec());
+
+			assert_eq!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]), Err("contract execution failed"));
+
+			// the value stays with the transactor and no transfer is reported.
+			assert_eq!(Staking::balance(&0), 111);
+			assert_eq!(Staking::balance(&1), 10);
+			assert!(System::events().is_empty());
+		});
+	}
+
+	/// This code returns its input data if `$skip` is 0, and traps otherwise.
+	const CODE_ECHO: &str = include_str!("../../contract/fixtures/echo.wat");
+
+	#[test]
+	fn contract_call_should_return_data() {
+		let code_echo = wabt::wat2wasm(CODE_ECHO.replace("$skip", "0")).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 10);
+
+			<CodeOf<Test>>::insert(1, code_echo.to_vec());
+
+			assert_eq!(Staking::call_contract(&0, &1, 11, 100_000, &[1, 2, 3]), Ok(vec![1, 2, 3]));
+			assert_eq!(Staking::call_contract(&0, &1, 11, 1, &[1, 2, 3]), Err("contract execution failed"));
+
+			// the call is only simulated.
+			assert_eq!(Staking::balance(&0), 111);
+			assert_eq!(Staking::balance(&1), 10);
+
+			assert_ok!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![1, 2, 3]));
+			assert_eq!(Staking::balance(&1), 21);
+		});
+	}
+
+	/// Returns code which calls the account `callee` with a value of 6 and 10000 gas and then, if
+	/// `fail` is set, traps.
+	fn code_call(callee: u8, fail: bool) -> String {
+		format!(r#"
+(module
+	;; ext_call(callee_ptr: u32, callee_len: u32, gas: u64, value_ptr: u32, value_len: u32, input_data_ptr: u32, input_data_len: u32) -> u32
+	(import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "call")
+		(drop
+			(call $ext_call
+				(i32.const 4)  ;; Pointer to "callee" address.
+				(i32.const 8)  ;; Length of "callee" address.
+				(i64.const 10000)  ;; How much gas to devote to the call.
+				(i32.const 12)  ;; Pointer to the buffer with value to transfer.
+				(i32.const 8)   ;; Length of the buffer with value to transfer.
+				(i32.const 0)  ;; Pointer to the input data.
+				(i32.const 0)  ;; Length of the input data.
+			)
+		)
+		{trap}
+	)
+	;; Callee AccountId.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 4) "\{callee:02x}\00\00\00\00\00\00\00")
+	;; Amount of value to transfer.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 12) "\06\00\00\00\00\00\00\00")
+)
+"#, callee = callee, trap = if fail { "unreachable" } else { "" })
+	}
+
+	#[test]
+	fn contract_call_should_revert_only_failed_frame() {
+		let code_caller = wabt::wat2wasm(code_call(2, false)).unwrap();
+		let code_failing = wabt::wat2wasm(code_call(3, true)).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(2, 30);
+			<FreeBalance<Test>>::insert(3, 30);
+
+			<CodeOf<Test>>::insert(1, code_caller.to_vec());
+			<CodeOf<Test>>::insert(2, code_failing.to_vec());
+
+			assert_ok!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]));
+
+			// the call from 1 to 2 failed, taking the transfer from 2 to 3 with it.
+			assert_eq!(Staking::balance(&0), 100);
+			assert_eq!(Staking::balance(&1), 11);
+			assert_eq!(Staking::balance(&2), 30);
+			assert_eq!(Staking::balance(&3), 30);
+
+			<CodeOf<Test>>::insert(2, vec![]);
+			assert_ok!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]));
+
+			assert_eq!(Staking::balance(&0), 89);
+			assert_eq!(Staking::balance(&1), 16);
+			assert_eq!(Staking::balance(&2), 36);
+		});
+	}
+
+	/// This code increments a counter in its storage and then calls itself.
+	const CODE_RECURSE: &str = r#"
+(module
+	;; ext_set_storage(location_ptr: i32, value_non_null: bool, value_ptr: i32)
+	(import "env" "ext_set_storage" (func $ext_set_storage (param i32 i32 i32)))
+	;; ext_get_storage(location_ptr: i32, value_ptr: i32)
+	(import "env" "ext_get_storage" (func $ext_get_storage (param i32 i32)))
+	;; ext_call(callee_ptr: u32, callee_len: u32, gas: u64, value_ptr: u32, value_len: u32, input_data_ptr: u32, input_data_len: u32) -> u32
+	(import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "call")
+		(call $ext_get_storage
+			(i32.const 4)  ;; Pointer to a location of the storage.
+			(i32.const 36)  ;; The value will be written at this address.
+		)
+		(i32.store
+			(i32.const 36)
+			(i32.add
+				(i32.load
+					(i32.const 36)
+				)
+				(i32.const 1)
+			)
+		)
+		(call $ext_set_storage
+			(i32.const 4)  ;; Pointer to a location of the storage.
+			(i32.const 1)  ;; Value is not null.
+			(i32.const 36)  ;; Pointer to a data we want to put in the storage.
+		)
+		(drop
+			(call $ext_call
+				(i32.const 68)  ;; Pointer to "callee" address.
+				(i32.const 8)  ;; Length of "callee" address.
+				(i64.const 0)  ;; How much gas to devote to the call; 0 means all of it.
+				(i32.const 76)  ;; Pointer to the buffer with value to transfer.
+				(i32.const 8)   ;; Length of the buffer with value to transfer.
+				(i32.const 0)  ;; Pointer to the input data.
+				(i32.const 0)  ;; Length of the input data.
+			)
+		)
+	)
+	;; Location of the counter in storage. 32 bytes.
+	(data (i32.const 4) "\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01")
+	;; Callee AccountId, which is the contract itself.
+	(data (i32.const 68) "\01\00\00\00\00\00\00\00")
+	;; Amount of value to transfer.
+	(data (i32.const 76) "\00\00\00\00\00\00\00\00")
+)
+"#;
+
+	#[test]
+	fn contract_call_depth_should_be_limited() {
+		let code_recurse = wabt::wat2wasm(CODE_RECURSE).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 10);
+
+			<CodeOf<Test>>::insert(1, code_recurse.to_vec());
+
+			assert_ok!(Staking::transfer(&0, 1, 0, 1_000_000, 0, vec![]));
+
+			// the call made by the transaction and each nested call below the maximum depth ran.
+			let counter = <StorageOf<Test>>::get(&(1, vec![1u8; 32])).unwrap();
+			assert_eq!(counter[0] as u32, MAX_CALL_DEPTH + 1);
+		});
+	}
+
+	/// Returns code which stores a value when deployed, or, if `fail` is set, traps.
+	fn code_constructor(fail: bool) -> String {
+		format!(r#"
+(module
+	;; ext_set_storage(location_ptr: i32, value_non_null: bool, value_ptr: i32)
+	(import "env" "ext_set_storage" (func $ext_set_storage (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "deploy")
+		(call $ext_set_storage
+			(i32.const 4)  ;; Pointer to a location of the storage.
+			(i32.const 1)  ;; Value is not null.
+			(i32.const 36)  ;; Pointer to a data we want to put in the storage.
+		)
+		{trap}
+	)
+	(func (export "call")
+		nop
+	)
+	;; Location of storage to store the data. 32 bytes.
+	(data (i32.const 4) "\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01")
+	;; Data to store. 32 bytes.
+	(data (i32.const 36) "\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02")
+)
+"#, trap = if fail { "unreachable" } else { "" })
+	}
+
+	#[test]
+	fn contract_create_should_run_constructor() {
+		let code = wabt::wat2wasm(code_constructor(false)).unwrap();
+		let code_failing = wabt::wat2wasm(code_constructor(true)).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+
+			assert_ok!(Staking::create(&0, code.clone(), 11, 100_000, 0, vec![], vec![]));
+			assert_eq!(Staking::balance(&0), 100);
+			assert_eq!(Staking::balance(&1), 11);
+			assert_eq!(<CodeOf<Test>>::get(1), code);
+			assert_eq!(<StorageOf<Test>>::get(&(1, vec![1u8; 32])), Some(vec![2u8; 32]));
+
+			// the same code may only be created again with another salt.
+			assert_noop!(
+				Staking::create(&0, code.clone(), 11, 100_000, 0, vec![], vec![]),
+				"contract address already in use"
+			);
+			assert_ok!(Staking::create(&0, code.clone(), 11, 100_000, 0, vec![0], vec![]));
+			assert_eq!(Staking::balance(&0), 89);
+			assert_eq!(Staking::balance(&2), 11);
+
+			// nothing is created if the constructor fails.
+			assert_eq!(
+				Staking::create(&0, code_failing, 11, 100_000, 0, vec![0, 0], vec![]),
+				Err("contract execution failed")
+			);
+			assert_eq!(Staking::balance(&0), 89);
+			assert_eq!(Staking::balance(&3), 0);
+			assert!(<CodeOf<Test>>::get(3).is_empty());
+
+			// funds sent to the address ahead of the creation are kept.
+			<FreeBalance<Test>>::insert(4, 5);
+			assert_ok!(Staking::create(&0, code.clone(), 11, 100_000, 0, vec![0, 0, 0], vec![]));
+			assert_eq!(Staking::balance(&0), 78);
+			assert_eq!(Staking::balance(&4), 16);
+			assert_eq!(<CodeOf<Test>>::get(4), code);
+		});
+	}
+
+	/// Returns code which deposits an event with a single topic of `0x11` bytes, then, if `fail`
+	/// is set, traps.
+	fn code_deposit_event(fail: bool) -> String {
+		format!(r#"
+(module
+	;; ext_deposit_event(topics_ptr: u32, topics_len: u32, data_ptr: u32, data_len: u32)
+	(import "env" "ext_deposit_event" (func $ext_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "call")
+		(call $ext_deposit_event
+			(i32.const 4)  ;; Pointer to the encoded topics.
+			(i32.const 36)  ;; Length of the encoded topics.
+			(i32.const 40)  ;; Pointer to the data.
+			(i32.const 4)  ;; Length of the data.
+		)
+		{trap}
+	)
+	;; Topics, a list of one 32-byte hash prefixed with its length as u32 in little endian.
+	(data (i32.const 4) "\01\00\00\00\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11\11")
+	;; Data of the event.
+	(data (i32.const 40) "\05\06\07\08")
+)
+"#, trap = if fail { "unreachable" } else { "" })
+	}
+
+	#[test]
+	fn contract_events_should_be_logged() {
+		let code = wabt::wat2wasm(code_deposit_event(false)).unwrap();
+		let code_failing = wabt::wat2wasm(code_deposit_event(true)).unwrap();
+
+		with_externalities(&mut new_test_ext(1, 3, 1, false), || {
+			<FreeBalance<Test>>::insert(0, 111);
+			<FreeBalance<Test>>::insert(1, 10);
+			<FreeBalance<Test>>::insert(2, 10);
+			<CodeOf<Test>>::insert(1, code.to_vec());
+			<CodeOf<Test>>::insert(2, code_failing.to_vec());
+			System::initialise(&1, &H256::default(), &H256::default());
+
+			assert_ok!(Staking::transfer(&0, 1, 0, 100_000, 0, vec![]));
+			let record = system::LogRecord {
+				extrinsic_index: 0,
+				address: 1,
+				topics: vec![H256::from([0x11; 32])],
+				data: vec![5, 6, 7, 8],
+			};
+			assert_eq!(System::log_records(), vec![record.clone()]);
+			assert_eq!(System::log_records_matching(&[1], &[H256::from([0x11; 32])]), vec![record]);
+			assert!(System::log_records_matching(&[2], &[]).is_empty());
+
+			// the event of a failed call is discarded with its other changes.
+			assert_eq!(Staking::transfer(&0, 2, 0, 100_000, 0, vec![]), Err("contract execution failed"));
+			assert_eq!(System::log_records().len(), 1);
+
+			// the block's header carries a bloom of the logged address and topic.
+			let header = System::finalise();
+			let bloom = header.digest().log_bloom().expect("a contract log was deposited");
+			assert!(bloom.contains(&1u64.encode()));
+			assert!(bloom.contains(&H256::from([0x11; 32]).encode()));
+			assert!(!bloom.contains(&2u64.encode()));
+		});
+	}
+
 	const CODE_MEM: &str =
 r#"
 (module
@@ -1188,7 +3005,7 @@ r#"
 			<CodeOf<Test>>::insert(1, code_mem.to_vec());
 
 			// Transfer some balance from 0 to 1.
-			assert_ok!(Staking::transfer(&0, 1, 11));
+			assert_eq!(Staking::transfer(&0, 1, 11, 100_000, 0, vec![]), Err("contract execution failed"));
 
 			// The balance should remain unchanged since we are expecting
 			// validation error caused by internal memory declaration.
@@ -34,19 +34,37 @@ extern crate substrate_runtime_primitives as primitives;
 extern crate safe_mix;
 
 use rstd::prelude::*;
+use rstd::marker::PhantomData;
 use runtime_io::Hashing;
-use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero, One, Bounded};
+use primitives::bloom::LogBloom;
+use primitives::traits::{self, CheckEqual, SimpleArithmetic, SimpleBitOps, Zero, One, Bounded, As};
 use runtime_support::{StorageValue, StorageMap, Parameter};
 use safe_mix::TripletMix;
 
-#[cfg(any(feature = "std", test))]
-use rstd::marker::PhantomData;
 #[cfg(any(feature = "std", test))]
 use codec::Slicable;
 
 #[cfg(any(feature = "std", test))]
 use runtime_io::{twox_128, TestExternalities};
 
+/// Implement `deposit_event` for a module, depositing one of its events into the block's event
+/// record by way of the module's `Trait::Event`:
+///
+/// ```ignore
+/// impl_deposit_event!(Module<T>, Event<T>);
+/// ```
+#[macro_export]
+macro_rules! impl_deposit_event {
+	($module:ident<$t:ident>, $event:ty) => {
+		impl<$t: Trait> $module<$t> {
+			/// Deposit one of this module's events.
+			fn deposit_event(event: $event) {
+				<$crate::Module<$t>>::deposit_event(<$t as Trait>::Event::from(event).into());
+			}
+		}
+	}
+}
+
 /// Compute the extrinsics root of a list of extrinsics.
 pub fn extrinsics_root<H: Hashing, E: codec::Slicable>(extrinsics: &[E]) -> H::Output {
 	extrinsics_data_root::<H>(extrinsics.iter().map(codec::Slicable::encode).collect())
@@ -58,6 +76,71 @@ pub fn extrinsics_data_root<H: Hashing>(xts: Vec<Vec<u8>>) -> H::Output {
 	H::enumerated_trie_root(&xts)
 }
 
+/// Record of an event happening during the execution of a block.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+pub struct EventRecord<E> {
+	/// The index of the extrinsic during which the event was deposited. Events deposited once
+	/// all extrinsics have been applied carry the number of extrinsics in the block.
+	pub extrinsic_index: u32,
+	/// The event itself.
+	pub event: E,
+}
+
+impl<E: codec::Slicable> codec::Slicable for EventRecord<E> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		codec::Slicable::using_encoded(&self.extrinsic_index, |s| v.extend(s));
+		codec::Slicable::using_encoded(&self.event, |s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		Some(EventRecord {
+			extrinsic_index: codec::Slicable::decode(input)?,
+			event: codec::Slicable::decode(input)?,
+		})
+	}
+}
+
+/// Record of a log deposited by a contract during the execution of a block.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Debug))]
+pub struct LogRecord<AccountId, Hash> {
+	/// The index of the extrinsic during which the log was deposited.
+	pub extrinsic_index: u32,
+	/// The address of the contract which deposited the log.
+	pub address: AccountId,
+	/// The topics under which the log may be looked up.
+	pub topics: Vec<Hash>,
+	/// The data of the log.
+	pub data: Vec<u8>,
+}
+
+impl<AccountId: codec::Slicable, Hash: codec::Slicable> codec::Slicable for LogRecord<AccountId, Hash> {
+	fn encode(&self) -> Vec<u8> {
+		let mut v = Vec::new();
+
+		codec::Slicable::using_encoded(&self.extrinsic_index, |s| v.extend(s));
+		codec::Slicable::using_encoded(&self.address, |s| v.extend(s));
+		codec::Slicable::using_encoded(&self.topics, |s| v.extend(s));
+		codec::Slicable::using_encoded(&self.data, |s| v.extend(s));
+
+		v
+	}
+
+	fn decode<I: codec::Input>(input: &mut I) -> Option<Self> {
+		Some(LogRecord {
+			extrinsic_index: codec::Slicable::decode(input)?,
+			address: codec::Slicable::decode(input)?,
+			topics: codec::Slicable::decode(input)?,
+			data: codec::Slicable::decode(input)?,
+		})
+	}
+}
+
 pub trait Trait {
 	type Index: Parameter + Default + SimpleArithmetic + Copy;
 	type BlockNumber: Parameter + SimpleArithmetic + Default + Bounded + Copy;
@@ -66,6 +149,8 @@ pub trait Trait {
 	type Digest: Parameter + Default + traits::Digest;
 	type AccountId: Parameter + Ord + Default;
 	type Header: traits::Header<Number = Self::BlockNumber, Hash = Self::Hash, Digest = Self::Digest>;
+	/// The outer event type of the runtime, into which each module's events are converted.
+	type Event: Parameter;
 }
 
 decl_module! {
@@ -86,6 +171,12 @@ decl_storage! {
 	ParentHash get(parent_hash): b"sys:pha" => required T::Hash;
 	ExtrinsicsRoot get(extrinsics_root): b"sys:txr" => required T::Hash;
 	Digest get(digest): b"sys:dig" => default T::Digest;
+	// The events deposited during the current block.
+	pub Events get(events): b"sys:events" => default Vec<EventRecord<T::Event>>;
+	// The contract logs deposited during the current block.
+	pub LogRecords get(log_records): b"sys:lgr" => default Vec<LogRecord<T::AccountId, T::Hash>>;
+	// Whether the dispatch of each extrinsic applied so far in the current block succeeded.
+	pub ExtrinsicSuccess get(extrinsic_success): b"sys:xts" => default Vec<bool>;
 }
 
 impl<T: Trait> Module<T> {
@@ -98,6 +189,9 @@ impl<T: Trait> Module<T> {
 		<ExtrinsicsRoot<T>>::put(txs_root);
 		<RandomSeed<T>>::put(Self::calculate_random());
 		<ExtrinsicIndex<T>>::put(0);
+		<Events<T>>::kill();
+		<LogRecords<T>>::kill();
+		<ExtrinsicSuccess<T>>::kill();
 	}
 
 	/// Remove temporary "environment" entries in storage.
@@ -107,7 +201,11 @@ impl<T: Trait> Module<T> {
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
-		let digest = <Digest<T>>::take();
+		let mut digest = <Digest<T>>::take();
+		let log_records = Self::log_records();
+		if !log_records.is_empty() {
+			traits::Digest::push_log_bloom(&mut digest, Self::log_bloom(&log_records));
+		}
 		let extrinsics_root = <ExtrinsicsRoot<T>>::take();
 		let storage_root = T::Hashing::storage_root();
 		<T::Header as traits::Header>::new(number, extrinsics_root, storage_root, parent_hash, digest)
@@ -120,6 +218,43 @@ impl<T: Trait> Module<T> {
 		<Digest<T>>::put(l);
 	}
 
+	/// Deposits an event into this block's event record, noting the current extrinsic index.
+	pub fn deposit_event(event: T::Event) {
+		let extrinsic_index = <ExtrinsicIndex<T>>::get();
+		let mut events = Self::events();
+		events.push(EventRecord { extrinsic_index, event });
+		<Events<T>>::put(events);
+	}
+
+	/// Deposits a contract log into this block's log records, noting the current extrinsic index.
+	pub fn deposit_log_record(address: T::AccountId, topics: Vec<T::Hash>, data: Vec<u8>) {
+		let extrinsic_index = <ExtrinsicIndex<T>>::get();
+		let mut records = Self::log_records();
+		records.push(LogRecord { extrinsic_index, address, topics, data });
+		<LogRecords<T>>::put(records);
+	}
+
+	/// The log records of the current block deposited by any of `addresses` under any of
+	/// `topics`. An empty list matches everything.
+	pub fn log_records_matching(addresses: &[T::AccountId], topics: &[T::Hash]) -> Vec<LogRecord<T::AccountId, T::Hash>> {
+		Self::log_records().into_iter()
+			.filter(|r| addresses.is_empty() || addresses.contains(&r.address))
+			.filter(|r| topics.is_empty() || r.topics.iter().any(|t| topics.contains(t)))
+			.collect()
+	}
+
+	/// Build the Bloom filter over the addresses and topics of `records`.
+	fn log_bloom(records: &[LogRecord<T::AccountId, T::Hash>]) -> LogBloom {
+		let mut bloom = LogBloom::new();
+		for record in records {
+			codec::Slicable::using_encoded(&record.address, |s| bloom.accrue(s));
+			for topic in &record.topics {
+				codec::Slicable::using_encoded(topic, |s| bloom.accrue(s));
+			}
+		}
+		bloom
+	}
+
 	/// Calculate the current block's random seed.
 	fn calculate_random() -> T::Hash {
 		assert!(Self::block_number() > Zero::zero(), "Block number may never be zero");
@@ -175,6 +310,15 @@ impl<T: Trait> Module<T> {
 		<ExtrinsicData<T>>::insert(Self::extrinsic_index(), encoded_xt);
 	}
 
+	/// To be called immediately after an extrinsic has been applied. Records whether its dispatch
+	/// succeeded and moves on to the next extrinsic index.
+	pub fn note_applied_extrinsic(success: bool) {
+		let mut results = Self::extrinsic_success();
+		results.push(success);
+		<ExtrinsicSuccess<T>>::put(results);
+		<ExtrinsicIndex<T>>::put(Self::extrinsic_index() + 1u32);
+	}
+
 	/// Remove all extrinsics data and save the extrinsics trie root.
 	pub fn derive_extrinsics() {
 		let extrinsics = (0..Self::extrinsic_index()).map(<ExtrinsicData<T>>::take).collect();
@@ -183,6 +327,32 @@ impl<T: Trait> Module<T> {
 	}
 }
 
+/// The context of the block being executed, against which the era of each extrinsic is checked.
+pub struct ChainContext<T>(PhantomData<T>);
+
+impl<T> Default for ChainContext<T> {
+	fn default() -> Self {
+		ChainContext(PhantomData)
+	}
+}
+
+impl<T: Trait> traits::ChainContext for ChainContext<T> {
+	type Hash = T::Hash;
+
+	fn current_height(&self) -> u64 {
+		<Module<T>>::block_number().as_()
+	}
+
+	fn block_hash(&self, number: u64) -> Option<T::Hash> {
+		let number = T::BlockNumber::sa(number);
+		if number < <Module<T>>::block_number() && <BlockHash<T>>::exists(&number) {
+			Some(<Module<T>>::block_hash(number))
+		} else {
+			None
+		}
+	}
+}
+
 #[cfg(any(feature = "std", test))]
 pub struct GenesisConfig<T: Trait>(PhantomData<T>);
 
@@ -66,12 +66,11 @@ impl<T: Trait> Module<T> {
 
 	/// Set the current time.
 	fn set(aux: &T::PublicAux, now: T::Value) -> Result {
-		assert!(aux.is_empty());
-		assert!(!<Self as Store>::DidUpdate::exists(), "Timestamp must be updated only once in the block");
-		assert!(
+		ensure!(aux.is_empty(), "Timestamp set must not be signed");
+		ensure!(!<Self as Store>::DidUpdate::exists(), "Timestamp must be updated only once in the block");
+		ensure!(
 			<system::Module<T>>::extrinsic_index() == T::SET_POSITION,
-			"Timestamp extrinsic must be at position {} in the block",
-			T::SET_POSITION
+			"Timestamp extrinsic must be at position SET_POSITION in the block"
 		);
 		<Self as Store>::Now::put(now);
 		<Self as Store>::DidUpdate::put(true);
@@ -126,6 +125,7 @@ mod tests {
 		type Digest = Digest;
 		type AccountId = u64;
 		type Header = Header;
+		type Event = ();
 	}
 	impl Trait for Test {
 		const SET_POSITION: u32 = 0;
@@ -144,4 +144,18 @@ mod tests {
 			assert_eq!(Timestamp::now(), 69);
 		});
 	}
+
+	#[test]
+	fn double_timestamp_should_fail() {
+		let mut t = system::GenesisConfig::<Test>::default().build_externalities();
+		t.extend(GenesisConfig::<Test> { now: 42 }.build_externalities());
+
+		with_externalities(&mut t, || {
+			assert_ok!(Timestamp::aux_dispatch(Call::set(69), &0));
+			assert_noop!(
+				Timestamp::aux_dispatch(Call::set(70), &0),
+				"Timestamp must be updated only once in the block"
+			);
+		});
+	}
 }
@@ -19,6 +19,10 @@
 use std::{error, fmt};
 use std::collections::HashMap;
 use std::sync::Arc;
+use ethereum_types::H256 as TrieH256;
+use memorydb::MemoryDB;
+use patricia_trie::{TrieDBMut, TrieMut};
+use trie_backend::TrieBackend;
 
 /// A state backend is used to read state data and can have changes committed
 /// to it.
@@ -41,6 +45,9 @@ pub trait Backend: Clone {
 
 	/// Get all key/value pairs into a Vec.
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)>;
+
+	/// Try convert into trie backend. Trie backends are able to produce execution proofs.
+	fn try_into_trie_backend(self) -> Option<TrieBackend>;
 }
 
 /// Error impossible.
@@ -122,5 +129,21 @@ impl Backend for InMemory {
 	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
 		self.inner.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
 	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		let mut root = TrieH256::default();
+		let mut mdb = MemoryDB::default();
+		{
+			let mut trie = TrieDBMut::new(&mut mdb, &mut root);
+			for (key, value) in self.inner.iter() {
+				if let Err(e) = trie.insert(&key, &value) {
+					warn!(target: "trie", "Failed to write to trie: {}", e);
+					return None;
+				}
+			}
+		}
+
+		Some(TrieBackend::with_memorydb(mdb, root))
+	}
 }
 
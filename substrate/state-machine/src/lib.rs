@@ -23,10 +23,17 @@ extern crate substrate_primitives as primitives;
 #[cfg_attr(test, macro_use)]
 extern crate hex_literal;
 
+extern crate ethereum_types;
+extern crate hashdb;
+extern crate memorydb;
 extern crate triehash;
+extern crate patricia_trie;
 
 extern crate byteorder;
 
+#[macro_use]
+extern crate log;
+
 use std::collections::HashMap;
 use std::collections::hash_map::Drain;
 use std::fmt;
@@ -34,10 +41,13 @@ use std::fmt;
 pub mod backend;
 mod ext;
 mod testing;
+mod proving_backend;
+mod trie_backend;
 
 pub use testing::TestExternalities;
 pub use ext::Ext;
 pub use backend::Backend;
+pub use trie_backend::{TrieBackend, TrieBackendStorage, Storage};
 
 /// The overlayed changes to state to be queried on top of the backend.
 ///
@@ -97,7 +107,11 @@ impl<E> Error for E where E: 'static + fmt::Debug + fmt::Display + Send {}
 #[derive(Debug, Eq, PartialEq)]
 pub enum ExecutionError {
 	/// The entry `:code` doesn't exist in storage so there's no way we can execute anything.
-	CodeEntryDoesNotExist
+	CodeEntryDoesNotExist,
+	/// Backend is incompatible with execution proof generation process.
+	UnableToGenerateProof,
+	/// Invalid execution proof.
+	InvalidProof,
 }
 
 impl fmt::Display for ExecutionError {
@@ -188,11 +202,82 @@ pub fn execute<B: backend::Backend, Exec: CodeExecutor>(
 	}
 }
 
+/// Prove execution using the given state backend, overlayed changes, and call executor.
+/// Produces the result of the call and the execution proof: the set of all storage trie nodes
+/// touched during the execution.
+///
+/// Note: changes to code will be in place if this call is made again. For running partial
+/// blocks (e.g. a transaction at a time), ensure a different method is used.
+pub fn prove_execution<B: backend::Backend, Exec: CodeExecutor>(
+	backend: B,
+	overlay: &mut OverlayedChanges,
+	exec: &Exec,
+	method: &str,
+	call_data: &[u8],
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), Box<Error>>
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::new(trie_backend);
+	let (result, _) = execute(&proving_backend, overlay, exec, method, call_data)?;
+	let proof = proving_backend.extract_proof();
+	Ok((result, proof))
+}
+
+/// Check execution proof, generated by `prove_execution` call, against the given state root.
+pub fn execution_proof_check<Exec: CodeExecutor>(
+	root: [u8; 32],
+	proof: Vec<Vec<u8>>,
+	overlay: &mut OverlayedChanges,
+	exec: &Exec,
+	method: &str,
+	call_data: &[u8],
+) -> Result<(Vec<u8>, memorydb::MemoryDB), Box<Error>>
+{
+	let backend = proving_backend::create_proof_check_backend(root.into(), proof)?;
+	execute(&backend, overlay, exec, method, call_data)
+}
+
+/// Generate storage read proof.
+pub fn prove_read<B: backend::Backend>(
+	backend: B,
+	key: &[u8],
+) -> Result<(Option<Vec<u8>>, Vec<Vec<u8>>), Box<Error>>
+{
+	let trie_backend = backend.try_into_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<Error>)?;
+	let proving_backend = proving_backend::ProvingBackend::new(trie_backend);
+	let result = proving_backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)?;
+	Ok((result, proving_backend.extract_proof()))
+}
+
+/// Check storage read proof, generated by `prove_read` call, against the given state root.
+pub fn read_proof_check(
+	root: [u8; 32],
+	proof: Vec<Vec<u8>>,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, Box<Error>>
+{
+	let backend = proving_backend::create_proof_check_backend(root.into(), proof)?;
+	backend.storage(key).map_err(|e| Box::new(e) as Box<Error>)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 	use super::backend::InMemory;
 	use super::ext::Ext;
+	use super::trie_backend::tests::test_trie;
+
+	struct DummyCodeExecutor;
+
+	impl CodeExecutor for DummyCodeExecutor {
+		type Error = u8;
+
+		fn call<E: Externalities>(&self, ext: &mut E, _code: &[u8], _method: &str, _data: &[u8]) -> Result<Vec<u8>, Self::Error> {
+			Ok(vec![ext.storage(b"value1").unwrap()[0] + ext.storage(b"value2").unwrap()[0]])
+		}
+	}
 
 	#[test]
 	fn overlayed_storage_works() {
@@ -252,4 +337,57 @@ mod tests {
 		const ROOT: [u8; 32] = hex!("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3");
 		assert_eq!(ext.storage_root(), ROOT);
 	}
+
+	#[test]
+	fn execute_works() {
+		assert_eq!(execute(
+			&test_trie(),
+			&mut Default::default(),
+			&DummyCodeExecutor,
+			"test",
+			&[],
+		).unwrap().0, vec![66]);
+	}
+
+	#[test]
+	fn prove_execution_and_proof_check_works() {
+		// fetch execution proof from 'remote' full node
+		let remote_backend = test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let (remote_result, remote_proof) = prove_execution(remote_backend,
+			&mut Default::default(), &DummyCodeExecutor, "test", &[]).unwrap();
+
+		// check proof locally
+		let (local_result, _) = execution_proof_check(remote_root, remote_proof,
+			&mut Default::default(), &DummyCodeExecutor, "test", &[]).unwrap();
+
+		// check that both results are correct
+		assert_eq!(remote_result, vec![66]);
+		assert_eq!(remote_result, local_result);
+	}
+
+	#[test]
+	fn proof_check_fails_with_wrong_root() {
+		let remote_backend = test_trie();
+		let (_, remote_proof) = prove_execution(remote_backend,
+			&mut Default::default(), &DummyCodeExecutor, "test", &[]).unwrap();
+
+		assert!(execution_proof_check([1; 32], remote_proof,
+			&mut Default::default(), &DummyCodeExecutor, "test", &[]).is_err());
+	}
+
+	#[test]
+	fn prove_read_and_proof_check_works() {
+		// fetch read proof from 'remote' full node
+		let remote_backend = test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+		let (remote_value, remote_proof) = prove_read(remote_backend, b"value2").unwrap();
+
+		// check proof locally
+		let local_value = read_proof_check(remote_root, remote_proof, b"value2").unwrap();
+
+		// check that both results are correct
+		assert_eq!(remote_value, Some(vec![24]));
+		assert_eq!(remote_value, local_value);
+	}
 }
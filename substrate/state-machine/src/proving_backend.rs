@@ -0,0 +1,176 @@
+// Copyright 2017 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proving state machine backend.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ethereum_types::H256 as TrieH256;
+use hashdb::{DBValue, HashDB};
+use memorydb::MemoryDB;
+use backend::Backend;
+use trie_backend::{Storage, TrieBackend, TrieBackendStorage};
+use {Error, ExecutionError};
+
+/// Trie node storage which remembers every node that has been read from it.
+struct ProofRecorder {
+	storage: TrieBackendStorage,
+	proof: Mutex<HashMap<TrieH256, DBValue>>,
+}
+
+impl Storage for ProofRecorder {
+	fn get(&self, key: &TrieH256) -> Result<Option<DBValue>, String> {
+		let value = self.storage.get(key)?;
+		if let Some(ref value) = value {
+			self.proof.lock().expect("proof recorder lock is never poisoned; qed")
+				.insert(*key, value.clone());
+		}
+		Ok(value)
+	}
+}
+
+/// Patricia trie-based backend which also tracks all touched storage trie nodes.
+/// These can be sent to a remote node and used as a proof of execution.
+///
+/// Clones of the backend share the same proof.
+#[derive(Clone)]
+pub struct ProvingBackend {
+	backend: TrieBackend,
+	recorder: Arc<ProofRecorder>,
+}
+
+impl ProvingBackend {
+	/// Create new proving backend.
+	pub fn new(backend: TrieBackend) -> Self {
+		let recorder = Arc::new(ProofRecorder {
+			storage: backend.backend_storage().clone(),
+			proof: Mutex::new(HashMap::new()),
+		});
+
+		ProvingBackend {
+			backend: TrieBackend::with_storage(recorder.clone(), *backend.root()),
+			recorder,
+		}
+	}
+
+	/// Get the set of trie nodes which have been touched so far.
+	pub fn extract_proof(&self) -> Vec<Vec<u8>> {
+		self.recorder.proof.lock().expect("proof recorder lock is never poisoned; qed")
+			.values()
+			.map(|node| node.to_vec())
+			.collect()
+	}
+}
+
+impl Backend for ProvingBackend {
+	type Error = String;
+	type Transaction = MemoryDB;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.backend.storage(key)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.backend.pairs()
+	}
+
+	fn storage_root<I>(&self, delta: I) -> ([u8; 32], MemoryDB)
+		where I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.backend.storage_root(delta)
+	}
+
+	fn try_into_trie_backend(self) -> Option<TrieBackend> {
+		None
+	}
+}
+
+/// Create a backend which is able to check a proof, generated by the `ProvingBackend`,
+/// against the given state root.
+pub fn create_proof_check_backend(root: TrieH256, proof: Vec<Vec<u8>>) -> Result<TrieBackend, Box<Error>> {
+	let mut db = MemoryDB::default();
+	for item in proof {
+		db.insert(&item);
+	}
+
+	if !db.contains(&root) {
+		return Err(Box::new(ExecutionError::InvalidProof) as Box<Error>);
+	}
+
+	Ok(TrieBackend::with_memorydb(db, root))
+}
+
+#[cfg(test)]
+mod tests {
+	use backend::InMemory;
+	use trie_backend::tests::test_trie;
+	use super::*;
+
+	fn test_proving() -> ProvingBackend {
+		ProvingBackend::new(test_trie())
+	}
+
+	#[test]
+	fn proof_is_empty_until_value_is_read() {
+		assert!(test_proving().extract_proof().is_empty());
+	}
+
+	#[test]
+	fn proof_is_non_empty_after_value_is_read() {
+		let backend = test_proving();
+		assert_eq!(backend.storage(b"key").unwrap(), Some(b"value".to_vec()));
+		assert!(!backend.extract_proof().is_empty());
+	}
+
+	#[test]
+	fn proof_is_invalid_when_does_not_contain_root() {
+		assert!(create_proof_check_backend(1.into(), vec![]).is_err());
+	}
+
+	#[test]
+	fn passes_through_backend_calls() {
+		let trie_backend = test_trie();
+		let proving_backend = test_proving();
+		assert_eq!(trie_backend.storage(b"key").unwrap(), proving_backend.storage(b"key").unwrap());
+		assert_eq!(trie_backend.pairs(), proving_backend.pairs());
+
+		let trie_root = trie_backend.storage_root(::std::iter::empty()).0;
+		let proving_root = proving_backend.storage_root(::std::iter::empty()).0;
+		assert_eq!(trie_root, proving_root);
+	}
+
+	#[test]
+	fn proof_recorded_and_checked() {
+		let contents = (0..64).map(|i| (vec![i], Some(vec![i]))).collect::<Vec<_>>();
+		let in_memory = InMemory::default().update(contents);
+		let in_memory_root = in_memory.storage_root(::std::iter::empty()).0;
+		(0..64).for_each(|i| assert_eq!(in_memory.storage(&[i]).unwrap().unwrap(), vec![i]));
+
+		let trie = in_memory.try_into_trie_backend().unwrap();
+		let trie_root = trie.storage_root(::std::iter::empty()).0;
+		assert_eq!(in_memory_root, trie_root);
+		(0..64).for_each(|i| assert_eq!(trie.storage(&[i]).unwrap().unwrap(), vec![i]));
+
+		let proving = ProvingBackend::new(trie);
+		assert_eq!(proving.storage(&[42]).unwrap().unwrap(), vec![42]);
+
+		let proof = proving.extract_proof();
+		assert!(proof.len() < 64);
+
+		let proof_check = create_proof_check_backend(in_memory_root.into(), proof).unwrap();
+		assert_eq!(proof_check.storage(&[42]).unwrap().unwrap(), vec![42]);
+	}
+}